@@ -0,0 +1,309 @@
+use crate::subgraph::Subgraph;
+use crate::types::*;
+use redis::{from_redis_value, RedisResult, Value};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+/// The direction in which relations are followed by the traversal and path
+/// algorithms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Follows relations from their source to their destination node.
+    Outgoing,
+    /// Follows relations from their destination to their source node.
+    Incoming,
+    /// Follows relations in both directions.
+    Both,
+}
+
+/// A path found by dijkstra or astar.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedPath {
+    /// The ids of the visited nodes from start to goal.
+    pub nodes: Vec<u64>,
+    /// The ids of the followed relations, one less than nodes.
+    pub relations: Vec<u64>,
+    /// The sum of the relation weights.
+    pub cost: f64,
+}
+
+/// Breadth first traversal starting at node start. Returns the ids of all
+/// reachable nodes in visiting order, starting with start.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let res = con.graph_ro_query("my_graph", "MATCH (a)-[r:road]->(b) RETURN a, r, b")?;
+/// let graph = res.to_subgraph();
+/// let reachable = bfs(&graph, 0, Direction::Outgoing);
+/// let route = dijkstra(&graph, 0, 42, "distance", Direction::Both)?;
+/// # Ok(()) }
+/// ```
+pub fn bfs(graph: &Subgraph, start: u64, direction: Direction) -> Vec<u64> {
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    let mut queue = VecDeque::new();
+    visited.insert(start);
+    queue.push_back(start);
+    while let Some(node) = queue.pop_front() {
+        order.push(node);
+        for (_, next) in steps(graph, node, direction) {
+            if visited.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    order
+}
+
+/// Depth first traversal starting at node start. Returns the ids of all
+/// reachable nodes in pre-order, starting with start.
+pub fn dfs(graph: &Subgraph, start: u64, direction: Direction) -> Vec<u64> {
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        if !visited.insert(node) {
+            continue;
+        }
+        order.push(node);
+        let mut next: Vec<u64> = steps(graph, node, direction)
+            .into_iter()
+            .map(|(_, next)| next)
+            .filter(|next| !visited.contains(next))
+            .collect();
+        next.reverse();
+        stack.extend(next);
+    }
+    order
+}
+
+/// Finds the cheapest path from start to goal using the numeric relation
+/// property weight as cost. Returns None if goal is not reachable and an
+/// error if a followed relation has a missing, negative or non numeric weight.
+pub fn dijkstra(
+    graph: &Subgraph,
+    start: u64,
+    goal: u64,
+    weight: &str,
+    direction: Direction,
+) -> RedisResult<Option<WeightedPath>> {
+    astar(graph, start, goal, weight, direction, |_| 0.0)
+}
+
+/// Like dijkstra but guided by heuristic, an estimate of the remaining cost
+/// from a node id to goal. The heuristic must never overestimate the cost for
+/// the result to be the cheapest path. It does not need to be consistent, as
+/// visited nodes are reopened when a cheaper path to them is found.
+pub fn astar<H: Fn(u64) -> f64>(
+    graph: &Subgraph,
+    start: u64,
+    goal: u64,
+    weight: &str,
+    direction: Direction,
+    heuristic: H,
+) -> RedisResult<Option<WeightedPath>> {
+    let mut costs: HashMap<u64, f64> = HashMap::new();
+    let mut previous: HashMap<u64, (u64, u64)> = HashMap::new();
+    let mut done = HashSet::new();
+    let mut queue = BinaryHeap::new();
+    costs.insert(start, 0.0);
+    queue.push(QueueEntry {
+        estimate: heuristic(start),
+        node: start,
+    });
+    while let Some(QueueEntry { node, .. }) = queue.pop() {
+        if node == goal {
+            return Ok(Some(build_path(&previous, start, goal, costs[&goal])));
+        }
+        if !done.insert(node) {
+            continue;
+        }
+        let cost = costs[&node];
+        for (relation, next) in steps(graph, node, direction) {
+            let next_cost = cost + relation_weight(relation, weight)?;
+            let shorter = match costs.get(&next) {
+                Some(known) => next_cost < *known,
+                None => true,
+            };
+            if shorter {
+                done.remove(&next);
+                costs.insert(next, next_cost);
+                previous.insert(next, (node, relation.id));
+                queue.push(QueueEntry {
+                    estimate: next_cost + heuristic(next),
+                    node: next,
+                });
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Weakly connected components, relations are followed in both directions.
+/// Every component is sorted by node id, components are ordered by their
+/// smallest node id. Nodes only referenced by relations are included.
+pub fn connected_components(graph: &Subgraph) -> Vec<Vec<u64>> {
+    let mut visited = HashSet::new();
+    let mut components = Vec::new();
+    for node in graph.node_ids() {
+        if visited.contains(&node) {
+            continue;
+        }
+        let mut component = bfs(graph, node, Direction::Both);
+        visited.extend(component.iter().copied());
+        component.sort_unstable();
+        components.push(component);
+    }
+    components
+}
+
+/// Returns true if the relations form a directed cycle.
+pub fn is_cyclic(graph: &Subgraph) -> bool {
+    find_cycle(graph).is_some()
+}
+
+/// Finds a directed cycle and returns its node ids in relation order, the
+/// relation back to the first node is implied. A self loop is a cycle of one
+/// node.
+pub fn find_cycle(graph: &Subgraph) -> Option<Vec<u64>> {
+    // 1: on the current path, 2: finished
+    let mut state: HashMap<u64, u8> = HashMap::new();
+    for root in graph.node_ids() {
+        if state.contains_key(&root) {
+            continue;
+        }
+        let mut path: Vec<u64> = Vec::new();
+        let mut stack: Vec<(u64, Vec<u64>)> = vec![(root, pending_successors(graph, root))];
+        state.insert(root, 1);
+        path.push(root);
+        while let Some((node, pending)) = stack.last_mut() {
+            let node = *node;
+            match pending.pop() {
+                Some(next) => match state.get(&next) {
+                    Some(1) => {
+                        let start = path.iter().position(|n| *n == next).unwrap_or(0);
+                        return Some(path[start..].to_vec());
+                    }
+                    Some(_) => {}
+                    None => {
+                        state.insert(next, 1);
+                        path.push(next);
+                        stack.push((next, pending_successors(graph, next)));
+                    }
+                },
+                None => {
+                    state.insert(node, 2);
+                    path.pop();
+                    stack.pop();
+                }
+            }
+        }
+    }
+    None
+}
+
+// Successors in reverse order so they are popped in relation order
+fn pending_successors(graph: &Subgraph, node: u64) -> Vec<u64> {
+    let mut successors = graph.successors(node);
+    successors.reverse();
+    successors
+}
+
+// The relations to follow from node together with the node at the other end
+fn steps(graph: &Subgraph, node: u64, direction: Direction) -> Vec<(&RelationValue, u64)> {
+    let mut out = Vec::new();
+    if direction != Direction::Incoming {
+        out.extend(graph.outgoing(node).into_iter().map(|r| (r, r.dest_node)));
+    }
+    if direction != Direction::Outgoing {
+        out.extend(graph.incoming(node).into_iter().map(|r| (r, r.src_node)));
+    }
+    out
+}
+
+fn relation_weight(relation: &RelationValue, weight: &str) -> RedisResult<f64> {
+    let value = match relation.properties.get(weight) {
+        Some(Value::Int(v)) => *v as f64,
+        Some(value) => from_redis_value::<String>(value)?
+            .parse::<f64>()
+            .map_err(|_| create_error(&format!("Relation weight {} is not numeric", weight)))?,
+        None => {
+            return Err(create_error(&format!(
+                "Relation {} is missing weight {}",
+                relation.id, weight
+            )))
+        }
+    };
+    if value.is_nan() {
+        return Err(create_error(&format!(
+            "Relation {} has weight {} NaN",
+            relation.id, weight
+        )));
+    }
+    if value < 0.0 {
+        return Err(create_error(&format!(
+            "Relation {} has negative weight {}",
+            relation.id, weight
+        )));
+    }
+    Ok(value)
+}
+
+fn build_path(
+    previous: &HashMap<u64, (u64, u64)>,
+    start: u64,
+    goal: u64,
+    cost: f64,
+) -> WeightedPath {
+    let mut nodes = vec![goal];
+    let mut relations = Vec::new();
+    let mut node = goal;
+    while node != start {
+        let (prev, relation) = previous[&node];
+        nodes.push(prev);
+        relations.push(relation);
+        node = prev;
+    }
+    nodes.reverse();
+    relations.reverse();
+    WeightedPath {
+        nodes,
+        relations,
+        cost,
+    }
+}
+
+// Min-heap entry ordered by the estimated total cost
+struct QueueEntry {
+    estimate: f64,
+    node: u64,
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueueEntry {}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
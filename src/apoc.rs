@@ -0,0 +1,474 @@
+use crate::params::{escape_identifier, string_literal, ParamValue};
+use crate::types::*;
+use redis::{FromRedisValue, RedisResult, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Lines};
+
+/// An import of the JSON lines written by Neo4j's `apoc.export.json.all`,
+/// `apoc.export.json.graph` or `apoc.export.json.query` with the default
+/// `JSON_LINES` format. Nodes are created in batches of consecutive records
+/// with the same labels, relations in batches of consecutive records with
+/// the same type. Neo4j node ids are mapped to the ids of the created nodes,
+/// so relation records have to come after the records of their nodes, as
+/// in the export files.
+///
+/// Booleans, numbers, strings and lists of them are imported as is,
+/// temporal values stay ISO 8601 strings unless their property is converted
+/// to epoch milliseconds with `with_timestamp_properties`. 2D WGS-84 points
+/// become points. Other points, maps, nulls and lists containing them have
+/// no graph counterpart and are skipped and listed in the report.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let import = ApocImport::new().with_timestamp_properties(&["born", "createdAt"]);
+/// let input = BufReader::new(File::open("all.json")?);
+/// let report = con.graph_import_apoc("my_graph", &import, input)?;
+/// println!("{} nodes, {} relations", report.nodes, report.relations);
+/// for skipped in &report.skipped {
+///     println!("line {}: {}", skipped.line, skipped.reason);
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApocImport {
+    batch_rows: usize,
+    timestamp_properties: HashSet<String>,
+}
+
+impl Default for ApocImport {
+    fn default() -> Self {
+        ApocImport::new()
+    }
+}
+
+impl ApocImport {
+    /// An import in batches of 1000 records.
+    pub fn new() -> Self {
+        ApocImport {
+            batch_rows: 1000,
+            timestamp_properties: HashSet::new(),
+        }
+    }
+
+    /// Sets the maximum number of records per query (at least 1).
+    pub fn with_batch_rows(mut self, rows: usize) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+
+    /// Converts the ISO 8601 dates and datetimes of these properties to
+    /// milliseconds since the epoch. Datetimes without offset are read as
+    /// UTC, values that are no date or datetime are skipped.
+    pub fn with_timestamp_properties(mut self, properties: &[&str]) -> Self {
+        self.timestamp_properties = properties.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    pub(crate) fn reader<R: BufRead>(&self, input: R) -> ApocReader<'_, R> {
+        ApocReader {
+            import: self,
+            lines: input.lines(),
+            line: 0,
+            carry: None,
+            sent: None,
+            report: ApocReport::default(),
+        }
+    }
+}
+
+/// A value or record that was not imported.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApocSkipped {
+    /// The line of the record in the input, starting at 1.
+    pub line: usize,
+    /// The Neo4j id of the node or relation.
+    pub id: String,
+    /// The skipped property, None if the whole record was skipped.
+    pub property: Option<String>,
+    pub reason: String,
+}
+
+/// The outcome of an APOC import.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct ApocReport {
+    pub nodes: usize,
+    pub relations: usize,
+    /// The id of the created node for every Neo4j node id.
+    pub node_ids: HashMap<String, u64>,
+    /// The skipped values and records in input order.
+    pub skipped: Vec<ApocSkipped>,
+    pub statistics: QueryStatistics,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ApocRecord {
+    Node {
+        id: serde_json::Value,
+        #[serde(default)]
+        labels: Vec<String>,
+        #[serde(default)]
+        properties: serde_json::Map<String, serde_json::Value>,
+    },
+    Relationship {
+        id: serde_json::Value,
+        label: String,
+        #[serde(default)]
+        properties: serde_json::Map<String, serde_json::Value>,
+        start: ApocEndpoint,
+        end: ApocEndpoint,
+    },
+}
+
+#[derive(serde::Deserialize)]
+struct ApocEndpoint {
+    id: serde_json::Value,
+}
+
+// The Cypher literals of the records of a batch
+enum ApocBatch {
+    Nodes(Vec<String>, Vec<String>),
+    Relations(String, Vec<String>),
+}
+
+impl ApocBatch {
+    fn with_row(mut self, row: String) -> Self {
+        match &mut self {
+            ApocBatch::Nodes(_, rows) | ApocBatch::Relations(_, rows) => rows.push(row),
+        }
+        self
+    }
+
+    fn rows(&self) -> usize {
+        match self {
+            ApocBatch::Nodes(_, rows) | ApocBatch::Relations(_, rows) => rows.len(),
+        }
+    }
+
+    fn query(&self) -> String {
+        match self {
+            ApocBatch::Nodes(labels, rows) => {
+                let pattern: String = labels
+                    .iter()
+                    .map(|label| format!(":{}", escape_identifier(label)))
+                    .collect();
+                format!(
+                    "UNWIND [{}] AS row CREATE (n{}) SET n = row.properties RETURN row.id, id(n)",
+                    rows.join(", "),
+                    pattern
+                )
+            }
+            ApocBatch::Relations(rel_type, rows) => format!(
+                "UNWIND [{}] AS row MATCH (a) WHERE id(a) = row.src MATCH (b) WHERE id(b) = row.dest \
+                 CREATE (a)-[r:{}]->(b) SET r = row.properties",
+                rows.join(", "),
+                escape_identifier(rel_type)
+            ),
+        }
+    }
+}
+
+// Reads the records and creates the batch queries, a relation batch is only
+// started once the responses of the previous node batches mapped their ids
+pub(crate) struct ApocReader<'a, R> {
+    import: &'a ApocImport,
+    lines: Lines<R>,
+    line: usize,
+    // A record of the next batch with its line
+    carry: Option<(usize, ApocRecord)>,
+    sent: Option<ApocBatch>,
+    report: ApocReport,
+}
+
+impl<'a, R: BufRead> ApocReader<'a, R> {
+    /// The query of the next batch, None after the last record.
+    pub(crate) fn next_query(&mut self) -> RedisResult<Option<String>> {
+        let mut batch: Option<ApocBatch> = None;
+        loop {
+            let (line, record) = match self.carry.take() {
+                Some(carried) => carried,
+                None => match self.read_record()? {
+                    Some(record) => record,
+                    None => break,
+                },
+            };
+            let fits = match (&batch, &record) {
+                (None, _) => true,
+                (Some(ApocBatch::Nodes(labels, _)), ApocRecord::Node { labels: next, .. }) => {
+                    labels == next
+                }
+                (
+                    Some(ApocBatch::Relations(rel_type, _)),
+                    ApocRecord::Relationship { label, .. },
+                ) => rel_type == label,
+                _ => false,
+            };
+            if !fits {
+                self.carry = Some((line, record));
+                break;
+            }
+            if let Some((started, row)) = self.row(line, record) {
+                batch = Some(batch.unwrap_or(started).with_row(row));
+            }
+            if batch.as_ref().map(ApocBatch::rows) == Some(self.import.batch_rows) {
+                break;
+            }
+        }
+        let query = batch.as_ref().map(ApocBatch::query);
+        self.sent = batch;
+        Ok(query)
+    }
+
+    /// Adds the response to the last batch query.
+    pub(crate) fn add_response(&mut self, v: &Value) -> RedisResult<()> {
+        let res = GraphResultSet::from_redis_value(v)?;
+        self.report.statistics.add(&res.statistics());
+        match self.sent.take() {
+            Some(ApocBatch::Nodes(_, rows)) => {
+                if res.data.len() != rows.len() {
+                    return Err(create_error(&format!(
+                        "Expected {} node ids of an import batch, got {}",
+                        rows.len(),
+                        res.data.len()
+                    )));
+                }
+                for row in res.data.iter() {
+                    match (row.get_scalar_at::<String>(0), row.get_scalar_at::<u64>(1)) {
+                        (Some(neo4j_id), Some(id)) => {
+                            self.report.node_ids.insert(neo4j_id, id);
+                        }
+                        _ => return Err(create_error("Unexpected node ids of an import batch")),
+                    }
+                }
+                self.report.nodes += rows.len();
+            }
+            Some(ApocBatch::Relations(_, rows)) => self.report.relations += rows.len(),
+            None => {}
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> ApocReport {
+        self.report
+    }
+
+    fn read_record(&mut self) -> RedisResult<Option<(usize, ApocRecord)>> {
+        for line in self.lines.by_ref() {
+            self.line += 1;
+            let number = self.line;
+            let line = line.map_err(|e| create_error(&format!("Line {}: {}", number, e)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line)
+                .map_err(|e| create_error(&format!("Line {}: {}", number, e)))?;
+            return Ok(Some((number, record)));
+        }
+        Ok(None)
+    }
+
+    // The empty batch and Cypher literal of a record, None if the record is
+    // skipped
+    fn row(&mut self, line: usize, record: ApocRecord) -> Option<(ApocBatch, String)> {
+        match record {
+            ApocRecord::Node {
+                id,
+                labels,
+                properties,
+            } => {
+                let id = record_id(&id);
+                let properties = self.properties(line, &id, properties);
+                let row = format!(
+                    "{{id: {}, properties: {}}}",
+                    string_literal(&id),
+                    properties
+                );
+                Some((ApocBatch::Nodes(labels, vec![]), row))
+            }
+            ApocRecord::Relationship {
+                id,
+                label,
+                properties,
+                start,
+                end,
+            } => {
+                let id = record_id(&id);
+                let mut endpoints = vec![];
+                for (name, endpoint) in [("start", start), ("end", end)] {
+                    let node = record_id(&endpoint.id);
+                    match self.report.node_ids.get(&node) {
+                        Some(node_id) => endpoints.push(*node_id),
+                        None => {
+                            self.report.skipped.push(ApocSkipped {
+                                line,
+                                id,
+                                property: None,
+                                reason: format!("The {} node {} was not imported", name, node),
+                            });
+                            return None;
+                        }
+                    }
+                }
+                let properties = self.properties(line, &id, properties);
+                let row = format!(
+                    "{{src: {}, dest: {}, properties: {}}}",
+                    endpoints[0], endpoints[1], properties
+                );
+                Some((ApocBatch::Relations(label, vec![]), row))
+            }
+        }
+    }
+
+    // The Cypher map literal of the importable properties
+    fn properties(
+        &mut self,
+        line: usize,
+        id: &str,
+        properties: serde_json::Map<String, serde_json::Value>,
+    ) -> String {
+        let mut entries = vec![];
+        for (key, value) in properties {
+            let timestamp = self.import.timestamp_properties.contains(&key);
+            match property_cypher(value, timestamp) {
+                Ok(value) => entries.push(format!("{}: {}", escape_identifier(&key), value)),
+                Err(reason) => self.report.skipped.push(ApocSkipped {
+                    line,
+                    id: id.to_string(),
+                    property: Some(key),
+                    reason,
+                }),
+            }
+        }
+        format!("{{{}}}", entries.join(", "))
+    }
+}
+
+// Neo4j ids are exported as strings by recent APOC versions and as numbers
+// by older ones
+fn record_id(id: &serde_json::Value) -> String {
+    match id {
+        serde_json::Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+
+// The Cypher literal of a property value, or why it can't be imported
+fn property_cypher(value: serde_json::Value, timestamp: bool) -> Result<String, String> {
+    match value {
+        serde_json::Value::Null => Err("Null values are not supported".to_string()),
+        serde_json::Value::Object(point) => point_cypher(&point),
+        serde_json::Value::Array(values) => {
+            let values = values
+                .into_iter()
+                .map(|value| match value {
+                    serde_json::Value::Null | serde_json::Value::Object(_) => {
+                        Err("Lists with nulls, maps or points are not supported".to_string())
+                    }
+                    value => scalar(value, timestamp),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            ParamValue::List(values)
+                .to_cypher()
+                .map_err(|e| e.to_string())
+        }
+        value => scalar(value, timestamp)?
+            .to_cypher()
+            .map_err(|e| e.to_string()),
+    }
+}
+
+fn scalar(value: serde_json::Value, timestamp: bool) -> Result<ParamValue, String> {
+    match value {
+        serde_json::Value::String(value) if timestamp => epoch_millis(&value)
+            .map(ParamValue::Int)
+            .ok_or_else(|| format!("'{}' is no ISO 8601 date or datetime", value)),
+        value => Ok(ParamValue::from_json(value)),
+    }
+}
+
+// APOC writes points as maps with the coordinate reference system
+fn point_cypher(point: &serde_json::Map<String, serde_json::Value>) -> Result<String, String> {
+    let coordinate = |name: &str| point.get(name).and_then(|v| v.as_f64());
+    match point.get("crs").and_then(|crs| crs.as_str()) {
+        Some("wgs-84") => match (coordinate("latitude"), coordinate("longitude")) {
+            (Some(latitude), Some(longitude)) => Ok(format!(
+                "point({{latitude: {:?}, longitude: {:?}}})",
+                latitude, longitude
+            )),
+            _ => Err("Point without latitude and longitude".to_string()),
+        },
+        Some(crs) => Err(format!("{} points are not supported", crs)),
+        None => Err("Maps are not supported".to_string()),
+    }
+}
+
+// Milliseconds since the epoch of an ISO 8601 date or datetime with an
+// optional offset and zone id, e.g. 2015-07-04T19:32:24.123+01:00[Europe/London]
+fn epoch_millis(value: &str) -> Option<i64> {
+    let value = value.split('[').next()?;
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+    if let Some(time) = time {
+        let (clock, offset) = match time.find(['Z', '+', '-']) {
+            Some(idx) => time.split_at(idx),
+            None => (time, ""),
+        };
+        let mut clock = clock.splitn(3, ':');
+        let hours: i64 = clock.next()?.parse().ok()?;
+        let minutes: i64 = clock.next()?.parse().ok()?;
+        let (seconds, fraction) = match clock.next() {
+            Some(seconds) => match seconds.split_once('.') {
+                Some((seconds, fraction)) => (seconds.parse().ok()?, fraction),
+                None => (seconds.parse().ok()?, ""),
+            },
+            None => (0, ""),
+        };
+        let fraction = format!("{:0<3}", fraction.get(..3).unwrap_or(fraction));
+        millis += ((hours * 60 + minutes) * 60 + seconds) * 1000 + fraction.parse::<i64>().ok()?;
+        millis -= offset_minutes(offset)? * 60_000;
+    }
+    Some(millis)
+}
+
+fn offset_minutes(offset: &str) -> Option<i64> {
+    let (sign, offset) = match offset.chars().next() {
+        None | Some('Z') if offset.len() <= 1 => return Some(0),
+        Some('+') => (1, &offset[1..]),
+        Some('-') => (-1, &offset[1..]),
+        _ => return None,
+    };
+    let digits: String = offset.chars().filter(|c| *c != ':').collect();
+    let hours: i64 = digits.get(..2)?.parse().ok()?;
+    let minutes: i64 = match digits.get(2..) {
+        Some("") | None => 0,
+        Some(minutes) => minutes.parse().ok()?,
+    };
+    Some(sign * (hours * 60 + minutes))
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
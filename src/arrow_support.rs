@@ -0,0 +1,674 @@
+use crate::stream::GraphResultStream;
+use crate::types::*;
+use arrow::array::{
+    new_null_array, ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, ListArray,
+    MapArray, StringArray, StructArray, UInt64Array,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::{DataType, Field, FieldRef, Fields, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use redis::{RedisResult, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Converts graph query results into Arrow record batches and with the
+/// `parquet` feature into Parquet files.
+///
+/// Column types are inferred from the values. Integers become Int64,
+/// strings Utf8 and non UTF-8 strings Binary. Redis graph returns doubles and
+/// booleans as strings, so their columns are Utf8 unless set to Float64 or
+/// Boolean with with_column_type, which converts the strings. Arrays become
+/// lists of the widened element type. Mixed columns widen to Utf8 with values
+/// rendered in Cypher notation. Columns containing only nulls are Utf8.
+///
+/// Node columns become structs of `id`, `labels` and `properties`, relation
+/// columns structs of `id`, `type`, `src`, `dest` and `properties`, where
+/// properties are a map of the property names to their rendered values.
+///
+/// ```rust
+/// use redis_graph::*;
+/// use arrow::datatypes::DataType;
+///
+/// let header = vec!["r.name".to_string(), "r.born".to_string()];
+/// let res = GraphResultSet {
+///     header: header.clone(),
+///     data: vec![GraphResult::from_values(
+///         header.into(),
+///         vec![
+///             GraphValue::Scalar(redis::Value::Data(b"Valentino Rossi".to_vec())),
+///             GraphValue::Scalar(redis::Value::Int(1979)),
+///         ],
+///     )],
+///     metadata: vec![],
+/// };
+/// let batches = ArrowExport::new().to_record_batches(&res).unwrap();
+/// assert_eq!(batches[0].schema().field(1).data_type(), &DataType::Int64);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ArrowExport {
+    batch_rows: usize,
+    column_types: HashMap<String, DataType>,
+}
+
+impl Default for ArrowExport {
+    fn default() -> Self {
+        ArrowExport {
+            batch_rows: 8192,
+            column_types: HashMap::new(),
+        }
+    }
+}
+
+impl ArrowExport {
+    /// Creates an export with batches of up to 8192 rows.
+    pub fn new() -> Self {
+        ArrowExport::default()
+    }
+
+    /// Sets the maximum number of rows per record batch.
+    pub fn with_batch_rows(mut self, rows: usize) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+
+    /// Uses the given type for a column instead of the inferred one. Values
+    /// are converted where possible, other values fail the conversion.
+    /// Supported are Boolean, Int64, Float64, Utf8, Binary and lists of them.
+    pub fn with_column_type(mut self, column: &str, data_type: DataType) -> Self {
+        self.column_types.insert(column.to_string(), data_type);
+        self
+    }
+
+    /// Infers the schema of the given rows.
+    fn schema<R: AsRef<[GraphValue]>, I: IntoIterator<Item = R>>(
+        &self,
+        header: &[String],
+        rows: I,
+    ) -> SchemaRef {
+        let mut types = vec![ColumnType::Null; header.len()];
+        for row in rows {
+            for (column_type, value) in types.iter_mut().zip(row.as_ref()) {
+                *column_type =
+                    std::mem::replace(column_type, ColumnType::Null).merge(ColumnType::of(value));
+            }
+        }
+        let fields: Vec<Field> = header
+            .iter()
+            .zip(types)
+            .map(|(name, column_type)| {
+                let data_type = match self.column_types.get(name) {
+                    Some(data_type) => data_type.clone(),
+                    None => column_type.data_type(),
+                };
+                Field::new(name, data_type, true)
+            })
+            .collect();
+        Arc::new(Schema::new(fields))
+    }
+
+    /// Converts the rows into a record batch with the given schema.
+    fn record_batch(&self, schema: &SchemaRef, rows: &[&[GraphValue]]) -> RedisResult<RecordBatch> {
+        let columns = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                let values: Vec<Option<&GraphValue>> =
+                    rows.iter().map(|row| row.get(idx)).collect();
+                build_column(field.data_type(), &values)
+                    .map_err(|e| create_error(&format!("Column {}: {}", field.name(), e)))
+            })
+            .collect::<RedisResult<Vec<ArrayRef>>>()?;
+        RecordBatch::try_new(schema.clone(), columns).map_err(arrow_error)
+    }
+
+    /// Converts a result set into record batches of at most batch_rows rows
+    /// sharing one schema inferred from all rows.
+    pub fn to_record_batches(&self, res: &GraphResultSet) -> RedisResult<Vec<RecordBatch>> {
+        let rows: Vec<&[GraphValue]> = res.data.iter().map(|row| row.values()).collect();
+        let schema = self.schema(&res.header, &rows);
+        if rows.is_empty() {
+            return Ok(vec![RecordBatch::new_empty(schema)]);
+        }
+        rows.chunks(self.batch_rows)
+            .map(|chunk| self.record_batch(&schema, chunk))
+            .collect()
+    }
+
+    /// Converts the rows of a stream into record batches as they are
+    /// decoded. All batches share one schema, which is inferred from all rows
+    /// of the stream upfront, decoding them one at a time without keeping
+    /// them.
+    pub fn stream_batches(&self, stream: GraphResultStream) -> ArrowBatches {
+        // Rows failing to decode fail their batch later
+        let rows = stream.peek_rows().filter_map(Result::ok);
+        let schema = self.schema(stream.header(), rows);
+        ArrowBatches {
+            export: self.clone(),
+            stream,
+            schema,
+        }
+    }
+
+    /// Writes a result set as a Parquet file.
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet<W: std::io::Write + Send>(
+        &self,
+        res: &GraphResultSet,
+        writer: W,
+    ) -> RedisResult<()> {
+        let batches = self.to_record_batches(res)?;
+        let mut parquet = parquet::arrow::ArrowWriter::try_new(writer, batches[0].schema(), None)
+            .map_err(parquet_error)?;
+        for batch in &batches {
+            parquet.write(batch).map_err(parquet_error)?;
+        }
+        parquet.close().map_err(parquet_error)?;
+        Ok(())
+    }
+
+    /// Writes the rows of a stream as a Parquet file batch by batch and
+    /// returns the number of written rows.
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet_stream<W: std::io::Write + Send>(
+        &self,
+        stream: GraphResultStream,
+        writer: W,
+    ) -> RedisResult<usize> {
+        let batches = self.stream_batches(stream);
+        let mut parquet = parquet::arrow::ArrowWriter::try_new(writer, batches.schema(), None)
+            .map_err(parquet_error)?;
+        let mut rows = 0;
+        for batch in batches {
+            let batch = batch?;
+            rows += batch.num_rows();
+            parquet.write(&batch).map_err(parquet_error)?;
+        }
+        parquet.close().map_err(parquet_error)?;
+        Ok(rows)
+    }
+}
+
+/// An iterator over the record batches of a GraphResultStream as returned by
+/// ArrowExport::stream_batches.
+#[derive(Debug)]
+pub struct ArrowBatches {
+    export: ArrowExport,
+    stream: GraphResultStream,
+    schema: SchemaRef,
+}
+
+impl ArrowBatches {
+    /// The schema of all batches.
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl Iterator for ArrowBatches {
+    type Item = RedisResult<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut rows = Vec::new();
+        for row in self.stream.by_ref().take(self.export.batch_rows) {
+            match row {
+                Ok(row) => rows.push(row.into_values()),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        if rows.is_empty() {
+            return None;
+        }
+        let rows: Vec<&[GraphValue]> = rows.iter().map(|row| &row[..]).collect();
+        Some(self.export.record_batch(&self.schema, &rows))
+    }
+}
+
+// The inferred type of a column
+#[derive(Clone, Debug, PartialEq)]
+enum ColumnType {
+    Null,
+    Int,
+    String,
+    Binary,
+    List(Box<ColumnType>),
+    Node,
+    Relation,
+}
+
+impl ColumnType {
+    fn of(v: &GraphValue) -> Self {
+        match v {
+            GraphValue::Scalar(v) => ColumnType::of_scalar(v),
+            GraphValue::Node(_) => ColumnType::Node,
+            GraphValue::Relation(_) => ColumnType::Relation,
+        }
+    }
+
+    fn of_scalar(v: &Value) -> Self {
+        match v {
+            Value::Nil => ColumnType::Null,
+            Value::Int(_) => ColumnType::Int,
+            Value::Bulk(values) => ColumnType::List(Box::new(
+                values
+                    .iter()
+                    .map(ColumnType::of_scalar)
+                    .fold(ColumnType::Null, ColumnType::merge),
+            )),
+            Value::Data(bytes) => match std::str::from_utf8(bytes) {
+                Ok(_) => ColumnType::String,
+                Err(_) => ColumnType::Binary,
+            },
+            Value::Status(_) | Value::Okay => ColumnType::String,
+        }
+    }
+
+    fn merge(self, other: ColumnType) -> Self {
+        match (self, other) {
+            (ColumnType::Null, other) | (other, ColumnType::Null) => other,
+            (ColumnType::List(a), ColumnType::List(b)) => ColumnType::List(Box::new(a.merge(*b))),
+            (a, b) if a == b => a,
+            _ => ColumnType::String,
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            ColumnType::Null | ColumnType::String => DataType::Utf8,
+            ColumnType::Int => DataType::Int64,
+            ColumnType::Binary => DataType::Binary,
+            ColumnType::List(item) => {
+                DataType::List(Arc::new(Field::new("item", item.data_type(), true)))
+            }
+            ColumnType::Node => node_type(),
+            ColumnType::Relation => relation_type(),
+        }
+    }
+}
+
+fn parse_double(s: &str) -> Option<f64> {
+    if s.chars().any(|c| c.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn properties_field() -> Field {
+    let entries = Field::new(
+        "entries",
+        DataType::Struct(Fields::from(vec![
+            Field::new("keys", DataType::Utf8, false),
+            Field::new("values", DataType::Utf8, true),
+        ])),
+        false,
+    );
+    Field::new("properties", DataType::Map(Arc::new(entries), false), false)
+}
+
+fn labels_field() -> Field {
+    Field::new(
+        "labels",
+        DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+        false,
+    )
+}
+
+fn node_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("id", DataType::UInt64, false),
+        labels_field(),
+        properties_field(),
+    ])
+}
+
+fn relation_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("id", DataType::UInt64, false),
+        Field::new("type", DataType::Utf8, false),
+        Field::new("src", DataType::UInt64, false),
+        Field::new("dest", DataType::UInt64, false),
+        properties_field(),
+    ])
+}
+
+fn node_type() -> DataType {
+    DataType::Struct(node_fields())
+}
+
+fn relation_type() -> DataType {
+    DataType::Struct(relation_fields())
+}
+
+fn arrow_error(e: ArrowError) -> redis::RedisError {
+    create_error(&format!("Arrow error: {}", e))
+}
+
+#[cfg(feature = "parquet")]
+fn parquet_error(e: parquet::errors::ParquetError) -> redis::RedisError {
+    create_error(&format!("Parquet error: {}", e))
+}
+
+fn nulls(valid: Vec<bool>) -> Option<NullBuffer> {
+    Some(NullBuffer::from(valid))
+}
+
+fn build_column(data_type: &DataType, values: &[Option<&GraphValue>]) -> Result<ArrayRef, String> {
+    if *data_type == node_type() {
+        let nodes: Vec<Option<&NodeValue>> = values
+            .iter()
+            .map(|v| match v {
+                Some(GraphValue::Node(node)) => Ok(Some(node)),
+                Some(GraphValue::Scalar(Value::Nil)) | None => Ok(None),
+                Some(v) => Err(format!("{} is not a node", render(v))),
+            })
+            .collect::<Result<_, _>>()?;
+        return build_nodes(&nodes);
+    }
+    if *data_type == relation_type() {
+        let relations: Vec<Option<&RelationValue>> = values
+            .iter()
+            .map(|v| match v {
+                Some(GraphValue::Relation(relation)) => Ok(Some(relation)),
+                Some(GraphValue::Scalar(Value::Nil)) | None => Ok(None),
+                Some(v) => Err(format!("{} is not a relation", render(v))),
+            })
+            .collect::<Result<_, _>>()?;
+        return build_relations(&relations);
+    }
+    if *data_type == DataType::Utf8 {
+        let strings: Vec<Option<String>> = values
+            .iter()
+            .map(|v| match v {
+                Some(GraphValue::Scalar(Value::Nil)) | None => None,
+                Some(v) => Some(render(v)),
+            })
+            .collect();
+        return Ok(Arc::new(StringArray::from(strings)));
+    }
+    let scalars: Vec<Option<&Value>> = values
+        .iter()
+        .map(|v| match v {
+            Some(GraphValue::Scalar(Value::Nil)) | None => Ok(None),
+            Some(GraphValue::Scalar(v)) => Ok(Some(v)),
+            Some(v) => Err(format!("{} is not a scalar", render(v))),
+        })
+        .collect::<Result<_, _>>()?;
+    build_scalars(data_type, &scalars)
+}
+
+fn build_scalars(data_type: &DataType, values: &[Option<&Value>]) -> Result<ArrayRef, String> {
+    let mismatch = |v: &Value| format!("{} does not match {}", render_scalar(v), data_type);
+    let convert = |f: &dyn Fn(&Value) -> Option<()>| {
+        values
+            .iter()
+            .flatten()
+            .try_for_each(|v| f(v).ok_or_else(|| mismatch(v)))
+    };
+    let text = |v: &Value| match v {
+        Value::Data(bytes) => std::str::from_utf8(bytes).ok().map(str::to_string),
+        _ => None,
+    };
+    let int = |v: &Value| match v {
+        Value::Int(v) => Some(*v),
+        v => text(v)?.parse().ok(),
+    };
+    let double = |v: &Value| match v {
+        Value::Int(v) => Some(*v as f64),
+        v => parse_double(&text(v)?),
+    };
+    let boolean = |v: &Value| match text(v).as_deref() {
+        Some("true") => Some(true),
+        Some("false") => Some(false),
+        _ => None,
+    };
+    Ok(match data_type {
+        DataType::Null => new_null_array(data_type, values.len()),
+        DataType::Int64 => {
+            convert(&|v| int(v).map(|_| ()))?;
+            Arc::new(Int64Array::from(
+                values.iter().map(|v| v.and_then(int)).collect::<Vec<_>>(),
+            ))
+        }
+        DataType::Float64 => {
+            convert(&|v| double(v).map(|_| ()))?;
+            Arc::new(Float64Array::from(
+                values
+                    .iter()
+                    .map(|v| v.and_then(double))
+                    .collect::<Vec<_>>(),
+            ))
+        }
+        DataType::Boolean => {
+            convert(&|v| boolean(v).map(|_| ()))?;
+            Arc::new(BooleanArray::from(
+                values
+                    .iter()
+                    .map(|v| v.and_then(boolean))
+                    .collect::<Vec<_>>(),
+            ))
+        }
+        DataType::Utf8 => Arc::new(StringArray::from(
+            values
+                .iter()
+                .map(|v| v.map(render_scalar))
+                .collect::<Vec<_>>(),
+        )),
+        DataType::Binary => {
+            let bytes: Vec<Option<Vec<u8>>> = values
+                .iter()
+                .map(|v| {
+                    v.map(|v| match v {
+                        Value::Data(bytes) => bytes.clone(),
+                        v => render_scalar(v).into_bytes(),
+                    })
+                })
+                .collect();
+            Arc::new(BinaryArray::from(
+                bytes.iter().map(|b| b.as_deref()).collect::<Vec<_>>(),
+            ))
+        }
+        DataType::List(item) => {
+            let mut lengths = Vec::with_capacity(values.len());
+            let mut items = Vec::new();
+            for v in values {
+                match v {
+                    Some(Value::Bulk(values)) => {
+                        lengths.push(values.len());
+                        items.extend(values.iter().map(Some));
+                    }
+                    Some(v) => return Err(mismatch(v)),
+                    None => lengths.push(0),
+                }
+            }
+            let items = build_scalars(item.data_type(), &items)?;
+            Arc::new(
+                ListArray::try_new(
+                    item.clone(),
+                    OffsetBuffer::from_lengths(lengths),
+                    items,
+                    nulls(values.iter().map(Option::is_some).collect()),
+                )
+                .map_err(|e| e.to_string())?,
+            )
+        }
+        data_type => return Err(format!("Unsupported type {}", data_type)),
+    })
+}
+
+fn build_labels(labels: &[&[String]]) -> Result<ArrayRef, String> {
+    let field: FieldRef = Arc::new(Field::new("item", DataType::Utf8, true));
+    let items: StringArray = labels.iter().flat_map(|l| l.iter().map(Some)).collect();
+    Ok(Arc::new(
+        ListArray::try_new(
+            field,
+            OffsetBuffer::from_lengths(labels.iter().map(|l| l.len())),
+            Arc::new(items),
+            None,
+        )
+        .map_err(|e| e.to_string())?,
+    ))
+}
+
+fn build_properties(properties: &[Option<&HashMap<String, Value>>]) -> Result<ArrayRef, String> {
+    let mut lengths = Vec::with_capacity(properties.len());
+    let mut keys = Vec::new();
+    let mut values = Vec::new();
+    for map in properties {
+        let mut entries: Vec<(&String, &Value)> =
+            map.map(|m| m.iter().collect()).unwrap_or_default();
+        entries.sort_by_key(|(k, _)| *k);
+        lengths.push(entries.len());
+        for (k, v) in entries {
+            keys.push(k.as_str());
+            values.push(match v {
+                Value::Nil => None,
+                v => Some(render_scalar(v)),
+            });
+        }
+    }
+    let entries_field = match properties_field().data_type() {
+        DataType::Map(entries, _) => entries.clone(),
+        _ => unreachable!(),
+    };
+    let entry_fields = match entries_field.data_type() {
+        DataType::Struct(fields) => fields.clone(),
+        _ => unreachable!(),
+    };
+    let entries = StructArray::try_new(
+        entry_fields,
+        vec![
+            Arc::new(StringArray::from(keys)),
+            Arc::new(StringArray::from(values)),
+        ],
+        None,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Arc::new(
+        MapArray::try_new(
+            entries_field,
+            OffsetBuffer::from_lengths(lengths),
+            entries,
+            None,
+            false,
+        )
+        .map_err(|e| e.to_string())?,
+    ))
+}
+
+fn build_nodes(nodes: &[Option<&NodeValue>]) -> Result<ArrayRef, String> {
+    let no_labels: &[String] = &[];
+    let labels: Vec<&[String]> = nodes
+        .iter()
+        .map(|n| n.map_or(no_labels, |n| &n.labels[..]))
+        .collect();
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from(
+            nodes
+                .iter()
+                .map(|n| n.map_or(0, |n| n.id))
+                .collect::<Vec<_>>(),
+        )),
+        build_labels(&labels)?,
+        build_properties(
+            &nodes
+                .iter()
+                .map(|n| n.map(|n| &n.properties))
+                .collect::<Vec<_>>(),
+        )?,
+    ];
+    let valid = nulls(nodes.iter().map(Option::is_some).collect());
+    Ok(Arc::new(
+        StructArray::try_new(node_fields(), columns, valid).map_err(|e| e.to_string())?,
+    ))
+}
+
+fn build_relations(relations: &[Option<&RelationValue>]) -> Result<ArrayRef, String> {
+    let ids = |f: &dyn Fn(&RelationValue) -> u64| -> ArrayRef {
+        Arc::new(UInt64Array::from(
+            relations.iter().map(|r| r.map_or(0, f)).collect::<Vec<_>>(),
+        ))
+    };
+    let columns: Vec<ArrayRef> = vec![
+        ids(&|r| r.id),
+        Arc::new(StringArray::from(
+            relations
+                .iter()
+                .map(|r| r.map_or("", |r| r.rel_type.as_str()))
+                .collect::<Vec<_>>(),
+        )),
+        ids(&|r| r.src_node),
+        ids(&|r| r.dest_node),
+        build_properties(
+            &relations
+                .iter()
+                .map(|r| r.map(|r| &r.properties))
+                .collect::<Vec<_>>(),
+        )?,
+    ];
+    let valid = nulls(relations.iter().map(Option::is_some).collect());
+    Ok(Arc::new(
+        StructArray::try_new(relation_fields(), columns, valid).map_err(|e| e.to_string())?,
+    ))
+}
+
+// Renders values of columns widened to Utf8 in Cypher notation
+fn render(v: &GraphValue) -> String {
+    match v {
+        GraphValue::Scalar(v) => render_scalar(v),
+        GraphValue::Node(node) => format!(
+            "({}{} {})",
+            node.id,
+            node.labels
+                .iter()
+                .map(|l| format!(":{}", l))
+                .collect::<String>(),
+            render_properties(&node.properties)
+        ),
+        GraphValue::Relation(r) => format!(
+            "({})-[{}:{} {}]->({})",
+            r.src_node,
+            r.id,
+            r.rel_type,
+            render_properties(&r.properties),
+            r.dest_node
+        ),
+    }
+}
+
+fn render_scalar(v: &Value) -> String {
+    match v {
+        Value::Nil => "null".to_string(),
+        Value::Int(v) => v.to_string(),
+        Value::Data(bytes) => String::from_utf8_lossy(bytes).to_string(),
+        Value::Status(s) => s.clone(),
+        Value::Okay => "OK".to_string(),
+        Value::Bulk(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(render_item)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+// Renders a list item or property value with strings in quotes
+fn render_item(v: &Value) -> String {
+    match v {
+        Value::Data(_) | Value::Status(_) => format!("'{}'", render_scalar(v)),
+        v => render_scalar(v),
+    }
+}
+
+fn render_properties(properties: &HashMap<String, Value>) -> String {
+    let mut entries: Vec<(&String, &Value)> = properties.iter().collect();
+    entries.sort_by_key(|(k, _)| *k);
+    let entries: Vec<String> = entries
+        .iter()
+        .map(|(k, v)| format!("{}: {}", k, render_item(v)))
+        .collect();
+    format!("{{{}}}", entries.join(", "))
+}
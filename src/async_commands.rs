@@ -1,6 +1,31 @@
+#[cfg(feature = "serde")]
+use crate::apoc::{ApocImport, ApocReport};
+use crate::bulk::{BulkRow, BulkWrite, BulkWriteResult};
+use crate::bulk_loader::{BulkEntity, BulkLoadResult, BulkLoader, BulkNode, BulkRelation};
+use crate::cypher::is_read_only_query;
+use crate::dump::{fresh_key_error, DumpResult, GraphDump, GraphRestore, RestoreProgress};
+use crate::fulltext::fulltext_cypher;
+use crate::migrations::*;
+use crate::pagination::Pagination;
+use crate::params::ParamValue;
+#[cfg(feature = "petgraph")]
+use crate::petgraph_support::{created_ids, PetgraphPlan, PetgraphRead, PetgraphWrite};
+#[cfg(feature = "petgraph")]
+use crate::petgraph_support::{PetgraphEdge, PetgraphNode, TypedGraph};
+use crate::procedures::*;
+use crate::row::FromGraphRow;
+use crate::schema::{GraphSchema, SchemaFetch};
+use crate::schema_diff::SchemaDiff;
+use crate::stream::GraphResultStream;
+use crate::subgraph::{Subgraph, SubgraphFetch};
 use crate::types::*;
+use futures_util::io::AsyncBufRead;
+use futures_util::stream::BoxStream;
 use redis::aio::ConnectionLike;
-use redis::{cmd, FromRedisValue, RedisFuture, ToRedisArgs};
+use redis::{cmd, FromRedisValue, RedisFuture, RedisResult, ToRedisArgs};
+#[cfg(feature = "serde")]
+use std::io::BufRead;
+use std::io::Write;
 
 /// Provides a high level asynchronous API to work with Redis graph data types.
 /// The graph command becomes directly available on ConnectionLike types from
@@ -54,6 +79,643 @@ pub trait AsyncGraphCommands: ConnectionLike + Send + Sized {
         })
     }
 
+    /// Executes a query converting every result row into T by column position.
+    fn graph_query_as<
+        'a,
+        K: ToRedisArgs + Send + Sync + 'a,
+        Q: ToRedisArgs + Send + Sync + 'a,
+        T: FromGraphRow + Send + 'a,
+    >(
+        &'a mut self,
+        key: K,
+        query: Q,
+    ) -> RedisFuture<'a, Vec<T>> {
+        Box::pin(async move { self.graph_query(key, query).await?.rows_as() })
+    }
+
+    /// Executes a read-only query converting every result row into T by
+    /// column position.
+    fn graph_ro_query_as<
+        'a,
+        K: ToRedisArgs + Send + Sync + 'a,
+        Q: ToRedisArgs + Send + Sync + 'a,
+        T: FromGraphRow + Send + 'a,
+    >(
+        &'a mut self,
+        key: K,
+        query: Q,
+    ) -> RedisFuture<'a, Vec<T>> {
+        Box::pin(async move { self.graph_ro_query(key, query).await?.rows_as() })
+    }
+
+    /// Writes all nodes and edges of a petgraph graph (Graph or StableGraph
+    /// with NodeValue and RelationValue or TypedNode and TypedRelation
+    /// weights) into the graph at key. Nodes are created in batches of
+    /// batch_size, then relations between the created nodes. Node and
+    /// relation ids of the weights are ignored.
+    #[cfg(feature = "petgraph")]
+    fn graph_write_petgraph<'a, K, G>(
+        &'a mut self,
+        key: K,
+        graph: G,
+        batch_size: usize,
+    ) -> RedisFuture<'a, PetgraphWrite<G::NodeId>>
+    where
+        K: ToRedisArgs + Send + Sync + 'a,
+        G: petgraph::visit::IntoNodeReferences + petgraph::visit::IntoEdgeReferences,
+        G::NodeWeight: PetgraphNode,
+        G::EdgeWeight: PetgraphEdge,
+        G::NodeId: std::hash::Hash + Eq + Send + 'a,
+    {
+        let plan = PetgraphPlan::new(graph, batch_size);
+        Box::pin(async move {
+            let plan = plan?;
+            let mut node_ids = std::collections::HashMap::new();
+            for (batch, query) in plan.node_queries() {
+                let res = self.graph_query(&key, query).await?;
+                created_ids(&batch, &res, &mut node_ids)?;
+            }
+            for query in plan.edge_queries(&node_ids)? {
+                self.graph_query(&key, query).await?;
+            }
+            Ok(PetgraphWrite {
+                nodes_created: node_ids.len(),
+                relations_created: plan.edge_count(),
+                node_ids,
+            })
+        })
+    }
+
+    /// Reads all nodes and relations of the graph at key page by page into a
+    /// petgraph Graph. Unlike converted query results the weights keep the
+    /// property types, which are read with typeOf.
+    #[cfg(feature = "petgraph")]
+    fn graph_read_petgraph<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        page_size: usize,
+    ) -> RedisFuture<'a, TypedGraph> {
+        Box::pin(async move {
+            let mut read = PetgraphRead::new(page_size);
+            while let Some(query) = read.next_query() {
+                let value = self
+                    .req_packed_command(cmd("GRAPH.RO_QUERY").arg(&key).arg(query))
+                    .await?;
+                read.add_response(&value)?;
+            }
+            Ok(read.finish())
+        })
+    }
+
+    /// Writes rows in batches with `UNWIND $rows AS row` and the query of
+    /// bulk, returning the summed up statistics of all batches. Batches
+    /// written before a failing batch are not rolled back, use
+    /// graph_bulk_write_progress to know where to resume.
+    fn graph_bulk_write<'a, K, I>(
+        &'a mut self,
+        key: K,
+        bulk: &'a BulkWrite,
+        rows: I,
+    ) -> RedisFuture<'a, BulkWriteResult>
+    where
+        K: ToRedisArgs + Send + Sync + 'a,
+        I: IntoIterator,
+        I::IntoIter: Send + 'a,
+        I::Item: BulkRow,
+    {
+        self.graph_bulk_write_progress(key, bulk, rows, |_| {})
+    }
+
+    /// Like graph_bulk_write but calls progress after every written batch.
+    /// Batches are not atomic as a whole: on an error the last reported
+    /// result holds the rows already written, and skipping that many rows
+    /// of the input resumes the write.
+    fn graph_bulk_write_progress<'a, K, I, F>(
+        &'a mut self,
+        key: K,
+        bulk: &'a BulkWrite,
+        rows: I,
+        mut progress: F,
+    ) -> RedisFuture<'a, BulkWriteResult>
+    where
+        K: ToRedisArgs + Send + Sync + 'a,
+        I: IntoIterator,
+        I::IntoIter: Send + 'a,
+        I::Item: BulkRow,
+        F: FnMut(&BulkWriteResult) + Send + 'a,
+    {
+        let rows = rows.into_iter();
+        Box::pin(async move {
+            let mut result = BulkWriteResult::default();
+            for batch in bulk.batches(rows) {
+                let (count, query) = batch?;
+                let res = self.graph_query(&key, query).await?;
+                result.add(count, &res);
+                progress(&result);
+            }
+            Ok(result)
+        })
+    }
+
+    /// Creates a new graph at key from nodes and relations with the binary
+    /// GRAPH.BULK protocol, which is much faster than Cypher queries. All
+    /// nodes are sent before the relations and the graph must not exist yet.
+    fn graph_bulk_load<'a, K, N, R>(
+        &'a mut self,
+        key: K,
+        loader: &'a BulkLoader,
+        nodes: N,
+        relations: R,
+    ) -> RedisFuture<'a, BulkLoadResult>
+    where
+        K: ToRedisArgs + Send + Sync + 'a,
+        N: IntoIterator,
+        N::IntoIter: Send + 'a,
+        N::Item: BulkEntity<BulkNode> + Send,
+        R: IntoIterator,
+        R::IntoIter: Send + 'a,
+        R::Item: BulkEntity<BulkRelation> + Send,
+    {
+        let nodes = nodes.into_iter();
+        let relations = relations.into_iter();
+        Box::pin(async move {
+            let mut encoder = loader.encoder();
+            let mut result = BulkLoadResult::default();
+            for node in nodes {
+                if let Some(command) = encoder.add_node(node.into_entity()?)? {
+                    result.add(&self.req_packed_command(&command.to_cmd(&key)).await?)?;
+                }
+            }
+            for relation in relations {
+                if let Some(command) = encoder.add_relation(relation.into_entity()?)? {
+                    result.add(&self.req_packed_command(&command.to_cmd(&key)).await?)?;
+                }
+            }
+            if let Some(command) = encoder.finish() {
+                result.add(&self.req_packed_command(&command.to_cmd(&key)).await?)?;
+            }
+            Ok(result)
+        })
+    }
+
+    /// Writes a logical dump of the graph with its indexes and constraints
+    /// to out, reading the nodes and relations page by page with read-only
+    /// queries.
+    fn graph_dump<'a, K, W>(
+        &'a mut self,
+        key: K,
+        dump: &'a GraphDump,
+        out: W,
+    ) -> RedisFuture<'a, DumpResult>
+    where
+        K: ToRedisArgs + Send + Sync + 'a,
+        W: Write + Send + 'a,
+    {
+        Box::pin(async move {
+            let mut fetch = dump.fetch(out);
+            while let Some(query) = fetch.next_query() {
+                let value = self
+                    .req_packed_command(cmd("GRAPH.RO_QUERY").arg(&key).arg(query))
+                    .await?;
+                fetch.add_response(&value)?;
+            }
+            Ok(fetch.finish())
+        })
+    }
+
+    /// Replays a dump into the graph at key, which must not exist yet.
+    /// progress is called after every executed statement. Statements
+    /// executed before a failing one are not rolled back. A script is read
+    /// line by line, a JSON archive is read completely before the restore.
+    fn graph_restore<'a, K, R, F>(
+        &'a mut self,
+        key: K,
+        restore: &'a GraphRestore,
+        input: R,
+        mut progress: F,
+    ) -> RedisFuture<'a, RestoreProgress>
+    where
+        K: ToRedisArgs + Send + Sync + 'a,
+        R: AsyncBufRead + Unpin + Send + 'a,
+        F: FnMut(&RestoreProgress) + Send + 'a,
+    {
+        Box::pin(async move {
+            let exists = self.req_packed_command(cmd("EXISTS").arg(&key)).await?;
+            if bool::from_redis_value(&exists)? {
+                return Err(fresh_key_error());
+            }
+            let mut current = RestoreProgress::default();
+            let mut statements = restore.async_statements(input).await?;
+            while let Some(statement) = statements.next().await {
+                let statement = statement?;
+                let value = self.req_packed_command(&statement.to_cmd(&key)).await?;
+                current.add(&statement, &value)?;
+                progress(&current);
+            }
+            Ok(current)
+        })
+    }
+
+    /// Imports the JSON lines of a Neo4j APOC export into the graph at key
+    /// with batched queries. Values and records that can't be imported are
+    /// listed in the report.
+    #[cfg(feature = "serde")]
+    fn graph_import_apoc<'a, K, R>(
+        &'a mut self,
+        key: K,
+        import: &'a ApocImport,
+        input: R,
+    ) -> RedisFuture<'a, ApocReport>
+    where
+        K: ToRedisArgs + Send + Sync + 'a,
+        R: BufRead + Send + 'a,
+    {
+        Box::pin(async move {
+            let mut reader = import.reader(input);
+            while let Some(query) = reader.next_query()? {
+                let value = self
+                    .req_packed_command(cmd("GRAPH.QUERY").arg(&key).arg(query))
+                    .await?;
+                reader.add_response(&value)?;
+            }
+            Ok(reader.finish())
+        })
+    }
+
+    /// Executes a query returning a stream that decodes result rows on demand.
+    fn graph_query_stream<
+        'a,
+        K: ToRedisArgs + Send + Sync + 'a,
+        Q: ToRedisArgs + Send + Sync + 'a,
+    >(
+        &'a mut self,
+        key: K,
+        query: Q,
+    ) -> RedisFuture<'a, GraphResultStream> {
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.QUERY").arg(key).arg(query))
+                .await?;
+            GraphResultStream::from_value(value)
+        })
+    }
+
+    /// Executes a read-only query returning a stream that decodes result rows
+    /// on demand.
+    fn graph_ro_query_stream<
+        'a,
+        K: ToRedisArgs + Send + Sync + 'a,
+        Q: ToRedisArgs + Send + Sync + 'a,
+    >(
+        &'a mut self,
+        key: K,
+        query: Q,
+    ) -> RedisFuture<'a, GraphResultStream> {
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            GraphResultStream::from_value(value)
+        })
+    }
+
+    /// Executes a query page by page, returning a stream over all rows.
+    fn graph_paginate(
+        &mut self,
+        pagination: Pagination,
+    ) -> BoxStream<'_, RedisResult<GraphResult>> {
+        pagination.stream(self)
+    }
+
+    /// Executes the query with GRAPH.RO_QUERY if it does not contain any write
+    /// clauses and with GRAPH.QUERY otherwise.
+    fn graph_auto_query<'a, K: ToRedisArgs + Send + Sync + 'a, Q: AsRef<str> + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        query: Q,
+    ) -> RedisFuture<'a, GraphResultSet> {
+        Box::pin(async move {
+            let command = if is_read_only_query(query.as_ref()) {
+                "GRAPH.RO_QUERY"
+            } else {
+                "GRAPH.QUERY"
+            };
+            cmd(command)
+                .arg(key)
+                .arg(query.as_ref())
+                .query_async(self)
+                .await
+        })
+    }
+
+    /// Runs the algo.BFS procedure.
+    fn graph_algo_bfs<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        config: &BfsConfig,
+    ) -> RedisFuture<'a, BfsResult> {
+        let query = config.to_query();
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            parse_bfs(&value)
+        })
+    }
+
+    /// Runs the algo.SPpaths procedure, the config requires a target.
+    fn graph_algo_sp_paths<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        config: &PathConfig,
+    ) -> RedisFuture<'a, Vec<AlgoPath>> {
+        let query = config.sp_paths_query();
+        Box::pin(async move {
+            let query = query?;
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            parse_paths(&value)
+        })
+    }
+
+    /// Runs the algo.SSpaths procedure.
+    fn graph_algo_ss_paths<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        config: &PathConfig,
+    ) -> RedisFuture<'a, Vec<AlgoPath>> {
+        let query = config.ss_paths_query();
+        Box::pin(async move {
+            let query = query?;
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            parse_paths(&value)
+        })
+    }
+
+    /// Runs the algo.pageRank procedure returning nodes with their rank.
+    fn graph_algo_page_rank<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        config: &PageRankConfig,
+    ) -> RedisFuture<'a, Vec<(NodeValue, f64)>> {
+        let query = config.to_query();
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            parse_node_scores(&value)
+        })
+    }
+
+    /// Runs the algo.WCC procedure returning nodes with their component id.
+    fn graph_algo_wcc<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        config: &WccConfig,
+    ) -> RedisFuture<'a, Vec<(NodeValue, u64)>> {
+        let query = config.to_query();
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            parse_node_groups(&value)
+        })
+    }
+
+    /// Runs the algo.betweenness procedure returning nodes with their
+    /// centrality.
+    fn graph_algo_betweenness<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        config: &BetweennessConfig,
+    ) -> RedisFuture<'a, Vec<(NodeValue, f64)>> {
+        let query = config.to_query();
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            parse_node_scores(&value)
+        })
+    }
+
+    /// Runs the algo.labelPropagation procedure returning nodes with their
+    /// community id.
+    fn graph_algo_label_propagation<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        config: &LabelPropagationConfig,
+    ) -> RedisFuture<'a, Vec<(NodeValue, u64)>> {
+        let query = config.to_query();
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            parse_node_groups(&value)
+        })
+    }
+
+    /// Searches the full-text index of label with a RediSearch query (a
+    /// string or FulltextQuery) returning the matching nodes with their score.
+    fn graph_fulltext_query<'a, K: ToRedisArgs + Send + Sync + 'a, Q: std::fmt::Display>(
+        &'a mut self,
+        key: K,
+        label: &str,
+        query: Q,
+    ) -> RedisFuture<'a, Vec<(NodeValue, f64)>> {
+        let query = fulltext_cypher(label, &query.to_string(), None);
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            parse_node_scores(&value)
+        })
+    }
+
+    /// Like graph_fulltext_query but returns limit results starting at offset
+    /// ordered by descending score.
+    fn graph_fulltext_query_page<'a, K: ToRedisArgs + Send + Sync + 'a, Q: std::fmt::Display>(
+        &'a mut self,
+        key: K,
+        label: &str,
+        query: Q,
+        offset: usize,
+        limit: usize,
+    ) -> RedisFuture<'a, Vec<(NodeValue, f64)>> {
+        let query = fulltext_cypher(label, &query.to_string(), Some((offset, limit)));
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))
+                .await?;
+            parse_node_scores(&value)
+        })
+    }
+
+    /// Calls a procedure with the arguments passed as query parameters. Rows
+    /// are keyed by the given yields, or by all outputs of the procedure if
+    /// yields is empty. Read-only procedures are called with GRAPH.RO_QUERY,
+    /// the mode of procedures that are not built-in is looked up with
+    /// graph_procedures first.
+    fn graph_call<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        procedure: &str,
+        args: &[ParamValue],
+        yields: &[&str],
+    ) -> RedisFuture<'a, Vec<ProcedureRow>> {
+        let query = call_query(procedure, args, yields);
+        let procedure = procedure.to_string();
+        Box::pin(async move {
+            let query = query?;
+            let mode = match builtin_mode(&procedure) {
+                Some(mode) => mode,
+                None => listed_mode(&self.graph_procedures(&key).await?, &procedure),
+            };
+            let value = self
+                .req_packed_command(cmd(call_command(mode)).arg(key).arg(query))
+                .await?;
+            parse_procedure_rows(&value)
+        })
+    }
+
+    /// Lists the procedures available on the graph with their mode.
+    fn graph_procedures<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+    ) -> RedisFuture<'a, Vec<ProcedureInfo>> {
+        let query = call_query("dbms.procedures", &[], &[]);
+        Box::pin(async move {
+            let value = self
+                .req_packed_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query?))
+                .await?;
+            parse_procedures(&value)
+        })
+    }
+
+    /// Fetches all nodes and relations of the graph into a Subgraph, e.g. to
+    /// export the whole graph with GraphExport. Nodes and relations are read
+    /// in pages of 1000 ordered by id, relations per 1000 source nodes so that
+    /// a page only scans the relations of those nodes.
+    fn graph_subgraph<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+    ) -> RedisFuture<'a, Subgraph> {
+        Box::pin(async move {
+            let mut fetch = SubgraphFetch::new(1000);
+            while let Some(query) = fetch.next_query() {
+                let value = self
+                    .req_packed_command(cmd("GRAPH.RO_QUERY").arg(&key).arg(query))
+                    .await?;
+                fetch.add_response(&value)?;
+            }
+            Ok(fetch.finish())
+        })
+    }
+
+    /// Fetches the schema of the graph, property types are sampled from up to
+    /// sample_size nodes per label and relations per type.
+    fn graph_schema<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        sample_size: usize,
+    ) -> RedisFuture<'a, GraphSchema> {
+        Box::pin(async move {
+            let mut fetch = SchemaFetch::new(sample_size);
+            while let Some(query) = fetch.next_query() {
+                let value = self
+                    .req_packed_command(cmd("GRAPH.RO_QUERY").arg(&key).arg(query))
+                    .await?;
+                fetch.add_response(&value)?;
+            }
+            Ok(fetch.finish())
+        })
+    }
+
+    /// Compares the schema of the graph against an expected schema, e.g. one
+    /// declared in Rust, read from a file or fetched from another graph.
+    fn graph_schema_diff<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        expected: &GraphSchema,
+        sample_size: usize,
+    ) -> RedisFuture<'a, SchemaDiff> {
+        let expected = expected.clone();
+        Box::pin(async move {
+            let actual = self.graph_schema(key, sample_size).await?;
+            Ok(SchemaDiff::compare(&expected, &actual))
+        })
+    }
+
+    /// Reads the migrations recorded as applied to the graph.
+    fn graph_migrations_applied<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        migrator: &'a Migrator,
+    ) -> RedisFuture<'a, Vec<AppliedMigration>> {
+        Box::pin(async move {
+            if let Some(exists) = migrator.store().exists_cmd(&key) {
+                if !exists.query_async::<_, bool>(self).await? {
+                    return Ok(Vec::new());
+                }
+            }
+            let res = self
+                .req_packed_command(&migrator.store().read_cmd(key))
+                .await?;
+            migrator.store().parse_applied(&res)
+        })
+    }
+
+    /// Applies all pending migrations in version order after verifying the
+    /// checksums of the applied ones. With dry_run only the plan is returned.
+    fn graph_migrate<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        migrator: &'a Migrator,
+        dry_run: bool,
+    ) -> RedisFuture<'a, MigrationPlan> {
+        Box::pin(async move {
+            let applied = self.graph_migrations_applied(&key, migrator).await?;
+            let plan = migrator.plan_migrate(&applied)?;
+            if !dry_run {
+                for step in plan.steps.iter() {
+                    for statement in step.statements.iter() {
+                        self.req_packed_command(&statement.to_cmd(&key)).await?;
+                    }
+                    self.req_packed_command(&migrator.store().record_cmd(&key, step))
+                        .await?;
+                }
+            }
+            Ok(plan)
+        })
+    }
+
+    /// Rolls back all applied migrations newer than target, newest first.
+    /// With dry_run only the plan is returned.
+    fn graph_rollback<'a, K: ToRedisArgs + Send + Sync + 'a>(
+        &'a mut self,
+        key: K,
+        migrator: &'a Migrator,
+        target: u64,
+        dry_run: bool,
+    ) -> RedisFuture<'a, MigrationPlan> {
+        Box::pin(async move {
+            let applied = self.graph_migrations_applied(&key, migrator).await?;
+            let plan = migrator.plan_rollback(&applied, target)?;
+            if !dry_run {
+                for step in plan.steps.iter() {
+                    for statement in step.statements.iter() {
+                        self.req_packed_command(&statement.to_cmd(&key)).await?;
+                    }
+                    self.req_packed_command(&migrator.store().record_cmd(&key, step))
+                        .await?;
+                }
+            }
+            Ok(plan)
+        })
+    }
+
     fn graph_profile<
         'a,
         K: ToRedisArgs + Send + Sync + 'a,
@@ -99,6 +761,10 @@ pub trait AsyncGraphCommands: ConnectionLike + Send + Sized {
         })
     }
 
+    fn graph_list(&mut self) -> RedisFuture<'_, Vec<String>> {
+        Box::pin(async move { cmd("GRAPH.LIST").query_async(self).await })
+    }
+
     fn graph_slowlog<'a, K: ToRedisArgs + Send + Sync + 'a>(
         &'a mut self,
         key: K,
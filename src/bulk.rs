@@ -0,0 +1,206 @@
+use crate::params::{escape_identifier, ParamValue};
+use crate::types::*;
+use redis::RedisResult;
+use std::collections::{BTreeMap, HashMap};
+
+/// A row for a bulk write. Rows are passed to the query as elements of the
+/// `$rows` list parameter. Implemented for ParamValue, property maps and with
+/// the `serde` feature for references to any serializable type.
+pub trait BulkRow {
+    fn into_row(self) -> RedisResult<ParamValue>;
+}
+
+impl BulkRow for ParamValue {
+    fn into_row(self) -> RedisResult<ParamValue> {
+        Ok(self)
+    }
+}
+
+impl BulkRow for RedisResult<ParamValue> {
+    fn into_row(self) -> RedisResult<ParamValue> {
+        self
+    }
+}
+
+impl<T: Into<ParamValue>> BulkRow for HashMap<String, T> {
+    fn into_row(self) -> RedisResult<ParamValue> {
+        Ok(self.into())
+    }
+}
+
+impl<T: Into<ParamValue>> BulkRow for BTreeMap<String, T> {
+    fn into_row(self) -> RedisResult<ParamValue> {
+        Ok(ParamValue::Map(
+            self.into_iter().map(|(k, v)| (k, v.into())).collect(),
+        ))
+    }
+}
+
+impl BulkRow for Vec<(String, ParamValue)> {
+    fn into_row(self) -> RedisResult<ParamValue> {
+        Ok(ParamValue::Map(self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> BulkRow for &T {
+    fn into_row(self) -> RedisResult<ParamValue> {
+        ParamValue::from_serialize(self)
+    }
+}
+
+/// A batched write of many rows with `UNWIND $rows AS row` followed by a
+/// query that uses `row`. Rows are sent in batches of at most batch_rows rows
+/// and batch_bytes bytes of encoded parameters. A single row larger than
+/// batch_bytes is sent in a batch of its own.
+///
+/// ```rust
+/// use redis_graph::BulkWrite;
+///
+/// let riders = BulkWrite::create_nodes("Rider").with_batch_rows(5000);
+/// assert_eq!(riders.query(), "UNWIND $rows AS row CREATE (n:Rider) SET n = row");
+///
+/// let teams = BulkWrite::merge_nodes("Team", &["name"]);
+/// assert_eq!(
+///     teams.query(),
+///     "UNWIND $rows AS row MERGE (n:Team {name: row.name}) SET n += row"
+/// );
+///
+/// let rides = BulkWrite::new(
+///     "MATCH (r:Rider {name: row.rider}), (t:Team {name: row.team}) CREATE (r)-[:rides]->(t)",
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct BulkWrite {
+    query: String,
+    batch_rows: usize,
+    batch_bytes: usize,
+}
+
+impl BulkWrite {
+    /// A bulk write executing query for every row.
+    pub fn new<Q: Into<String>>(query: Q) -> Self {
+        BulkWrite {
+            query: format!("UNWIND $rows AS row {}", query.into()),
+            batch_rows: 1000,
+            batch_bytes: 1024 * 1024,
+        }
+    }
+
+    /// Creates a node with the given label for every row, using the row map
+    /// as properties.
+    pub fn create_nodes(label: &str) -> Self {
+        BulkWrite::new(format!(
+            "CREATE (n:{}) SET n = row",
+            escape_identifier(label)
+        ))
+    }
+
+    /// Merges a node with the given label on the key properties for every
+    /// row and sets all other properties of the row map.
+    pub fn merge_nodes(label: &str, keys: &[&str]) -> Self {
+        let keys: Vec<String> = keys
+            .iter()
+            .map(|k| format!("{0}: row.{0}", escape_identifier(k)))
+            .collect();
+        BulkWrite::new(format!(
+            "MERGE (n:{} {{{}}}) SET n += row",
+            escape_identifier(label),
+            keys.join(", ")
+        ))
+    }
+
+    /// Sets the maximum number of rows per query (at least 1).
+    pub fn with_batch_rows(mut self, rows: usize) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+
+    /// Sets the maximum size of the encoded rows parameter per query.
+    pub fn with_batch_bytes(mut self, bytes: usize) -> Self {
+        self.batch_bytes = bytes.max(1);
+        self
+    }
+
+    /// The query executed for every batch, without the rows parameter.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub(crate) fn batches<I: Iterator>(&self, rows: I) -> BulkBatches<'_, I> {
+        BulkBatches {
+            bulk: self,
+            rows,
+            next: None,
+        }
+    }
+}
+
+/// The result of a bulk write with the summed up statistics of all queries.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct BulkWriteResult {
+    /// Number of executed queries.
+    pub batches: usize,
+    /// Number of written rows.
+    pub rows: usize,
+    pub statistics: QueryStatistics,
+}
+
+impl BulkWriteResult {
+    pub(crate) fn add(&mut self, rows: usize, res: &GraphResultSet) {
+        self.batches += 1;
+        self.rows += rows;
+        self.statistics.add(&res.statistics());
+    }
+}
+
+// Lazily encodes rows into queries of `(row count, query)`.
+pub(crate) struct BulkBatches<'a, I> {
+    bulk: &'a BulkWrite,
+    rows: I,
+    next: Option<String>,
+}
+
+impl<'a, I> Iterator for BulkBatches<'a, I>
+where
+    I: Iterator,
+    I::Item: BulkRow,
+{
+    type Item = RedisResult<(usize, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut encoded = String::from("CYPHER rows=[");
+        let start = encoded.len();
+        let mut count = 0;
+        loop {
+            let row = match self.next.take() {
+                Some(row) => row,
+                None => match self.rows.next() {
+                    Some(row) => match row.into_row().and_then(|row| row.to_cypher()) {
+                        Ok(row) => row,
+                        Err(e) => return Some(Err(e)),
+                    },
+                    None => break,
+                },
+            };
+            if count > 0 && encoded.len() - start + row.len() + 2 > self.bulk.batch_bytes {
+                self.next = Some(row);
+                break;
+            }
+            if count > 0 {
+                encoded.push_str(", ");
+            }
+            encoded.push_str(&row);
+            count += 1;
+            if count >= self.bulk.batch_rows {
+                break;
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        encoded.push_str("] ");
+        encoded.push_str(&self.bulk.query);
+        Some(Ok((count, encoded)))
+    }
+}
@@ -0,0 +1,507 @@
+use crate::params::ParamValue;
+use crate::types::*;
+use redis::{cmd, from_redis_value, Cmd, RedisResult, ToRedisArgs, Value};
+use std::collections::HashMap;
+
+// Property type tags of the GRAPH.BULK binary encoding
+const BI_NULL: u8 = 0;
+const BI_BOOL: u8 = 1;
+const BI_DOUBLE: u8 = 2;
+const BI_STRING: u8 = 3;
+const BI_LONG: u8 = 4;
+const BI_ARRAY: u8 = 5;
+
+/// A node for GRAPH.BULK. The id is only used to resolve the source and
+/// destination of relations and must be unique within one load.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct BulkNode {
+    pub id: String,
+    pub labels: Vec<String>,
+    pub properties: Vec<(String, ParamValue)>,
+}
+
+impl BulkNode {
+    pub fn new<I: Into<String>, L: Into<String>>(id: I, label: L) -> Self {
+        BulkNode {
+            id: id.into(),
+            labels: vec![label.into()],
+            properties: vec![],
+        }
+    }
+
+    pub fn with_label<L: Into<String>>(mut self, label: L) -> Self {
+        self.labels.push(label.into());
+        self
+    }
+
+    pub fn with_property<N: Into<String>, V: Into<ParamValue>>(
+        mut self,
+        name: N,
+        value: V,
+    ) -> Self {
+        self.properties.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// A relation for GRAPH.BULK between the nodes with the src and dest ids.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct BulkRelation {
+    pub src: String,
+    pub dest: String,
+    pub rel_type: String,
+    pub properties: Vec<(String, ParamValue)>,
+}
+
+impl BulkRelation {
+    pub fn new<S: Into<String>, T: Into<String>, D: Into<String>>(
+        src: S,
+        rel_type: T,
+        dest: D,
+    ) -> Self {
+        BulkRelation {
+            src: src.into(),
+            dest: dest.into(),
+            rel_type: rel_type.into(),
+            properties: vec![],
+        }
+    }
+
+    pub fn with_property<N: Into<String>, V: Into<ParamValue>>(
+        mut self,
+        name: N,
+        value: V,
+    ) -> Self {
+        self.properties.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// An element of the node and relation iterators of a bulk load, either the
+/// entity itself or the result of reading it (eg. from CSV).
+pub trait BulkEntity<T> {
+    fn into_entity(self) -> RedisResult<T>;
+}
+
+impl BulkEntity<BulkNode> for BulkNode {
+    fn into_entity(self) -> RedisResult<BulkNode> {
+        Ok(self)
+    }
+}
+
+impl BulkEntity<BulkNode> for RedisResult<BulkNode> {
+    fn into_entity(self) -> RedisResult<BulkNode> {
+        self
+    }
+}
+
+impl BulkEntity<BulkRelation> for BulkRelation {
+    fn into_entity(self) -> RedisResult<BulkRelation> {
+        Ok(self)
+    }
+}
+
+impl BulkEntity<BulkRelation> for RedisResult<BulkRelation> {
+    fn into_entity(self) -> RedisResult<BulkRelation> {
+        self
+    }
+}
+
+/// Settings of a GRAPH.BULK load. Entities are encoded into one binary token
+/// per label or relation type and tokens are sent in commands of at most
+/// max_token_count tokens and max_buffer_size bytes. A token is split when it
+/// grows beyond max_token_size bytes.
+///
+/// The graph must not exist before the load, the first command creates it
+/// with `GRAPH.BULK key BEGIN`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BulkLoader {
+    max_token_count: usize,
+    max_buffer_size: usize,
+    max_token_size: usize,
+}
+
+impl Default for BulkLoader {
+    fn default() -> Self {
+        BulkLoader {
+            max_token_count: 1024,
+            max_buffer_size: 64 * 1024 * 1024,
+            max_token_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+impl BulkLoader {
+    pub fn new() -> Self {
+        BulkLoader::default()
+    }
+
+    pub fn with_max_token_count(mut self, count: usize) -> Self {
+        self.max_token_count = count.max(1);
+        self
+    }
+
+    pub fn with_max_buffer_size(mut self, bytes: usize) -> Self {
+        self.max_buffer_size = bytes.max(1);
+        self
+    }
+
+    pub fn with_max_token_size(mut self, bytes: usize) -> Self {
+        self.max_token_size = bytes.max(1);
+        self
+    }
+
+    pub(crate) fn encoder(&self) -> BulkEncoder<'_> {
+        BulkEncoder {
+            loader: self,
+            node_ids: HashMap::new(),
+            next_node_id: 0,
+            begin: true,
+            nodes: vec![],
+            relations: vec![],
+            labels: vec![],
+            reltypes: vec![],
+            node_count: 0,
+            relation_count: 0,
+            size: 0,
+        }
+    }
+}
+
+/// The number of entities created by a bulk load.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct BulkLoadResult {
+    /// Number of GRAPH.BULK commands sent.
+    pub commands: usize,
+    pub nodes_created: u64,
+    pub relations_created: u64,
+}
+
+impl BulkLoadResult {
+    // Adds the counts of a reply like "3 nodes created, 2 edges created"
+    pub(crate) fn add(&mut self, v: &Value) -> RedisResult<()> {
+        let reply: String = from_redis_value(v)?;
+        let counts: Vec<u64> = reply
+            .split(',')
+            .map(|part| part.split_whitespace().next().and_then(|n| n.parse().ok()))
+            .collect::<Option<Vec<u64>>>()
+            .filter(|counts| counts.len() == 2)
+            .ok_or_else(|| create_error(&format!("Invalid GRAPH.BULK reply {}", reply)))?;
+        self.commands += 1;
+        self.nodes_created += counts[0];
+        self.relations_created += counts[1];
+        Ok(())
+    }
+}
+
+// An entity token being encoded: the header (name and property names)
+// followed by the encoded entities. Node tokens keep the ids of their nodes
+// to number them once the token is finished.
+struct Token {
+    name: String,
+    columns: Vec<String>,
+    data: Vec<u8>,
+    count: u64,
+    node_ids: Vec<String>,
+}
+
+impl Token {
+    fn header_len(&self) -> usize {
+        self.name.len() + 5 + self.columns.iter().map(|c| c.len() + 1).sum::<usize>()
+    }
+
+    fn len(&self) -> usize {
+        self.header_len() + self.data.len()
+    }
+
+    fn encode(self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.len());
+        out.extend_from_slice(self.name.as_bytes());
+        out.push(0);
+        out.extend_from_slice(&(self.columns.len() as u32).to_le_bytes());
+        for column in &self.columns {
+            out.extend_from_slice(column.as_bytes());
+            out.push(0);
+        }
+        out.extend_from_slice(&self.data);
+        out
+    }
+}
+
+fn encode_value(out: &mut Vec<u8>, value: &ParamValue) -> RedisResult<()> {
+    match value {
+        ParamValue::Null => out.push(BI_NULL),
+        ParamValue::Bool(v) => {
+            out.push(BI_BOOL);
+            out.push(*v as u8);
+        }
+        ParamValue::Float(v) => {
+            out.push(BI_DOUBLE);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        ParamValue::String(v) => {
+            if v.contains('\0') {
+                return Err(create_error("String property contains a NUL character"));
+            }
+            out.push(BI_STRING);
+            out.extend_from_slice(v.as_bytes());
+            out.push(0);
+        }
+        ParamValue::Int(v) => {
+            out.push(BI_LONG);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        ParamValue::List(values) => {
+            out.push(BI_ARRAY);
+            out.extend_from_slice(&(values.len() as i64).to_le_bytes());
+            for value in values {
+                encode_value(out, value)?;
+            }
+        }
+        ParamValue::Map(_) => return Err(create_error("Map properties are not supported")),
+    }
+    Ok(())
+}
+
+fn check_name(name: &str) -> RedisResult<()> {
+    if name.is_empty() || name.contains('\0') {
+        Err(create_error(&format!(
+            "Invalid bulk entity name {:?}",
+            name
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// The arguments of a single GRAPH.BULK command.
+pub(crate) struct BulkCommand {
+    begin: bool,
+    node_count: u64,
+    relation_count: u64,
+    labels: Vec<Vec<u8>>,
+    reltypes: Vec<Vec<u8>>,
+}
+
+impl BulkCommand {
+    pub(crate) fn to_cmd<K: ToRedisArgs>(&self, key: K) -> Cmd {
+        let mut c = cmd("GRAPH.BULK");
+        c.arg(key);
+        if self.begin {
+            c.arg("BEGIN");
+        }
+        c.arg(self.node_count)
+            .arg(self.relation_count)
+            .arg(self.labels.len())
+            .arg(self.reltypes.len());
+        for token in self.labels.iter().chain(self.reltypes.iter()) {
+            c.arg(&token[..]);
+        }
+        c
+    }
+}
+
+// Encodes nodes and relations into GRAPH.BULK commands. add_node and
+// add_relation return a command whenever the buffered tokens reach the
+// limits of the loader, finish returns the remaining command. Redis graph
+// creates the nodes token by token, so nodes get consecutive ids in the
+// order their tokens are finished. The open node tokens are finished before
+// a relation is encoded, which needs the ids of its nodes.
+pub(crate) struct BulkEncoder<'a> {
+    loader: &'a BulkLoader,
+    // The server ids of all nodes, None while the token is open
+    node_ids: HashMap<String, Option<u64>>,
+    next_node_id: u64,
+    begin: bool,
+    nodes: Vec<Token>,
+    relations: Vec<Token>,
+    labels: Vec<Vec<u8>>,
+    reltypes: Vec<Vec<u8>>,
+    node_count: u64,
+    relation_count: u64,
+    size: usize,
+}
+
+impl<'a> BulkEncoder<'a> {
+    pub(crate) fn add_node(&mut self, node: BulkNode) -> RedisResult<Option<BulkCommand>> {
+        if self.node_ids.contains_key(&node.id) {
+            return Err(create_error(&format!("Duplicate bulk node id {}", node.id)));
+        }
+        if node.labels.is_empty() {
+            return Err(create_error(&format!("Bulk node {} has no label", node.id)));
+        }
+        for label in &node.labels {
+            check_name(label)?;
+        }
+        let name = node.labels.join(":");
+        let res = self.add_entity(false, name, vec![], &node.properties, Some(&node.id))?;
+        self.node_ids.insert(node.id, None);
+        Ok(res)
+    }
+
+    pub(crate) fn add_relation(
+        &mut self,
+        relation: BulkRelation,
+    ) -> RedisResult<Option<BulkCommand>> {
+        check_name(&relation.rel_type)?;
+        for token in std::mem::take(&mut self.nodes) {
+            self.finish_token(false, token);
+        }
+        let mut prefix = Vec::with_capacity(16);
+        for id in [&relation.src, &relation.dest].iter() {
+            let id = self
+                .node_ids
+                .get(id.as_str())
+                .copied()
+                .flatten()
+                .ok_or_else(|| create_error(&format!("Unknown bulk node id {}", id)))?;
+            prefix.extend_from_slice(&id.to_le_bytes());
+        }
+        self.add_entity(true, relation.rel_type, prefix, &relation.properties, None)
+    }
+
+    pub(crate) fn finish(mut self) -> Option<BulkCommand> {
+        self.flush()
+    }
+
+    fn add_entity(
+        &mut self,
+        relation: bool,
+        name: String,
+        prefix: Vec<u8>,
+        properties: &[(String, ParamValue)],
+        node_id: Option<&str>,
+    ) -> RedisResult<Option<BulkCommand>> {
+        for (key, _) in properties {
+            check_name(key)?;
+        }
+        let open = if relation {
+            &self.relations
+        } else {
+            &self.nodes
+        };
+        let existing = open.iter().position(|t| t.name == name);
+        let mut columns = existing.map_or_else(Vec::new, |idx| open[idx].columns.clone());
+        for (key, _) in properties {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+        let mut row = prefix;
+        for column in &columns {
+            match properties.iter().find(|(key, _)| key == column) {
+                Some((_, value)) => encode_value(&mut row, value)?,
+                None => row.push(BI_NULL),
+            }
+        }
+
+        // A new token is needed for a new name, new columns or a full token
+        let max_token_size = self.loader.max_token_size;
+        let reuse = |token: &Token| {
+            token.columns == columns
+                && (token.count == 0 || token.len() + row.len() <= max_token_size)
+        };
+        let new_token = existing.map_or(1, |idx| !reuse(&open[idx]) as usize);
+
+        // Flush the buffer if the entity does not fit anymore
+        let open_size: usize = self
+            .nodes
+            .iter()
+            .chain(self.relations.iter())
+            .map(Token::len)
+            .sum();
+        let token_count = self.labels.len()
+            + self.reltypes.len()
+            + self.nodes.len()
+            + self.relations.len()
+            + new_token;
+        let buffered = self.size + open_size > 0;
+        let res = if buffered
+            && (self.size + open_size + row.len() > self.loader.max_buffer_size
+                || token_count > self.loader.max_token_count)
+        {
+            self.flush()
+        } else {
+            None
+        };
+
+        let open = if relation {
+            &mut self.relations
+        } else {
+            &mut self.nodes
+        };
+        let idx = match open.iter().position(|t| t.name == name) {
+            Some(idx) if reuse(&open[idx]) => idx,
+            Some(idx) => {
+                let token = open.remove(idx);
+                self.finish_token(relation, token);
+                self.open_token(relation, name, columns)
+            }
+            None => self.open_token(relation, name, columns),
+        };
+        let token = if relation {
+            &mut self.relations[idx]
+        } else {
+            &mut self.nodes[idx]
+        };
+        token.data.extend_from_slice(&row);
+        token.count += 1;
+        token.node_ids.extend(node_id.map(str::to_string));
+        Ok(res)
+    }
+
+    fn open_token(&mut self, relation: bool, name: String, columns: Vec<String>) -> usize {
+        let open = if relation {
+            &mut self.relations
+        } else {
+            &mut self.nodes
+        };
+        open.push(Token {
+            name,
+            columns,
+            data: vec![],
+            count: 0,
+            node_ids: vec![],
+        });
+        open.len() - 1
+    }
+
+    fn finish_token(&mut self, relation: bool, mut token: Token) {
+        self.size += token.len();
+        if relation {
+            self.relation_count += token.count;
+            self.reltypes.push(token.encode());
+        } else {
+            for id in std::mem::take(&mut token.node_ids) {
+                self.node_ids.insert(id, Some(self.next_node_id));
+                self.next_node_id += 1;
+            }
+            self.node_count += token.count;
+            self.labels.push(token.encode());
+        }
+    }
+
+    fn flush(&mut self) -> Option<BulkCommand> {
+        for token in std::mem::take(&mut self.nodes) {
+            self.finish_token(false, token);
+        }
+        for token in std::mem::take(&mut self.relations) {
+            self.finish_token(true, token);
+        }
+        if self.labels.is_empty() && self.reltypes.is_empty() {
+            return None;
+        }
+        let command = BulkCommand {
+            begin: self.begin,
+            node_count: self.node_count,
+            relation_count: self.relation_count,
+            labels: std::mem::take(&mut self.labels),
+            reltypes: std::mem::take(&mut self.reltypes),
+        };
+        self.begin = false;
+        self.node_count = 0;
+        self.relation_count = 0;
+        self.size = 0;
+        Some(command)
+    }
+}
@@ -0,0 +1,372 @@
+use crate::types::*;
+use redis::cluster::ClusterConnection;
+use redis::cluster_routing::{
+    get_slot, MultipleNodeRoutingInfo, ResponsePolicy, Route, RoutingInfo, SingleNodeRoutingInfo,
+    SlotAddr,
+};
+use redis::{
+    cmd, Cmd, ConnectionAddr, ConnectionInfo, ConnectionLike, FromRedisValue, IntoConnectionInfo,
+    RedisResult, RedisWrite, ToRedisArgs, Value,
+};
+use std::fmt;
+
+/// A graph key that is optionally prefixed with a Redis cluster hash tag.
+/// All keys sharing the same tag are stored in the same hash slot and
+/// thereby on the same cluster node, which allows co-locating related
+/// graphs (eg. all graphs of a single tenant).
+///
+/// ```rust
+/// use redis_graph::GraphKey;
+///
+/// let social = GraphKey::tagged("tenant42", "social").unwrap();
+/// let billing = social.sibling("billing");
+///
+/// assert_eq!(social.to_string(), "{tenant42}:social");
+/// assert_eq!(social.slot(), billing.slot());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GraphKey {
+    tag: Option<String>,
+    name: String,
+}
+
+impl GraphKey {
+    /// Creates a plain graph key without a hash tag.
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        GraphKey {
+            tag: None,
+            name: name.into(),
+        }
+    }
+
+    /// Creates a graph key with hash tag. Will return an error in case the tag
+    /// is empty or contains curly braces as Redis would not use it for hashing.
+    pub fn tagged<T: Into<String>, N: Into<String>>(tag: T, name: N) -> RedisResult<Self> {
+        let tag = tag.into();
+        if tag.is_empty() || tag.contains('{') || tag.contains('}') {
+            return Err(create_error("Invalid graph key hash tag"));
+        }
+        Ok(GraphKey {
+            tag: Some(tag),
+            name: name.into(),
+        })
+    }
+
+    /// Creates another graph key sharing the hash tag of this key.
+    pub fn sibling<N: Into<String>>(&self, name: N) -> Self {
+        GraphKey {
+            tag: self.tag.clone(),
+            name: name.into(),
+        }
+    }
+
+    /// The hash tag of this key if there is one.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// The graph name without hash tag.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The cluster hash slot this key is stored in.
+    pub fn slot(&self) -> u16 {
+        get_slot(self.to_string().as_bytes())
+    }
+}
+
+impl fmt::Display for GraphKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.tag {
+            Some(tag) => write!(f, "{{{}}}:{}", tag, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl ToRedisArgs for GraphKey {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg(self.to_string().as_bytes())
+    }
+}
+
+/// Returns the cluster routing for a graph command. Keyed commands are routed
+/// by their graph key with read-only queries being eligible for replicas,
+/// GRAPH.LIST is sent to all primaries and GRAPH.CONFIG SET to all nodes.
+/// Will return None for commands that are not graph commands.
+pub fn graph_routing(cmd: &Cmd) -> Option<RoutingInfo> {
+    let mut args = cmd.args_iter().map(|arg| match arg {
+        redis::Arg::Simple(v) => v.to_vec(),
+        redis::Arg::Cursor => Vec::default(),
+    });
+    let name = args.next()?.to_ascii_uppercase();
+    match name.as_slice() {
+        b"GRAPH.QUERY" | b"GRAPH.PROFILE" | b"GRAPH.DELETE" | b"GRAPH.EXPLAIN"
+        | b"GRAPH.SLOWLOG" | b"GRAPH.BULK" => args
+            .next()
+            .map(|key| single_node(Route::new(get_slot(&key), SlotAddr::Master))),
+        b"GRAPH.RO_QUERY" => args
+            .next()
+            .map(|key| single_node(Route::new(get_slot(&key), SlotAddr::Replica))),
+        b"GRAPH.LIST" => Some(RoutingInfo::MultiNode((
+            MultipleNodeRoutingInfo::AllMasters,
+            Some(ResponsePolicy::CombineArrays),
+        ))),
+        b"GRAPH.CONFIG" => match args.next().map(|a| a.to_ascii_uppercase()) {
+            Some(sub) if sub == b"SET" => Some(RoutingInfo::MultiNode((
+                MultipleNodeRoutingInfo::AllNodes,
+                Some(ResponsePolicy::AllSucceeded),
+            ))),
+            _ => Some(RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random)),
+        },
+        _ => None,
+    }
+}
+
+fn single_node(route: Route) -> RoutingInfo {
+    RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(route))
+}
+
+/// Provides cluster aware graph commands for the synchronous cluster connection.
+/// Keyed graph commands (query, delete, ...) are routed by their key argument and
+/// work as is through GraphCommands. Commands that are not bound to a key, like
+/// GRAPH.LIST, end up on a random node when sent through GraphCommands, so they
+/// need to be sent to every node that graph_routing selects.
+pub trait ClusterGraphCommands {
+    /// Lists the graphs of all primaries in the cluster. As the synchronous cluster
+    /// connection can not be routed to individual nodes, a connection is opened to
+    /// every primary announced by CLUSTER SLOTS. The addresses take the TLS mode,
+    /// certificates and credentials of node, which should be one of the nodes the
+    /// cluster client was built with.
+    fn graph_cluster_list<T: IntoConnectionInfo>(&mut self, node: T) -> RedisResult<Vec<String>>;
+
+    /// Sets a config value on all nodes of the cluster, see graph_cluster_list
+    /// for the node connection settings.
+    fn graph_cluster_config_set<T: IntoConnectionInfo, K: ToRedisArgs, V: ToRedisArgs>(
+        &mut self,
+        node: T,
+        name: K,
+        value: V,
+    ) -> RedisResult<bool>;
+}
+
+impl ClusterGraphCommands for ClusterConnection {
+    fn graph_cluster_list<T: IntoConnectionInfo>(&mut self, node: T) -> RedisResult<Vec<String>> {
+        let mut graphs: Vec<String> = Vec::new();
+        for value in route_graph_command_sync(self, node, &cmd("GRAPH.LIST"))? {
+            graphs.extend(Vec::<String>::from_redis_value(&value)?);
+        }
+        Ok(graphs)
+    }
+
+    fn graph_cluster_config_set<T: IntoConnectionInfo, K: ToRedisArgs, V: ToRedisArgs>(
+        &mut self,
+        node: T,
+        name: K,
+        value: V,
+    ) -> RedisResult<bool> {
+        let command = cmd("GRAPH.CONFIG")
+            .arg("SET")
+            .arg(name)
+            .arg(value)
+            .to_owned();
+        for value in route_graph_command_sync(self, node, &command)? {
+            if !bool::from_redis_value(&value)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+// Sends a command to the nodes graph_routing selects, single node commands go
+// through the cluster connection, multi node commands over a connection per node
+fn route_graph_command_sync<T: IntoConnectionInfo>(
+    con: &mut ClusterConnection,
+    node: T,
+    command: &Cmd,
+) -> RedisResult<Vec<Value>> {
+    let replicas = match graph_routing(command) {
+        Some(RoutingInfo::MultiNode((MultipleNodeRoutingInfo::AllMasters, _))) => false,
+        Some(RoutingInfo::MultiNode(_)) => true,
+        _ => return Ok(vec![con.req_command(command)?]),
+    };
+    let node = node.into_connection_info()?;
+    let slots: Value = cmd("CLUSTER").arg("SLOTS").query(con)?;
+    let mut values = vec![];
+    for (host, port) in cluster_nodes(&slots, replicas)? {
+        let info = node_connection_info(&node, host, port)?;
+        values.push(
+            redis::Client::open(info)?
+                .get_connection()?
+                .req_command(command)?,
+        );
+    }
+    Ok(values)
+}
+
+// The connection info of another cluster node with the settings of node
+fn node_connection_info(
+    node: &ConnectionInfo,
+    host: String,
+    port: u16,
+) -> RedisResult<ConnectionInfo> {
+    let mut addr = node.addr.clone();
+    match &mut addr {
+        ConnectionAddr::Tcp(node_host, node_port)
+        | ConnectionAddr::TcpTls {
+            host: node_host,
+            port: node_port,
+            ..
+        } => {
+            if !host.is_empty() {
+                *node_host = host;
+            }
+            *node_port = port;
+        }
+        ConnectionAddr::Unix(_) => {
+            return Err(create_error("Cluster nodes need a TCP connection"));
+        }
+    }
+    Ok(ConnectionInfo {
+        addr,
+        redis: node.redis.clone(),
+    })
+}
+
+/// Provides cluster aware graph commands for the asynchronous cluster connection.
+/// Keyed graph commands (query, delete, ...) are routed by their key argument and
+/// work as is through AsyncGraphCommands.
+#[cfg(feature = "cluster-async")]
+pub trait AsyncClusterGraphCommands {
+    /// Lists the graphs of all primaries in the cluster.
+    fn graph_cluster_list(&mut self) -> redis::RedisFuture<'_, Vec<String>>;
+
+    /// Executes a read-only query on a replica of the node owning the graph key,
+    /// if the cluster client was built with replica reads enabled.
+    fn graph_cluster_ro_query<
+        'a,
+        K: ToRedisArgs + Send + Sync + 'a,
+        Q: ToRedisArgs + Send + Sync + 'a,
+    >(
+        &'a mut self,
+        key: K,
+        query: Q,
+    ) -> redis::RedisFuture<'a, GraphResultSet>;
+
+    /// Sets a config value on all nodes of the cluster.
+    fn graph_cluster_config_set<
+        'a,
+        K: ToRedisArgs + Send + Sync + 'a,
+        V: ToRedisArgs + Send + Sync + 'a,
+    >(
+        &'a mut self,
+        name: K,
+        value: V,
+    ) -> redis::RedisFuture<'a, bool>;
+}
+
+#[cfg(feature = "cluster-async")]
+impl AsyncClusterGraphCommands for redis::cluster_async::ClusterConnection {
+    fn graph_cluster_list(&mut self) -> redis::RedisFuture<'_, Vec<String>> {
+        Box::pin(async move { route_graph_command(self, cmd("GRAPH.LIST")).await })
+    }
+
+    fn graph_cluster_ro_query<
+        'a,
+        K: ToRedisArgs + Send + Sync + 'a,
+        Q: ToRedisArgs + Send + Sync + 'a,
+    >(
+        &'a mut self,
+        key: K,
+        query: Q,
+    ) -> redis::RedisFuture<'a, GraphResultSet> {
+        Box::pin(async move {
+            route_graph_command(self, cmd("GRAPH.RO_QUERY").arg(key).arg(query).to_owned()).await
+        })
+    }
+
+    fn graph_cluster_config_set<
+        'a,
+        K: ToRedisArgs + Send + Sync + 'a,
+        V: ToRedisArgs + Send + Sync + 'a,
+    >(
+        &'a mut self,
+        name: K,
+        value: V,
+    ) -> redis::RedisFuture<'a, bool> {
+        Box::pin(async move {
+            route_graph_command(
+                self,
+                cmd("GRAPH.CONFIG")
+                    .arg("SET")
+                    .arg(name)
+                    .arg(value)
+                    .to_owned(),
+            )
+            .await
+        })
+    }
+}
+
+#[cfg(feature = "cluster-async")]
+async fn route_graph_command<RV: redis::FromRedisValue>(
+    con: &mut redis::cluster_async::ClusterConnection,
+    command: Cmd,
+) -> RedisResult<RV> {
+    let routing =
+        graph_routing(&command).unwrap_or(RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random));
+    redis::from_redis_value(&con.route_command(&command, routing).await?)
+}
+
+// Extracts the distinct node addresses from a CLUSTER SLOTS reply, the
+// primaries only or with their replicas. An empty host is the host of the
+// node that answered, an unknown host ("?") is replaced with the announced
+// ip or hostname of the node metadata.
+fn cluster_nodes(v: &Value, replicas: bool) -> RedisResult<Vec<(String, u16)>> {
+    let mut nodes: Vec<(String, u16)> = Vec::new();
+    if let Value::Bulk(ranges) = v {
+        for range in ranges {
+            let items = match range {
+                Value::Bulk(items) if items.len() > 2 => items,
+                _ => return Err(create_error("Could not parse cluster slots")),
+            };
+            let count = if replicas { items.len() - 2 } else { 1 };
+            for node in items.iter().skip(2).take(count) {
+                let node = match node {
+                    Value::Bulk(node) if node.len() > 1 => node,
+                    _ => return Err(create_error("Could not parse cluster slots")),
+                };
+                let port: u16 = redis::from_redis_value(&node[1])?;
+                let host = match redis::from_redis_value::<String>(&node[0])?.as_str() {
+                    "?" => announced_host(node).ok_or_else(|| {
+                        create_error(&format!("Cluster node on port {} has no known host", port))
+                    })?,
+                    host => host.to_string(),
+                };
+                if !nodes.contains(&(host.clone(), port)) {
+                    nodes.push((host, port));
+                }
+            }
+        }
+    }
+    Ok(nodes)
+}
+
+// The ip or hostname of the node metadata added with Redis 7
+fn announced_host(node: &[Value]) -> Option<String> {
+    let metadata: Vec<String> = node
+        .get(3)
+        .and_then(|v| redis::from_redis_value(v).ok())
+        .unwrap_or_default();
+    ["ip", "hostname"].iter().find_map(|key| {
+        metadata
+            .chunks(2)
+            .find(|pair| pair.len() == 2 && pair[0] == *key && !pair[1].is_empty())
+            .map(|pair| pair[1].clone())
+    })
+}
@@ -1,5 +1,28 @@
+#[cfg(feature = "serde")]
+use crate::apoc::{ApocImport, ApocReport};
+use crate::bulk::{BulkRow, BulkWrite, BulkWriteResult};
+use crate::bulk_loader::{BulkEntity, BulkLoadResult, BulkLoader, BulkNode, BulkRelation};
+#[cfg(feature = "csv")]
+use crate::csv_support::{run_export, run_import, CsvImport};
+use crate::cypher::is_read_only_query;
+use crate::dump::{fresh_key_error, DumpResult, GraphDump, GraphRestore, RestoreProgress};
+use crate::fulltext::fulltext_cypher;
+use crate::migrations::*;
+use crate::pagination::{GraphPages, Pagination};
+use crate::params::ParamValue;
+#[cfg(feature = "petgraph")]
+use crate::petgraph_support::{created_ids, PetgraphPlan, PetgraphRead, PetgraphWrite};
+#[cfg(feature = "petgraph")]
+use crate::petgraph_support::{PetgraphEdge, PetgraphNode, TypedGraph};
+use crate::procedures::*;
+use crate::row::FromGraphRow;
+use crate::schema::{GraphSchema, SchemaFetch};
+use crate::schema_diff::SchemaDiff;
+use crate::stream::GraphResultStream;
+use crate::subgraph::{Subgraph, SubgraphFetch};
 use crate::types::*;
 use redis::{cmd, ConnectionLike, FromRedisValue, RedisResult, ToRedisArgs};
+use std::io::{BufRead, Write};
 
 /// Provides a high level synchronous API to work with Redis graph data types.
 /// The graph command becomes directly available on ConnectionLike types from
@@ -41,6 +64,491 @@ pub trait GraphCommands: ConnectionLike + Sized {
         cmd("GRAPH.RO_QUERY").arg(key).arg(query).query(self)
     }
 
+    /// Executes a query converting every result row into T by column position.
+    fn graph_query_as<K: ToRedisArgs, Q: ToRedisArgs, T: FromGraphRow>(
+        &mut self,
+        key: K,
+        query: Q,
+    ) -> RedisResult<Vec<T>> {
+        self.graph_query(key, query)?.rows_as()
+    }
+
+    /// Executes a read-only query converting every result row into T by
+    /// column position.
+    fn graph_ro_query_as<K: ToRedisArgs, Q: ToRedisArgs, T: FromGraphRow>(
+        &mut self,
+        key: K,
+        query: Q,
+    ) -> RedisResult<Vec<T>> {
+        self.graph_ro_query(key, query)?.rows_as()
+    }
+
+    /// Writes all nodes and edges of a petgraph graph (Graph or StableGraph
+    /// with NodeValue and RelationValue or TypedNode and TypedRelation
+    /// weights) into the graph at key. Nodes are created in batches of
+    /// batch_size, then relations between the created nodes. Node and
+    /// relation ids of the weights are ignored.
+    #[cfg(feature = "petgraph")]
+    fn graph_write_petgraph<K, G>(
+        &mut self,
+        key: K,
+        graph: G,
+        batch_size: usize,
+    ) -> RedisResult<PetgraphWrite<G::NodeId>>
+    where
+        K: ToRedisArgs,
+        G: petgraph::visit::IntoNodeReferences + petgraph::visit::IntoEdgeReferences,
+        G::NodeWeight: PetgraphNode,
+        G::EdgeWeight: PetgraphEdge,
+        G::NodeId: std::hash::Hash + Eq,
+    {
+        let plan = PetgraphPlan::new(graph, batch_size)?;
+        let mut node_ids = std::collections::HashMap::new();
+        for (batch, query) in plan.node_queries() {
+            let res = self.graph_query(&key, query)?;
+            created_ids(&batch, &res, &mut node_ids)?;
+        }
+        for query in plan.edge_queries(&node_ids)? {
+            self.graph_query(&key, query)?;
+        }
+        Ok(PetgraphWrite {
+            nodes_created: node_ids.len(),
+            relations_created: plan.edge_count(),
+            node_ids,
+        })
+    }
+
+    /// Reads all nodes and relations of the graph at key page by page into a
+    /// petgraph Graph. Unlike converted query results the weights keep the
+    /// property types, which are read with typeOf.
+    #[cfg(feature = "petgraph")]
+    fn graph_read_petgraph<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        page_size: usize,
+    ) -> RedisResult<TypedGraph> {
+        let mut read = PetgraphRead::new(page_size);
+        while let Some(query) = read.next_query() {
+            let value = self.req_command(cmd("GRAPH.RO_QUERY").arg(&key).arg(query))?;
+            read.add_response(&value)?;
+        }
+        Ok(read.finish())
+    }
+
+    /// Writes rows in batches with `UNWIND $rows AS row` and the query of
+    /// bulk, returning the summed up statistics of all batches. Batches
+    /// written before a failing batch are not rolled back, use
+    /// graph_bulk_write_progress to know where to resume.
+    fn graph_bulk_write<K: ToRedisArgs, I>(
+        &mut self,
+        key: K,
+        bulk: &BulkWrite,
+        rows: I,
+    ) -> RedisResult<BulkWriteResult>
+    where
+        I: IntoIterator,
+        I::Item: BulkRow,
+    {
+        self.graph_bulk_write_progress(key, bulk, rows, |_| {})
+    }
+
+    /// Like graph_bulk_write but calls progress after every written batch.
+    /// Batches are not atomic as a whole: on an error the last reported
+    /// result holds the rows already written, and skipping that many rows
+    /// of the input resumes the write.
+    fn graph_bulk_write_progress<K: ToRedisArgs, I, F>(
+        &mut self,
+        key: K,
+        bulk: &BulkWrite,
+        rows: I,
+        mut progress: F,
+    ) -> RedisResult<BulkWriteResult>
+    where
+        I: IntoIterator,
+        I::Item: BulkRow,
+        F: FnMut(&BulkWriteResult),
+    {
+        let mut result = BulkWriteResult::default();
+        for batch in bulk.batches(rows.into_iter()) {
+            let (count, query) = batch?;
+            let res = self.graph_query(&key, query)?;
+            result.add(count, &res);
+            progress(&result);
+        }
+        Ok(result)
+    }
+
+    /// Creates a new graph at key from nodes and relations with the binary
+    /// GRAPH.BULK protocol, which is much faster than Cypher queries. All
+    /// nodes are sent before the relations and the graph must not exist yet.
+    fn graph_bulk_load<K, N, R>(
+        &mut self,
+        key: K,
+        loader: &BulkLoader,
+        nodes: N,
+        relations: R,
+    ) -> RedisResult<BulkLoadResult>
+    where
+        K: ToRedisArgs,
+        N: IntoIterator,
+        N::Item: BulkEntity<BulkNode>,
+        R: IntoIterator,
+        R::Item: BulkEntity<BulkRelation>,
+    {
+        let mut encoder = loader.encoder();
+        let mut result = BulkLoadResult::default();
+        for node in nodes {
+            if let Some(command) = encoder.add_node(node.into_entity()?)? {
+                result.add(&self.req_command(&command.to_cmd(&key))?)?;
+            }
+        }
+        for relation in relations {
+            if let Some(command) = encoder.add_relation(relation.into_entity()?)? {
+                result.add(&self.req_command(&command.to_cmd(&key))?)?;
+            }
+        }
+        if let Some(command) = encoder.finish() {
+            result.add(&self.req_command(&command.to_cmd(&key))?)?;
+        }
+        Ok(result)
+    }
+
+    /// Imports node and relation CSV files into the graph at key with
+    /// batched queries, returning the summed up statistics. Errors name the
+    /// file and line of the failing record.
+    #[cfg(feature = "csv")]
+    fn graph_import_csv<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        import: &CsvImport,
+    ) -> RedisResult<BulkWriteResult> {
+        run_import(self, key, import)
+    }
+
+    /// Exports all nodes and relations of the graph at key into CSV files in
+    /// dir, `nodes.<labels>.csv` per label set and `relations.<type>.csv` per
+    /// relation type, and returns the paths of the written files. Characters
+    /// other than letters, digits, `_` and `-` are replaced by `_` in the
+    /// file names, and the export fails if two label sets or relation types
+    /// map to the same file. The files can be imported with
+    /// `CsvImport::from_dir`.
+    #[cfg(feature = "csv")]
+    fn graph_export_csv<K: ToRedisArgs, P: AsRef<std::path::Path>>(
+        &mut self,
+        key: K,
+        dir: P,
+    ) -> RedisResult<Vec<std::path::PathBuf>> {
+        run_export(self, key, dir.as_ref())
+    }
+
+    /// Writes a logical dump of the graph with its indexes and constraints
+    /// to out, reading the nodes and relations page by page with read-only
+    /// queries.
+    fn graph_dump<K: ToRedisArgs, W: Write>(
+        &mut self,
+        key: K,
+        dump: &GraphDump,
+        out: W,
+    ) -> RedisResult<DumpResult> {
+        let mut fetch = dump.fetch(out);
+        while let Some(query) = fetch.next_query() {
+            let value = self.req_command(cmd("GRAPH.RO_QUERY").arg(&key).arg(query))?;
+            fetch.add_response(&value)?;
+        }
+        Ok(fetch.finish())
+    }
+
+    /// Replays a dump into the graph at key, which must not exist yet.
+    /// progress is called after every executed statement. Statements
+    /// executed before a failing one are not rolled back.
+    fn graph_restore<K, R, F>(
+        &mut self,
+        key: K,
+        restore: &GraphRestore,
+        input: R,
+        mut progress: F,
+    ) -> RedisResult<RestoreProgress>
+    where
+        K: ToRedisArgs,
+        R: BufRead,
+        F: FnMut(&RestoreProgress),
+    {
+        if bool::from_redis_value(&self.req_command(cmd("EXISTS").arg(&key))?)? {
+            return Err(fresh_key_error());
+        }
+        let mut current = RestoreProgress::default();
+        for statement in restore.statements(input)? {
+            let statement = statement?;
+            let value = self.req_command(&statement.to_cmd(&key))?;
+            current.add(&statement, &value)?;
+            progress(&current);
+        }
+        Ok(current)
+    }
+
+    /// Imports the JSON lines of a Neo4j APOC export into the graph at key
+    /// with batched queries. Values and records that can't be imported are
+    /// listed in the report.
+    #[cfg(feature = "serde")]
+    fn graph_import_apoc<K: ToRedisArgs, R: BufRead>(
+        &mut self,
+        key: K,
+        import: &ApocImport,
+        input: R,
+    ) -> RedisResult<ApocReport> {
+        let mut reader = import.reader(input);
+        while let Some(query) = reader.next_query()? {
+            let value = self.req_command(cmd("GRAPH.QUERY").arg(&key).arg(query))?;
+            reader.add_response(&value)?;
+        }
+        Ok(reader.finish())
+    }
+
+    /// Executes a query returning a stream that decodes result rows on demand.
+    fn graph_query_stream<K: ToRedisArgs, Q: ToRedisArgs>(
+        &mut self,
+        key: K,
+        query: Q,
+    ) -> RedisResult<GraphResultStream> {
+        GraphResultStream::from_value(self.req_command(cmd("GRAPH.QUERY").arg(key).arg(query))?)
+    }
+
+    /// Executes a read-only query returning a stream that decodes result rows
+    /// on demand.
+    fn graph_ro_query_stream<K: ToRedisArgs, Q: ToRedisArgs>(
+        &mut self,
+        key: K,
+        query: Q,
+    ) -> RedisResult<GraphResultStream> {
+        GraphResultStream::from_value(self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))?)
+    }
+
+    /// Executes a query page by page, returning an iterator over all rows.
+    fn graph_paginate(&mut self, pagination: Pagination) -> GraphPages<'_, Self> {
+        pagination.iter(self)
+    }
+
+    /// Executes the query with GRAPH.RO_QUERY if it does not contain any write
+    /// clauses and with GRAPH.QUERY otherwise.
+    fn graph_auto_query<K: ToRedisArgs, Q: AsRef<str>>(
+        &mut self,
+        key: K,
+        query: Q,
+    ) -> RedisResult<GraphResultSet> {
+        if is_read_only_query(query.as_ref()) {
+            self.graph_ro_query(key, query.as_ref())
+        } else {
+            self.graph_query(key, query.as_ref())
+        }
+    }
+
+    /// Runs the algo.BFS procedure.
+    fn graph_algo_bfs<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        config: &BfsConfig,
+    ) -> RedisResult<BfsResult> {
+        parse_bfs(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(config.to_query()))?)
+    }
+
+    /// Runs the algo.SPpaths procedure, the config requires a target.
+    fn graph_algo_sp_paths<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        config: &PathConfig,
+    ) -> RedisResult<Vec<AlgoPath>> {
+        let query = config.sp_paths_query()?;
+        parse_paths(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))?)
+    }
+
+    /// Runs the algo.SSpaths procedure.
+    fn graph_algo_ss_paths<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        config: &PathConfig,
+    ) -> RedisResult<Vec<AlgoPath>> {
+        let query = config.ss_paths_query()?;
+        parse_paths(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))?)
+    }
+
+    /// Runs the algo.pageRank procedure returning nodes with their rank.
+    fn graph_algo_page_rank<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        config: &PageRankConfig,
+    ) -> RedisResult<Vec<(NodeValue, f64)>> {
+        parse_node_scores(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(config.to_query()))?)
+    }
+
+    /// Runs the algo.WCC procedure returning nodes with their component id.
+    fn graph_algo_wcc<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        config: &WccConfig,
+    ) -> RedisResult<Vec<(NodeValue, u64)>> {
+        parse_node_groups(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(config.to_query()))?)
+    }
+
+    /// Runs the algo.betweenness procedure returning nodes with their
+    /// centrality.
+    fn graph_algo_betweenness<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        config: &BetweennessConfig,
+    ) -> RedisResult<Vec<(NodeValue, f64)>> {
+        parse_node_scores(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(config.to_query()))?)
+    }
+
+    /// Runs the algo.labelPropagation procedure returning nodes with their
+    /// community id.
+    fn graph_algo_label_propagation<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        config: &LabelPropagationConfig,
+    ) -> RedisResult<Vec<(NodeValue, u64)>> {
+        parse_node_groups(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(config.to_query()))?)
+    }
+
+    /// Searches the full-text index of label with a RediSearch query (a
+    /// string or FulltextQuery) returning the matching nodes with their score.
+    fn graph_fulltext_query<K: ToRedisArgs, Q: std::fmt::Display>(
+        &mut self,
+        key: K,
+        label: &str,
+        query: Q,
+    ) -> RedisResult<Vec<(NodeValue, f64)>> {
+        let query = fulltext_cypher(label, &query.to_string(), None);
+        parse_node_scores(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))?)
+    }
+
+    /// Like graph_fulltext_query but returns limit results starting at offset
+    /// ordered by descending score.
+    fn graph_fulltext_query_page<K: ToRedisArgs, Q: std::fmt::Display>(
+        &mut self,
+        key: K,
+        label: &str,
+        query: Q,
+        offset: usize,
+        limit: usize,
+    ) -> RedisResult<Vec<(NodeValue, f64)>> {
+        let query = fulltext_cypher(label, &query.to_string(), Some((offset, limit)));
+        parse_node_scores(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))?)
+    }
+
+    /// Calls a procedure with the arguments passed as query parameters. Rows
+    /// are keyed by the given yields, or by all outputs of the procedure if
+    /// yields is empty. Read-only procedures are called with GRAPH.RO_QUERY,
+    /// the mode of procedures that are not built-in is looked up with
+    /// graph_procedures first.
+    fn graph_call<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        procedure: &str,
+        args: &[ParamValue],
+        yields: &[&str],
+    ) -> RedisResult<Vec<ProcedureRow>> {
+        let query = call_query(procedure, args, yields)?;
+        let mode = match builtin_mode(procedure) {
+            Some(mode) => mode,
+            None => listed_mode(&self.graph_procedures(&key)?, procedure),
+        };
+        parse_procedure_rows(&self.req_command(cmd(call_command(mode)).arg(key).arg(query))?)
+    }
+
+    /// Lists the procedures available on the graph with their mode.
+    fn graph_procedures<K: ToRedisArgs>(&mut self, key: K) -> RedisResult<Vec<ProcedureInfo>> {
+        let query = call_query("dbms.procedures", &[], &[])?;
+        parse_procedures(&self.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))?)
+    }
+
+    /// Fetches all nodes and relations of the graph into a Subgraph, e.g. to
+    /// export the whole graph with GraphExport. Nodes and relations are read
+    /// in pages of 1000 ordered by id, relations per 1000 source nodes so that
+    /// a page only scans the relations of those nodes.
+    fn graph_subgraph<K: ToRedisArgs>(&mut self, key: K) -> RedisResult<Subgraph> {
+        let mut fetch = SubgraphFetch::new(1000);
+        while let Some(query) = fetch.next_query() {
+            let value = self.req_command(cmd("GRAPH.RO_QUERY").arg(&key).arg(query))?;
+            fetch.add_response(&value)?;
+        }
+        Ok(fetch.finish())
+    }
+
+    /// Fetches the schema of the graph, property types are sampled from up to
+    /// sample_size nodes per label and relations per type.
+    fn graph_schema<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        sample_size: usize,
+    ) -> RedisResult<GraphSchema> {
+        let mut fetch = SchemaFetch::new(sample_size);
+        while let Some(query) = fetch.next_query() {
+            let value = self.req_command(cmd("GRAPH.RO_QUERY").arg(&key).arg(query))?;
+            fetch.add_response(&value)?;
+        }
+        Ok(fetch.finish())
+    }
+
+    /// Compares the schema of the graph against an expected schema, e.g. one
+    /// declared in Rust, read from a file or fetched from another graph.
+    fn graph_schema_diff<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        expected: &GraphSchema,
+        sample_size: usize,
+    ) -> RedisResult<SchemaDiff> {
+        let actual = self.graph_schema(key, sample_size)?;
+        Ok(SchemaDiff::compare(expected, &actual))
+    }
+
+    /// Reads the migrations recorded as applied to the graph.
+    fn graph_migrations_applied<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        migrator: &Migrator,
+    ) -> RedisResult<Vec<AppliedMigration>> {
+        if let Some(exists) = migrator.store().exists_cmd(&key) {
+            if !exists.query::<bool>(self)? {
+                return Ok(Vec::new());
+            }
+        }
+        let res = self.req_command(&migrator.store().read_cmd(key))?;
+        migrator.store().parse_applied(&res)
+    }
+
+    /// Applies all pending migrations in version order after verifying the
+    /// checksums of the applied ones. With dry_run only the plan is returned.
+    fn graph_migrate<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        migrator: &Migrator,
+        dry_run: bool,
+    ) -> RedisResult<MigrationPlan> {
+        let applied = self.graph_migrations_applied(&key, migrator)?;
+        let plan = migrator.plan_migrate(&applied)?;
+        if !dry_run {
+            run_plan(self, &key, migrator.store(), &plan)?;
+        }
+        Ok(plan)
+    }
+
+    /// Rolls back all applied migrations newer than target, newest first.
+    /// With dry_run only the plan is returned.
+    fn graph_rollback<K: ToRedisArgs>(
+        &mut self,
+        key: K,
+        migrator: &Migrator,
+        target: u64,
+        dry_run: bool,
+    ) -> RedisResult<MigrationPlan> {
+        let applied = self.graph_migrations_applied(&key, migrator)?;
+        let plan = migrator.plan_rollback(&applied, target)?;
+        if !dry_run {
+            run_plan(self, &key, migrator.store(), &plan)?;
+        }
+        Ok(plan)
+    }
+
     fn graph_profile<K: ToRedisArgs, Q: ToRedisArgs, RV: FromRedisValue>(
         &mut self,
         key: K,
@@ -61,6 +569,10 @@ pub trait GraphCommands: ConnectionLike + Sized {
         cmd("GRAPH.EXPLAIN").arg(key).arg(query).query(self)
     }
 
+    fn graph_list(&mut self) -> RedisResult<Vec<String>> {
+        cmd("GRAPH.LIST").query(self)
+    }
+
     fn graph_slowlog<K: ToRedisArgs>(&mut self, key: K) -> RedisResult<Vec<SlowLogEntry>> {
         cmd("GRAPH.SLOWLOG").arg(key).query(self)
     }
@@ -0,0 +1,1204 @@
+use crate::bulk::{BulkWrite, BulkWriteResult};
+use crate::bulk_loader::{BulkNode, BulkRelation};
+use crate::commands::GraphCommands;
+use crate::json_support::JsonScalar;
+use crate::params::{escape_identifier, query_with_id, ParamValue};
+use crate::schema::{EntityType, SchemaFetch};
+use crate::types::*;
+use redis::{cmd, from_redis_value, RedisError, RedisResult, ToRedisArgs, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq)]
+enum CsvType {
+    Infer,
+    Bool,
+    Double,
+    String,
+    Long,
+    Array,
+    Ignore,
+    Id,
+    Label,
+    Type,
+    StartId(Option<String>),
+    EndId(Option<String>),
+}
+
+// A column of a CSV file, parsed from a header like `born:INT`
+struct CsvColumn {
+    name: String,
+    column_type: CsvType,
+}
+
+impl CsvColumn {
+    fn parse(header: &str) -> Result<Self, String> {
+        let (name, column_type) = match header.rsplit_once(':') {
+            Some((name, column_type)) => (name, column_type),
+            None => {
+                return Ok(CsvColumn {
+                    name: header.to_string(),
+                    column_type: CsvType::Infer,
+                })
+            }
+        };
+        let (column_type, label) = match column_type.split_once('(') {
+            Some((column_type, label)) if label.ends_with(')') => {
+                (column_type, Some(label[..label.len() - 1].to_string()))
+            }
+            _ => (column_type, None),
+        };
+        let column_type = match (column_type.to_uppercase().as_str(), label) {
+            ("BOOL", None) | ("BOOLEAN", None) => CsvType::Bool,
+            ("DOUBLE", None) | ("FLOAT", None) => CsvType::Double,
+            ("STRING", None) => CsvType::String,
+            ("INT", None) | ("INTEGER", None) | ("LONG", None) => CsvType::Long,
+            ("ARRAY", None) => CsvType::Array,
+            ("IGNORE", None) => CsvType::Ignore,
+            ("ID", None) => CsvType::Id,
+            ("LABEL", None) => CsvType::Label,
+            ("TYPE", None) => CsvType::Type,
+            ("START_ID", label) => CsvType::StartId(label),
+            ("END_ID", label) => CsvType::EndId(label),
+            _ => return Err(format!("Unknown column type in header {}", header)),
+        };
+        Ok(CsvColumn {
+            name: name.to_string(),
+            column_type,
+        })
+    }
+
+    // Whether the column is stored as property
+    fn is_property(&self) -> bool {
+        !self.name.is_empty()
+            && !self.name.starts_with('_')
+            && matches!(
+                self.column_type,
+                CsvType::Infer
+                    | CsvType::Bool
+                    | CsvType::Double
+                    | CsvType::String
+                    | CsvType::Long
+                    | CsvType::Array
+                    | CsvType::Id
+            )
+    }
+}
+
+fn parse_value(value: &str, column_type: &CsvType) -> Result<ParamValue, String> {
+    let value = value.trim();
+    let invalid = || format!("Invalid value {}", value);
+    if value.is_empty() {
+        return Ok(ParamValue::Null);
+    }
+    match column_type {
+        CsvType::Bool => match value.to_lowercase().as_str() {
+            "true" => Ok(ParamValue::Bool(true)),
+            "false" => Ok(ParamValue::Bool(false)),
+            _ => Err(invalid()),
+        },
+        CsvType::Double => value.parse().map(ParamValue::Float).map_err(|_| invalid()),
+        CsvType::Long => value.parse().map(ParamValue::Int).map_err(|_| invalid()),
+        CsvType::String
+        | CsvType::Id
+        | CsvType::Label
+        | CsvType::Type
+        | CsvType::StartId(_)
+        | CsvType::EndId(_) => Ok(ParamValue::String(unquote(value).to_string())),
+        CsvType::Array => parse_array(value)?.ok_or_else(invalid),
+        CsvType::Ignore => Ok(ParamValue::Null),
+        CsvType::Infer => {
+            if let Ok(v) = value.parse::<i64>() {
+                Ok(ParamValue::Int(v))
+            } else if value.chars().any(|c| c.is_ascii_digit()) && value.parse::<f64>().is_ok() {
+                Ok(ParamValue::Float(value.parse().unwrap_or_default()))
+            } else if value.eq_ignore_ascii_case("true") {
+                Ok(ParamValue::Bool(true))
+            } else if value.eq_ignore_ascii_case("false") {
+                Ok(ParamValue::Bool(false))
+            } else if let Some(array) = parse_array(value)? {
+                Ok(array)
+            } else {
+                Ok(ParamValue::String(unquote(value).to_string()))
+            }
+        }
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''].iter() {
+        if value.len() >= 2 && value.starts_with(*quote) && value.ends_with(*quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+// Parses `[1, 'a', [true]]`, returns None if the value is not an array
+fn parse_array(value: &str) -> Result<Option<ParamValue>, String> {
+    if !(value.starts_with('[') && value.ends_with(']')) {
+        return Ok(None);
+    }
+    let inner = &value[1..value.len() - 1];
+    let mut elements = vec![];
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (idx, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                elements.push(&inner[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    if !inner.trim().is_empty() {
+        elements.push(&inner[start..]);
+    }
+    elements
+        .into_iter()
+        .map(|element| parse_value(element, &CsvType::Infer))
+        .collect::<Result<Vec<_>, _>>()
+        .map(|values| Some(ParamValue::List(values)))
+}
+
+// An open CSV file with parsed header. Errors name the file and line.
+struct CsvFile {
+    path: PathBuf,
+    columns: Vec<CsvColumn>,
+    records: csv::StringRecordsIntoIter<File>,
+}
+
+impl CsvFile {
+    fn open(path: &Path) -> RedisResult<Self> {
+        let file_error =
+            |e: &dyn std::fmt::Display| create_error(&format!("{}: {}", path.display(), e));
+        let mut reader = csv::Reader::from_path(path).map_err(|e| file_error(&e))?;
+        let columns = reader
+            .headers()
+            .map_err(|e| file_error(&e))?
+            .iter()
+            .map(CsvColumn::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|e| file_error(&format!("line 1: {}", e)))?;
+        Ok(CsvFile {
+            path: path.to_path_buf(),
+            columns,
+            records: reader.into_records(),
+        })
+    }
+
+    // The file name up to the first dot, eg. `rides` for `rides.2.csv`
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    fn error(&self, line: u64, msg: &str) -> RedisError {
+        create_error(&format!("{} line {}: {}", self.path.display(), line, msg))
+    }
+
+    fn position(&self, matches: fn(&CsvType) -> bool) -> Option<usize> {
+        self.columns.iter().position(|c| matches(&c.column_type))
+    }
+
+    fn next_record(&mut self) -> Option<RedisResult<(u64, csv::StringRecord)>> {
+        let record = self.records.next()?;
+        Some(match record {
+            Ok(record) => Ok((record.position().map_or(0, |p| p.line()), record)),
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                Err(self.error(line, &e.to_string()))
+            }
+        })
+    }
+
+    fn value(&self, line: u64, record: &csv::StringRecord, idx: usize) -> RedisResult<ParamValue> {
+        let column = &self.columns[idx];
+        parse_value(record.get(idx).unwrap_or_default(), &column.column_type)
+            .map_err(|e| self.error(line, &format!("{}: {}", column.name, e)))
+    }
+
+    fn properties(
+        &self,
+        line: u64,
+        record: &csv::StringRecord,
+        skip: &[usize],
+    ) -> RedisResult<Vec<(String, ParamValue)>> {
+        let mut properties = vec![];
+        for (idx, column) in self.columns.iter().enumerate() {
+            if column.is_property() && !skip.contains(&idx) {
+                properties.push((column.name.clone(), self.value(line, record, idx)?));
+            }
+        }
+        Ok(properties)
+    }
+}
+
+/// Reads the nodes of a CSV file. The label is the file name up to the first
+/// dot (`Rider.csv` or `Rider.2.csv`) unless set with with_label. The column
+/// with type ID, or otherwise the first column, is the node id, which is also
+/// stored as property unless the column name starts with an underscore.
+/// Column types are inferred from the values or declared in the header, eg.
+/// `id:ID`, `born:INT`, `name:STRING`, `score:DOUBLE`, `active:BOOL`,
+/// `teams:ARRAY` or `note:IGNORE`. A `:LABEL` column sets the labels of each
+/// node, separated by `:`, in addition to those set with with_label, and the
+/// file name is not used as label. Errors name the file and line.
+pub struct CsvNodes {
+    file: CsvFile,
+    labels: Vec<String>,
+    id: usize,
+    label: Option<usize>,
+}
+
+impl CsvNodes {
+    pub fn open<P: AsRef<Path>>(path: P) -> RedisResult<Self> {
+        let file = CsvFile::open(path.as_ref())?;
+        if file.columns.is_empty() {
+            return Err(file.error(1, "Node CSV files need an id column"));
+        }
+        let label = file.position(|t| *t == CsvType::Label);
+        Ok(CsvNodes {
+            labels: match label {
+                Some(_) => vec![],
+                None => vec![file.name()],
+            },
+            id: file.position(|t| *t == CsvType::Id).unwrap_or(0),
+            label,
+            file,
+        })
+    }
+
+    /// Sets the labels of all nodes, separated by `:` for multiple labels.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.labels = label.split(':').map(str::to_string).collect();
+        self
+    }
+
+    fn id_column(&self) -> &CsvColumn {
+        &self.file.columns[self.id]
+    }
+
+    // The next node and its line
+    fn next_line(&mut self) -> Option<RedisResult<(u64, BulkNode)>> {
+        let (line, record) = match self.file.next_record()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        Some((|| {
+            self.file.value(line, &record, self.id)?;
+            let mut labels = self.labels.clone();
+            if let Some(idx) = self.label {
+                labels.extend(
+                    record
+                        .get(idx)
+                        .unwrap_or_default()
+                        .split(':')
+                        .map(str::trim)
+                        .filter(|label| !label.is_empty())
+                        .map(str::to_string),
+                );
+            }
+            Ok((
+                line,
+                BulkNode {
+                    id: record.get(self.id).unwrap_or_default().trim().to_string(),
+                    labels,
+                    properties: self.file.properties(line, &record, &[])?,
+                },
+            ))
+        })())
+    }
+}
+
+impl Iterator for CsvNodes {
+    type Item = RedisResult<BulkNode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line().map(|node| node.map(|(_, node)| node))
+    }
+}
+
+/// Reads the relations of a CSV file. The relation type is the file name up
+/// to the first dot unless set with with_type, or the value of a `:TYPE`
+/// column. The columns with types START_ID and END_ID, or otherwise the first
+/// two columns, are the ids of the source and destination nodes. For imports
+/// with Cypher the labels of the nodes are declared with the id type, eg.
+/// `:START_ID(Rider)` and `:END_ID(Team)`, or `:START_ID()` for nodes without
+/// labels. The other columns are properties as for CsvNodes.
+pub struct CsvRelations {
+    file: CsvFile,
+    rel_type: String,
+    src: usize,
+    dest: usize,
+    type_column: Option<usize>,
+}
+
+impl CsvRelations {
+    pub fn open<P: AsRef<Path>>(path: P) -> RedisResult<Self> {
+        let file = CsvFile::open(path.as_ref())?;
+        if file.columns.len() < 2 {
+            return Err(file.error(
+                1,
+                "Relation CSV files need source and destination id columns",
+            ));
+        }
+        Ok(CsvRelations {
+            rel_type: file.name(),
+            src: file
+                .position(|t| matches!(t, CsvType::StartId(_)))
+                .unwrap_or(0),
+            dest: file
+                .position(|t| matches!(t, CsvType::EndId(_)))
+                .unwrap_or(1),
+            type_column: file.position(|t| *t == CsvType::Type),
+            file,
+        })
+    }
+
+    pub fn with_type(mut self, rel_type: &str) -> Self {
+        self.rel_type = rel_type.to_string();
+        self
+    }
+
+    fn label(&self, idx: usize) -> Option<&str> {
+        match &self.file.columns[idx].column_type {
+            CsvType::StartId(label) | CsvType::EndId(label) => label.as_deref(),
+            _ => None,
+        }
+    }
+
+    // The next relation and its line
+    fn next_line(&mut self) -> Option<RedisResult<(u64, BulkRelation)>> {
+        let (line, record) = match self.file.next_record()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        let id = |idx: usize| record.get(idx).unwrap_or_default().trim().to_string();
+        let rel_type = match self.type_column.map(id) {
+            Some(rel_type) if !rel_type.is_empty() => rel_type,
+            _ => self.rel_type.clone(),
+        };
+        Some(
+            self.file
+                .properties(line, &record, &[self.src, self.dest])
+                .map(|properties| {
+                    (
+                        line,
+                        BulkRelation {
+                            src: id(self.src),
+                            dest: id(self.dest),
+                            rel_type,
+                            properties,
+                        },
+                    )
+                }),
+        )
+    }
+}
+
+impl Iterator for CsvRelations {
+    type Item = RedisResult<BulkRelation>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line()
+            .map(|relation| relation.map(|(_, relation)| relation))
+    }
+}
+
+/// An import of node and relation CSV files (see CsvNodes and CsvRelations)
+/// into a new or existing graph with batched Cypher queries. All node files
+/// are imported before the relation files. Relations are matched to the
+/// nodes by the label declared in the START_ID and END_ID columns and the id
+/// property of the node file with that label, which is indexed during the
+/// import unless an index exists. Id columns whose name starts with an
+/// underscore are removed from the imported nodes after the import.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// // Rider.csv: name:ID,born:INT
+/// // Team.csv: name:ID
+/// // rides.csv: :START_ID(Rider),:END_ID(Team),since:INT
+/// let import = CsvImport::new()
+///     .with_nodes("Rider.csv")
+///     .with_nodes("Team.csv")
+///     .with_relations("rides.csv");
+/// let res = con.graph_import_csv("my_graph", &import)?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvImport {
+    nodes: Vec<(PathBuf, Option<String>)>,
+    relations: Vec<(PathBuf, Option<String>)>,
+    batch_rows: usize,
+}
+
+impl Default for CsvImport {
+    fn default() -> Self {
+        CsvImport {
+            nodes: vec![],
+            relations: vec![],
+            batch_rows: 1000,
+        }
+    }
+}
+
+impl CsvImport {
+    pub fn new() -> Self {
+        CsvImport::default()
+    }
+
+    /// Adds a node file labeled by its file name.
+    pub fn with_nodes<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.nodes.push((path.as_ref().to_path_buf(), None));
+        self
+    }
+
+    /// Adds a node file with the given label.
+    pub fn with_nodes_as<P: AsRef<Path>>(mut self, path: P, label: &str) -> Self {
+        self.nodes
+            .push((path.as_ref().to_path_buf(), Some(label.to_string())));
+        self
+    }
+
+    /// Adds a relation file typed by its file name.
+    pub fn with_relations<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.relations.push((path.as_ref().to_path_buf(), None));
+        self
+    }
+
+    /// Adds a relation file with the given relation type.
+    pub fn with_relations_as<P: AsRef<Path>>(mut self, path: P, rel_type: &str) -> Self {
+        self.relations
+            .push((path.as_ref().to_path_buf(), Some(rel_type.to_string())));
+        self
+    }
+
+    /// Adds all `.csv` files of a directory as written by graph_export_csv:
+    /// files with an ID column are node files, all others relation files.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> RedisResult<Self> {
+        let dir = dir.as_ref();
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map_err(|e| create_error(&format!("Failed to read {}: {}", dir.display(), e)))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("csv"))
+            .collect();
+        paths.sort();
+        let mut import = CsvImport::new();
+        for path in paths {
+            if CsvFile::open(&path)?
+                .position(|t| *t == CsvType::Id)
+                .is_some()
+            {
+                import = import.with_nodes(path);
+            } else {
+                import = import.with_relations(path);
+            }
+        }
+        Ok(import)
+    }
+
+    /// Sets the number of rows per query.
+    pub fn with_batch_rows(mut self, rows: usize) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+}
+
+// The id property and type of the nodes of a label
+#[derive(Clone)]
+struct NodeKey {
+    property: String,
+    column_type: CsvType,
+}
+
+// Marks the nodes whose id property is removed after the import, so that
+// nodes that existed before keep theirs
+const IMPORT_MARKER: &str = "__csv_import";
+
+fn add_result(result: &mut BulkWriteResult, res: BulkWriteResult) {
+    result.batches += res.batches;
+    result.rows += res.rows;
+    result.statistics.add(&res.statistics);
+}
+
+pub(crate) fn run_import<C: GraphCommands, K: ToRedisArgs>(
+    con: &mut C,
+    key: K,
+    import: &CsvImport,
+) -> RedisResult<BulkWriteResult> {
+    let mut result = BulkWriteResult::default();
+    let mut node_keys: HashMap<String, NodeKey> = HashMap::new();
+    let mut hidden: Vec<(String, String)> = vec![];
+
+    for (path, label) in &import.nodes {
+        let mut nodes = CsvNodes::open(path)?;
+        if let Some(label) = label {
+            nodes = nodes.with_label(label);
+        }
+        let id = nodes.id_column();
+        if id.name.is_empty() {
+            return Err(nodes.file.error(1, "The id column needs a name"));
+        }
+        let id_name = id.name.clone();
+        let id_type = id.column_type.clone();
+        let hide = !id.is_property();
+        let node_key = NodeKey {
+            property: id_name.clone(),
+            column_type: id_type.clone(),
+        };
+        for label in &nodes.labels {
+            node_keys.insert(label.clone(), node_key.clone());
+        }
+        // Consecutive nodes with the same labels are created by one write
+        let mut next = nodes.next_line();
+        while let Some(first) = next.take() {
+            let first = first?;
+            let labels = first.1.labels.clone();
+            if labels.is_empty() {
+                node_keys.insert(String::new(), node_key.clone());
+            }
+            for label in &labels {
+                node_keys.insert(label.clone(), node_key.clone());
+            }
+            let pattern = label_pattern(&labels);
+            let mut query = format!("CREATE (n{}) SET n = row", pattern);
+            if hide {
+                let marked = (pattern, id_name.clone());
+                if !hidden.contains(&marked) {
+                    hidden.push(marked);
+                }
+                query.push_str(&format!(", n.{} = true", IMPORT_MARKER));
+            }
+            let bulk = BulkWrite::new(query).with_batch_rows(import.batch_rows);
+            let mut first = Some(first);
+            let rows = std::iter::from_fn(|| {
+                let (line, node) = match first.take().map(Ok).or_else(|| nodes.next_line())? {
+                    Ok(node) => node,
+                    Err(e) => return Some(Err(e)),
+                };
+                if node.labels != labels {
+                    next = Some(Ok((line, node)));
+                    return None;
+                }
+                let mut properties = node.properties;
+                if hide {
+                    match parse_value(&node.id, &id_type) {
+                        Ok(id) => properties.push((id_name.clone(), id)),
+                        Err(e) => {
+                            return Some(Err(nodes
+                                .file
+                                .error(line, &format!("{}: {}", id_name, e))))
+                        }
+                    }
+                }
+                Some(Ok(ParamValue::Map(properties)))
+            })
+            .fuse();
+            add_result(&mut result, con.graph_bulk_write(&key, &bulk, rows)?);
+        }
+    }
+
+    let mut indexed = vec![];
+    let relations = import_relations(con, &key, import, &node_keys, &mut indexed, &mut result);
+    for (label, property) in indexed {
+        let res = con.graph_query(&key, format!("DROP INDEX ON :{}({})", label, property))?;
+        result.statistics.add(&res.statistics());
+    }
+    relations?;
+
+    for (pattern, property) in hidden {
+        let res = con.graph_query(
+            &key,
+            format!(
+                "MATCH (n{0}) WHERE n.{1} = true SET n.{2} = NULL, n.{1} = NULL",
+                pattern,
+                IMPORT_MARKER,
+                escape_identifier(&property)
+            ),
+        )?;
+        result.statistics.add(&res.statistics());
+    }
+    Ok(result)
+}
+
+// The labels of a node pattern, eg. `:Rider:Person`, empty without labels
+fn label_pattern(labels: &[String]) -> String {
+    labels
+        .iter()
+        .map(|label| format!(":{}", escape_identifier(label)))
+        .collect()
+}
+
+// Imports the relation files. The id properties the relations are matched on
+// are indexed first unless a range index exists, the created indexes are
+// added to indexed for removal after the import.
+fn import_relations<C: GraphCommands, K: ToRedisArgs>(
+    con: &mut C,
+    key: &K,
+    import: &CsvImport,
+    node_keys: &HashMap<String, NodeKey>,
+    indexed: &mut Vec<(String, String)>,
+    result: &mut BulkWriteResult,
+) -> RedisResult<()> {
+    if import.relations.is_empty() {
+        return Ok(());
+    }
+    let mut fetch = SchemaFetch::indexes();
+    while let Some(query) = fetch.next_query() {
+        let value = con.req_command(cmd("GRAPH.RO_QUERY").arg(key).arg(query))?;
+        fetch.add_response(&value)?;
+    }
+    let existing = fetch.finish().indexes;
+
+    for (path, rel_type) in &import.relations {
+        let mut relations = CsvRelations::open(path)?;
+        if let Some(rel_type) = rel_type {
+            relations = relations.with_type(rel_type);
+        }
+        let mut patterns = vec![];
+        for (idx, name) in [(relations.src, "START_ID"), (relations.dest, "END_ID")].iter() {
+            let label = relations.label(*idx).ok_or_else(|| {
+                relations
+                    .file
+                    .error(1, &format!("{} needs a label, eg. :{}(Rider)", name, name))
+            })?;
+            let node_key = node_keys.get(label).ok_or_else(|| {
+                relations
+                    .file
+                    .error(1, &format!("No node file with label {}", label))
+            })?;
+            let exists = existing.iter().any(|i| {
+                i.entity_type == EntityType::Node
+                    && i.label == label
+                    && i.properties.contains(&node_key.property)
+                    && matches!(i.index_type.as_str(), "range" | "exact-match")
+            });
+            let index = (
+                escape_identifier(label),
+                escape_identifier(&node_key.property),
+            );
+            // Nodes without labels can't be indexed
+            if !label.is_empty() && !exists && !indexed.contains(&index) {
+                let res = con.graph_query(
+                    key,
+                    format!("CREATE INDEX FOR (n:{}) ON (n.{})", index.0, index.1),
+                )?;
+                result.statistics.add(&res.statistics());
+                indexed.push(index.clone());
+            }
+            let pattern = match label {
+                "" => String::new(),
+                _ => format!(":{}", index.0),
+            };
+            patterns.push((pattern, index.1, node_key.column_type.clone(), *name));
+        }
+        let (src_type, src_name) = (&patterns[0].2, patterns[0].3);
+        let (dest_type, dest_name) = (&patterns[1].2, patterns[1].3);
+        // Consecutive relations with the same type are created by one write
+        let mut next = relations.next_line();
+        while let Some(first) = next.take() {
+            let first = first?;
+            let rel_type = first.1.rel_type.clone();
+            let bulk = BulkWrite::new(format!(
+                "MATCH (a{} {{{}: row.src}}), (b{} {{{}: row.dest}}) \
+                 CREATE (a)-[r:{}]->(b) SET r = row.properties",
+                patterns[0].0,
+                patterns[0].1,
+                patterns[1].0,
+                patterns[1].1,
+                escape_identifier(&rel_type)
+            ))
+            .with_batch_rows(import.batch_rows);
+            let mut first = Some(first);
+            let rows = std::iter::from_fn(|| {
+                let (line, relation) =
+                    match first.take().map(Ok).or_else(|| relations.next_line())? {
+                        Ok(relation) => relation,
+                        Err(e) => return Some(Err(e)),
+                    };
+                if relation.rel_type != rel_type {
+                    next = Some(Ok((line, relation)));
+                    return None;
+                }
+                let id = |id: &str, column_type: &CsvType, name: &str| {
+                    parse_value(id, column_type)
+                        .map_err(|e| relations.file.error(line, &format!("{}: {}", name, e)))
+                };
+                Some((|| {
+                    Ok(ParamValue::Map(vec![
+                        ("src".to_string(), id(&relation.src, src_type, src_name)?),
+                        (
+                            "dest".to_string(),
+                            id(&relation.dest, dest_type, dest_name)?,
+                        ),
+                        (
+                            "properties".to_string(),
+                            ParamValue::Map(relation.properties),
+                        ),
+                    ]))
+                })())
+            })
+            .fuse();
+            add_result(result, con.graph_bulk_write(key, &bulk, rows)?);
+        }
+    }
+    Ok(())
+}
+
+/// How nodes and relations in query results are written to CSV.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntityColumns {
+    /// One column per id, labels or type and property, eg. `r._id`,
+    /// `r._labels` and `r.name` for a node column `r`.
+    Flatten,
+    /// A single column with the entity as JSON object.
+    Json,
+}
+
+/// Writes query results as CSV with one column per result column, or with
+/// flattened entities one column per entity field.
+///
+/// ```rust
+/// use redis_graph::*;
+/// use redis::Value;
+/// use std::collections::HashMap;
+///
+/// let mut properties = HashMap::new();
+/// properties.insert("name".to_string(), Value::Data(b"Valentino Rossi".to_vec()));
+/// let rider = NodeValue { id: 1, labels: vec!["Rider".to_string()], properties };
+/// let res = GraphResultSet {
+///     header: vec!["r".to_string(), "r.born".to_string()],
+///     data: vec![GraphResult::from_values(
+///         vec!["r".to_string(), "r.born".to_string()].into(),
+///         vec![GraphValue::Node(rider), GraphValue::Scalar(Value::Int(1979))],
+///     )],
+///     metadata: vec![],
+/// };
+///
+/// let csv = CsvExport::new().to_string(&res).unwrap();
+/// assert_eq!(csv, "r._id,r._labels,r.name,r.born\n1,Rider,Valentino Rossi,1979\n");
+///
+/// let csv = CsvExport::new().with_entities(EntityColumns::Json).to_string(&res).unwrap();
+/// assert_eq!(
+///     csv,
+///     "r,r.born\n\"{\"\"id\"\":1,\"\"labels\"\":[\"\"Rider\"\"],\"\"properties\"\":{\"\"name\"\":\"\"Valentino Rossi\"\"}}\",1979\n"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvExport {
+    entities: EntityColumns,
+}
+
+impl Default for CsvExport {
+    fn default() -> Self {
+        CsvExport {
+            entities: EntityColumns::Flatten,
+        }
+    }
+}
+
+// The output columns of a result column
+enum ExportColumn {
+    Value(usize),
+    Id(usize),
+    Labels(usize),
+    Type(usize),
+    Src(usize),
+    Dest(usize),
+    Property(usize, String),
+}
+
+impl CsvExport {
+    pub fn new() -> Self {
+        CsvExport::default()
+    }
+
+    pub fn with_entities(mut self, entities: EntityColumns) -> Self {
+        self.entities = entities;
+        self
+    }
+
+    /// Writes the header and all rows of the result set.
+    pub fn write<W: Write>(&self, res: &GraphResultSet, writer: W) -> RedisResult<()> {
+        let columns = self.columns(res);
+        let mut writer = csv::Writer::from_writer(writer);
+        let header: Vec<String> = columns
+            .iter()
+            .map(|column| match column {
+                ExportColumn::Value(idx) => res.header[*idx].clone(),
+                ExportColumn::Id(idx) => format!("{}._id", res.header[*idx]),
+                ExportColumn::Labels(idx) => format!("{}._labels", res.header[*idx]),
+                ExportColumn::Type(idx) => format!("{}._type", res.header[*idx]),
+                ExportColumn::Src(idx) => format!("{}._src", res.header[*idx]),
+                ExportColumn::Dest(idx) => format!("{}._dest", res.header[*idx]),
+                ExportColumn::Property(idx, name) => format!("{}.{}", res.header[*idx], name),
+            })
+            .collect();
+        writer.write_record(&header).map_err(csv_error)?;
+        for row in &res.data {
+            let record: Vec<String> = columns
+                .iter()
+                .map(|column| export_field(row, column))
+                .collect();
+            writer.write_record(&record).map_err(csv_error)?;
+        }
+        writer.flush().map_err(|e| csv_error(e.into()))
+    }
+
+    /// Writes the result set into a new file at path.
+    pub fn write_file<P: AsRef<Path>>(&self, res: &GraphResultSet, path: P) -> RedisResult<()> {
+        let file = File::create(path.as_ref()).map_err(|e| {
+            create_error(&format!(
+                "Failed to create {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        self.write(res, file)
+    }
+
+    /// Returns the result set as CSV string.
+    pub fn to_string(&self, res: &GraphResultSet) -> RedisResult<String> {
+        let mut out = vec![];
+        self.write(res, &mut out)?;
+        String::from_utf8(out).map_err(|e| create_error(&e.to_string()))
+    }
+
+    fn columns(&self, res: &GraphResultSet) -> Vec<ExportColumn> {
+        let mut columns = vec![];
+        for idx in 0..res.header.len() {
+            let values = res.data.iter().filter_map(|row| row.get_at(idx));
+            let mut nodes = false;
+            let mut relations = false;
+            let mut properties = BTreeSet::new();
+            for value in values {
+                match value {
+                    GraphValue::Node(node) => {
+                        nodes = true;
+                        properties.extend(node.properties.keys());
+                    }
+                    GraphValue::Relation(relation) => {
+                        relations = true;
+                        properties.extend(relation.properties.keys());
+                    }
+                    GraphValue::Scalar(_) => {}
+                }
+            }
+            if self.entities == EntityColumns::Json || !(nodes || relations) {
+                columns.push(ExportColumn::Value(idx));
+                continue;
+            }
+            columns.push(ExportColumn::Id(idx));
+            if nodes {
+                columns.push(ExportColumn::Labels(idx));
+            }
+            if relations {
+                columns.push(ExportColumn::Type(idx));
+                columns.push(ExportColumn::Src(idx));
+                columns.push(ExportColumn::Dest(idx));
+            }
+            for property in properties {
+                columns.push(ExportColumn::Property(idx, property.clone()));
+            }
+        }
+        columns
+    }
+}
+
+fn csv_error(e: csv::Error) -> RedisError {
+    create_error(&format!("Failed to write CSV: {}", e))
+}
+
+fn export_field(row: &GraphResult, column: &ExportColumn) -> String {
+    let idx = match column {
+        ExportColumn::Value(idx)
+        | ExportColumn::Id(idx)
+        | ExportColumn::Labels(idx)
+        | ExportColumn::Type(idx)
+        | ExportColumn::Src(idx)
+        | ExportColumn::Dest(idx)
+        | ExportColumn::Property(idx, _) => *idx,
+    };
+    let value = match row.get_at(idx) {
+        Some(value) => value,
+        None => return String::new(),
+    };
+    match (column, value) {
+        (ExportColumn::Value(_), GraphValue::Scalar(v)) => scalar_field(v),
+        (ExportColumn::Value(_), entity) => serde_json::to_string(entity).unwrap_or_default(),
+        (ExportColumn::Id(_), GraphValue::Node(node)) => node.id.to_string(),
+        (ExportColumn::Id(_), GraphValue::Relation(relation)) => relation.id.to_string(),
+        (ExportColumn::Labels(_), GraphValue::Node(node)) => node.labels.join(":"),
+        (ExportColumn::Type(_), GraphValue::Relation(r)) => r.rel_type.clone(),
+        (ExportColumn::Src(_), GraphValue::Relation(r)) => r.src_node.to_string(),
+        (ExportColumn::Dest(_), GraphValue::Relation(r)) => r.dest_node.to_string(),
+        (ExportColumn::Property(_, name), GraphValue::Node(node)) => node
+            .properties
+            .get(name)
+            .map(scalar_field)
+            .unwrap_or_default(),
+        (ExportColumn::Property(_, name), GraphValue::Relation(relation)) => relation
+            .properties
+            .get(name)
+            .map(scalar_field)
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn scalar_field(v: &Value) -> String {
+    match v {
+        Value::Nil => String::new(),
+        Value::Int(v) => v.to_string(),
+        Value::Bulk(_) => serde_json::to_string(&JsonScalar(v)).unwrap_or_default(),
+        v => from_redis_value(v).unwrap_or_default(),
+    }
+}
+
+// The declared type of an exported property column: INT and ARRAY if all
+// values have that type, STRING if no value would be inferred as another type
+fn export_type(values: &[&Value]) -> &'static str {
+    let present: Vec<&&Value> = values.iter().filter(|v| ***v != Value::Nil).collect();
+    if present.is_empty() {
+        ""
+    } else if present.iter().all(|v| matches!(***v, Value::Int(_))) {
+        ":INT"
+    } else if present.iter().all(|v| matches!(***v, Value::Bulk(_))) {
+        ":ARRAY"
+    } else if present.iter().all(|v| {
+        matches!(***v, Value::Data(_) | Value::Status(_))
+            && matches!(
+                parse_value(&scalar_field(v), &CsvType::Infer),
+                Ok(ParamValue::String(_))
+            )
+    }) {
+        ":STRING"
+    } else {
+        ""
+    }
+}
+
+fn write_entities<F>(
+    path: &Path,
+    meta: &[&str],
+    entities: &[(Vec<String>, &HashMap<String, Value>)],
+    property_keys: F,
+) -> RedisResult<()>
+where
+    F: Fn() -> BTreeSet<String>,
+{
+    let file = File::create(path)
+        .map_err(|e| create_error(&format!("Failed to create {}: {}", path.display(), e)))?;
+    let mut writer = csv::Writer::from_writer(file);
+    let keys: Vec<String> = property_keys().into_iter().collect();
+    let mut header: Vec<String> = meta.iter().map(|m| m.to_string()).collect();
+    for key in &keys {
+        let values: Vec<&Value> = entities
+            .iter()
+            .filter_map(|(_, properties)| properties.get(key))
+            .collect();
+        header.push(format!("{}{}", key, export_type(&values)));
+    }
+    writer.write_record(&header).map_err(csv_error)?;
+    for (ids, properties) in entities {
+        let mut record = ids.clone();
+        for key in &keys {
+            record.push(properties.get(key).map(scalar_field).unwrap_or_default());
+        }
+        writer.write_record(&record).map_err(csv_error)?;
+    }
+    writer.flush().map_err(|e| csv_error(e.into()))
+}
+
+// Nodes and relations are exported in pages of this many entities
+const EXPORT_PAGE_SIZE: usize = 1000;
+
+// Reads all entities matched by pattern in pages ordered by id
+fn export_entities<C: GraphCommands, K: ToRedisArgs>(
+    con: &mut C,
+    key: &K,
+    pattern: &str,
+    var: &str,
+) -> RedisResult<Vec<GraphValue>> {
+    let mut entities = vec![];
+    let mut from = 0;
+    loop {
+        let query = query_with_id(
+            &format!(
+                "MATCH {} WHERE id({var}) >= $from RETURN {var} ORDER BY id({var}) LIMIT {}",
+                pattern,
+                EXPORT_PAGE_SIZE,
+                var = var
+            ),
+            "from",
+            from,
+        );
+        let res = con.graph_ro_query(key, query)?;
+        let count = res.data.len();
+        for row in res.data {
+            let entity = row.into_values().into_iter().next();
+            from = match &entity {
+                Some(GraphValue::Node(node)) => node.id + 1,
+                Some(GraphValue::Relation(relation)) => relation.id + 1,
+                _ => return Err(create_error("Unexpected export page response")),
+            };
+            entities.extend(entity);
+        }
+        if count < EXPORT_PAGE_SIZE {
+            return Ok(entities);
+        }
+    }
+}
+
+// The path of an exported file named by its parts, eg. `nodes.Rider.csv`.
+// Parts are restricted to letters, digits, `_` and `-`, so that files stay in
+// dir and the dots only separate parts. Fails if the path is already used.
+fn export_path(dir: &Path, files: &[PathBuf], parts: &[&str]) -> RedisResult<PathBuf> {
+    let name: Vec<String> = parts
+        .iter()
+        .map(|part| {
+            part.chars()
+                .map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => c,
+                    _ => '_',
+                })
+                .collect()
+        })
+        .collect();
+    let path = dir.join(format!("{}.csv", name.join(".")));
+    if files.contains(&path) {
+        return Err(create_error(&format!(
+            "{} would be written for more than one label or relation type",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+// The relations of a type grouped by the first labels of their endpoints
+type RelationGroups<'a> = Vec<((&'a str, &'a str), Vec<RelationValue>)>;
+
+// Writes a file per label set and per relation type and endpoint labels in
+// the format of CsvImport, `nodes.<labels>.csv` and `relations.<type>.csv`.
+// The ids of the nodes are written to `_id:ID` columns, the labels and types
+// to `:LABEL` and `:TYPE` columns, and relations name the first label of
+// their endpoints, which is empty for nodes without labels.
+pub(crate) fn run_export<C: GraphCommands, K: ToRedisArgs>(
+    con: &mut C,
+    key: K,
+    dir: &Path,
+) -> RedisResult<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| create_error(&format!("Failed to create {}: {}", dir.display(), e)))?;
+    let mut files = vec![];
+
+    let mut label_sets: BTreeMap<Vec<String>, Vec<NodeValue>> = BTreeMap::new();
+    for entity in export_entities(con, &key, "(n)", "n")? {
+        if let GraphValue::Node(node) = entity {
+            label_sets
+                .entry(node.labels.clone())
+                .or_default()
+                .push(node);
+        }
+    }
+    let mut node_labels: HashMap<u64, String> = HashMap::new();
+    for (labels, nodes) in &label_sets {
+        let entities: Vec<(Vec<String>, &HashMap<String, Value>)> = nodes
+            .iter()
+            .map(|node| {
+                (
+                    vec![node.id.to_string(), labels.join(":")],
+                    &node.properties,
+                )
+            })
+            .collect();
+        for node in nodes {
+            node_labels.insert(node.id, labels.first().cloned().unwrap_or_default());
+        }
+        let mut parts = vec!["nodes"];
+        parts.extend(labels.iter().map(String::as_str));
+        let path = export_path(dir, &files, &parts)?;
+        write_entities(&path, &["_id:ID", ":LABEL"], &entities, || {
+            nodes
+                .iter()
+                .flat_map(|node| node.properties.keys().cloned())
+                .collect()
+        })?;
+        files.push(path);
+    }
+
+    let mut rel_types: BTreeMap<String, RelationGroups> = BTreeMap::new();
+    for entity in export_entities(con, &key, "()-[r]->()", "r")? {
+        let relation = match entity {
+            GraphValue::Relation(relation) => relation,
+            _ => continue,
+        };
+        let label = |id: u64| {
+            node_labels.get(&id).map(String::as_str).ok_or_else(|| {
+                create_error(&format!(
+                    "Relation {} connects node {}, which was not exported",
+                    relation.id, id
+                ))
+            })
+        };
+        let endpoints = (label(relation.src_node)?, label(relation.dest_node)?);
+        let groups = rel_types.entry(relation.rel_type.clone()).or_default();
+        match groups.iter_mut().find(|(e, _)| *e == endpoints) {
+            Some((_, relations)) => relations.push(relation),
+            None => groups.push((endpoints, vec![relation])),
+        }
+    }
+    for (rel_type, groups) in &rel_types {
+        for (idx, ((src, dest), relations)) in groups.iter().enumerate() {
+            let entities: Vec<(Vec<String>, &HashMap<String, Value>)> = relations
+                .iter()
+                .map(|r| {
+                    (
+                        vec![
+                            r.src_node.to_string(),
+                            r.dest_node.to_string(),
+                            rel_type.clone(),
+                        ],
+                        &r.properties,
+                    )
+                })
+                .collect();
+            let group = (idx + 1).to_string();
+            let mut parts = vec!["relations", rel_type.as_str()];
+            if idx > 0 {
+                parts.push(&group);
+            }
+            let path = export_path(dir, &files, &parts)?;
+            let start = format!(":START_ID({})", src);
+            let end = format!(":END_ID({})", dest);
+            write_entities(&path, &[&start, &end, ":TYPE"], &entities, || {
+                relations
+                    .iter()
+                    .flat_map(|r| r.properties.keys().cloned())
+                    .collect()
+            })?;
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
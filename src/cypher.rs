@@ -0,0 +1,189 @@
+use crate::types::create_error;
+use redis::RedisResult;
+
+/// Classification of a Cypher query into read-only and writing queries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryKind {
+    /// The query can be executed with GRAPH.RO_QUERY.
+    ReadOnly,
+    /// The query contains write clauses and needs GRAPH.QUERY.
+    Write,
+}
+
+const WRITE_CLAUSES: [&str; 6] = ["CREATE", "MERGE", "SET", "DELETE", "REMOVE", "DROP"];
+const WRITE_PROCEDURE_PREFIXES: [&str; 4] = ["create", "drop", "delete", "set"];
+
+/// Classifies a Cypher query by looking for write clauses (CREATE, MERGE, SET,
+/// DELETE, REMOVE, DROP) and calls to write procedures (eg.
+/// db.idx.fulltext.createNodeIndex) outside of string literals, comments,
+/// escaped identifiers, labels, map keys and parameters. This is a lightweight
+/// lexical check and not a full parser, in case of doubt a query is classified
+/// as Write. This includes queries with unterminated string literals, escaped
+/// identifiers or block comments.
+///
+/// ```rust
+/// use redis_graph::{classify_query, QueryKind};
+///
+/// assert_eq!(classify_query("MATCH (n:Set) RETURN n.create"), QueryKind::ReadOnly);
+/// assert_eq!(classify_query("MATCH (n) SET n.name = 'x'"), QueryKind::Write);
+/// assert_eq!(classify_query("MATCH (n) WHERE n.name = 'x"), QueryKind::Write);
+/// ```
+pub fn classify_query(query: &str) -> QueryKind {
+    let tokens: Vec<Token> = match tokenize(query) {
+        Some(tokens) => tokens.into_iter().map(|(token, _)| token).collect(),
+        None => return QueryKind::Write,
+    };
+    for (idx, token) in tokens.iter().enumerate() {
+        if let Token::Word { text, qualified } = token {
+            if is_word_context(&tokens, idx) {
+                continue;
+            }
+            let upper = text.to_ascii_uppercase();
+            if !qualified && WRITE_CLAUSES.contains(&upper.as_str()) {
+                return QueryKind::Write;
+            }
+            if upper == "CALL" && calls_write_procedure(&tokens[idx + 1..]) {
+                return QueryKind::Write;
+            }
+        }
+    }
+    QueryKind::ReadOnly
+}
+
+/// Returns true if the query does not contain any write clauses.
+pub fn is_read_only_query(query: &str) -> bool {
+    classify_query(query) == QueryKind::ReadOnly
+}
+
+// Strips trailing semicolons, comments and whitespace from a query, so SKIP
+// and LIMIT clauses can be appended. Fails for queries with unterminated
+// literals or comments, with multiple statements and for queries that already
+// have SKIP or LIMIT after the last RETURN.
+pub(crate) fn pageable_query(query: &str) -> RedisResult<&str> {
+    let (mut tokens, mut ends): (Vec<Token>, Vec<usize>) = tokenize(query)
+        .ok_or_else(|| create_error("Unterminated literal or comment in paginated query"))?
+        .into_iter()
+        .unzip();
+    while tokens.last() == Some(&Token::Symbol(';')) {
+        tokens.pop();
+        ends.pop();
+    }
+    if tokens.contains(&Token::Symbol(';')) {
+        return Err(create_error("Paginated query contains multiple statements"));
+    }
+    let clause = |idx: usize| match &tokens[idx] {
+        Token::Word { text, .. } if !is_word_context(&tokens, idx) => {
+            Some(text.to_ascii_uppercase())
+        }
+        _ => None,
+    };
+    let last_return = (0..tokens.len())
+        .rev()
+        .find(|idx| clause(*idx).as_deref() == Some("RETURN"));
+    if let Some(start) = last_return {
+        let paging = (start..tokens.len())
+            .filter_map(clause)
+            .find(|word| word == "SKIP" || word == "LIMIT");
+        if let Some(paging) = paging {
+            return Err(create_error(&format!(
+                "Paginated query already has a {} clause",
+                paging
+            )));
+        }
+    }
+    Ok(&query[..ends.last().copied().unwrap_or_default()])
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Word { text: &'a str, qualified: bool },
+    Symbol(char),
+}
+
+// Words directly following a colon (labels, relation types), followed by a
+// colon (map keys) or following a $ (parameters) are not clauses.
+fn is_word_context(tokens: &[Token], idx: usize) -> bool {
+    let before = idx.checked_sub(1).and_then(|i| tokens.get(i));
+    let after = tokens.get(idx + 1);
+    matches!(before, Some(Token::Symbol(':')) | Some(Token::Symbol('$')))
+        || matches!(after, Some(Token::Symbol(':')))
+}
+
+fn calls_write_procedure(tokens: &[Token]) -> bool {
+    match tokens.first() {
+        Some(Token::Word { text, .. }) => {
+            let name = text.rsplit('.').next().unwrap_or_default().to_lowercase();
+            WRITE_PROCEDURE_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+        }
+        _ => false,
+    }
+}
+
+// Splits a query into words and symbols with their end positions, skipping
+// whitespace, comments, string literals and backtick escaped identifiers.
+// None if a literal, identifier or block comment is not terminated.
+fn tokenize(query: &str) -> Option<Vec<(Token<'_>, usize)>> {
+    let bytes = query.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        if c.is_ascii_whitespace() {
+            pos += 1;
+        } else if bytes[pos..].starts_with(b"//") {
+            pos = find_from(bytes, pos, b"\n").map_or(bytes.len(), |end| end + 1);
+        } else if bytes[pos..].starts_with(b"/*") {
+            pos = find_from(bytes, pos + 2, b"*/")? + 2;
+        } else if c == b'\'' || c == b'"' || c == b'`' {
+            pos = skip_quoted(bytes, pos)?;
+            tokens.push((Token::Symbol('"'), pos));
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let start = pos;
+            while pos < bytes.len()
+                && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_' || bytes[pos] == b'.')
+            {
+                pos += 1;
+            }
+            let text = &query[start..pos];
+            tokens.push((
+                Token::Word {
+                    text,
+                    qualified: text.contains('.'),
+                },
+                pos,
+            ));
+        } else {
+            let symbol = query[pos..].chars().next().unwrap_or_default();
+            pos += symbol.len_utf8();
+            tokens.push((Token::Symbol(symbol), pos));
+        }
+    }
+    Some(tokens)
+}
+
+// Returns the position after the closing quote of a literal starting at pos,
+// None if the literal is not closed.
+fn skip_quoted(bytes: &[u8], pos: usize) -> Option<usize> {
+    let quote = bytes[pos];
+    let mut idx = pos + 1;
+    while idx < bytes.len() {
+        if bytes[idx] == b'\\' && quote != b'`' {
+            idx += 2;
+        } else if bytes[idx] == quote {
+            return Some(idx + 1);
+        } else {
+            idx += 1;
+        }
+    }
+    None
+}
+
+fn find_from(bytes: &[u8], pos: usize, needle: &[u8]) -> Option<usize> {
+    bytes
+        .get(pos..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|idx| idx + pos)
+}
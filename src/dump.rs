@@ -0,0 +1,947 @@
+use crate::params::{escape_identifier, query_with_id, ParamValue};
+#[cfg(feature = "serde")]
+use crate::schema::{ConstraintInfo, IndexInfo};
+use crate::schema::{EntityType, GraphSchema, PropertyType, SchemaFetch};
+use crate::schema_diff::{SchemaDiff, SchemaStatement};
+use crate::types::*;
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+use futures_util::io::{AsyncBufRead, AsyncBufReadExt};
+use redis::{from_redis_value, FromRedisValue, RedisResult, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{BufRead, Lines, Write};
+
+// The property holding the dumped node id while relations are restored
+const DUMP_ID: &str = "__dump_id";
+
+/// The file format of a logical graph dump.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// A script with one statement per line: batched Cypher queries creating
+    /// the nodes and relations, followed by the index queries and
+    /// GRAPH.CONSTRAINT commands. Lines starting with `//` are comments.
+    Cypher,
+    /// A JSON archive with the indexes, constraints, nodes and relations.
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+/// A logical dump of a graph, independent of the module version unlike
+/// DUMP and RESTORE of the graph key. Nodes and relations are read in pages
+/// ordered by id with read-only queries, so the graph should not be written
+/// while it is dumped. Property types are kept, except for the elements of
+/// nested lists which are restored as integers or strings. Points, maps,
+/// vectors and lists containing them are skipped and listed in the result.
+/// Indexes other than range and fulltext indexes on nodes are not supported.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let dump = GraphDump::new(DumpFormat::Cypher).with_page_size(5000);
+/// let summary = con.graph_dump("my_graph", &dump, File::create("my_graph.cypher")?)?;
+/// println!("Dumped {} nodes and {} relations", summary.nodes, summary.relations);
+///
+/// let input = BufReader::new(File::open("my_graph.cypher")?);
+/// con.graph_restore("my_graph_copy", &GraphRestore::new(DumpFormat::Cypher), input, |p| {
+///     println!("{} statements, {} nodes created", p.statements, p.statistics.nodes_created);
+/// })?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphDump {
+    format: DumpFormat,
+    page_size: usize,
+}
+
+impl GraphDump {
+    /// A dump in the given format with pages of 1000 nodes or relations.
+    pub fn new(format: DumpFormat) -> Self {
+        GraphDump {
+            format,
+            page_size: 1000,
+        }
+    }
+
+    /// Sets the number of nodes or relations read per query (at least 1),
+    /// which is also the batch size of the statements in a Cypher script.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    pub(crate) fn fetch<W: Write>(&self, out: W) -> DumpFetch<W> {
+        DumpFetch {
+            page_size: self.page_size,
+            schema: Some(SchemaFetch::indexes_and_constraints()),
+            step: DumpStep::Schema,
+            writer: DumpWriter {
+                out,
+                format: self.format,
+                statements: DumpStatements::default(),
+                schema: GraphSchema::default(),
+                #[cfg(feature = "serde")]
+                first: true,
+                #[cfg(feature = "serde")]
+                relations: false,
+            },
+            result: DumpResult::default(),
+        }
+    }
+}
+
+/// The number of entries written by a dump.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct DumpResult {
+    pub nodes: usize,
+    pub relations: usize,
+    pub indexes: usize,
+    pub constraints: usize,
+    /// The properties that were not written, in dump order.
+    pub skipped: Vec<DumpSkipped>,
+}
+
+/// A property that was not dumped as its type has no Cypher literal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DumpSkipped {
+    pub entity_type: EntityType,
+    /// The id of the node or relation in the dumped graph.
+    pub id: u64,
+    pub property: String,
+    pub reason: String,
+}
+
+/// Replays a dump into a fresh graph. A Cypher script is executed
+/// statement by statement, a JSON archive is written in batches of
+/// batch_rows nodes or relations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphRestore {
+    format: DumpFormat,
+    batch_rows: usize,
+}
+
+impl GraphRestore {
+    pub fn new(format: DumpFormat) -> Self {
+        GraphRestore {
+            format,
+            batch_rows: 1000,
+        }
+    }
+
+    /// Sets the maximum number of nodes or relations per query of a JSON
+    /// archive restore (at least 1).
+    pub fn with_batch_rows(mut self, rows: usize) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+
+    pub(crate) fn statements<R: BufRead>(&self, input: R) -> RedisResult<RestoreStatements<R>> {
+        match self.format {
+            DumpFormat::Cypher => Ok(RestoreStatements::Script {
+                lines: input.lines(),
+                line: 0,
+            }),
+            #[cfg(feature = "serde")]
+            DumpFormat::Json => Ok(RestoreStatements::Archive(self.archive_statements(input)?)),
+        }
+    }
+
+    // The statements of an async restore. A script is read line by line, an
+    // archive is read completely and then parsed.
+    #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+    pub(crate) async fn async_statements<R>(
+        &self,
+        input: R,
+    ) -> RedisResult<AsyncRestoreStatements<R>>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        match self.format {
+            DumpFormat::Cypher => Ok(AsyncRestoreStatements::Script { input, line: 0 }),
+            #[cfg(feature = "serde")]
+            DumpFormat::Json => {
+                let (mut input, mut archive) = (input, vec![]);
+                futures_util::io::AsyncReadExt::read_to_end(&mut input, &mut archive)
+                    .await
+                    .map_err(|e| create_error(&format!("Failed to read dump: {}", e)))?;
+                Ok(AsyncRestoreStatements::Archive(
+                    self.archive_statements(archive.as_slice())?,
+                ))
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn archive_statements<R: std::io::Read>(
+        &self,
+        input: R,
+    ) -> RedisResult<std::vec::IntoIter<SchemaStatement>> {
+        let archive: JsonArchive = serde_json::from_reader(input)
+            .map_err(|e| create_error(&format!("Failed to read dump: {}", e)))?;
+        Ok(archive.statements(self.batch_rows)?.into_iter())
+    }
+}
+
+/// The progress of a restore, passed to the progress callback after every
+/// statement.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct RestoreProgress {
+    /// Number of executed statements.
+    pub statements: usize,
+    pub statistics: QueryStatistics,
+}
+
+impl RestoreProgress {
+    pub(crate) fn add(&mut self, statement: &SchemaStatement, v: &Value) -> RedisResult<()> {
+        self.statements += 1;
+        if let SchemaStatement::Query(_) = statement {
+            self.statistics
+                .add(&GraphResultSet::from_redis_value(v)?.statistics());
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn fresh_key_error() -> redis::RedisError {
+    create_error("The graph already exists, a restore needs a fresh key")
+}
+
+/// A node read by a dump or copy with typed property values, as reported by
+/// the typeOf function.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypedNode {
+    pub id: u64,
+    pub labels: Vec<String>,
+    pub properties: BTreeMap<String, ParamValue>,
+}
+
+/// A relation read by a dump or copy with typed property values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypedRelation {
+    pub id: u64,
+    pub rel_type: String,
+    /// The id of the source node in the dumped or copied graph.
+    pub src: u64,
+    /// The id of the destination node in the dumped or copied graph.
+    pub dest: u64,
+    pub properties: BTreeMap<String, ParamValue>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum DumpStep {
+    Schema,
+    Nodes(u64),
+    Relations(u64),
+    Done,
+}
+
+// Walks a graph with a sequence of read-only queries: the indexes and
+// constraints first, then the nodes and the relations page by page.
+pub(crate) struct DumpFetch<W: Write> {
+    page_size: usize,
+    schema: Option<SchemaFetch>,
+    step: DumpStep,
+    writer: DumpWriter<W>,
+    result: DumpResult,
+}
+
+impl<W: Write> DumpFetch<W> {
+    // The next query to run, None once the dump is written
+    pub(crate) fn next_query(&self) -> Option<String> {
+        match self.step {
+            DumpStep::Schema => self.schema.as_ref()?.next_query(),
+            DumpStep::Nodes(from) => Some(node_page_query(from, self.page_size)),
+            DumpStep::Relations(from) => Some(relation_page_query(from, self.page_size)),
+            DumpStep::Done => None,
+        }
+    }
+
+    // Applies the response to the query returned by next_query
+    pub(crate) fn add_response(&mut self, v: &Value) -> RedisResult<()> {
+        match self.step {
+            DumpStep::Schema => {
+                let mut fetch = match self.schema.take() {
+                    Some(fetch) => fetch,
+                    None => return Err(create_error("Dump is already complete")),
+                };
+                fetch.add_response(v)?;
+                if fetch.next_query().is_some() {
+                    self.schema = Some(fetch);
+                    return Ok(());
+                }
+                let schema = fetch.finish();
+                self.result.indexes = schema.indexes.len();
+                self.result.constraints = schema.constraints.len();
+                self.writer.start(schema)?;
+                self.step = DumpStep::Nodes(0);
+            }
+            DumpStep::Nodes(_) => {
+                let nodes = parse_nodes(v, Some(&mut self.result.skipped))?;
+                self.result.nodes += nodes.len();
+                self.writer.nodes(&nodes)?;
+                // Pages may be truncated by RESULTSET_SIZE, so only an empty
+                // page ends the nodes
+                self.step = match nodes.last() {
+                    Some(node) => DumpStep::Nodes(node.id + 1),
+                    None => DumpStep::Relations(0),
+                };
+            }
+            DumpStep::Relations(_) => {
+                let relations = parse_relations(v, Some(&mut self.result.skipped))?;
+                self.result.relations += relations.len();
+                self.writer.relations(&relations)?;
+                self.step = match relations.last() {
+                    Some(relation) => DumpStep::Relations(relation.id + 1),
+                    None => {
+                        self.writer.finish()?;
+                        DumpStep::Done
+                    }
+                };
+            }
+            DumpStep::Done => return Err(create_error("Dump is already complete")),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> DumpResult {
+        self.result
+    }
+}
+
+// A page of nodes ordered by id, starting at id from
+pub(crate) fn node_page_query(from: u64, page_size: usize) -> String {
+    query_with_id(
+        &format!(
+            "MATCH (n) WHERE id(n) >= $from RETURN n, {} ORDER BY id(n) LIMIT {}",
+            property_types("n"),
+            page_size
+        ),
+        "from",
+        from,
+    )
+}
+
+// A page of relations ordered by id, starting at id from
+pub(crate) fn relation_page_query(from: u64, page_size: usize) -> String {
+    query_with_id(
+        &format!(
+            "MATCH ()-[r]->() WHERE id(r) >= $from RETURN r, {} ORDER BY id(r) LIMIT {}",
+            property_types("r"),
+            page_size
+        ),
+        "from",
+        from,
+    )
+}
+
+// Parses a page of nodes. Properties of unsupported types are added to
+// skipped, or fail the page without it.
+pub(crate) fn parse_nodes(
+    v: &Value,
+    mut skipped: Option<&mut Vec<DumpSkipped>>,
+) -> RedisResult<Vec<TypedNode>> {
+    let mut nodes = vec![];
+    for (entity, types) in page_rows(v)? {
+        let node = NodeValue::from_redis_value(entity)?;
+        let entity = (EntityType::Node, node.id);
+        nodes.push(TypedNode {
+            id: node.id,
+            properties: typed_properties(&node.properties, types, entity, &mut skipped)?,
+            labels: node.labels,
+        });
+    }
+    Ok(nodes)
+}
+
+// Parses a page of relations like parse_nodes
+pub(crate) fn parse_relations(
+    v: &Value,
+    mut skipped: Option<&mut Vec<DumpSkipped>>,
+) -> RedisResult<Vec<TypedRelation>> {
+    let mut relations = vec![];
+    for (entity, types) in page_rows(v)? {
+        let relation = RelationValue::from_redis_value(entity)?;
+        let entity = (EntityType::Relationship, relation.id);
+        relations.push(TypedRelation {
+            id: relation.id,
+            properties: typed_properties(&relation.properties, types, entity, &mut skipped)?,
+            rel_type: relation.rel_type,
+            src: relation.src_node,
+            dest: relation.dest_node,
+        });
+    }
+    Ok(relations)
+}
+
+// Returns every property key with its type and the types of list elements,
+// since the reply does not tell floats and booleans from strings
+fn property_types(var: &str) -> String {
+    format!(
+        "[k IN keys({0}) | [k, typeOf({0}[k]), \
+         CASE typeOf({0}[k]) WHEN 'List' THEN [x IN {0}[k] | typeOf(x)] ELSE [] END]]",
+        var
+    )
+}
+
+// The (entity, property types) rows of a page
+fn page_rows(v: &Value) -> RedisResult<Vec<(&Value, &Value)>> {
+    let invalid = || create_error("Unexpected dump page response");
+    let rows = match v {
+        Value::Bulk(values) if values.len() == 3 => match &values[1] {
+            Value::Bulk(rows) => rows,
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+    rows.iter()
+        .map(|row| match row {
+            Value::Bulk(values) if values.len() == 2 => Ok((&values[0], &values[1])),
+            _ => Err(invalid()),
+        })
+        .collect()
+}
+
+fn typed_properties(
+    properties: &HashMap<String, Value>,
+    types: &Value,
+    (entity_type, id): (EntityType, u64),
+    skipped: &mut Option<&mut Vec<DumpSkipped>>,
+) -> RedisResult<BTreeMap<String, ParamValue>> {
+    let types: Vec<Value> = from_redis_value(types)?;
+    let mut typed = BTreeMap::new();
+    for types in types.iter() {
+        let (key, type_name, element_types): (String, String, Vec<String>) =
+            from_redis_value(types)?;
+        let value = match properties.get(&key) {
+            Some(value) => value,
+            None => continue,
+        };
+        let property_type = PropertyType::from(type_name);
+        if let Some(skipped) = skipped.as_mut() {
+            let unsupported = std::iter::once(property_type.clone())
+                .chain(element_types.iter().map(|t| PropertyType::from(t.as_str())))
+                .find(|t| !is_supported(t));
+            if let Some(unsupported) = unsupported {
+                skipped.push(DumpSkipped {
+                    entity_type,
+                    id,
+                    property: key,
+                    reason: format!("unsupported type {}", unsupported),
+                });
+                continue;
+            }
+        }
+        let value = typed_value(value, &property_type, &element_types)
+            .map_err(|e| create_error(&format!("Cannot dump property {}: {}", key, e)))?;
+        typed.insert(key, value);
+    }
+    Ok(typed)
+}
+
+// Whether typed_value converts values of the type
+fn is_supported(property_type: &PropertyType) -> bool {
+    matches!(
+        property_type,
+        PropertyType::Boolean
+            | PropertyType::Integer
+            | PropertyType::Float
+            | PropertyType::String
+            | PropertyType::List
+    )
+}
+
+fn typed_value(
+    value: &Value,
+    property_type: &PropertyType,
+    element_types: &[String],
+) -> Result<ParamValue, String> {
+    match (property_type, value) {
+        (_, Value::Nil) => Ok(ParamValue::Null),
+        (PropertyType::Integer, Value::Int(v)) => Ok(ParamValue::Int(*v)),
+        (PropertyType::Float, _) => from_redis_value(value)
+            .map(ParamValue::Float)
+            .map_err(|e| e.to_string()),
+        (PropertyType::String, _) => from_redis_value(value)
+            .map(ParamValue::String)
+            .map_err(|e| e.to_string()),
+        (PropertyType::Boolean, _) => match from_redis_value::<String>(value).as_deref() {
+            Ok("true") => Ok(ParamValue::Bool(true)),
+            Ok("false") => Ok(ParamValue::Bool(false)),
+            _ => Err(format!("{:?} is not a boolean", value)),
+        },
+        (PropertyType::List, Value::Bulk(values)) => values
+            .iter()
+            .enumerate()
+            .map(|(idx, v)| match element_types.get(idx) {
+                Some(element_type) => {
+                    typed_value(v, &PropertyType::from(element_type.as_str()), &[])
+                }
+                None => ParamValue::from_redis(v).map_err(|e| e.to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(ParamValue::List),
+        (property_type, _) => Err(format!("unsupported type {}", property_type)),
+    }
+}
+
+// Writes the dump in either format as the pages arrive
+pub(crate) struct DumpWriter<W: Write> {
+    out: W,
+    format: DumpFormat,
+    statements: DumpStatements,
+    schema: GraphSchema,
+    // Whether the next JSON record is the first of its list
+    #[cfg(feature = "serde")]
+    first: bool,
+    #[cfg(feature = "serde")]
+    relations: bool,
+}
+
+impl<W: Write> DumpWriter<W> {
+    fn start(&mut self, schema: GraphSchema) -> RedisResult<()> {
+        match self.format {
+            DumpFormat::Cypher => {
+                self.write("// Logical dump of a Redis graph, one statement per line\n")?
+            }
+            #[cfg(feature = "serde")]
+            DumpFormat::Json => {
+                let header = format!(
+                    "{{\"version\":1,\"indexes\":{},\"constraints\":{},\"nodes\":[",
+                    to_json(&schema.indexes)?,
+                    to_json(&schema.constraints)?
+                );
+                self.write(&header)?;
+            }
+        }
+        self.schema = schema;
+        Ok(())
+    }
+
+    fn nodes(&mut self, nodes: &[TypedNode]) -> RedisResult<()> {
+        match self.format {
+            DumpFormat::Cypher => {
+                let statements = self.statements.nodes(nodes)?;
+                self.write_statements(&statements)
+            }
+            #[cfg(feature = "serde")]
+            DumpFormat::Json => nodes
+                .iter()
+                .try_for_each(|node| self.write_record(&JsonNode::from(node))),
+        }
+    }
+
+    fn relations(&mut self, relations: &[TypedRelation]) -> RedisResult<()> {
+        match self.format {
+            DumpFormat::Cypher => {
+                let statements = self.statements.relations(relations)?;
+                self.write_statements(&statements)
+            }
+            #[cfg(feature = "serde")]
+            DumpFormat::Json => {
+                if !self.relations {
+                    self.relations = true;
+                    self.first = true;
+                    self.write("\n],\"relations\":[")?;
+                }
+                relations
+                    .iter()
+                    .try_for_each(|relation| self.write_record(&JsonRelation::from(relation)))
+            }
+        }
+    }
+
+    fn finish(&mut self) -> RedisResult<()> {
+        match self.format {
+            DumpFormat::Cypher => {
+                let statements = self.statements.finish(&self.schema);
+                self.write_statements(&statements)?;
+            }
+            #[cfg(feature = "serde")]
+            DumpFormat::Json => self.write("\n]}\n")?,
+        }
+        self.out
+            .flush()
+            .map_err(|e| create_error(&format!("Failed to write dump: {}", e)))
+    }
+
+    fn write_statements(&mut self, statements: &[SchemaStatement]) -> RedisResult<()> {
+        statements
+            .iter()
+            .try_for_each(|statement| self.write(&format!("{}\n", statement)))
+    }
+
+    #[cfg(feature = "serde")]
+    fn write_record<T: serde::Serialize>(&mut self, record: &T) -> RedisResult<()> {
+        let separator = if self.first { "\n" } else { ",\n" };
+        self.first = false;
+        self.write(separator)?;
+        self.write(&to_json(record)?)
+    }
+
+    fn write(&mut self, s: &str) -> RedisResult<()> {
+        self.out
+            .write_all(s.as_bytes())
+            .map_err(|e| create_error(&format!("Failed to write dump: {}", e)))
+    }
+}
+
+// The source and destination node patterns and the type of a relation
+type RelationGroup<'a> = (String, String, &'a str);
+
+// Turns dumped nodes and relations into batched queries. Nodes are created
+// with their dumped id in a temporary property, which relations match on
+// with an index per first label and which is removed at the end.
+#[derive(Default)]
+pub(crate) struct DumpStatements {
+    first_labels: HashMap<u64, Option<String>>,
+    indexed: BTreeSet<String>,
+    unlabeled: bool,
+}
+
+impl DumpStatements {
+    fn nodes(&mut self, nodes: &[TypedNode]) -> RedisResult<Vec<SchemaStatement>> {
+        let mut statements = vec![];
+        let mut groups: Vec<(&[String], Vec<ParamValue>)> = vec![];
+        for node in nodes {
+            let first = node.labels.first().cloned();
+            match &first {
+                Some(label) if self.indexed.insert(label.clone()) => {
+                    statements.push(SchemaStatement::Query(format!(
+                        "CREATE INDEX FOR (n:{}) ON (n.{})",
+                        escape_identifier(label),
+                        DUMP_ID
+                    )));
+                }
+                Some(_) => {}
+                None => self.unlabeled = true,
+            }
+            self.first_labels.insert(node.id, first);
+            let mut row = vec![(DUMP_ID.to_string(), ParamValue::Int(node.id as i64))];
+            row.extend(node.properties.clone());
+            let row = ParamValue::Map(row);
+            match groups.iter_mut().find(|(labels, _)| *labels == node.labels) {
+                Some((_, rows)) => rows.push(row),
+                None => groups.push((&node.labels, vec![row])),
+            }
+        }
+        for (labels, rows) in groups {
+            let labels: String = labels
+                .iter()
+                .map(|label| format!(":{}", escape_identifier(label)))
+                .collect();
+            statements.push(SchemaStatement::Query(format!(
+                "UNWIND {} AS row CREATE (n{}) SET n = row",
+                ParamValue::List(rows).to_cypher()?,
+                labels
+            )));
+        }
+        Ok(statements)
+    }
+
+    fn relations(&mut self, relations: &[TypedRelation]) -> RedisResult<Vec<SchemaStatement>> {
+        let mut groups: Vec<(RelationGroup, Vec<ParamValue>)> = vec![];
+        for relation in relations {
+            let pattern = |id: u64| match self.first_labels.get(&id) {
+                Some(Some(label)) => Ok(format!(":{}", escape_identifier(label))),
+                Some(None) => Ok(String::new()),
+                None => Err(create_error(&format!(
+                    "Relation {} references node {} missing from the dump",
+                    relation.id, id
+                ))),
+            };
+            let group = (
+                pattern(relation.src)?,
+                pattern(relation.dest)?,
+                relation.rel_type.as_str(),
+            );
+            let row = ParamValue::Map(vec![
+                ("src".to_string(), ParamValue::Int(relation.src as i64)),
+                ("dest".to_string(), ParamValue::Int(relation.dest as i64)),
+                (
+                    "properties".to_string(),
+                    ParamValue::Map(relation.properties.clone().into_iter().collect()),
+                ),
+            ]);
+            match groups.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, rows)) => rows.push(row),
+                None => groups.push((group, vec![row])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|((src, dest, rel_type), rows)| {
+                Ok(SchemaStatement::Query(format!(
+                    "UNWIND {rows} AS row MATCH (a{src} {{{id}: row.src}}), (b{dest} {{{id}: row.dest}}) \
+                     CREATE (a)-[r:{rel_type}]->(b) SET r = row.properties",
+                    rows = ParamValue::List(rows).to_cypher()?,
+                    id = DUMP_ID,
+                    rel_type = escape_identifier(rel_type)
+                )))
+            })
+            .collect()
+    }
+
+    // Removes the temporary ids and indexes, then creates the indexes and
+    // constraints of the dumped graph
+    fn finish(&self, schema: &GraphSchema) -> Vec<SchemaStatement> {
+        let mut statements = vec![];
+        for label in self.indexed.iter() {
+            statements.push(SchemaStatement::Query(format!(
+                "MATCH (n:{0}) WHERE n.{1} IS NOT NULL SET n.{1} = NULL",
+                escape_identifier(label),
+                DUMP_ID
+            )));
+            statements.push(SchemaStatement::Query(format!(
+                "DROP INDEX ON :{}({})",
+                escape_identifier(label),
+                DUMP_ID
+            )));
+        }
+        if self.unlabeled {
+            statements.push(SchemaStatement::Query(format!(
+                "MATCH (n) WHERE n.{0} IS NOT NULL SET n.{0} = NULL",
+                DUMP_ID
+            )));
+        }
+        statements.extend(SchemaDiff::compare(schema, &GraphSchema::new()).statements(false));
+        statements
+    }
+}
+
+// The statements of a restore, read line by line from a script or generated
+// from an archive
+pub(crate) enum RestoreStatements<R> {
+    Script {
+        lines: Lines<R>,
+        line: usize,
+    },
+    #[cfg(feature = "serde")]
+    Archive(std::vec::IntoIter<SchemaStatement>),
+}
+
+impl<R: BufRead> Iterator for RestoreStatements<R> {
+    type Item = RedisResult<SchemaStatement>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            RestoreStatements::Script { lines, line } => loop {
+                *line += 1;
+                let text = match lines.next()? {
+                    Ok(text) => text,
+                    Err(e) => return Some(Err(read_error(*line, e))),
+                };
+                if let Some(statement) = script_statement(&text) {
+                    return Some(statement);
+                }
+            },
+            #[cfg(feature = "serde")]
+            RestoreStatements::Archive(statements) => statements.next().map(Ok),
+        }
+    }
+}
+
+// The statements of an async restore
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub(crate) enum AsyncRestoreStatements<R> {
+    Script {
+        input: R,
+        line: usize,
+    },
+    #[cfg(feature = "serde")]
+    Archive(std::vec::IntoIter<SchemaStatement>),
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+impl<R: AsyncBufRead + Unpin> AsyncRestoreStatements<R> {
+    pub(crate) async fn next(&mut self) -> Option<RedisResult<SchemaStatement>> {
+        match self {
+            AsyncRestoreStatements::Script { input, line } => loop {
+                *line += 1;
+                let mut text = String::new();
+                match input.read_line(&mut text).await {
+                    Ok(0) => return None,
+                    Ok(_) => {}
+                    Err(e) => return Some(Err(read_error(*line, e))),
+                }
+                if let Some(statement) = script_statement(&text) {
+                    return Some(statement);
+                }
+            },
+            #[cfg(feature = "serde")]
+            AsyncRestoreStatements::Archive(statements) => statements.next().map(Ok),
+        }
+    }
+}
+
+fn read_error(line: usize, e: std::io::Error) -> redis::RedisError {
+    create_error(&format!("Failed to read dump line {}: {}", line, e))
+}
+
+// The statement of a script line, None for empty lines and comments
+fn script_statement(text: &str) -> Option<RedisResult<SchemaStatement>> {
+    let text = text.trim();
+    if text.is_empty() || text.starts_with("//") {
+        return None;
+    }
+    Some(SchemaStatement::parse(text))
+}
+
+#[cfg(feature = "serde")]
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> RedisResult<String> {
+    serde_json::to_string(value).map_err(|e| create_error(&format!("Failed to write dump: {}", e)))
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct JsonArchive {
+    version: u32,
+    #[serde(default)]
+    indexes: Vec<IndexInfo>,
+    #[serde(default)]
+    constraints: Vec<ConstraintInfo>,
+    nodes: Vec<JsonNode>,
+    relations: Vec<JsonRelation>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct JsonNode {
+    id: u64,
+    labels: Vec<String>,
+    properties: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct JsonRelation {
+    id: u64,
+    #[serde(rename = "type")]
+    rel_type: String,
+    src: u64,
+    dest: u64,
+    properties: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "serde")]
+impl JsonArchive {
+    fn statements(self, batch_rows: usize) -> RedisResult<Vec<SchemaStatement>> {
+        if self.version != 1 {
+            return Err(create_error(&format!(
+                "Unsupported dump version {}",
+                self.version
+            )));
+        }
+        let nodes: Vec<TypedNode> = self.nodes.into_iter().map(TypedNode::from).collect();
+        let relations: Vec<TypedRelation> = self
+            .relations
+            .into_iter()
+            .map(TypedRelation::from)
+            .collect();
+        let mut generator = DumpStatements::default();
+        let mut statements = vec![];
+        for batch in nodes.chunks(batch_rows) {
+            statements.extend(generator.nodes(batch)?);
+        }
+        for batch in relations.chunks(batch_rows) {
+            statements.extend(generator.relations(batch)?);
+        }
+        let schema = GraphSchema {
+            indexes: self.indexes,
+            constraints: self.constraints,
+            ..GraphSchema::default()
+        };
+        statements.extend(generator.finish(&schema));
+        Ok(statements)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn json_properties<'a, I: IntoIterator<Item = (&'a String, &'a ParamValue)>>(
+    properties: I,
+) -> serde_json::Map<String, serde_json::Value> {
+    properties
+        .into_iter()
+        .map(|(key, value)| (key.clone(), json_value(value)))
+        .collect()
+}
+
+#[cfg(feature = "serde")]
+fn json_value(value: &ParamValue) -> serde_json::Value {
+    match value {
+        ParamValue::Null => serde_json::Value::Null,
+        ParamValue::Bool(v) => serde_json::Value::Bool(*v),
+        ParamValue::Int(v) => serde_json::Value::from(*v),
+        ParamValue::Float(v) => serde_json::Number::from_f64(*v)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        ParamValue::String(v) => serde_json::Value::String(v.clone()),
+        ParamValue::List(values) => {
+            serde_json::Value::Array(values.iter().map(json_value).collect())
+        }
+        ParamValue::Map(entries) => {
+            serde_json::Value::Object(json_properties(entries.iter().map(|(k, v)| (k, v))))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn param_properties(
+    properties: serde_json::Map<String, serde_json::Value>,
+) -> BTreeMap<String, ParamValue> {
+    properties
+        .into_iter()
+        .map(|(key, value)| (key, ParamValue::from_json(value)))
+        .collect()
+}
+
+#[cfg(feature = "serde")]
+impl From<&TypedNode> for JsonNode {
+    fn from(node: &TypedNode) -> Self {
+        JsonNode {
+            id: node.id,
+            labels: node.labels.clone(),
+            properties: json_properties(&node.properties),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<JsonNode> for TypedNode {
+    fn from(node: JsonNode) -> Self {
+        TypedNode {
+            id: node.id,
+            labels: node.labels,
+            properties: param_properties(node.properties),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<&TypedRelation> for JsonRelation {
+    fn from(relation: &TypedRelation) -> Self {
+        JsonRelation {
+            id: relation.id,
+            rel_type: relation.rel_type.clone(),
+            src: relation.src,
+            dest: relation.dest,
+            properties: json_properties(&relation.properties),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<JsonRelation> for TypedRelation {
+    fn from(relation: JsonRelation) -> Self {
+        TypedRelation {
+            id: relation.id,
+            rel_type: relation.rel_type,
+            src: relation.src,
+            dest: relation.dest,
+            properties: param_properties(relation.properties),
+        }
+    }
+}
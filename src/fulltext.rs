@@ -0,0 +1,151 @@
+use crate::params::string_literal;
+use std::fmt;
+
+// Characters with a meaning in the RediSearch query syntax
+const SPECIAL_CHARS: &str = ",.<>{}[]\"':;!@#$%^&*()-+=~|/\\";
+
+/// Builder for RediSearch query strings as used by full-text indexes. All
+/// text is escaped, the added clauses are combined with AND. Clauses built
+/// from empty queries are skipped, as they have no valid syntax.
+///
+/// ```rust
+/// use redis_graph::FulltextQuery;
+///
+/// let query = FulltextQuery::new()
+///     .prefix("rob")
+///     .field("title", FulltextQuery::new().phrase("the doctor"))
+///     .not(FulltextQuery::new().term("co-pilot"));
+/// assert_eq!(query.to_string(), "rob* @title:\"the doctor\" -co\\-pilot");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FulltextQuery {
+    clauses: Vec<String>,
+}
+
+impl FulltextQuery {
+    /// Creates an empty query.
+    pub fn new() -> Self {
+        FulltextQuery::default()
+    }
+
+    /// Matches a single term.
+    pub fn term<T: AsRef<str>>(self, term: T) -> Self {
+        let clause = escape_fulltext(term.as_ref());
+        self.push(clause)
+    }
+
+    /// Matches terms starting with prefix.
+    pub fn prefix<T: AsRef<str>>(self, prefix: T) -> Self {
+        let clause = format!("{}*", escape_fulltext(prefix.as_ref()));
+        self.push(clause)
+    }
+
+    /// Matches terms within a Levenshtein distance of 1 to 3 of term.
+    pub fn fuzzy<T: AsRef<str>>(self, term: T, distance: u8) -> Self {
+        let marks = "%".repeat(distance.clamp(1, 3) as usize);
+        let clause = format!("{}{}{}", marks, escape_fulltext(term.as_ref()), marks);
+        self.push(clause)
+    }
+
+    /// Matches the exact phrase.
+    pub fn phrase<T: AsRef<str>>(self, phrase: T) -> Self {
+        let escaped = phrase.as_ref().replace('\\', "\\\\").replace('"', "\\\"");
+        self.push(format!("\"{}\"", escaped))
+    }
+
+    /// Restricts query to the indexed property field. Skipped if query is
+    /// empty.
+    pub fn field<F: AsRef<str>>(self, field: F, query: FulltextQuery) -> Self {
+        if query.is_empty() {
+            return self;
+        }
+        let clause = format!("@{}:{}", escape_fulltext(field.as_ref()), query.grouped());
+        self.push(clause)
+    }
+
+    /// Matches if any of the queries matches. Empty queries are skipped, the
+    /// clause is skipped if all are empty.
+    pub fn any(self, queries: Vec<FulltextQuery>) -> Self {
+        let alternatives: Vec<String> = queries
+            .iter()
+            .filter(|query| !query.is_empty())
+            .map(FulltextQuery::grouped)
+            .collect();
+        match alternatives.len() {
+            0 => self,
+            1 => self.push(alternatives[0].clone()),
+            _ => self.push(format!("({})", alternatives.join("|"))),
+        }
+    }
+
+    /// Excludes results matching query. Skipped if query is empty.
+    pub fn not(self, query: FulltextQuery) -> Self {
+        if query.is_empty() {
+            return self;
+        }
+        let clause = format!("-{}", query.grouped());
+        self.push(clause)
+    }
+
+    /// Adds a raw clause in RediSearch syntax without escaping.
+    pub fn raw<T: Into<String>>(self, clause: T) -> Self {
+        self.push(clause.into())
+    }
+
+    /// Returns true if no clause was added.
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    fn push(mut self, clause: String) -> Self {
+        if !clause.is_empty() {
+            self.clauses.push(clause);
+        }
+        self
+    }
+
+    // Wraps queries with multiple clauses in parentheses
+    fn grouped(&self) -> String {
+        if self.clauses.len() > 1 {
+            format!("({})", self)
+        } else {
+            self.to_string()
+        }
+    }
+}
+
+impl fmt::Display for FulltextQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.clauses.join(" "))
+    }
+}
+
+/// Escapes all characters with a meaning in the RediSearch query syntax and
+/// whitespace with a backslash.
+pub fn escape_fulltext(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_whitespace() || SPECIAL_CHARS.contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// Calls db.idx.fulltext.queryNodes, pages are ordered by descending score and
+// node id to be stable.
+pub(crate) fn fulltext_cypher(label: &str, query: &str, page: Option<(usize, usize)>) -> String {
+    let call = format!(
+        "CALL db.idx.fulltext.queryNodes({}, {}) YIELD node, score RETURN node, score",
+        string_literal(label),
+        string_literal(query)
+    );
+    match page {
+        Some((offset, limit)) => format!(
+            "{} ORDER BY score DESC, id(node) SKIP {} LIMIT {}",
+            call, offset, limit
+        ),
+        None => call,
+    }
+}
@@ -0,0 +1,610 @@
+use crate::dump::{
+    node_page_query, parse_nodes, parse_relations, relation_page_query, TypedNode, TypedRelation,
+};
+use crate::params::{escape_identifier, query_with_id, ParamValue};
+use crate::schema::{EntityType, GraphSchema, SchemaFetch};
+use crate::schema_diff::{SchemaDiff, SchemaStatement};
+use crate::types::*;
+use redis::{cmd, ConnectionLike, FromRedisValue, RedisResult, ToRedisArgs, Value};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+// The source id kept on the created nodes until the relations are copied
+const COPY_ID: &str = "__copy_id";
+
+type NodeTransform = Box<dyn FnMut(TypedNode) -> Option<TypedNode> + Send>;
+type RelationTransform = Box<dyn FnMut(TypedRelation) -> Option<TypedRelation> + Send>;
+
+/// The phase a copy is in.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CopyStep {
+    #[default]
+    Nodes,
+    Relations,
+    /// Recreating the indexes and constraints.
+    Schema,
+    Done,
+}
+
+/// The position of a copy, updated after every write to the target. Passing
+/// the last checkpoint to `GraphCopy::with_checkpoint` resumes an
+/// interrupted copy without skipping or repeating a write.
+#[derive(Default, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct CopyCheckpoint {
+    pub step: CopyStep,
+    /// The next source node or relation id to read, or the number of index
+    /// and constraint statements already executed.
+    pub next: u64,
+    /// The labels written to the target.
+    pub labels: BTreeSet<String>,
+    /// The relationship types written to the target.
+    pub relationship_types: BTreeSet<String>,
+}
+
+/// The number of copied and skipped entities of a copy run, a resumed copy
+/// only counts the entities of the resumed run.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct CopyResult {
+    pub nodes: usize,
+    /// Nodes dropped by the node transform.
+    pub nodes_skipped: usize,
+    pub relations: usize,
+    /// Relations dropped by the relation transform or with a skipped node.
+    pub relations_skipped: usize,
+    /// Executed index and constraint statements.
+    pub statements: usize,
+    pub statistics: QueryStatistics,
+}
+
+/// Copies a graph from one connection to another, e.g. between servers.
+/// Nodes and relations are read in id-ordered pages with read-only queries,
+/// passed through the transforms and written in batches of consecutive
+/// entities with the same labels or type. Source node ids are mapped to the
+/// ids of the created nodes, which the relations are created between. The
+/// created nodes keep their source id in a `__copy_id` property until the
+/// relations are copied, so a resumed copy reads the mapping back from the
+/// target instead of the checkpoint. Finally the indexes and constraints of
+/// the source are recreated for the labels and relationship types written to
+/// the target, so indexes of renamed labels have to be created separately.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let mut source = redis::Client::open("redis://staging/")?.get_connection()?;
+/// let mut target = redis::Client::open("redis://production/")?.get_connection()?;
+///
+/// let mut copy = GraphCopy::new()
+///     .with_node_transform(|mut node| {
+///         for label in node.labels.iter_mut().filter(|l| *l == "Rider") {
+///             *label = "Pilot".to_string();
+///         }
+///         node.properties.remove("email");
+///         Some(node)
+///     })
+///     .with_relation_transform(|relation| {
+///         (relation.rel_type != "follows").then_some(relation)
+///     });
+/// let result = copy.copy(&mut source, "my_graph", &mut target, "my_graph", |checkpoint| {
+///     // Persist the checkpoint to resume with GraphCopy::with_checkpoint
+///     println!("{:?} at {}", checkpoint.step, checkpoint.next);
+/// })?;
+/// println!("{} nodes and {} relations copied", result.nodes, result.relations);
+/// # Ok(()) }
+/// ```
+pub struct GraphCopy {
+    page_size: usize,
+    node_transform: NodeTransform,
+    relation_transform: RelationTransform,
+    checkpoint: CopyCheckpoint,
+    // The target node id of every source node copied by this GraphCopy
+    node_ids: HashMap<u64, u64>,
+}
+
+impl Default for GraphCopy {
+    fn default() -> Self {
+        GraphCopy::new()
+    }
+}
+
+impl GraphCopy {
+    /// A copy of all entities in pages of 1000 nodes or relations.
+    pub fn new() -> Self {
+        GraphCopy {
+            page_size: 1000,
+            node_transform: Box::new(Some),
+            relation_transform: Box::new(Some),
+            checkpoint: CopyCheckpoint::default(),
+            node_ids: HashMap::new(),
+        }
+    }
+
+    /// Sets the number of nodes or relations read per query (at least 1),
+    /// which also limits the size of the write batches.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Sets a transform applied to every node before it is written, which
+    /// can change labels and properties or drop the node by returning None.
+    pub fn with_node_transform<F>(mut self, transform: F) -> Self
+    where
+        F: FnMut(TypedNode) -> Option<TypedNode> + Send + 'static,
+    {
+        self.node_transform = Box::new(transform);
+        self
+    }
+
+    /// Sets a transform applied to every relation before it is written,
+    /// which can change the type and properties or drop the relation by
+    /// returning None.
+    pub fn with_relation_transform<F>(mut self, transform: F) -> Self
+    where
+        F: FnMut(TypedRelation) -> Option<TypedRelation> + Send + 'static,
+    {
+        self.relation_transform = Box::new(transform);
+        self
+    }
+
+    /// Resumes a copy from a checkpoint of an interrupted copy into the
+    /// same target.
+    pub fn with_checkpoint(mut self, checkpoint: CopyCheckpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// The current position, also available after a failed copy.
+    pub fn checkpoint(&self) -> &CopyCheckpoint {
+        &self.checkpoint
+    }
+
+    /// Copies the graph at source_key into the graph at target_key, calling
+    /// on_checkpoint after every write to the target.
+    pub fn copy<S, T, SK, TK, F>(
+        &mut self,
+        source: &mut S,
+        source_key: SK,
+        target: &mut T,
+        target_key: TK,
+        mut on_checkpoint: F,
+    ) -> RedisResult<CopyResult>
+    where
+        S: ConnectionLike,
+        T: ConnectionLike,
+        SK: ToRedisArgs,
+        TK: ToRedisArgs,
+        F: FnMut(&CopyCheckpoint),
+    {
+        let mut run = self.start_run();
+        loop {
+            let value = match self.next_action(&run) {
+                Some(CopyAction::Read(query)) => {
+                    source.req_command(cmd("GRAPH.RO_QUERY").arg(&source_key).arg(query))?
+                }
+                Some(CopyAction::ReadTarget(query)) => {
+                    target.req_command(cmd("GRAPH.RO_QUERY").arg(&target_key).arg(query))?
+                }
+                Some(CopyAction::Write(statement)) => {
+                    target.req_command(&statement.to_cmd(&target_key))?
+                }
+                None => return Ok(run.result),
+            };
+            if self.add_response(&mut run, &value)? {
+                on_checkpoint(&self.checkpoint);
+            }
+        }
+    }
+
+    /// Copies the graph at source_key into the graph at target_key over
+    /// async connections, calling on_checkpoint after every write to the
+    /// target.
+    #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+    pub async fn copy_async<S, T, SK, TK, F>(
+        &mut self,
+        source: &mut S,
+        source_key: SK,
+        target: &mut T,
+        target_key: TK,
+        mut on_checkpoint: F,
+    ) -> RedisResult<CopyResult>
+    where
+        S: redis::aio::ConnectionLike,
+        T: redis::aio::ConnectionLike,
+        SK: ToRedisArgs,
+        TK: ToRedisArgs,
+        F: FnMut(&CopyCheckpoint),
+    {
+        let mut run = self.start_run();
+        loop {
+            let value = match self.next_action(&run) {
+                Some(CopyAction::Read(query)) => {
+                    source
+                        .req_packed_command(cmd("GRAPH.RO_QUERY").arg(&source_key).arg(query))
+                        .await?
+                }
+                Some(CopyAction::ReadTarget(query)) => {
+                    target
+                        .req_packed_command(cmd("GRAPH.RO_QUERY").arg(&target_key).arg(query))
+                        .await?
+                }
+                Some(CopyAction::Write(statement)) => {
+                    target
+                        .req_packed_command(&statement.to_cmd(&target_key))
+                        .await?
+                }
+                None => return Ok(run.result),
+            };
+            if self.add_response(&mut run, &value)? {
+                on_checkpoint(&self.checkpoint);
+            }
+        }
+    }
+
+    // A run resuming after copied nodes first reads their ids back from the
+    // target, unless this GraphCopy copied them itself
+    fn start_run(&self) -> CopyRun {
+        let resumed = match self.checkpoint.step {
+            CopyStep::Nodes => self.checkpoint.next > 0,
+            CopyStep::Relations => true,
+            CopyStep::Schema | CopyStep::Done => false,
+        };
+        CopyRun {
+            node_ids_from: (resumed && self.node_ids.is_empty()).then_some(0),
+            ..CopyRun::default()
+        }
+    }
+
+    // The next read from the source or target or write to the target, None
+    // once the copy is done
+    fn next_action<'a>(&self, run: &'a CopyRun) -> Option<CopyAction<'a>> {
+        if let Some(from) = run.node_ids_from {
+            return Some(CopyAction::ReadTarget(node_ids_query(from, self.page_size)));
+        }
+        if let Some(write) = run.pending.front() {
+            return Some(CopyAction::Write(&write.statement));
+        }
+        let next = self.checkpoint.next;
+        match self.checkpoint.step {
+            CopyStep::Nodes => Some(CopyAction::Read(node_page_query(next, self.page_size))),
+            CopyStep::Relations => {
+                Some(CopyAction::Read(relation_page_query(next, self.page_size)))
+            }
+            CopyStep::Schema => run
+                .schema
+                .as_ref()
+                .and_then(|fetch| fetch.next_query())
+                .map(CopyAction::Read),
+            CopyStep::Done => None,
+        }
+    }
+
+    // Applies the response to the last action, returns whether the
+    // checkpoint moved
+    fn add_response(&mut self, run: &mut CopyRun, v: &Value) -> RedisResult<bool> {
+        if run.node_ids_from.is_some() {
+            run.node_ids_from = self.add_node_ids(&GraphResultSet::from_redis_value(v)?)?;
+            return Ok(false);
+        }
+        if let Some(write) = run.pending.pop_front() {
+            self.apply_write(run, write, v)?;
+            return Ok(true);
+        }
+        let page_end = match self.checkpoint.step {
+            CopyStep::Nodes => self.plan_nodes(run, v)?,
+            CopyStep::Relations => self.plan_relations(run, v)?,
+            CopyStep::Schema => match self.plan_schema(run, v)? {
+                Some(end) => end,
+                None => return Ok(false),
+            },
+            CopyStep::Done => return Err(create_error("Copy is already complete")),
+        };
+        match run.pending.back_mut() {
+            Some(write) => {
+                write.checkpoint = page_end;
+                Ok(false)
+            }
+            None => {
+                (self.checkpoint.step, self.checkpoint.next) = page_end;
+                Ok(true)
+            }
+        }
+    }
+
+    fn apply_write(
+        &mut self,
+        run: &mut CopyRun,
+        write: PendingWrite,
+        v: &Value,
+    ) -> RedisResult<()> {
+        match (&write.statement, &write.kind) {
+            (_, WriteKind::Cleanup) => {}
+            (SchemaStatement::Query(_), kind) => {
+                let res = GraphResultSet::from_redis_value(v)?;
+                run.result.statistics.add(&res.statistics());
+                if let WriteKind::Nodes(labels) = kind {
+                    if res.data.len() != write.rows {
+                        return Err(create_error(&format!(
+                            "Expected {} node ids of a copy batch, got {}",
+                            write.rows,
+                            res.data.len()
+                        )));
+                    }
+                    self.add_node_ids(&res)?;
+                    self.checkpoint.labels.extend(labels.iter().cloned());
+                }
+            }
+            _ => {}
+        }
+        match write.kind {
+            WriteKind::Nodes(_) => run.result.nodes += write.rows,
+            WriteKind::Relations(rel_type) => {
+                run.result.relations += write.rows;
+                self.checkpoint.relationship_types.insert(rel_type);
+            }
+            WriteKind::Schema => run.result.statements += 1,
+            WriteKind::Cleanup => {}
+        }
+        (self.checkpoint.step, self.checkpoint.next) = write.checkpoint;
+        Ok(())
+    }
+
+    // Maps the source ids to the target ids of a node write or a page of
+    // node ids read from the target, returns the next target id to read
+    // unless the page is empty
+    fn add_node_ids(&mut self, res: &GraphResultSet) -> RedisResult<Option<u64>> {
+        let mut next = None;
+        for row in res.data.iter() {
+            match (row.get_scalar_at::<u64>(0), row.get_scalar_at::<u64>(1)) {
+                (Some(source_id), Some(target_id)) => {
+                    self.node_ids.insert(source_id, target_id);
+                    next = Some(target_id + 1);
+                }
+                _ => return Err(create_error("Unexpected node ids of a copy batch")),
+            }
+        }
+        Ok(next)
+    }
+
+    // Transforms a page of nodes into writes of consecutive nodes with the
+    // same labels, each returning the source and target ids. Nodes that are
+    // already mapped were written by a batch whose reply was lost, only their
+    // labels are recorded.
+    fn plan_nodes(&mut self, run: &mut CopyRun, v: &Value) -> RedisResult<(CopyStep, u64)> {
+        let nodes = parse_nodes(v, None)?;
+        // Only an empty page ends the nodes, as RESULTSET_SIZE can truncate
+        // a page below the page size
+        let page_end = match nodes.last() {
+            Some(node) => (CopyStep::Nodes, node.id + 1),
+            None => (CopyStep::Relations, 0),
+        };
+        let mut batches: Vec<(Vec<String>, Vec<ParamValue>, u64)> = vec![];
+        for node in nodes {
+            let id = node.id;
+            let node = match (self.node_transform)(node) {
+                Some(node) => node,
+                None => {
+                    run.result.nodes_skipped += 1;
+                    continue;
+                }
+            };
+            if self.node_ids.contains_key(&id) {
+                self.checkpoint.labels.extend(node.labels);
+                continue;
+            }
+            let row = ParamValue::Map(vec![
+                ("id".to_string(), ParamValue::Int(id as i64)),
+                ("properties".to_string(), property_map(node.properties)),
+            ]);
+            match batches.last_mut() {
+                Some((labels, rows, last)) if *labels == node.labels => {
+                    rows.push(row);
+                    *last = id;
+                }
+                _ => batches.push((node.labels, vec![row], id)),
+            }
+        }
+        for (labels, rows, last) in batches {
+            let pattern: String = labels
+                .iter()
+                .map(|label| format!(":{}", escape_identifier(label)))
+                .collect();
+            run.pending.push_back(PendingWrite {
+                rows: rows.len(),
+                statement: SchemaStatement::Query(format!(
+                    "UNWIND {} AS row CREATE (n{}) SET n = row.properties, n.{} = row.id \
+                     RETURN row.id, id(n)",
+                    ParamValue::List(rows).to_cypher()?,
+                    pattern,
+                    COPY_ID
+                )),
+                kind: WriteKind::Nodes(labels),
+                checkpoint: (CopyStep::Nodes, last + 1),
+            });
+        }
+        Ok(page_end)
+    }
+
+    // Transforms a page of relations into writes of consecutive relations
+    // with the same type between the mapped node ids
+    fn plan_relations(&mut self, run: &mut CopyRun, v: &Value) -> RedisResult<(CopyStep, u64)> {
+        let relations = parse_relations(v, None)?;
+        let page_end = match relations.last() {
+            Some(relation) => (CopyStep::Relations, relation.id + 1),
+            None => {
+                // All relations are copied, so the source ids can go
+                run.pending.push_back(PendingWrite {
+                    rows: 0,
+                    statement: SchemaStatement::Query(format!(
+                        "MATCH (n) WHERE n.{id} IS NOT NULL SET n.{id} = NULL",
+                        id = COPY_ID
+                    )),
+                    kind: WriteKind::Cleanup,
+                    checkpoint: (CopyStep::Schema, 0),
+                });
+                (CopyStep::Schema, 0)
+            }
+        };
+        let mut batches: Vec<(String, Vec<ParamValue>, u64)> = vec![];
+        for relation in relations {
+            let id = relation.id;
+            let relation = (self.relation_transform)(relation);
+            let node_ids = &self.node_ids;
+            let (relation, src, dest) = match relation.map(|r| {
+                let src = node_ids.get(&r.src).copied();
+                let dest = node_ids.get(&r.dest).copied();
+                (r, src, dest)
+            }) {
+                Some((relation, Some(src), Some(dest))) => (relation, src, dest),
+                _ => {
+                    run.result.relations_skipped += 1;
+                    continue;
+                }
+            };
+            let row = ParamValue::Map(vec![
+                ("src".to_string(), ParamValue::Int(src as i64)),
+                ("dest".to_string(), ParamValue::Int(dest as i64)),
+                ("properties".to_string(), property_map(relation.properties)),
+            ]);
+            match batches.last_mut() {
+                Some((rel_type, rows, last)) if *rel_type == relation.rel_type => {
+                    rows.push(row);
+                    *last = id;
+                }
+                _ => batches.push((relation.rel_type, vec![row], id)),
+            }
+        }
+        for (rel_type, rows, last) in batches {
+            run.pending.push_back(PendingWrite {
+                rows: rows.len(),
+                statement: SchemaStatement::Query(format!(
+                    "UNWIND {} AS row MATCH (a) WHERE id(a) = row.src MATCH (b) WHERE id(b) = row.dest \
+                     CREATE (a)-[r:{}]->(b) SET r = row.properties",
+                    ParamValue::List(rows).to_cypher()?,
+                    escape_identifier(&rel_type)
+                )),
+                kind: WriteKind::Relations(rel_type),
+                checkpoint: (CopyStep::Relations, last + 1),
+            });
+        }
+        Ok(page_end)
+    }
+
+    // Fetches the source indexes and constraints, then plans the statements
+    // for the written labels and types not executed yet. None while the
+    // fetch is incomplete.
+    fn plan_schema(
+        &mut self,
+        run: &mut CopyRun,
+        v: &Value,
+    ) -> RedisResult<Option<(CopyStep, u64)>> {
+        let mut fetch = match run.schema.take() {
+            Some(fetch) => fetch,
+            None => return Err(create_error("Copy is already complete")),
+        };
+        fetch.add_response(v)?;
+        if fetch.next_query().is_some() {
+            run.schema = Some(fetch);
+            return Ok(None);
+        }
+        let source = fetch.finish();
+        let written = |entity_type: EntityType, label: &String| match entity_type {
+            EntityType::Node => self.checkpoint.labels.contains(label),
+            EntityType::Relationship => self.checkpoint.relationship_types.contains(label),
+        };
+        let schema = GraphSchema {
+            indexes: source
+                .indexes
+                .into_iter()
+                .filter(|index| written(index.entity_type, &index.label))
+                .collect(),
+            constraints: source
+                .constraints
+                .into_iter()
+                .filter(|constraint| written(constraint.entity_type, &constraint.label))
+                .collect(),
+            ..GraphSchema::default()
+        };
+        let statements = SchemaDiff::compare(&schema, &GraphSchema::new()).statements(false);
+        for (idx, statement) in statements
+            .into_iter()
+            .enumerate()
+            .skip(self.checkpoint.next as usize)
+        {
+            run.pending.push_back(PendingWrite {
+                rows: 0,
+                statement,
+                kind: WriteKind::Schema,
+                checkpoint: (CopyStep::Schema, idx as u64 + 1),
+            });
+        }
+        Ok(Some((CopyStep::Done, 0)))
+    }
+}
+
+// A page of the source and target ids of the copied nodes in the target,
+// starting at target id from
+fn node_ids_query(from: u64, page_size: usize) -> String {
+    query_with_id(
+        &format!(
+            "MATCH (n) WHERE id(n) >= $from AND n.{id} IS NOT NULL \
+             RETURN n.{id}, id(n) ORDER BY id(n) LIMIT {}",
+            page_size,
+            id = COPY_ID
+        ),
+        "from",
+        from,
+    )
+}
+
+fn property_map(properties: std::collections::BTreeMap<String, ParamValue>) -> ParamValue {
+    ParamValue::Map(properties.into_iter().collect())
+}
+
+enum CopyAction<'a> {
+    Read(String),
+    ReadTarget(String),
+    Write(&'a SchemaStatement),
+}
+
+enum WriteKind {
+    // The labels of the created nodes
+    Nodes(Vec<String>),
+    Relations(String),
+    Schema,
+    // Removing the source ids from the created nodes
+    Cleanup,
+}
+
+struct PendingWrite {
+    statement: SchemaStatement,
+    kind: WriteKind,
+    rows: usize,
+    // The checkpoint once the write succeeded
+    checkpoint: (CopyStep, u64),
+}
+
+// The state of a single copy run, the checkpoint is kept by the GraphCopy
+struct CopyRun {
+    // The next target id to read the node id mapping from
+    node_ids_from: Option<u64>,
+    pending: VecDeque<PendingWrite>,
+    schema: Option<SchemaFetch>,
+    result: CopyResult,
+}
+
+impl Default for CopyRun {
+    fn default() -> Self {
+        CopyRun {
+            node_ids_from: None,
+            pending: VecDeque::new(),
+            schema: Some(SchemaFetch::indexes_and_constraints()),
+            result: CopyResult::default(),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use crate::subgraph::Subgraph;
+use crate::types::*;
+use redis::{RedisResult, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// The text formats a Subgraph can be exported to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// GraphML XML with labels, relationship types and properties as data
+    /// attributes.
+    GraphMl,
+    /// Graphviz DOT with properties as tooltips.
+    Dot,
+    /// Mermaid flowchart with properties as additional lines of the node
+    /// text.
+    Mermaid,
+}
+
+/// The shape of a node in DOT and Mermaid output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeShape {
+    Box,
+    Round,
+    Circle,
+    Diamond,
+}
+
+impl NodeShape {
+    fn dot(&self) -> &'static str {
+        match self {
+            NodeShape::Box => "box",
+            NodeShape::Round => "ellipse",
+            NodeShape::Circle => "circle",
+            NodeShape::Diamond => "diamond",
+        }
+    }
+
+    fn mermaid(&self) -> (&'static str, &'static str) {
+        match self {
+            NodeShape::Box => ("[", "]"),
+            NodeShape::Round => ("(", ")"),
+            NodeShape::Circle => ("((", "))"),
+            NodeShape::Diamond => ("{", "}"),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            NodeShape::Box => "box",
+            NodeShape::Round => "round",
+            NodeShape::Circle => "circle",
+            NodeShape::Diamond => "diamond",
+        }
+    }
+}
+
+/// The style of the nodes of a label. The color is any color understood by
+/// the renderer, e.g. `#f4a261`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeStyle {
+    pub color: Option<String>,
+    pub shape: Option<NodeShape>,
+}
+
+impl NodeStyle {
+    /// Creates a style without color and shape.
+    pub fn new() -> Self {
+        NodeStyle::default()
+    }
+
+    /// Sets the fill color.
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.color = Some(color.to_string());
+        self
+    }
+
+    /// Sets the shape.
+    pub fn with_shape(mut self, shape: NodeShape) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+}
+
+/// Exports a Subgraph as GraphML, Graphviz DOT or Mermaid flowchart text.
+/// Since a Subgraph deduplicates nodes and relations by id, every entity is
+/// written once, whether it comes from a result set via `to_subgraph` or
+/// from a whole graph via `graph_subgraph`.
+///
+/// Nodes are captioned with the value of the caption property, or with
+/// their labels and id if the property is not set. Styles are applied to
+/// nodes by their first label with a style.
+///
+/// ```rust
+/// use redis_graph::*;
+///
+/// let mut graph = Subgraph::new();
+/// let mut rider = NodeValue::default();
+/// rider.id = 1;
+/// rider.labels = vec!["Rider".to_string()];
+/// rider.properties.insert("name".to_string(), redis::Value::Data(b"Valentino Rossi".to_vec()));
+/// graph.add_node(rider);
+///
+/// let export = GraphExport::new(GraphFormat::Mermaid)
+///     .with_caption("name")
+///     .with_style("Rider", NodeStyle::new().with_color("#f4a261"))
+///     .with_properties(false);
+/// assert_eq!(
+///     export.to_string(&graph),
+///     "flowchart LR\n    n1[\"Valentino Rossi\"]\n    classDef Rider fill:#f4a261\n    class n1 Rider\n"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct GraphExport {
+    format: GraphFormat,
+    caption: Option<String>,
+    styles: Vec<(String, NodeStyle)>,
+    properties: bool,
+}
+
+impl GraphExport {
+    /// Creates an export to the given format including all properties.
+    pub fn new(format: GraphFormat) -> Self {
+        GraphExport {
+            format,
+            caption: None,
+            styles: Vec::new(),
+            properties: true,
+        }
+    }
+
+    /// Captions nodes with the value of the given property.
+    pub fn with_caption(mut self, property: &str) -> Self {
+        self.caption = Some(property.to_string());
+        self
+    }
+
+    /// Styles nodes carrying label. Styles are looked up in the order they
+    /// were added.
+    pub fn with_style(mut self, label: &str, style: NodeStyle) -> Self {
+        self.styles.push((label.to_string(), style));
+        self
+    }
+
+    /// Includes properties in the DOT tooltips and Mermaid node texts,
+    /// which is the default. GraphML output always contains all properties.
+    pub fn with_properties(mut self, properties: bool) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Writes the subgraph to writer.
+    pub fn write<W: Write>(&self, graph: &Subgraph, mut writer: W) -> RedisResult<()> {
+        writer.write_all(self.to_string(graph).as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the subgraph in the export format.
+    pub fn to_string(&self, graph: &Subgraph) -> String {
+        match self.format {
+            GraphFormat::GraphMl => self.graphml(graph),
+            GraphFormat::Dot => self.dot(graph),
+            GraphFormat::Mermaid => self.mermaid(graph),
+        }
+    }
+
+    fn caption(&self, node: Option<&NodeValue>, id: u64) -> String {
+        let node = match node {
+            Some(node) => node,
+            None => return id.to_string(),
+        };
+        match self.caption.as_ref().and_then(|p| node.properties.get(p)) {
+            Some(value) if *value != Value::Nil => render_value(value),
+            _ if node.labels.is_empty() => id.to_string(),
+            _ => format!("{} {}", node.labels.join(":"), id),
+        }
+    }
+
+    fn style(&self, node: Option<&NodeValue>) -> Option<(&str, &NodeStyle)> {
+        let node = node?;
+        self.styles
+            .iter()
+            .find(|(label, _)| node.labels.contains(label))
+            .map(|(label, style)| (label.as_str(), style))
+    }
+
+    fn graphml(&self, graph: &Subgraph) -> String {
+        let node_keys = property_keys(graph.nodes().map(|n| &n.properties));
+        let edge_keys = property_keys(graph.relations().map(|r| &r.properties));
+        let styled = !self.styles.is_empty();
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+             \x20 <key id=\"labels\" for=\"node\" attr.name=\"labels\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"caption\" for=\"node\" attr.name=\"caption\" attr.type=\"string\"/>\n",
+        );
+        if styled {
+            out.push_str(
+                "  <key id=\"color\" for=\"node\" attr.name=\"color\" attr.type=\"string\"/>\n\
+                 \x20 <key id=\"shape\" for=\"node\" attr.name=\"shape\" attr.type=\"string\"/>\n",
+            );
+        }
+        out.push_str(
+            "  <key id=\"label\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        );
+        for (prefix, domain, keys) in [("n_", "node", &node_keys), ("e_", "edge", &edge_keys)] {
+            for (name, attr_type) in keys {
+                let _ = writeln!(
+                    out,
+                    "  <key id=\"{}{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                    prefix,
+                    xml_escape(name),
+                    domain,
+                    xml_escape(name),
+                    attr_type
+                );
+            }
+        }
+        out.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+        for id in graph.node_ids() {
+            let node = graph.node(id);
+            let _ = writeln!(out, "    <node id=\"n{}\">", id);
+            let labels = node.map(|n| n.labels.join(":")).unwrap_or_default();
+            data(&mut out, "labels", &format!(":{}", labels));
+            data(&mut out, "caption", &self.caption(node, id));
+            if let Some((_, style)) = self.style(node) {
+                if let Some(color) = &style.color {
+                    data(&mut out, "color", color);
+                }
+                if let Some(shape) = &style.shape {
+                    data(&mut out, "shape", shape.name());
+                }
+            }
+            if let Some(node) = node {
+                for (name, value) in sorted(&node.properties) {
+                    data(&mut out, &format!("n_{}", name), &render_value(value));
+                }
+            }
+            out.push_str("    </node>\n");
+        }
+        for relation in graph.relations() {
+            let _ = writeln!(
+                out,
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">",
+                relation.id, relation.src_node, relation.dest_node
+            );
+            data(&mut out, "label", &relation.rel_type);
+            for (name, value) in sorted(&relation.properties) {
+                data(&mut out, &format!("e_{}", name), &render_value(value));
+            }
+            out.push_str("    </edge>\n");
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    fn dot(&self, graph: &Subgraph) -> String {
+        let mut out = String::from("digraph G {\n");
+        for id in graph.node_ids() {
+            let node = graph.node(id);
+            let mut attributes = vec![format!("label=\"{}\"", dot_escape(&self.caption(node, id)))];
+            if let Some((_, style)) = self.style(node) {
+                if let Some(color) = &style.color {
+                    attributes.push(format!("style=filled, fillcolor=\"{}\"", dot_escape(color)));
+                }
+                if let Some(shape) = &style.shape {
+                    attributes.push(format!("shape={}", shape.dot()));
+                }
+            }
+            if let Some(node) = node.filter(|_| self.properties) {
+                let mut lines = vec![format!(":{}", node.labels.join(":"))];
+                lines.extend(property_lines(&node.properties));
+                attributes.push(format!("tooltip=\"{}\"", dot_escape(&lines.join("\n"))));
+            }
+            let _ = writeln!(out, "  n{} [{}];", id, attributes.join(", "));
+        }
+        for relation in graph.relations() {
+            let mut attributes = vec![format!("label=\"{}\"", dot_escape(&relation.rel_type))];
+            if self.properties && !relation.properties.is_empty() {
+                let lines = property_lines(&relation.properties);
+                attributes.push(format!("tooltip=\"{}\"", dot_escape(&lines.join("\n"))));
+            }
+            let _ = writeln!(
+                out,
+                "  n{} -> n{} [{}];",
+                relation.src_node,
+                relation.dest_node,
+                attributes.join(", ")
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn mermaid(&self, graph: &Subgraph) -> String {
+        let mut out = String::from("flowchart LR\n");
+        let mut classes: BTreeMap<&str, (&NodeStyle, Vec<u64>)> = BTreeMap::new();
+        for id in graph.node_ids() {
+            let node = graph.node(id);
+            let mut lines = vec![self.caption(node, id)];
+            if let Some(node) = node.filter(|_| self.properties) {
+                lines.extend(property_lines(&node.properties));
+            }
+            let style = self.style(node);
+            let (open, close) = style
+                .and_then(|(_, style)| style.shape)
+                .map_or(("[", "]"), |shape| shape.mermaid());
+            let text: Vec<String> = lines.iter().map(|l| mermaid_escape(l)).collect();
+            let _ = writeln!(
+                out,
+                "    n{}{}\"{}\"{}",
+                id,
+                open,
+                text.join("<br/>"),
+                close
+            );
+            if let Some((label, style)) = style {
+                classes.entry(label).or_insert((style, vec![])).1.push(id);
+            }
+        }
+        for relation in graph.relations() {
+            let mut text = mermaid_escape(&relation.rel_type);
+            if self.properties && !relation.properties.is_empty() {
+                let lines: Vec<String> = property_lines(&relation.properties)
+                    .iter()
+                    .map(|l| mermaid_escape(l))
+                    .collect();
+                text = format!("{}<br/>{}", text, lines.join("<br/>"));
+            }
+            let _ = writeln!(
+                out,
+                "    n{} -->|\"{}\"| n{}",
+                relation.src_node, text, relation.dest_node
+            );
+        }
+        for (label, (style, ids)) in classes {
+            if let Some(color) = &style.color {
+                let _ = writeln!(out, "    classDef {} fill:{}", mermaid_class(label), color);
+            } else {
+                let _ = writeln!(out, "    classDef {}", mermaid_class(label));
+            }
+            let ids: Vec<String> = ids.iter().map(|id| format!("n{}", id)).collect();
+            let _ = writeln!(out, "    class {} {}", ids.join(","), mermaid_class(label));
+        }
+        out
+    }
+}
+
+fn sorted(properties: &HashMap<String, Value>) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<(&String, &Value)> = properties.iter().collect();
+    entries.sort_by_key(|(k, _)| *k);
+    entries
+}
+
+fn property_lines(properties: &HashMap<String, Value>) -> Vec<String> {
+    sorted(properties)
+        .into_iter()
+        .map(|(k, v)| format!("{}: {}", k, render_value(v)))
+        .collect()
+}
+
+// The GraphML key types of all properties, long if all values are integers
+fn property_keys<'a, I>(properties: I) -> BTreeMap<&'a String, &'static str>
+where
+    I: Iterator<Item = &'a HashMap<String, Value>>,
+{
+    let mut keys = BTreeMap::new();
+    for map in properties {
+        for (name, value) in map {
+            let attr_type = match value {
+                Value::Int(_) | Value::Nil => "long",
+                _ => "string",
+            };
+            let entry = keys.entry(name).or_insert(attr_type);
+            if *entry != attr_type {
+                *entry = "string";
+            }
+        }
+    }
+    keys
+}
+
+fn render_value(v: &Value) -> String {
+    match v {
+        Value::Nil => String::new(),
+        Value::Int(v) => v.to_string(),
+        Value::Data(bytes) => String::from_utf8_lossy(bytes).to_string(),
+        Value::Bulk(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(render_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Status(s) => s.clone(),
+        Value::Okay => "OK".to_string(),
+    }
+}
+
+fn data(out: &mut String, key: &str, value: &str) {
+    let _ = writeln!(
+        out,
+        "      <data key=\"{}\">{}</data>",
+        xml_escape(key),
+        xml_escape(value)
+    );
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Entity codes start with `#`, so it is escaped first
+fn mermaid_escape(s: &str) -> String {
+    s.replace('#', "#35;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('\n', "<br/>")
+}
+
+// Class names may only contain word characters, other characters including
+// `_` are written as their hex code between underscores, so that labels like
+// `A-B` and `A_B` get different classes
+fn mermaid_class(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_string()
+            } else {
+                format!("_{:x}_", c as u32)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,373 @@
+use crate::schema::PropertyType;
+use crate::stream::{GraphResultStream, GraphRow};
+use crate::types::*;
+use base64::Engine;
+use redis::{RedisResult, Value};
+use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+/// Serializes a raw Redis value with its JSON type. Integers stay numbers,
+/// UTF-8 data is a string and binary data a base64 encoded string. Redis
+/// graph returns doubles and booleans as strings, which are kept as strings
+/// unless the column type is declared (see TypedScalar).
+pub(crate) struct JsonScalar<'a>(pub(crate) &'a Value);
+
+impl Serialize for JsonScalar<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Nil => serializer.serialize_none(),
+            Value::Int(v) => serializer.serialize_i64(*v),
+            Value::Data(bytes) => match std::str::from_utf8(bytes) {
+                Ok(s) => serializer.serialize_str(s),
+                Err(_) => serializer
+                    .serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes)),
+            },
+            Value::Bulk(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for v in values {
+                    seq.serialize_element(&JsonScalar(v))?;
+                }
+                seq.end()
+            }
+            Value::Status(s) => serializer.serialize_str(s),
+            Value::Okay => serializer.serialize_str("OK"),
+        }
+    }
+}
+
+// A scalar of a column with declared type. Boolean, Integer and Float
+// values are converted from the strings returned by Redis graph, also in
+// lists, String values are always strings. Values of other types are
+// written as by JsonScalar.
+struct TypedScalar<'a>(&'a Value, &'a PropertyType);
+
+impl Serialize for TypedScalar<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let TypedScalar(value, property_type) = *self;
+        let text = match value {
+            Value::Data(bytes) => std::str::from_utf8(bytes).ok(),
+            Value::Status(s) => Some(s.as_str()),
+            _ => None,
+        };
+        let mismatch = || S::Error::custom(format!("{:?} is not a {}", value, property_type));
+        match (property_type, value) {
+            (_, Value::Nil) => serializer.serialize_none(),
+            (_, Value::Bulk(values)) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for v in values {
+                    seq.serialize_element(&TypedScalar(v, property_type))?;
+                }
+                seq.end()
+            }
+            (PropertyType::Boolean, _) => match text {
+                Some("true") => serializer.serialize_bool(true),
+                Some("false") => serializer.serialize_bool(false),
+                _ => Err(mismatch()),
+            },
+            (PropertyType::Integer, Value::Int(v)) => serializer.serialize_i64(*v),
+            (PropertyType::Integer, _) => {
+                serializer.serialize_i64(text.and_then(|s| s.parse().ok()).ok_or_else(mismatch)?)
+            }
+            (PropertyType::Float, Value::Int(v)) => serializer.serialize_f64(*v as f64),
+            (PropertyType::Float, _) => {
+                serializer.serialize_f64(text.and_then(|s| s.parse().ok()).ok_or_else(mismatch)?)
+            }
+            (PropertyType::String, Value::Int(v)) => serializer.serialize_str(&v.to_string()),
+            _ => JsonScalar(value).serialize(serializer),
+        }
+    }
+}
+
+// Properties are written sorted by name for a stable output
+struct JsonProperties<'a>(&'a HashMap<String, Value>);
+
+impl Serialize for JsonProperties<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sorted: BTreeMap<&String, JsonScalar> =
+            self.0.iter().map(|(k, v)| (k, JsonScalar(v))).collect();
+        sorted.serialize(serializer)
+    }
+}
+
+impl Serialize for NodeValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut node = serializer.serialize_struct("NodeValue", 3)?;
+        node.serialize_field("id", &self.id)?;
+        node.serialize_field("labels", &self.labels)?;
+        node.serialize_field("properties", &JsonProperties(&self.properties))?;
+        node.end()
+    }
+}
+
+impl Serialize for RelationValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut relation = serializer.serialize_struct("RelationValue", 5)?;
+        relation.serialize_field("id", &self.id)?;
+        relation.serialize_field("type", &self.rel_type)?;
+        relation.serialize_field("src", &self.src_node)?;
+        relation.serialize_field("dest", &self.dest_node)?;
+        relation.serialize_field("properties", &JsonProperties(&self.properties))?;
+        relation.end()
+    }
+}
+
+impl Serialize for GraphValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            GraphValue::Scalar(v) => JsonScalar(v).serialize(serializer),
+            GraphValue::Node(node) => node.serialize(serializer),
+            GraphValue::Relation(relation) => relation.serialize(serializer),
+        }
+    }
+}
+
+// A row is an object of the RETURN keys in column order, scalars of columns
+// with declared type are converted to it
+struct JsonRow<'a> {
+    header: &'a [String],
+    values: &'a [GraphValue],
+    column_types: &'a HashMap<String, PropertyType>,
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut row = serializer.serialize_map(Some(self.values.len()))?;
+        for (key, value) in self.header.iter().zip(self.values) {
+            match (value, self.column_types.get(key)) {
+                (GraphValue::Scalar(v), Some(property_type)) => {
+                    row.serialize_entry(key, &TypedScalar(v, property_type))?
+                }
+                (value, _) => row.serialize_entry(key, value)?,
+            }
+        }
+        row.end()
+    }
+}
+
+fn serialize_row<S: Serializer>(
+    header: &[String],
+    values: &[GraphValue],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    JsonRow {
+        header,
+        values,
+        column_types: &HashMap::new(),
+    }
+    .serialize(serializer)
+}
+
+impl Serialize for GraphResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_row(self.header(), self.values(), serializer)
+    }
+}
+
+impl Serialize for GraphRow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_row(self.header(), self.values(), serializer)
+    }
+}
+
+// A result set with the rows serialized as JsonRow
+struct JsonResultSet<'a> {
+    res: &'a GraphResultSet,
+    column_types: &'a HashMap<String, PropertyType>,
+}
+
+impl Serialize for JsonResultSet<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rows: Vec<JsonRow> = self
+            .res
+            .data
+            .iter()
+            .map(|row| JsonRow {
+                header: row.header(),
+                values: row.values(),
+                column_types: self.column_types,
+            })
+            .collect();
+        let mut res = serializer.serialize_struct("GraphResultSet", 3)?;
+        res.serialize_field("header", &self.res.header)?;
+        res.serialize_field("rows", &rows)?;
+        res.serialize_field("statistics", &self.res.statistics())?;
+        res.end()
+    }
+}
+
+impl Serialize for GraphResultSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JsonResultSet {
+            res: self,
+            column_types: &HashMap::new(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl GraphResultSet {
+    /// Encodes the result set as JSON with the header, the rows as objects
+    /// of the RETURN keys and the query statistics.
+    ///
+    /// ```rust
+    /// use redis_graph::*;
+    ///
+    /// let res = GraphResultSet {
+    ///     header: vec!["r.name".to_string(), "r.born".to_string()],
+    ///     data: vec![GraphResult::from_values(
+    ///         vec!["r.name".to_string(), "r.born".to_string()].into(),
+    ///         vec![
+    ///             GraphValue::Scalar(redis::Value::Data(b"Valentino Rossi".to_vec())),
+    ///             GraphValue::Scalar(redis::Value::Int(1979)),
+    ///         ],
+    ///     )],
+    ///     metadata: vec![],
+    /// };
+    /// let json = res.to_json().unwrap();
+    /// assert!(json.starts_with(
+    ///     r#"{"header":["r.name","r.born"],"rows":[{"r.name":"Valentino Rossi","r.born":1979}]"#
+    /// ));
+    /// ```
+    pub fn to_json(&self) -> RedisResult<String> {
+        self.to_json_with_types(&[])
+    }
+
+    /// Encodes the result set as JSON like to_json with the scalars of the
+    /// given columns converted to their type. Redis graph returns doubles
+    /// and booleans as strings, which are numbers and booleans in the JSON
+    /// for columns declared as Float or Boolean. Values which can not be
+    /// converted fail the encoding.
+    ///
+    /// ```rust
+    /// use redis_graph::*;
+    ///
+    /// let header = vec!["r.name".to_string(), "r.score".to_string(), "r.active".to_string()];
+    /// let res = GraphResultSet {
+    ///     header: header.clone(),
+    ///     data: vec![GraphResult::from_values(
+    ///         header.into(),
+    ///         vec![
+    ///             GraphValue::Scalar(redis::Value::Data(b"007".to_vec())),
+    ///             GraphValue::Scalar(redis::Value::Data(b"1.5".to_vec())),
+    ///             GraphValue::Scalar(redis::Value::Data(b"true".to_vec())),
+    ///         ],
+    ///     )],
+    ///     metadata: vec![],
+    /// };
+    /// let json = res
+    ///     .to_json_with_types(&[("r.score", PropertyType::Float), ("r.active", PropertyType::Boolean)])
+    ///     .unwrap();
+    /// assert!(json.contains(r#"{"r.name":"007","r.score":1.5,"r.active":true}"#));
+    /// ```
+    pub fn to_json_with_types(&self, column_types: &[(&str, PropertyType)]) -> RedisResult<String> {
+        let column_types = column_types
+            .iter()
+            .map(|(column, property_type)| (column.to_string(), property_type.clone()))
+            .collect();
+        serde_json::to_string(&JsonResultSet {
+            res: self,
+            column_types: &column_types,
+        })
+        .map_err(|e| create_error(&format!("Could not encode result: {}", e)))
+    }
+}
+
+/// Writes graph rows as newline delimited JSON, one row object per line.
+/// Other than GraphResultSet::to_json rows are written as they are decoded,
+/// so large results from a GraphResultStream are never held in memory as
+/// JSON.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let riders = con.graph_ro_query_stream("my_graph", "MATCH (r:Rider) RETURN r")?;
+/// let out = std::io::BufWriter::new(std::fs::File::create("riders.ndjson")?);
+/// let rows = NdjsonWriter::new(out).write_stream(riders)?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+    rows: usize,
+    column_types: HashMap<String, PropertyType>,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    /// Creates a writer writing lines to the given writer.
+    pub fn new(writer: W) -> Self {
+        NdjsonWriter {
+            writer,
+            rows: 0,
+            column_types: HashMap::new(),
+        }
+    }
+
+    /// Converts the scalars of a column of written result sets and streams
+    /// to the given type, see GraphResultSet::to_json_with_types.
+    pub fn with_column_type(mut self, column: &str, property_type: PropertyType) -> Self {
+        self.column_types.insert(column.to_string(), property_type);
+        self
+    }
+
+    /// Writes a single row or any other serializable value as one line.
+    pub fn write_row<R: Serialize>(&mut self, row: &R) -> RedisResult<()> {
+        write_line(&mut self.writer, row)?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Writes all rows of a result set and returns their number.
+    pub fn write_result_set(&mut self, res: &GraphResultSet) -> RedisResult<usize> {
+        for row in &res.data {
+            self.write_values(row.header(), row.values())?;
+        }
+        self.writer.flush()?;
+        Ok(res.data.len())
+    }
+
+    /// Decodes and writes the rows of a stream one at a time and returns
+    /// their number. Fails on the first row which can not be decoded.
+    pub fn write_stream(&mut self, stream: GraphResultStream) -> RedisResult<usize> {
+        let mut count = 0;
+        for row in stream {
+            let row = row?;
+            self.write_values(row.header(), row.values())?;
+            count += 1;
+        }
+        self.writer.flush()?;
+        Ok(count)
+    }
+
+    fn write_values(&mut self, header: &[String], values: &[GraphValue]) -> RedisResult<()> {
+        let row = JsonRow {
+            header,
+            values,
+            column_types: &self.column_types,
+        };
+        write_line(&mut self.writer, &row)?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// The number of lines written so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn write_line<W: Write, R: Serialize>(writer: &mut W, row: &R) -> RedisResult<()> {
+    serde_json::to_writer(&mut *writer, row)
+        .map_err(|e| create_error(&format!("Could not encode row: {}", e)))?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
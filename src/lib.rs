@@ -140,6 +140,11 @@
 //! let team_name:Option<String> = entry.get_scalar("team.name");
 //!
 //!
+//! /// Values can also be accessed by column position in RETURN order, which
+//! /// works for duplicate column names as well.
+//! let team_name:Option<String> = entry.get_scalar_at(1);
+//!
+//!
 //! /// Node and Relation values can contain properties for which there are
 //! /// value extractors as well.
 //! let rider_name:Option<String> = rider.unwrap().get_property_option("name");
@@ -147,6 +152,151 @@
 //! # Ok(()) }
 //! ```
 //!
+//! ## Tuple extraction
+//! Rows can be converted into tuples (up to 12 elements), `Vec<GraphValue>` or
+//! any single FromGraphValue type. Elements are matched to the RETURN columns
+//! by position.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let riders: Vec<(NodeValue, String, Option<i64>)> = con.graph_ro_query_as(
+//!     "my_graph",
+//!     "MATCH (r:Rider) RETURN r, r.name, r.born",
+//! )?;
+//!
+//! let res = con.graph_ro_query("my_graph", "MATCH (r:Rider) RETURN r.name")?;
+//! let names: Vec<String> = res.rows_as()?;
+//!
+//! # Ok(()) }
+//! ```
+//!
+//! ## Streaming results
+//! For large result sets the stream variants of the query commands return a
+//! GraphResultStream that only decodes a row when it is requested. Rows share
+//! the header and allow access by key or column position.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! for row in con.graph_ro_query_stream("my_graph", "MATCH (r:Rider) RETURN r.name")? {
+//!     let name: Option<String> = row?.get_scalar_at(0);
+//! }
+//!
+//! # Ok(()) }
+//! ```
+//!
+//! ## Paginated queries
+//! Redis graph truncates results to the RESULTSET_SIZE config. A Pagination
+//! re-issues a query with SKIP/LIMIT (or a key-set cursor) until all rows are
+//! read. The sync commands return an Iterator, the async commands a Stream.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let pagination = Pagination::new(
+//!     "my_graph",
+//!     "MATCH (r:Rider) WHERE id(r) > $cursor RETURN r.name, id(r) AS cursor ORDER BY cursor",
+//!     1000,
+//! )
+//! .with_key_set("cursor", "cursor", -1);
+//!
+//! for row in con.graph_paginate(pagination) {
+//!     let name: Option<String> = row?.get_scalar("r.name");
+//! }
+//!
+//! # Ok(()) }
+//! ```
+//!
+//! ## Automatic query routing
+//! With graph_auto_query the query is classified client side. Queries without
+//! write clauses (CREATE, MERGE, SET, DELETE, REMOVE, DROP or write procedures)
+//! are sent as GRAPH.RO_QUERY, all others as GRAPH.QUERY.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! assert!(is_read_only_query("MATCH (r:Rider) RETURN r.name"));
+//! let riders = con.graph_auto_query("my_graph", "MATCH (r:Rider) RETURN r.name")?;
+//!
+//! # Ok(()) }
+//! ```
+//!
+//! ## Algorithm procedures
+//! The server side algo procedures (algo.BFS, algo.SPpaths, algo.SSpaths,
+//! algo.pageRank, algo.WCC, algo.betweenness, algo.labelPropagation) have typed
+//! methods taking a config builder and returning parsed results.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let ranks:Vec<(NodeValue, f64)> = con.graph_algo_page_rank(
+//!     "my_graph",
+//!     &PageRankConfig::new().label("Rider").relationship_type("rides"),
+//! )?;
+//!
+//! let paths:Vec<AlgoPath> = con.graph_algo_sp_paths(
+//!     "my_graph",
+//!     &PathConfig::new(0).target(42).weight_prop("distance").max_len(5),
+//! )?;
+//! # Ok(()) }
+//! ```
+//!
+//! ## Full-text search
+//! graph_fulltext_query searches a full-text index with
+//! db.idx.fulltext.queryNodes. FulltextQuery builds RediSearch queries from
+//! terms, prefixes, fuzzy matches, phrases and field modifiers and escapes all
+//! special characters.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let query = FulltextQuery::new().prefix("vale").fuzzy("rosi", 1);
+//! let riders:Vec<(NodeValue, f64)> = con.graph_fulltext_query("my_graph", "Rider", &query)?;
+//!
+//! let second_page = con.graph_fulltext_query_page("my_graph", "Rider", &query, 20, 20)?;
+//! # Ok(()) }
+//! ```
+//!
+//! ## Procedure calls
+//! graph_call calls any procedure, passing the arguments as query parameters
+//! and returning the rows keyed by yield name. Read-only procedures are called
+//! with GRAPH.RO_QUERY. graph_procedures lists the available procedures with
+//! their read/write mode.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let rows:Vec<ProcedureRow> = con.graph_call("my_graph", "db.labels", &[], &["label"])?;
+//! for row in rows {
+//!     let label:Option<String> = row.get_value("label")?;
+//! }
+//!
+//! let procedures:Vec<ProcedureInfo> = con.graph_procedures("my_graph")?;
+//! # Ok(()) }
+//! ```
+//!
 //! ## GRAPH.PROFILE
 //! Executes a query and produces an execution plan augmented with metrics
 //! for each operation's execution. Returns strings in a list format.
@@ -235,12 +385,504 @@
 //!
 //! # Ok(()) }
 //! ```
+//!
+//! ## GRAPH.LIST
+//! Lists all graph keys in the keyspace.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let graphs:Vec<String> = con.graph_list()?;
+//!
+//! # Ok(()) }
+//! ```
+//!
+//! # Subgraphs and algorithms
+//!
+//! Nodes and relations of a result set can be collected into a deduplicated
+//! in-memory Subgraph with adjacency. Traversals (bfs, dfs), weighted shortest
+//! paths (dijkstra, astar), connected components and cycle detection run on a
+//! Subgraph without further queries.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let res = con.graph_ro_query("my_graph", "MATCH (a:City)-[r:road]->(b:City) RETURN a, r, b")?;
+//! let graph = res.to_subgraph();
+//!
+//! let reachable = bfs(&graph, 0, Direction::Outgoing);
+//! let route: Option<WeightedPath> = dijkstra(&graph, 0, 42, "distance", Direction::Both)?;
+//! let islands = connected_components(&graph);
+//! # Ok(()) }
+//! ```
+//!
+//! # Schema introspection
+//!
+//! graph_schema returns a GraphSchema with the labels, relationship types and
+//! property keys of a graph, the number of nodes per label and relations per
+//! type, the property types found in a sample of them and the indexes and
+//! constraints. The schema implements Display, and with the `serde` feature
+//! it can be written to and read from JSON.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let schema:GraphSchema = con.graph_schema("my_graph", 100)?;
+//! println!("{}", schema);
+//!
+//! let riders = schema.label("Rider").map(|label| label.count).unwrap_or(0);
+//! # Ok(()) }
+//! ```
+//!
+//! A SchemaDiff compares a schema against an expected one, either fetched from
+//! another graph or declared with GraphSchema::new, from JSON or with the
+//! `toml` feature from TOML. It reports missing and extra labels, relationship
+//! types, properties, indexes and constraints and provides the statements that
+//! converge indexes and constraints.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let expected = GraphSchema::new()
+//!     .with_label("Rider", &[("name", PropertyType::String)])
+//!     .with_constraint(ConstraintInfo::node("Rider", "UNIQUE", &["name"]));
+//! let diff:SchemaDiff = con.graph_schema_diff("my_graph", &expected, 100)?;
+//! for statement in diff.statements(false) {
+//!     let _:redis::Value = statement.to_cmd("my_graph").query(&mut con)?;
+//! }
+//!
+//! let staging = con.graph_schema("staging_graph", 100)?;
+//! let production = con.graph_schema("production_graph", 100)?;
+//! println!("{}", SchemaDiff::compare(&staging, &production));
+//! # Ok(()) }
+//! ```
+//!
+//! # Migrations
+//!
+//! A Migrator applies versioned migrations, written as Cypher scripts or as
+//! Rust functions, and records the applied versions with a checksum in a hash
+//! next to the graph (`<graph>:migrations`), in a hash at another key or in
+//! `_Migration` nodes of the graph. graph_migrate applies the pending
+//! migrations and graph_rollback reverts them down to a target version. Both
+//! return the MigrationPlan, and with `dry_run` they only return it.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let migrator = Migrator::new(vec![
+//!     Migration::cypher(1, "rider_index", "CREATE INDEX FOR (r:Rider) ON (r.name)")
+//!         .with_down("DROP INDEX ON :Rider(name)"),
+//!     Migration::rust(2, "teams", |ctx| {
+//!         ctx.query("CREATE (:Team {name: 'Yamaha'})");
+//!         Ok(())
+//!     }),
+//! ])?;
+//! println!("{}", con.graph_migrate("my_graph", &migrator, true)?);
+//! con.graph_migrate("my_graph", &migrator, false)?;
+//!
+//! let migrator = Migrator::from_dir("migrations")?
+//!     .with_store(MigrationStore::Key("my_graph:migrations".to_string()));
+//! con.graph_rollback("my_graph", &migrator, 0, false)?;
+//! # Ok(()) }
+//! ```
+//!
+//! # Bulk writes
+//!
+//! graph_bulk_write writes many rows with `UNWIND $rows AS row` queries,
+//! sending the rows as encoded parameter in batches limited by row count and
+//! parameter size. Rows can be ParamValues, property maps or with the `serde`
+//! feature references to serializable structs. The statistics of all batches
+//! are summed up in the result. The batches are not written atomically,
+//! graph_bulk_write_progress reports the written rows after every batch so a
+//! failed write can be resumed by skipping them.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # use std::collections::HashMap;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let riders = (0..100_000).map(|idx| {
+//!     let mut rider = HashMap::new();
+//!     rider.insert("name".to_string(), ParamValue::from(format!("Rider {}", idx)));
+//!     rider
+//! });
+//! let bulk = BulkWrite::create_nodes("Rider")
+//!     .with_batch_rows(5000)
+//!     .with_batch_bytes(512 * 1024);
+//! let res:BulkWriteResult = con.graph_bulk_write("my_graph", &bulk, riders)?;
+//! println!("{} nodes in {} queries", res.statistics.nodes_created, res.batches);
+//! # Ok(()) }
+//! ```
+//!
+//! # GRAPH.BULK loader
+//!
+//! graph_bulk_load creates a new graph with the binary GRAPH.BULK protocol of
+//! the Redis graph bulk loader. Nodes and relations are encoded into one
+//! token per label and relation type and sent in commands limited by token
+//! count and size. Relations reference nodes by the ids given to the nodes.
+//! With the `csv` feature CsvNodes and CsvRelations read the entities from
+//! CSV files in the format of the Redis graph bulk loader.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let nodes = vec![
+//!     BulkNode::new("r1", "Rider").with_property("name", "Valentino Rossi"),
+//!     BulkNode::new("t1", "Team").with_property("name", "Yamaha"),
+//! ];
+//! let relations = vec![BulkRelation::new("r1", "rides", "t1").with_property("since", 2004)];
+//! let res:BulkLoadResult = con.graph_bulk_load("new_graph", &BulkLoader::new(), nodes, relations)?;
+//! println!("{} nodes, {} relations", res.nodes_created, res.relations_created);
+//! # Ok(()) }
+//! ```
+//!
+//! # CSV import and export
+//!
+//! With the `csv` feature graph_import_csv loads node and relation CSV files
+//! in the Redis graph bulk loader format through batched Cypher writes into an
+//! existing graph, and graph_export_csv writes a graph into the same format,
+//! one file per label set and relation type with `:LABEL` and `:TYPE`
+//! columns. CsvExport writes any result set as
+//! CSV, with nodes and relations flattened into columns or encoded as JSON.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "csv")]
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let import = CsvImport::new()
+//!     .with_nodes("Rider.csv")
+//!     .with_nodes("Team.csv")
+//!     .with_relations_as("RIDES.csv", "rides");
+//! con.graph_import_csv("my_graph", &import)?;
+//! con.graph_export_csv("my_graph", "backup")?;
+//!
+//! let res = con.graph_query("my_graph", "MATCH (r:Rider) RETURN r")?;
+//! CsvExport::new().write_file(&res, "riders.csv")?;
+//! # Ok(()) }
+//! ```
+//!
+//! # JSON output
+//!
+//! With the `serde` feature result sets, rows and graph values implement
+//! Serialize with a stable JSON shape: a result set has a `header`, `rows` as
+//! objects of the RETURN keys and `statistics`. Nodes are objects with `id`,
+//! `labels` and `properties`, relations with `id`, `type`, `src`, `dest` and
+//! `properties`. Integers stay numbers, binary strings are base64 encoded.
+//! Redis graph returns doubles and booleans as strings, which become numbers
+//! and booleans in columns declared with to_json_with_types. NdjsonWriter
+//! writes one row per line, decoding rows of a GraphResultStream one at a
+//! time.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "serde")]
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let res = con.graph_ro_query("my_graph", "MATCH (r:Rider) RETURN r")?;
+//! let json: String = res.to_json()?;
+//!
+//! let res = con.graph_ro_query("my_graph", "MATCH (r:Rider) RETURN r.name, r.score")?;
+//! let json = res.to_json_with_types(&[("r.score", PropertyType::Float)])?;
+//!
+//! let stream = con.graph_ro_query_stream("my_graph", "MATCH (r:Rider) RETURN r")?;
+//! NdjsonWriter::new(std::io::stdout().lock()).write_stream(stream)?;
+//! # Ok(()) }
+//! ```
+//!
+//! # Arrow and Parquet export
+//!
+//! With the `arrow` feature ArrowExport converts a result set or a streamed
+//! result into Arrow record batches. Scalar column types are inferred from
+//! the values and widened to Utf8 where they vary across rows. Doubles and
+//! booleans, which Redis graph returns as strings, are converted for
+//! columns set to Float64 or Boolean with with_column_type.
+//! Nodes and relations become struct columns with a properties map. The
+//! `parquet` feature adds Parquet file output on top.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "parquet")]
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let res = con.graph_ro_query("my_graph", "MATCH (r:Rider) RETURN r, r.born")?;
+//! let batches = ArrowExport::new().to_record_batches(&res)?;
+//!
+//! let stream = con.graph_ro_query_stream("my_graph", "MATCH (r:Rider) RETURN r, r.born")?;
+//! ArrowExport::new().write_parquet_stream(stream, std::fs::File::create("riders.parquet")?)?;
+//! # Ok(()) }
+//! ```
+//!
+//! # GraphML, DOT and Mermaid export
+//!
+//! GraphExport writes a Subgraph as GraphML, Graphviz DOT or a Mermaid
+//! flowchart, keeping labels, relationship types and properties. Nodes can be
+//! captioned by a property and styled by label. The subgraph can come from
+//! any result set or from a whole graph fetched with graph_subgraph.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let export = GraphExport::new(GraphFormat::Dot)
+//!     .with_caption("name")
+//!     .with_style("Team", NodeStyle::new().with_color("#f4a261").with_shape(NodeShape::Box));
+//!
+//! let res = con.graph_ro_query("my_graph", "MATCH (r:Rider)-[x]->(t) RETURN r, x, t")?;
+//! let dot: String = export.to_string(&res.to_subgraph());
+//! export.write(&con.graph_subgraph("my_graph")?, std::fs::File::create("graph.dot")?)?;
+//! # Ok(()) }
+//! ```
+//!
+//! # Logical dump and restore
+//!
+//! graph_dump writes a graph with its indexes and constraints as a Cypher
+//! script or, with the `serde` feature, as a JSON archive. Unlike DUMP and
+//! RESTORE of the key the dump does not depend on the module version. Nodes
+//! and relations are read in id-ordered pages with read-only queries.
+//! graph_restore replays a dump into a fresh key with batched queries and
+//! reports the progress after every statement.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis::Commands;
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let mut script = Vec::new();
+//! con.graph_dump("my_graph", &GraphDump::new(DumpFormat::Cypher), &mut script)?;
+//!
+//! let restore = GraphRestore::new(DumpFormat::Cypher);
+//! con.graph_restore("my_graph_copy", &restore, script.as_slice(), |progress| {
+//!     println!("{} nodes created", progress.statistics.nodes_created);
+//! })?;
+//! # Ok(()) }
+//! ```
+//!
+//! # Copying graphs
+//!
+//! GraphCopy streams a graph from one connection to another, e.g. between
+//! servers, and recreates its indexes and constraints. Node and relation
+//! transforms can rename labels and types, change properties or drop
+//! entities. The checkpoint passed after every write resumes an interrupted
+//! copy.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis_graph::*;
+//! let mut source = redis::Client::open("redis://staging/")?.get_connection()?;
+//! let mut target = redis::Client::open("redis://production/")?.get_connection()?;
+//! let mut copy = GraphCopy::new().with_node_transform(|mut node| {
+//!     node.properties.remove("email");
+//!     Some(node)
+//! });
+//! let mut checkpoint = CopyCheckpoint::default();
+//! copy.copy(&mut source, "my_graph", &mut target, "my_graph", |c| checkpoint = c.clone())?;
+//! # Ok(()) }
+//! ```
+//!
+//! # Neo4j APOC import
+//!
+//! With the `serde` feature graph_import_apoc reads the JSON lines written
+//! by Neo4j's `apoc.export.json.all` and creates the nodes and relations in
+//! batches, mapping the Neo4j node ids to the created nodes. Values without
+//! a graph counterpart, like maps or cartesian points, are skipped and
+//! listed in the report.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "serde")]
+//! # fn run() -> redis::RedisResult<()> {
+//! # use redis_graph::*;
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let input = std::io::BufReader::new(std::fs::File::open("all.json")?);
+//! let report = con.graph_import_apoc("my_graph", &ApocImport::new(), input)?;
+//! println!("{} nodes, {} values skipped", report.nodes, report.skipped.len());
+//! # Ok(()) }
+//! ```
+//!
+//! # Replica routing
+//!
+//! A ReplicaRouter wraps a primary and a set of replica connections and sends
+//! read-only commands (GRAPH.RO_QUERY, GRAPH.EXPLAIN and GRAPH.SLOWLOG) to the
+//! replicas, either round-robin or to the least loaded replica. All other
+//! commands go to the primary. With read-your-writes enabled a WAIT is issued
+//! on the primary before the first read following a write.
+//!
+//! ```rust,no_run
+//! # fn run() -> redis::RedisResult<()> {
+//! use redis_graph::*;
+//!
+//! let primary = redis::Client::open("redis://127.0.0.1:6379/")?.get_connection()?;
+//! let replica = redis::Client::open("redis://127.0.0.1:6380/")?.get_connection()?;
+//! let mut router = ReplicaRouter::new(primary, vec![replica]).with_read_your_writes(1, 100);
+//!
+//! let _:GraphResultSet = router.graph_query("my_graph", "CREATE (:Rider {name:'Pam'})")?;
+//! let _:GraphResultSet = router.graph_ro_query("my_graph", "MATCH (r:Rider) RETURN r")?;
+//! # Ok(()) }
+//! ```
+//!
+//! # Redis Cluster
+//!
+//! With the `cluster` feature (and `cluster-async` for the async cluster
+//! connection, together with `tokio-comp` or `async-std-comp`) the graph
+//! commands can be used on cluster connections. Keyed commands are routed by
+//! their graph key. GraphKey allows building hash tagged keys to co-locate
+//! related graphs in the same slot and ClusterGraphCommands provide commands
+//! that need to run on all primaries.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "cluster")]
+//! # fn run() -> redis::RedisResult<()> {
+//! use redis_graph::*;
+//!
+//! let client = redis::cluster::ClusterClient::new(vec!["redis://127.0.0.1:7000/"])?;
+//! let mut con = client.get_connection()?;
+//!
+//! let key = GraphKey::tagged("tenant42", "social")?;
+//! let _:GraphResultSet = con.graph_query(&key, "CREATE (:Person {name:'Pam'})")?;
+//!
+//! let graphs:Vec<String> = con.graph_cluster_list("redis://127.0.0.1:7000/")?;
+//! # Ok(()) }
+//! ```
+//!
+//! # petgraph
+//!
+//! With the `petgraph` feature query results convert into a petgraph Graph or
+//! StableGraph with NodeValue and RelationValue weights, together with a map
+//! from Redis graph ids to node indices. graph_write_petgraph writes such a
+//! graph back into a graph key with batched CREATE statements. As doubles and
+//! booleans come back as strings in query results, graph_read_petgraph reads a
+//! whole graph with typed property values for an exact round trip.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "petgraph")]
+//! # fn run() -> redis::RedisResult<()> {
+//! use redis_graph::*;
+//!
+//! # let client = redis::Client::open("redis://127.0.0.1/")?;
+//! # let mut con = client.get_connection()?;
+//! let res = con.graph_ro_query("my_graph", "MATCH (a)-[r]->(b) RETURN a, r, b")?;
+//! let indexed = res.to_petgraph();
+//! let components = petgraph::algo::kosaraju_scc(&indexed.graph);
+//!
+//! let written = con.graph_write_petgraph("my_graph_copy", &indexed.graph, 500)?;
+//!
+//! let typed = con.graph_read_petgraph("my_graph", 1000)?;
+//! let written = con.graph_write_petgraph("my_graph_copy", &typed.graph, 500)?;
+//! # Ok(()) }
+//! ```
+pub use crate::algorithms::{
+    astar, bfs, connected_components, dfs, dijkstra, find_cycle, is_cyclic, Direction, WeightedPath,
+};
+#[cfg(feature = "serde")]
+pub use crate::apoc::{ApocImport, ApocReport, ApocSkipped};
+#[cfg(feature = "arrow")]
+pub use crate::arrow_support::{ArrowBatches, ArrowExport};
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
 pub use crate::async_commands::AsyncGraphCommands;
+pub use crate::bulk::{BulkRow, BulkWrite, BulkWriteResult};
+pub use crate::bulk_loader::{BulkEntity, BulkLoadResult, BulkLoader, BulkNode, BulkRelation};
+#[cfg(feature = "cluster-async")]
+pub use crate::cluster::AsyncClusterGraphCommands;
+#[cfg(feature = "cluster")]
+pub use crate::cluster::{graph_routing, ClusterGraphCommands, GraphKey};
 pub use crate::commands::GraphCommands;
+#[cfg(feature = "csv")]
+pub use crate::csv_support::{CsvExport, CsvImport, CsvNodes, CsvRelations, EntityColumns};
+pub use crate::cypher::{classify_query, is_read_only_query, QueryKind};
+pub use crate::dump::{
+    DumpFormat, DumpResult, DumpSkipped, GraphDump, GraphRestore, RestoreProgress, TypedNode,
+    TypedRelation,
+};
+pub use crate::fulltext::{escape_fulltext, FulltextQuery};
+pub use crate::graph_copy::{CopyCheckpoint, CopyResult, CopyStep, GraphCopy};
+pub use crate::graph_export::{GraphExport, GraphFormat, NodeShape, NodeStyle};
+#[cfg(feature = "serde")]
+pub use crate::json_support::NdjsonWriter;
+pub use crate::migrations::{
+    parse_script, AppliedMigration, Migration, MigrationContext, MigrationDirection, MigrationPlan,
+    MigrationStore, Migrator, PlannedMigration,
+};
+pub use crate::pagination::{GraphPages, PageMode, Pagination};
+pub use crate::params::{escape_identifier, query_with_params, ParamValue};
+#[cfg(feature = "petgraph")]
+pub use crate::petgraph_support::{
+    IndexedGraph, PetgraphEdge, PetgraphNode, PetgraphWrite, TypedGraph,
+};
+pub use crate::procedures::{
+    AlgoPath, BetweennessConfig, BfsConfig, BfsResult, LabelPropagationConfig, PageRankConfig,
+    PathConfig, ProcedureInfo, ProcedureMode, ProcedureRow, WccConfig,
+};
+pub use crate::replica::{is_replica_command, ReplicaRouter, ReplicaStrategy, PROBE_INTERVAL};
+pub use crate::row::{FromGraphRow, FromGraphValue};
+pub use crate::schema::{
+    ConstraintInfo, EntitySchema, EntityType, GraphSchema, IndexInfo, PropertyType,
+};
+pub use crate::schema_diff::{PropertyDifference, SchemaDiff, SchemaStatement};
+pub use crate::stream::{GraphResultStream, GraphRow};
+pub use crate::subgraph::Subgraph;
 pub use crate::types::*;
 
+mod algorithms;
+#[cfg(feature = "serde")]
+mod apoc;
+#[cfg(feature = "arrow")]
+mod arrow_support;
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
 mod async_commands;
+mod bulk;
+mod bulk_loader;
+#[cfg(feature = "cluster")]
+mod cluster;
 mod commands;
+#[cfg(feature = "csv")]
+mod csv_support;
+mod cypher;
+mod dump;
+mod fulltext;
+mod graph_copy;
+mod graph_export;
+#[cfg(feature = "serde")]
+mod json_support;
+mod migrations;
+mod pagination;
+mod params;
+#[cfg(feature = "petgraph")]
+mod petgraph_support;
+mod procedures;
+mod replica;
+mod row;
+mod schema;
+mod schema_diff;
+mod stream;
+mod subgraph;
 mod types;
@@ -0,0 +1,651 @@
+use crate::params::{escape_identifier, string_literal, ParamValue};
+use crate::procedures::parse_procedure_rows;
+use crate::schema_diff::SchemaStatement;
+use crate::types::*;
+use redis::{cmd, from_redis_value, Cmd, RedisResult, ToRedisArgs, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+type RustAction = Arc<dyn Fn(&mut MigrationContext) -> RedisResult<()> + Send + Sync>;
+
+// The statements of a migration direction, either a Cypher script or a Rust
+// closure generating statements
+#[derive(Clone)]
+enum MigrationAction {
+    Cypher(String),
+    Rust(RustAction),
+}
+
+impl MigrationAction {
+    fn statements(&self) -> RedisResult<Vec<SchemaStatement>> {
+        match self {
+            MigrationAction::Cypher(script) => parse_script(script),
+            MigrationAction::Rust(action) => {
+                let mut context = MigrationContext::default();
+                action(&mut context)?;
+                Ok(context.statements)
+            }
+        }
+    }
+}
+
+/// A versioned migration of a graph. The up statements apply the migration,
+/// the optional down statements revert it.
+///
+/// Cypher scripts contain statements separated by semicolons. Line comments
+/// (`//`) and block comments (`/* */`) outside of quotes are removed anywhere
+/// in the script, also behind a statement, and don't change the checksum.
+/// Constraints are changed with GRAPH.CONSTRAINT
+/// statements without the graph key, e.g.
+/// `GRAPH.CONSTRAINT CREATE UNIQUE NODE Rider PROPERTIES 1 name`.
+///
+/// ```rust
+/// use redis_graph::*;
+///
+/// let index = Migration::cypher(1, "rider_name_index", "CREATE INDEX FOR (r:Rider) ON (r.name)")
+///     .with_down("DROP INDEX ON :Rider(name)");
+/// let teams = Migration::rust(2, "teams", |ctx| {
+///     for team in ["Yamaha", "Honda", "Ducati"].iter() {
+///         ctx.query_with_params("CREATE (:Team {name: $name})", &[("name", ParamValue::from(*team))])?;
+///     }
+///     Ok(())
+/// });
+/// let migrator = Migrator::new(vec![teams, index]).unwrap();
+/// assert_eq!(migrator.migrations()[0].version(), 1);
+/// ```
+#[derive(Clone)]
+pub struct Migration {
+    version: u64,
+    name: String,
+    up: MigrationAction,
+    down: Option<MigrationAction>,
+}
+
+impl Migration {
+    /// A migration applying a Cypher script.
+    pub fn cypher<N: Into<String>, S: Into<String>>(version: u64, name: N, up: S) -> Self {
+        Migration {
+            version,
+            name: name.into(),
+            up: MigrationAction::Cypher(up.into()),
+            down: None,
+        }
+    }
+
+    /// A migration applying the statements generated by a closure. The
+    /// closure is called when the migration is planned, the generated
+    /// statements are part of the checksum.
+    pub fn rust<N, F>(version: u64, name: N, up: F) -> Self
+    where
+        N: Into<String>,
+        F: Fn(&mut MigrationContext) -> RedisResult<()> + Send + Sync + 'static,
+    {
+        Migration {
+            version,
+            name: name.into(),
+            up: MigrationAction::Rust(Arc::new(up)),
+            down: None,
+        }
+    }
+
+    /// Makes the migration reversible with a Cypher script.
+    pub fn with_down<S: Into<String>>(mut self, down: S) -> Self {
+        self.down = Some(MigrationAction::Cypher(down.into()));
+        self
+    }
+
+    /// Makes the migration reversible with a closure.
+    pub fn with_down_rust<F>(mut self, down: F) -> Self
+    where
+        F: Fn(&mut MigrationContext) -> RedisResult<()> + Send + Sync + 'static,
+    {
+        self.down = Some(MigrationAction::Rust(Arc::new(down)));
+        self
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns true if the migration can be rolled back.
+    pub fn is_reversible(&self) -> bool {
+        self.down.is_some()
+    }
+
+    /// The statements applying the migration.
+    pub fn up_statements(&self) -> RedisResult<Vec<SchemaStatement>> {
+        self.up.statements()
+    }
+
+    /// The checksum of the up statements, used to detect migrations that
+    /// changed after they were applied.
+    pub fn checksum(&self) -> RedisResult<String> {
+        let statements: Vec<String> = self
+            .up_statements()?
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        Ok(format!("{:016x}", fnv1a(statements.join("\n").as_bytes())))
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("name", &self.name)
+            .field("reversible", &self.is_reversible())
+            .finish()
+    }
+}
+
+/// Collects the statements generated by a Rust migration.
+#[derive(Default, Debug)]
+pub struct MigrationContext {
+    statements: Vec<SchemaStatement>,
+}
+
+impl MigrationContext {
+    /// Adds a Cypher query.
+    pub fn query<Q: Into<String>>(&mut self, query: Q) {
+        self.statements.push(SchemaStatement::Query(query.into()));
+    }
+
+    /// Adds a Cypher query with parameters, see query_with_params. Fails if a
+    /// parameter can't be encoded.
+    pub fn query_with_params<N: AsRef<str>>(
+        &mut self,
+        query: &str,
+        params: &[(N, ParamValue)],
+    ) -> RedisResult<()> {
+        self.query(crate::params::query_with_params(query, params)?);
+        Ok(())
+    }
+
+    /// Adds a statement, e.g. a constraint change.
+    pub fn statement(&mut self, statement: SchemaStatement) {
+        self.statements.push(statement);
+    }
+
+    /// Adds all statements of a Cypher script.
+    pub fn script(&mut self, script: &str) -> RedisResult<()> {
+        self.statements.extend(parse_script(script)?);
+        Ok(())
+    }
+}
+
+/// Where the applied migrations of a graph are recorded. The default is a
+/// companion hash at the graph key followed by `:migrations`, which is not
+/// removed by GRAPH.DELETE.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MigrationStore {
+    /// A Redis hash at the given key mapping versions to checksum and name.
+    Key(String),
+    /// A Redis hash like Key at the graph key followed by the given suffix.
+    Companion(String),
+    /// Nodes with the given label inside the migrated graph. The nodes are
+    /// part of the graph, so they also show up in its schema, dumps, exports
+    /// and copies.
+    Node(String),
+}
+
+impl Default for MigrationStore {
+    fn default() -> Self {
+        MigrationStore::Companion(":migrations".to_string())
+    }
+}
+
+/// A migration recorded as applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppliedMigration {
+    pub version: u64,
+    pub name: String,
+    pub checksum: String,
+}
+
+/// Whether a planned migration is applied or rolled back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigrationDirection {
+    Up,
+    Down,
+}
+
+/// A migration step of a plan with the statements it executes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlannedMigration {
+    pub version: u64,
+    pub name: String,
+    pub direction: MigrationDirection,
+    pub checksum: String,
+    pub statements: Vec<SchemaStatement>,
+}
+
+/// The migrations a runner executes in order. With dry-run the plan is
+/// returned without executing it, its Display output lists all statements.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MigrationPlan {
+    pub steps: Vec<PlannedMigration>,
+}
+
+impl MigrationPlan {
+    /// Returns true if there is nothing to do.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl fmt::Display for MigrationPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.steps.is_empty() {
+            return writeln!(f, "Nothing to migrate");
+        }
+        for step in self.steps.iter() {
+            let direction = match step.direction {
+                MigrationDirection::Up => "up",
+                MigrationDirection::Down => "down",
+            };
+            writeln!(f, "{} {} {}", direction, step.version, step.name)?;
+            for statement in step.statements.iter() {
+                writeln!(f, "  {}", statement)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An ordered set of migrations together with the store recording them. The
+/// runners are graph_migrate and graph_rollback on GraphCommands and
+/// AsyncGraphCommands.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let migrator = Migrator::from_dir("migrations")?
+///     .with_store(MigrationStore::Key("my_graph:migrations".to_string()));
+/// println!("{}", con.graph_migrate("my_graph", &migrator, true)?);
+/// let applied = con.graph_migrate("my_graph", &migrator, false)?;
+/// let reverted = con.graph_rollback("my_graph", &migrator, 0, false)?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+    store: MigrationStore,
+}
+
+impl Migrator {
+    /// Creates a migrator for migrations with distinct versions, recorded in
+    /// the default store.
+    pub fn new(mut migrations: Vec<Migration>) -> RedisResult<Self> {
+        migrations.sort_by_key(|m| m.version);
+        for pair in migrations.windows(2) {
+            if pair[0].version == pair[1].version {
+                return Err(create_error(&format!(
+                    "Duplicate migration version {}",
+                    pair[0].version
+                )));
+            }
+        }
+        Ok(Migrator {
+            migrations,
+            store: MigrationStore::default(),
+        })
+    }
+
+    /// Reads Cypher migrations from a directory. Files are named
+    /// `<version>_<name>.cypher` or `<version>_<name>.up.cypher`, the
+    /// optional down script is `<version>_<name>.down.cypher`.
+    pub fn from_dir<P: AsRef<Path>>(path: P) -> RedisResult<Self> {
+        let mut ups: BTreeMap<u64, (String, String)> = BTreeMap::new();
+        let mut downs: HashMap<u64, String> = HashMap::new();
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            let file_name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) if name.ends_with(".cypher") => name.to_string(),
+                _ => continue,
+            };
+            let stem = file_name.trim_end_matches(".cypher");
+            let (stem, down) = match stem.strip_suffix(".down") {
+                Some(stem) => (stem, true),
+                None => (stem.trim_end_matches(".up"), false),
+            };
+            let (version, name) = stem
+                .split_once('_')
+                .and_then(|(v, n)| Some((v.parse::<u64>().ok()?, n)))
+                .ok_or_else(|| {
+                    create_error(&format!("Invalid migration file name {}", file_name))
+                })?;
+            let script = std::fs::read_to_string(&path)?;
+            let duplicate = if down {
+                downs.insert(version, script).is_some()
+            } else {
+                ups.insert(version, (name.to_string(), script)).is_some()
+            };
+            if duplicate {
+                return Err(create_error(&format!(
+                    "Duplicate migration version {}",
+                    version
+                )));
+            }
+        }
+        if let Some(version) = downs.keys().find(|v| !ups.contains_key(v)) {
+            return Err(create_error(&format!(
+                "Down migration {} without up migration",
+                version
+            )));
+        }
+        let migrations = ups
+            .into_iter()
+            .map(|(version, (name, script))| {
+                let migration = Migration::cypher(version, name, script);
+                match downs.remove(&version) {
+                    Some(down) => migration.with_down(down),
+                    None => migration,
+                }
+            })
+            .collect();
+        Migrator::new(migrations)
+    }
+
+    /// Changes where applied migrations are recorded.
+    pub fn with_store(mut self, store: MigrationStore) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// The migrations ordered by version.
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    pub fn store(&self) -> &MigrationStore {
+        &self.store
+    }
+
+    /// Plans applying all pending migrations. Fails if an applied migration
+    /// is unknown or changed, or if a pending migration is older than the
+    /// latest applied one.
+    pub fn plan_migrate(&self, applied: &[AppliedMigration]) -> RedisResult<MigrationPlan> {
+        let applied = self.verify(applied)?;
+        let latest = applied.keys().next_back().copied();
+        let mut steps = Vec::new();
+        for migration in self.migrations.iter() {
+            if applied.contains_key(&migration.version) {
+                continue;
+            }
+            if let Some(latest) = latest.filter(|latest| *latest > migration.version) {
+                return Err(create_error(&format!(
+                    "Migration {} is older than the applied migration {}",
+                    migration.version, latest
+                )));
+            }
+            steps.push(PlannedMigration {
+                version: migration.version,
+                name: migration.name.clone(),
+                direction: MigrationDirection::Up,
+                checksum: migration.checksum()?,
+                statements: migration.up_statements()?,
+            });
+        }
+        Ok(MigrationPlan { steps })
+    }
+
+    /// Plans rolling back all applied migrations newer than target, newest
+    /// first. Fails if one of them is not reversible.
+    pub fn plan_rollback(
+        &self,
+        applied: &[AppliedMigration],
+        target: u64,
+    ) -> RedisResult<MigrationPlan> {
+        let applied = self.verify(applied)?;
+        let mut steps = Vec::new();
+        for migration in self.migrations.iter().rev() {
+            if migration.version <= target || !applied.contains_key(&migration.version) {
+                continue;
+            }
+            let down = migration.down.as_ref().ok_or_else(|| {
+                create_error(&format!(
+                    "Migration {} is not reversible",
+                    migration.version
+                ))
+            })?;
+            steps.push(PlannedMigration {
+                version: migration.version,
+                name: migration.name.clone(),
+                direction: MigrationDirection::Down,
+                checksum: applied[&migration.version].checksum.clone(),
+                statements: down.statements()?,
+            });
+        }
+        Ok(MigrationPlan { steps })
+    }
+
+    // Checks that every applied migration is known and unchanged
+    fn verify<'a>(
+        &self,
+        applied: &'a [AppliedMigration],
+    ) -> RedisResult<BTreeMap<u64, &'a AppliedMigration>> {
+        let mut by_version = BTreeMap::new();
+        for entry in applied {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|m| m.version == entry.version)
+                .ok_or_else(|| {
+                    create_error(&format!("Applied migration {} is unknown", entry.version))
+                })?;
+            if migration.checksum()? != entry.checksum {
+                return Err(create_error(&format!(
+                    "Checksum mismatch for migration {} {}",
+                    entry.version, entry.name
+                )));
+            }
+            by_version.insert(entry.version, entry);
+        }
+        Ok(by_version)
+    }
+}
+
+// A store resolved for a graph key
+enum StoreKey<'a> {
+    Hash(Vec<u8>),
+    Node(&'a str),
+}
+
+impl MigrationStore {
+    fn resolve<K: ToRedisArgs>(&self, key: K) -> StoreKey<'_> {
+        match self {
+            MigrationStore::Key(store) => StoreKey::Hash(store.as_bytes().to_vec()),
+            MigrationStore::Companion(suffix) => {
+                let mut store = key.to_redis_args().concat();
+                store.extend_from_slice(suffix.as_bytes());
+                StoreKey::Hash(store)
+            }
+            MigrationStore::Node(label) => StoreKey::Node(label),
+        }
+    }
+
+    // Reads the applied migrations, the key is the graph key
+    pub(crate) fn read_cmd<K: ToRedisArgs>(&self, key: K) -> Cmd {
+        match self.resolve(&key) {
+            StoreKey::Hash(store) => cmd("HGETALL").arg(store).clone(),
+            StoreKey::Node(label) => cmd("GRAPH.RO_QUERY")
+                .arg(key)
+                .arg(format!(
+                    "MATCH (m:{}) RETURN m.version AS version, m.name AS name, \
+                     m.checksum AS checksum ORDER BY m.version",
+                    escape_identifier(label)
+                ))
+                .clone(),
+        }
+    }
+
+    // Checks whether the graph holding the migration nodes exists, as a graph
+    // that does not exist yet can not be queried and has no migrations. Hash
+    // stores need no check.
+    pub(crate) fn exists_cmd<K: ToRedisArgs>(&self, key: K) -> Option<Cmd> {
+        match self {
+            MigrationStore::Key(_) | MigrationStore::Companion(_) => None,
+            MigrationStore::Node(_) => Some(cmd("EXISTS").arg(key).clone()),
+        }
+    }
+
+    pub(crate) fn parse_applied(&self, value: &Value) -> RedisResult<Vec<AppliedMigration>> {
+        let mut applied = match self {
+            MigrationStore::Key(_) | MigrationStore::Companion(_) => {
+                let entries: HashMap<u64, String> = from_redis_value(value)?;
+                entries
+                    .into_iter()
+                    .map(|(version, entry)| {
+                        let (checksum, name) = entry.split_once(' ').unwrap_or((&entry, ""));
+                        AppliedMigration {
+                            version,
+                            name: name.to_string(),
+                            checksum: checksum.to_string(),
+                        }
+                    })
+                    .collect()
+            }
+            MigrationStore::Node(_) => parse_procedure_rows(value)?
+                .iter()
+                .map(|row| {
+                    Ok(AppliedMigration {
+                        version: row
+                            .get_value("version")?
+                            .ok_or_else(|| create_error("Migration without version"))?,
+                        name: row.get_value("name")?.unwrap_or_default(),
+                        checksum: row.get_value("checksum")?.unwrap_or_default(),
+                    })
+                })
+                .collect::<RedisResult<Vec<AppliedMigration>>>()?,
+        };
+        applied.sort_by_key(|m| m.version);
+        Ok(applied)
+    }
+
+    // Records a step as applied, or removes the record of a rolled back step
+    pub(crate) fn record_cmd<K: ToRedisArgs>(&self, key: K, step: &PlannedMigration) -> Cmd {
+        match (self.resolve(&key), step.direction) {
+            (StoreKey::Hash(store), MigrationDirection::Up) => cmd("HSET")
+                .arg(store)
+                .arg(step.version)
+                .arg(format!("{} {}", step.checksum, step.name))
+                .clone(),
+            (StoreKey::Hash(store), MigrationDirection::Down) => {
+                cmd("HDEL").arg(store).arg(step.version).clone()
+            }
+            (StoreKey::Node(label), MigrationDirection::Up) => cmd("GRAPH.QUERY")
+                .arg(key)
+                .arg(format!(
+                    "CREATE (:{} {{version: {}, name: {}, checksum: {}}})",
+                    escape_identifier(label),
+                    step.version,
+                    string_literal(&step.name),
+                    string_literal(&step.checksum)
+                ))
+                .clone(),
+            (StoreKey::Node(label), MigrationDirection::Down) => cmd("GRAPH.QUERY")
+                .arg(key)
+                .arg(format!(
+                    "MATCH (m:{}) WHERE m.version = {} DELETE m",
+                    escape_identifier(label),
+                    step.version
+                ))
+                .clone(),
+        }
+    }
+}
+
+/// Splits a Cypher script into statements at semicolons outside of quotes and
+/// comments. Line comments (`//`) and block comments (`/* */`) are removed.
+pub fn parse_script(script: &str) -> RedisResult<Vec<SchemaStatement>> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    // Whether the previous character was an unescaped backslash in a string
+    let mut escaped = false;
+    let mut chars = script.chars().peekable();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(q) if c == '\\' && q != '`' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' || c == '`' => quote = Some(c),
+            None if c == ';' => {
+                push_statement(&mut statements, &current)?;
+                current.clear();
+                continue;
+            }
+            None if c == '/' && chars.peek() == Some(&'/') => {
+                while chars.next_if(|next| *next != '\n').is_some() {}
+                continue;
+            }
+            None if c == '/' && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = None;
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == Some('*') => break,
+                        Some(next) => previous = Some(next),
+                        None => {
+                            return Err(create_error(
+                                "Unterminated block comment in migration script",
+                            ))
+                        }
+                    }
+                }
+                current.push(' ');
+                continue;
+            }
+            None => {}
+        }
+        current.push(c);
+    }
+    if quote.is_some() {
+        return Err(create_error("Unterminated quote in migration script"));
+    }
+    push_statement(&mut statements, &current)?;
+    Ok(statements)
+}
+
+fn push_statement(statements: &mut Vec<SchemaStatement>, statement: &str) -> RedisResult<()> {
+    if !statement.trim().is_empty() {
+        statements.push(SchemaStatement::parse(statement)?);
+    }
+    Ok(())
+}
+
+// 64 bit FNV-1a, stable across platforms and releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Executes the steps of a plan in order, recording every step once all of
+// its statements succeeded
+pub(crate) fn run_plan<C: redis::ConnectionLike, K: ToRedisArgs>(
+    con: &mut C,
+    key: K,
+    store: &MigrationStore,
+    plan: &MigrationPlan,
+) -> RedisResult<()> {
+    for step in plan.steps.iter() {
+        for statement in step.statements.iter() {
+            con.req_command(&statement.to_cmd(&key))?;
+        }
+        con.req_command(&store.record_cmd(&key, step))?;
+    }
+    Ok(())
+}
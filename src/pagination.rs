@@ -0,0 +1,340 @@
+use crate::commands::GraphCommands;
+use crate::cypher::pageable_query;
+use crate::params::{query_with_params, ParamValue};
+use crate::types::*;
+use redis::RedisResult;
+
+/// Defines how the pages of a paginated query are requested.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PageMode {
+    /// Appends `SKIP offset LIMIT page_size` to the query. The query needs a
+    /// stable ORDER BY clause for the pages to be consistent.
+    SkipLimit,
+    /// Key-set pagination. The value of `column` in the last row of a page is
+    /// passed as parameter `param` to the query for the next page (starting
+    /// with `start`). The query needs to filter and order by it, eg.
+    /// `MATCH (n) WHERE id(n) > $cursor RETURN n, id(n) AS cursor ORDER BY cursor`.
+    /// As Redis graph returns doubles and booleans as strings, the cursor is
+    /// converted to the type of `start`.
+    KeySet {
+        column: String,
+        param: String,
+        start: ParamValue,
+    },
+}
+
+/// Describes a query that is executed page by page. The query is re-issued
+/// with SKIP/LIMIT or a key-set cursor until a page comes back with less rows
+/// than the page size.
+///
+/// As Redis graph silently truncates results to the RESULTSET_SIZE config, the
+/// limit is read from the server before the first page. A page that comes back
+/// with exactly RESULTSET_SIZE rows although more were requested was truncated
+/// by the server. It is not taken as the last page and the following pages are
+/// requested with RESULTSET_SIZE as page size, see GraphPages::truncated.
+///
+/// The query must not end with SKIP or LIMIT clauses and must be a single
+/// statement. Trailing semicolons and comments are removed before the clauses
+/// for the pages are appended.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let pagination = Pagination::new(
+///     "my_graph",
+///     "MATCH (r:Rider) RETURN r.name ORDER BY r.name",
+///     1000,
+/// );
+/// for row in con.graph_paginate(pagination) {
+///     let name: Option<String> = row?.get_scalar("r.name");
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Pagination {
+    key: String,
+    query: String,
+    page_size: usize,
+    mode: PageMode,
+    read_only: bool,
+    result_set_cap: Option<Option<usize>>,
+}
+
+impl Pagination {
+    /// Creates a read-only SKIP/LIMIT pagination for query on graph key.
+    pub fn new<K: Into<String>, Q: Into<String>>(key: K, query: Q, page_size: usize) -> Self {
+        Pagination {
+            key: key.into(),
+            query: query.into(),
+            page_size: page_size.max(1),
+            mode: PageMode::SkipLimit,
+            read_only: true,
+            result_set_cap: None,
+        }
+    }
+
+    /// Switches to key-set pagination, see PageMode::KeySet.
+    pub fn with_key_set<C: Into<String>, P: Into<String>, S: Into<ParamValue>>(
+        mut self,
+        column: C,
+        param: P,
+        start: S,
+    ) -> Self {
+        self.mode = PageMode::KeySet {
+            column: column.into(),
+            param: param.into(),
+            start: start.into(),
+        };
+        self
+    }
+
+    /// Whether the pages are executed with GRAPH.RO_QUERY (true, the default)
+    /// or with GRAPH.QUERY (false, for queries that write).
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Sets the server result set limit (None for unlimited) instead of reading
+    /// it with GRAPH.CONFIG GET RESULTSET_SIZE.
+    pub fn with_result_set_cap(mut self, cap: Option<usize>) -> Self {
+        self.result_set_cap = Some(cap);
+        self
+    }
+
+    /// Returns an iterator over all rows of all pages.
+    pub fn iter<C: GraphCommands>(self, con: &mut C) -> GraphPages<'_, C> {
+        GraphPages {
+            con,
+            state: PageState::new(&self),
+            pagination: self,
+            buffer: Vec::default().into_iter(),
+        }
+    }
+
+    /// Returns a stream over all rows of all pages.
+    #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+    pub fn stream<C: crate::AsyncGraphCommands>(
+        self,
+        con: &mut C,
+    ) -> futures_util::stream::BoxStream<'_, RedisResult<GraphResult>> {
+        use futures_util::StreamExt;
+
+        let state = PageState::new(&self);
+        let init = (con, self, state, Vec::default().into_iter());
+        futures_util::stream::unfold(
+            init,
+            |(con, pagination, mut state, mut buffer)| async move {
+                loop {
+                    if let Some(row) = buffer.next() {
+                        return Some((Ok(row), (con, pagination, state, buffer)));
+                    }
+                    if state.done {
+                        return None;
+                    }
+                    if state.cap.is_none() {
+                        let cap = match pagination.result_set_cap {
+                            Some(cap) => cap,
+                            None => match parse_cap(con.graph_config_get("RESULTSET_SIZE").await) {
+                                Ok(cap) => cap,
+                                Err(e) => {
+                                    state.done = true;
+                                    return Some((Err(e), (con, pagination, state, buffer)));
+                                }
+                            },
+                        };
+                        state.set_cap(cap);
+                    }
+                    let page = match pagination.page_query(&state) {
+                        Ok(query) if pagination.read_only => {
+                            con.graph_ro_query(pagination.key.as_str(), query).await
+                        }
+                        Ok(query) => con.graph_query(pagination.key.as_str(), query).await,
+                        Err(e) => Err(e),
+                    };
+                    match page.and_then(|page| state.apply(&pagination, page)) {
+                        Ok(rows) => buffer = rows.into_iter(),
+                        Err(e) => {
+                            state.done = true;
+                            return Some((Err(e), (con, pagination, state, buffer)));
+                        }
+                    }
+                }
+            },
+        )
+        .boxed()
+    }
+
+    // Fails if SKIP and LIMIT can't be appended to the query or if the cursor
+    // of a key-set query can't be encoded
+    fn page_query(&self, state: &PageState) -> RedisResult<String> {
+        let query = pageable_query(&self.query)?;
+        match &self.mode {
+            PageMode::SkipLimit => Ok(format!(
+                "{} SKIP {} LIMIT {}",
+                query, state.offset, state.page_size
+            )),
+            PageMode::KeySet { param, .. } => query_with_params(
+                &format!("{} LIMIT {}", query, state.page_size),
+                &[(param.as_str(), state.cursor.clone())],
+            ),
+        }
+    }
+}
+
+/// Iterator over the rows of a paginated query. Pages are requested from the
+/// server when the rows of the previous page are consumed.
+pub struct GraphPages<'a, C> {
+    con: &'a mut C,
+    pagination: Pagination,
+    state: PageState,
+    buffer: std::vec::IntoIter<GraphResult>,
+}
+
+impl<'a, C: GraphCommands> GraphPages<'a, C> {
+    /// The result set limit of the server if known (after the first page).
+    pub fn result_set_cap(&self) -> Option<usize> {
+        self.state.cap.flatten()
+    }
+
+    /// The page size used for requests, which is reduced to the result set
+    /// limit of the server once it truncated a page.
+    pub fn page_size(&self) -> usize {
+        self.state.page_size
+    }
+
+    /// Whether the server truncated a page to RESULTSET_SIZE rows, because the
+    /// page size was larger. Such a page is not taken as the last page, the
+    /// next page is requested to make sure no rows are lost. Full pages of the
+    /// requested page size are not truncated.
+    pub fn truncated(&self) -> bool {
+        self.state.truncated
+    }
+
+    fn fetch(&mut self) -> RedisResult<Vec<GraphResult>> {
+        if self.state.cap.is_none() {
+            let cap = match self.pagination.result_set_cap {
+                Some(cap) => cap,
+                None => parse_cap(self.con.graph_config_get("RESULTSET_SIZE"))?,
+            };
+            self.state.set_cap(cap);
+        }
+        let query = self.pagination.page_query(&self.state)?;
+        let page = if self.pagination.read_only {
+            self.con
+                .graph_ro_query(self.pagination.key.as_str(), query)?
+        } else {
+            self.con.graph_query(self.pagination.key.as_str(), query)?
+        };
+        self.state.apply(&self.pagination, page)
+    }
+}
+
+impl<'a, C: GraphCommands> Iterator for GraphPages<'a, C> {
+    type Item = RedisResult<GraphResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.buffer.next() {
+                return Some(Ok(row));
+            }
+            if self.state.done {
+                return None;
+            }
+            match self.fetch() {
+                Ok(rows) => self.buffer = rows.into_iter(),
+                Err(e) => {
+                    self.state.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+struct PageState {
+    offset: usize,
+    cursor: ParamValue,
+    page_size: usize,
+    cap: Option<Option<usize>>,
+    truncated: bool,
+    done: bool,
+}
+
+impl PageState {
+    fn new(pagination: &Pagination) -> Self {
+        PageState {
+            offset: 0,
+            cursor: match &pagination.mode {
+                PageMode::KeySet { start, .. } => start.clone(),
+                PageMode::SkipLimit => ParamValue::Null,
+            },
+            page_size: pagination.page_size,
+            cap: None,
+            truncated: false,
+            done: false,
+        }
+    }
+
+    fn set_cap(&mut self, cap: Option<usize>) {
+        self.cap = Some(cap);
+    }
+
+    // Advances offset and cursor returning the rows of the page
+    fn apply(
+        &mut self,
+        pagination: &Pagination,
+        page: GraphResultSet,
+    ) -> RedisResult<Vec<GraphResult>> {
+        let rows = page.data;
+        let capped = self
+            .cap
+            .flatten()
+            .is_some_and(|cap| !rows.is_empty() && rows.len() == cap && cap < self.page_size);
+        if capped {
+            self.truncated = true;
+            self.page_size = rows.len();
+        } else if rows.len() < self.page_size {
+            self.done = true;
+        }
+        self.offset += rows.len();
+        if let (PageMode::KeySet { column, start, .. }, Some(last)) =
+            (&pagination.mode, rows.last())
+        {
+            self.cursor = match last.get_value(column) {
+                Some(GraphValue::Scalar(value)) => typed_cursor(value, start)?,
+                _ => return Err(create_error("Missing key-set cursor column in page")),
+            };
+        }
+        Ok(rows)
+    }
+}
+
+// Converts a cursor value to the type of the start value
+fn typed_cursor(value: &redis::Value, start: &ParamValue) -> RedisResult<ParamValue> {
+    let cursor = ParamValue::from_redis(value)?;
+    let invalid = || create_error(&format!("Invalid key-set cursor {:?}", cursor));
+    Ok(match (start, &cursor) {
+        (ParamValue::Float(_), ParamValue::Int(v)) => ParamValue::Float(*v as f64),
+        (ParamValue::Float(_), ParamValue::String(v)) => {
+            ParamValue::Float(v.parse().map_err(|_| invalid())?)
+        }
+        (ParamValue::Bool(_), ParamValue::String(v)) => {
+            ParamValue::Bool(v.parse().map_err(|_| invalid())?)
+        }
+        (ParamValue::Int(_), ParamValue::String(v)) => {
+            ParamValue::Int(v.parse().map_err(|_| invalid())?)
+        }
+        _ => cursor,
+    })
+}
+
+// A negative RESULTSET_SIZE means unlimited
+fn parse_cap(cap: RedisResult<i64>) -> RedisResult<Option<usize>> {
+    let cap = cap?;
+    Ok(if cap >= 0 { Some(cap as usize) } else { None })
+}
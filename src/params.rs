@@ -0,0 +1,263 @@
+use crate::types::create_error;
+use redis::{from_redis_value, RedisResult, Value};
+use std::collections::HashMap;
+
+/// A query parameter value. Parameters are passed to Redis graph with a
+/// `CYPHER name=value` prefix in front of the query and are encoded as Cypher
+/// literals.
+///
+/// Floats that are NaN or infinite have no Cypher literal and fail to encode.
+///
+/// ```rust
+/// use redis_graph::{query_with_params, ParamValue};
+///
+/// let query = query_with_params(
+///     "MATCH (r:Rider) WHERE r.name = $name RETURN r",
+///     &[("name", ParamValue::from("Valentino 'The Doctor' Rossi"))],
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     query,
+///     "CYPHER name='Valentino \\'The Doctor\\' Rossi' MATCH (r:Rider) WHERE r.name = $name RETURN r"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<ParamValue>),
+    Map(Vec<(String, ParamValue)>),
+}
+
+impl ParamValue {
+    /// Encodes the value as a Cypher literal. Fails for NaN or infinite floats.
+    pub fn to_cypher(&self) -> RedisResult<String> {
+        let mut out = String::new();
+        self.write_cypher(&mut out)?;
+        Ok(out)
+    }
+
+    fn write_cypher(&self, out: &mut String) -> RedisResult<()> {
+        match self {
+            ParamValue::Null => out.push_str("null"),
+            ParamValue::Bool(v) => out.push_str(if *v { "true" } else { "false" }),
+            ParamValue::Int(v) => out.push_str(&v.to_string()),
+            ParamValue::Float(v) if v.is_finite() => out.push_str(&format!("{:?}", v)),
+            ParamValue::Float(v) => {
+                return Err(create_error(&format!(
+                    "Float parameter {} has no Cypher literal",
+                    v
+                )))
+            }
+            ParamValue::String(v) => write_string(v, out),
+            ParamValue::List(values) => {
+                out.push('[');
+                for (idx, value) in values.iter().enumerate() {
+                    if idx > 0 {
+                        out.push_str(", ");
+                    }
+                    value.write_cypher(out)?;
+                }
+                out.push(']');
+            }
+            ParamValue::Map(entries) => {
+                out.push('{');
+                for (idx, (key, value)) in entries.iter().enumerate() {
+                    if idx > 0 {
+                        out.push_str(", ");
+                    }
+                    out.push_str(&escape_identifier(key));
+                    out.push_str(": ");
+                    value.write_cypher(out)?;
+                }
+                out.push('}');
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts any serializable value into a parameter. Structs and maps
+    /// become maps, sequences become lists and integers that fit into i64
+    /// stay integers.
+    #[cfg(feature = "serde")]
+    pub fn from_serialize<T: serde::Serialize + ?Sized>(value: &T) -> RedisResult<Self> {
+        serde_json::to_value(value)
+            .map(ParamValue::from_json)
+            .map_err(|e| create_error(&format!("Failed to serialize parameter: {}", e)))
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_json(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => ParamValue::Null,
+            serde_json::Value::Bool(v) => ParamValue::Bool(v),
+            serde_json::Value::Number(v) => match v.as_i64() {
+                Some(v) => ParamValue::Int(v),
+                None => ParamValue::Float(v.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(v) => ParamValue::String(v),
+            serde_json::Value::Array(values) => {
+                ParamValue::List(values.into_iter().map(ParamValue::from_json).collect())
+            }
+            serde_json::Value::Object(entries) => ParamValue::Map(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, ParamValue::from_json(v)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Converts a scalar Redis value as returned by a graph query into a
+    /// parameter. Integers stay integers, data is read as string and nested
+    /// arrays become lists.
+    pub fn from_redis(v: &Value) -> RedisResult<Self> {
+        match v {
+            Value::Nil => Ok(ParamValue::Null),
+            Value::Int(v) => Ok(ParamValue::Int(*v)),
+            Value::Data(_) | Value::Status(_) => Ok(ParamValue::String(from_redis_value(v)?)),
+            Value::Okay => Ok(ParamValue::String("OK".to_string())),
+            Value::Bulk(values) => values
+                .iter()
+                .map(ParamValue::from_redis)
+                .collect::<RedisResult<Vec<_>>>()
+                .map(ParamValue::List),
+        }
+    }
+}
+
+fn write_string(v: &str, out: &mut String) {
+    out.push('\'');
+    for c in v.chars() {
+        match c {
+            '\'' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+}
+
+// Encodes a string as Cypher literal, which unlike floats can't fail
+pub(crate) fn string_literal(v: &str) -> String {
+    let mut out = String::new();
+    write_string(v, &mut out);
+    out
+}
+
+// Encodes an optional string as Cypher literal or null
+pub(crate) fn optional_string_literal(v: Option<&str>) -> String {
+    v.map_or_else(|| "null".to_string(), string_literal)
+}
+
+/// Escapes a name (property, parameter, label) with backticks in case it is
+/// not a plain identifier.
+pub fn escape_identifier(name: &str) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+/// Prefixes a query with the given parameters as `CYPHER name=value ...`.
+/// Fails if a parameter contains a NaN or infinite float.
+pub fn query_with_params<N: AsRef<str>>(
+    query: &str,
+    params: &[(N, ParamValue)],
+) -> RedisResult<String> {
+    if params.is_empty() {
+        return Ok(query.to_string());
+    }
+    let mut out = String::from("CYPHER");
+    for (name, value) in params {
+        out.push(' ');
+        out.push_str(&escape_identifier(name.as_ref()));
+        out.push('=');
+        value.write_cypher(&mut out)?;
+    }
+    out.push(' ');
+    out.push_str(query);
+    Ok(out)
+}
+
+// Prefixes a query with a single id parameter, which unlike floats can't fail
+pub(crate) fn query_with_id(query: &str, name: &str, id: u64) -> String {
+    format!("CYPHER {}={} {}", escape_identifier(name), id, query)
+}
+
+impl From<bool> for ParamValue {
+    fn from(v: bool) -> Self {
+        ParamValue::Bool(v)
+    }
+}
+
+macro_rules! int_param {
+    ($($t:ty),*) => {
+        $(impl From<$t> for ParamValue {
+            fn from(v: $t) -> Self {
+                ParamValue::Int(v as i64)
+            }
+        })*
+    };
+}
+
+int_param!(i8, i16, i32, i64, u8, u16, u32, isize);
+
+impl From<f32> for ParamValue {
+    fn from(v: f32) -> Self {
+        ParamValue::Float(v as f64)
+    }
+}
+
+impl From<f64> for ParamValue {
+    fn from(v: f64) -> Self {
+        ParamValue::Float(v)
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(v: &str) -> Self {
+        ParamValue::String(v.to_string())
+    }
+}
+
+impl From<String> for ParamValue {
+    fn from(v: String) -> Self {
+        ParamValue::String(v)
+    }
+}
+
+impl<T: Into<ParamValue>> From<Option<T>> for ParamValue {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(ParamValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<ParamValue>> From<Vec<T>> for ParamValue {
+    fn from(v: Vec<T>) -> Self {
+        ParamValue::List(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<ParamValue>> From<HashMap<String, T>> for ParamValue {
+    fn from(v: HashMap<String, T>) -> Self {
+        let mut entries: Vec<(String, ParamValue)> =
+            v.into_iter().map(|(k, v)| (k, v.into())).collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        ParamValue::Map(entries)
+    }
+}
@@ -0,0 +1,446 @@
+use crate::dump::{node_page_query, parse_nodes, parse_relations, relation_page_query};
+use crate::dump::{TypedNode, TypedRelation};
+use crate::params::{escape_identifier, ParamValue};
+use crate::subgraph::Subgraph;
+use crate::types::*;
+use petgraph::graph::{EdgeIndex, Graph, NodeIndex};
+use petgraph::stable_graph::StableGraph;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences, IntoNodeReferences, NodeRef};
+use redis::{RedisResult, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// A petgraph graph converted from query results together with the mapping
+/// from Redis graph ids to petgraph indices.
+///
+/// Redis graph returns doubles and booleans as strings, so the NodeValue and
+/// RelationValue weights of a converted result do not tell them from string
+/// properties. Use graph_read_petgraph to read a whole graph with typed
+/// property values that survive a graph_write_petgraph round trip.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let res = con.graph_ro_query("my_graph", "MATCH (a)-[r]->(b) RETURN a, r, b")?;
+/// let indexed = res.to_petgraph();
+/// let sorted = petgraph::algo::toposort(&indexed.graph, None);
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug)]
+pub struct IndexedGraph<G> {
+    /// The petgraph graph with NodeValue and RelationValue weights.
+    pub graph: G,
+    /// Maps Redis graph node ids to node indices.
+    pub node_indices: HashMap<u64, NodeIndex>,
+    /// Maps Redis graph relation ids to edge indices.
+    pub edge_indices: HashMap<u64, EdgeIndex>,
+}
+
+/// A petgraph Graph with typed property values as read by graph_read_petgraph.
+pub type TypedGraph = IndexedGraph<Graph<TypedNode, TypedRelation>>;
+
+impl<G> IndexedGraph<G> {
+    /// Returns the node index for a Redis graph node id.
+    pub fn node_index(&self, id: u64) -> Option<NodeIndex> {
+        self.node_indices.get(&id).copied()
+    }
+
+    /// Returns the edge index for a Redis graph relation id.
+    pub fn edge_index(&self, id: u64) -> Option<EdgeIndex> {
+        self.edge_indices.get(&id).copied()
+    }
+}
+
+/// The result of writing a petgraph graph into a graph key.
+#[derive(Clone, Debug)]
+pub struct PetgraphWrite<N> {
+    /// Maps the petgraph node ids to the ids of the created nodes.
+    pub node_ids: HashMap<N, u64>,
+    /// The number of created nodes.
+    pub nodes_created: usize,
+    /// The number of created relations.
+    pub relations_created: usize,
+}
+
+impl Subgraph {
+    /// Converts the subgraph into a petgraph Graph. Relations pointing to
+    /// nodes that are not part of the subgraph get a node without labels and
+    /// properties.
+    pub fn to_petgraph(&self) -> IndexedGraph<Graph<NodeValue, RelationValue>> {
+        let mut graph = Graph::new();
+        let (node_indices, edge_indices) = self.fill(&mut graph);
+        IndexedGraph {
+            graph,
+            node_indices,
+            edge_indices,
+        }
+    }
+
+    /// Converts the subgraph into a petgraph StableGraph, whose indices stay
+    /// valid when nodes or edges are removed.
+    pub fn to_stable_graph(&self) -> IndexedGraph<StableGraph<NodeValue, RelationValue>> {
+        let mut graph = StableGraph::new();
+        let (node_indices, edge_indices) = self.fill(&mut graph);
+        IndexedGraph {
+            graph,
+            node_indices,
+            edge_indices,
+        }
+    }
+
+    fn fill<G: PetgraphBuilder>(
+        &self,
+        graph: &mut G,
+    ) -> (HashMap<u64, NodeIndex>, HashMap<u64, EdgeIndex>) {
+        let mut nodes = HashMap::new();
+        for node in self.nodes() {
+            nodes.insert(node.id, graph.push_node(node.clone()));
+        }
+        let mut edges = HashMap::new();
+        for relation in self.relations() {
+            let mut index = |id: u64| {
+                *nodes.entry(id).or_insert_with(|| {
+                    graph.push_node(NodeValue {
+                        id,
+                        ..NodeValue::default()
+                    })
+                })
+            };
+            let src = index(relation.src_node);
+            let dest = index(relation.dest_node);
+            edges.insert(relation.id, graph.push_edge(src, dest, relation.clone()));
+        }
+        (nodes, edges)
+    }
+}
+
+/// A node weight that graph_write_petgraph can write. NodeValue properties
+/// are written with their reply types, so doubles and booleans become strings.
+pub trait PetgraphNode {
+    fn labels(&self) -> &[String];
+    fn typed_properties(&self) -> RedisResult<BTreeMap<String, ParamValue>>;
+}
+
+/// An edge weight that graph_write_petgraph can write, see PetgraphNode.
+pub trait PetgraphEdge {
+    fn rel_type(&self) -> &str;
+    fn typed_properties(&self) -> RedisResult<BTreeMap<String, ParamValue>>;
+}
+
+impl PetgraphNode for NodeValue {
+    fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    fn typed_properties(&self) -> RedisResult<BTreeMap<String, ParamValue>> {
+        reply_properties(&self.properties)
+    }
+}
+
+impl PetgraphNode for TypedNode {
+    fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    fn typed_properties(&self) -> RedisResult<BTreeMap<String, ParamValue>> {
+        Ok(self.properties.clone())
+    }
+}
+
+impl PetgraphEdge for RelationValue {
+    fn rel_type(&self) -> &str {
+        &self.rel_type
+    }
+
+    fn typed_properties(&self) -> RedisResult<BTreeMap<String, ParamValue>> {
+        reply_properties(&self.properties)
+    }
+}
+
+impl PetgraphEdge for TypedRelation {
+    fn rel_type(&self) -> &str {
+        &self.rel_type
+    }
+
+    fn typed_properties(&self) -> RedisResult<BTreeMap<String, ParamValue>> {
+        Ok(self.properties.clone())
+    }
+}
+
+fn reply_properties(
+    properties: &HashMap<String, Value>,
+) -> RedisResult<BTreeMap<String, ParamValue>> {
+    properties
+        .iter()
+        .map(|(k, v)| Ok((k.clone(), ParamValue::from_redis(v)?)))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ReadStep {
+    Nodes(u64),
+    Relations(u64),
+    Done,
+}
+
+// Reads a whole graph page by page with the typed property queries of a dump.
+// A step ends with an empty page, so pages truncated by RESULTSET_SIZE do not
+// end it early.
+pub(crate) struct PetgraphRead {
+    page_size: usize,
+    step: ReadStep,
+    graph: TypedGraph,
+}
+
+impl PetgraphRead {
+    pub(crate) fn new(page_size: usize) -> Self {
+        PetgraphRead {
+            page_size: page_size.max(1),
+            step: ReadStep::Nodes(0),
+            graph: IndexedGraph {
+                graph: Graph::new(),
+                node_indices: HashMap::new(),
+                edge_indices: HashMap::new(),
+            },
+        }
+    }
+
+    // The next query to run, None once the graph is read
+    pub(crate) fn next_query(&self) -> Option<String> {
+        match self.step {
+            ReadStep::Nodes(from) => Some(node_page_query(from, self.page_size)),
+            ReadStep::Relations(from) => Some(relation_page_query(from, self.page_size)),
+            ReadStep::Done => None,
+        }
+    }
+
+    // Applies the response to the query returned by next_query
+    pub(crate) fn add_response(&mut self, v: &Value) -> RedisResult<()> {
+        let IndexedGraph {
+            graph,
+            node_indices,
+            edge_indices,
+        } = &mut self.graph;
+        self.step = match self.step {
+            ReadStep::Nodes(_) => {
+                let nodes = parse_nodes(v, None)?;
+                let next = nodes.last().map(|node| node.id + 1);
+                for node in nodes {
+                    let id = node.id;
+                    node_indices.insert(id, graph.add_node(node));
+                }
+                next.map_or(ReadStep::Relations(0), ReadStep::Nodes)
+            }
+            ReadStep::Relations(_) => {
+                let relations = parse_relations(v, None)?;
+                let next = relations.last().map(|relation| relation.id + 1);
+                for relation in relations {
+                    let mut index = |id: u64| {
+                        *node_indices.entry(id).or_insert_with(|| {
+                            graph.add_node(TypedNode {
+                                id,
+                                labels: vec![],
+                                properties: BTreeMap::new(),
+                            })
+                        })
+                    };
+                    let (src, dest) = (index(relation.src), index(relation.dest));
+                    let id = relation.id;
+                    edge_indices.insert(id, graph.add_edge(src, dest, relation));
+                }
+                next.map_or(ReadStep::Done, ReadStep::Relations)
+            }
+            ReadStep::Done => return Err(create_error("Graph is already read")),
+        };
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> TypedGraph {
+        self.graph
+    }
+}
+
+// Common insertion interface of Graph and StableGraph
+trait PetgraphBuilder {
+    fn push_node(&mut self, node: NodeValue) -> NodeIndex;
+    fn push_edge(&mut self, src: NodeIndex, dest: NodeIndex, rel: RelationValue) -> EdgeIndex;
+}
+
+impl PetgraphBuilder for Graph<NodeValue, RelationValue> {
+    fn push_node(&mut self, node: NodeValue) -> NodeIndex {
+        self.add_node(node)
+    }
+
+    fn push_edge(&mut self, src: NodeIndex, dest: NodeIndex, rel: RelationValue) -> EdgeIndex {
+        self.add_edge(src, dest, rel)
+    }
+}
+
+impl PetgraphBuilder for StableGraph<NodeValue, RelationValue> {
+    fn push_node(&mut self, node: NodeValue) -> NodeIndex {
+        self.add_node(node)
+    }
+
+    fn push_edge(&mut self, src: NodeIndex, dest: NodeIndex, rel: RelationValue) -> EdgeIndex {
+        self.add_edge(src, dest, rel)
+    }
+}
+
+impl GraphResultSet {
+    /// Converts all distinct nodes and relations into a petgraph Graph, see
+    /// Subgraph::to_petgraph.
+    pub fn to_petgraph(&self) -> IndexedGraph<Graph<NodeValue, RelationValue>> {
+        self.to_subgraph().to_petgraph()
+    }
+
+    /// Converts all distinct nodes and relations into a petgraph StableGraph,
+    /// see Subgraph::to_stable_graph.
+    pub fn to_stable_graph(&self) -> IndexedGraph<StableGraph<NodeValue, RelationValue>> {
+        self.to_subgraph().to_stable_graph()
+    }
+}
+
+// The CREATE statements for writing a petgraph graph. Nodes are created first
+// returning their ids, relations are then created between nodes matched by id.
+pub(crate) struct PetgraphPlan<N> {
+    nodes: Vec<(N, String)>,
+    edges: Vec<(N, N, String)>,
+    batch_size: usize,
+}
+
+impl<N: Copy + Hash + Eq> PetgraphPlan<N> {
+    pub(crate) fn new<G>(graph: G, batch_size: usize) -> RedisResult<Self>
+    where
+        G: IntoNodeReferences<NodeId = N> + IntoEdgeReferences<NodeId = N>,
+        G::NodeWeight: PetgraphNode,
+        G::EdgeWeight: PetgraphEdge,
+    {
+        let nodes = graph
+            .node_references()
+            .map(|node| Ok((node.id(), node_pattern(node.weight())?)))
+            .collect::<RedisResult<_>>()?;
+        let edges = graph
+            .edge_references()
+            .map(|edge| {
+                let pattern = relation_pattern(edge.weight())?;
+                Ok((edge.source(), edge.target(), pattern))
+            })
+            .collect::<RedisResult<_>>()?;
+        Ok(PetgraphPlan {
+            nodes,
+            edges,
+            batch_size: batch_size.max(1),
+        })
+    }
+
+    pub(crate) fn node_queries(&self) -> Vec<(Vec<N>, String)> {
+        self.nodes
+            .chunks(self.batch_size)
+            .map(|batch| {
+                let ids = batch.iter().map(|(id, _)| *id).collect();
+                let patterns: Vec<String> = batch
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (_, pattern))| format!("(n{}{})", idx, pattern))
+                    .collect();
+                let returns: Vec<String> = (0..batch.len())
+                    .map(|idx| format!("id(n{})", idx))
+                    .collect();
+                let query = format!(
+                    "CREATE {} RETURN {}",
+                    patterns.join(", "),
+                    returns.join(", ")
+                );
+                (ids, query)
+            })
+            .collect()
+    }
+
+    pub(crate) fn edge_queries(&self, node_ids: &HashMap<N, u64>) -> RedisResult<Vec<String>> {
+        let mut queries = Vec::new();
+        for batch in self.edges.chunks(self.batch_size) {
+            let mut vars: Vec<u64> = Vec::new();
+            let mut var_index: HashMap<u64, usize> = HashMap::new();
+            let mut patterns = Vec::new();
+            for (src, dest, pattern) in batch {
+                let mut var = |node: &N| -> RedisResult<usize> {
+                    let id = *node_ids
+                        .get(node)
+                        .ok_or_else(|| create_error("Relation endpoint was not created"))?;
+                    Ok(*var_index.entry(id).or_insert_with(|| {
+                        vars.push(id);
+                        vars.len() - 1
+                    }))
+                };
+                let (src, dest) = (var(src)?, var(dest)?);
+                patterns.push(format!("(n{})-[{}]->(n{})", src, pattern, dest));
+            }
+            let matches: Vec<String> = (0..vars.len()).map(|idx| format!("(n{})", idx)).collect();
+            let filters: Vec<String> = vars
+                .iter()
+                .enumerate()
+                .map(|(idx, id)| format!("id(n{}) = {}", idx, id))
+                .collect();
+            queries.push(format!(
+                "MATCH {} WHERE {} CREATE {}",
+                matches.join(", "),
+                filters.join(" AND "),
+                patterns.join(", ")
+            ));
+        }
+        Ok(queries)
+    }
+
+    pub(crate) fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+}
+
+// Reads the ids returned by a node batch query
+pub(crate) fn created_ids<N: Copy + Hash + Eq>(
+    batch: &[N],
+    res: &GraphResultSet,
+    node_ids: &mut HashMap<N, u64>,
+) -> RedisResult<()> {
+    let row = res
+        .data
+        .first()
+        .ok_or_else(|| create_error("Missing ids of created nodes"))?;
+    for (idx, node) in batch.iter().enumerate() {
+        let id: u64 = row
+            .get_scalar_at(idx)
+            .ok_or_else(|| create_error("Missing ids of created nodes"))?;
+        node_ids.insert(*node, id);
+    }
+    Ok(())
+}
+
+fn node_pattern<W: PetgraphNode>(node: &W) -> RedisResult<String> {
+    let mut out = String::new();
+    for label in node.labels().iter() {
+        out.push(':');
+        out.push_str(&escape_identifier(label));
+    }
+    out.push_str(&properties_literal(node.typed_properties()?)?);
+    Ok(out)
+}
+
+fn relation_pattern<W: PetgraphEdge>(relation: &W) -> RedisResult<String> {
+    Ok(format!(
+        ":{}{}",
+        escape_identifier(relation.rel_type()),
+        properties_literal(relation.typed_properties()?)?
+    ))
+}
+
+fn properties_literal(properties: BTreeMap<String, ParamValue>) -> RedisResult<String> {
+    if properties.is_empty() {
+        return Ok(String::new());
+    }
+    let entries = properties.into_iter().collect();
+    Ok(format!(" {}", ParamValue::Map(entries).to_cypher()?))
+}
@@ -0,0 +1,693 @@
+use crate::algorithms::Direction;
+use crate::params::{
+    escape_identifier, optional_string_literal, query_with_params, string_literal, ParamValue,
+};
+use crate::types::*;
+use redis::{from_redis_value, FromRedisValue, RedisResult, Value};
+use std::collections::HashMap;
+
+/// Configuration of the algo.BFS procedure, a breadth first traversal
+/// starting at a node.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let res = con.graph_algo_bfs("my_graph", &BfsConfig::new(0).max_level(2).relationship_type("knows"))?;
+/// let ranks = con.graph_algo_page_rank("my_graph", &PageRankConfig::new().label("Person"))?;
+/// let paths = con.graph_algo_sp_paths(
+///     "my_graph",
+///     &PathConfig::new(0).target(42).weight_prop("distance").path_count(3),
+/// )?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct BfsConfig {
+    source: u64,
+    max_level: i64,
+    relationship_type: Option<String>,
+}
+
+impl BfsConfig {
+    /// Traverses from the node with id source over all levels and relations.
+    pub fn new(source: u64) -> Self {
+        BfsConfig {
+            source,
+            max_level: 0,
+            relationship_type: None,
+        }
+    }
+
+    /// Limits the traversal depth, 0 for unlimited.
+    pub fn max_level(mut self, max_level: i64) -> Self {
+        self.max_level = max_level;
+        self
+    }
+
+    /// Only follows relations of the given type.
+    pub fn relationship_type<T: Into<String>>(mut self, rel_type: T) -> Self {
+        self.relationship_type = Some(rel_type.into());
+        self
+    }
+
+    /// The query calling the procedure.
+    pub fn to_query(&self) -> String {
+        format!(
+            "MATCH (s) WHERE id(s) = {} CALL algo.BFS(s, {}, {}) YIELD nodes, edges RETURN nodes, edges",
+            self.source,
+            self.max_level,
+            optional_string_literal(self.relationship_type.as_deref())
+        )
+    }
+}
+
+/// The nodes and relations reached by algo.BFS.
+#[derive(Default, Clone, Debug)]
+pub struct BfsResult {
+    pub nodes: Vec<NodeValue>,
+    pub relations: Vec<RelationValue>,
+}
+
+/// Configuration of the path procedures algo.SPpaths (single pair, requires a
+/// target) and algo.SSpaths (single source).
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathConfig {
+    source: u64,
+    target: Option<u64>,
+    rel_types: Vec<String>,
+    direction: Direction,
+    max_len: Option<i64>,
+    weight_prop: Option<String>,
+    cost_prop: Option<String>,
+    max_cost: Option<f64>,
+    path_count: Option<i64>,
+}
+
+impl PathConfig {
+    /// Searches paths starting at the node with id source following outgoing
+    /// relations.
+    pub fn new(source: u64) -> Self {
+        PathConfig {
+            source,
+            target: None,
+            rel_types: Vec::new(),
+            direction: Direction::Outgoing,
+            max_len: None,
+            weight_prop: None,
+            cost_prop: None,
+            max_cost: None,
+            path_count: None,
+        }
+    }
+
+    /// The node id paths have to end at, required by algo.SPpaths.
+    pub fn target(mut self, target: u64) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Only follows relations of the given types.
+    pub fn rel_types<T: Into<String>>(mut self, rel_types: Vec<T>) -> Self {
+        self.rel_types = rel_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The direction in which relations are followed.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// The maximum number of relations in a path.
+    pub fn max_len(mut self, max_len: i64) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// The relation property used as weight, paths are ordered by it.
+    pub fn weight_prop<T: Into<String>>(mut self, weight_prop: T) -> Self {
+        self.weight_prop = Some(weight_prop.into());
+        self
+    }
+
+    /// The relation property used as cost, limited by max_cost.
+    pub fn cost_prop<T: Into<String>>(mut self, cost_prop: T) -> Self {
+        self.cost_prop = Some(cost_prop.into());
+        self
+    }
+
+    /// The maximum summed cost of a path.
+    pub fn max_cost(mut self, max_cost: f64) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+
+    /// The number of paths to return (0 for all minimal weight paths).
+    pub fn path_count(mut self, path_count: i64) -> Self {
+        self.path_count = Some(path_count);
+        self
+    }
+
+    /// The query calling algo.SPpaths, fails if no target is set.
+    pub fn sp_paths_query(&self) -> RedisResult<String> {
+        let target = self
+            .target
+            .ok_or_else(|| create_error("algo.SPpaths requires a target node"))?;
+        Ok(format!(
+            "MATCH (s) WHERE id(s) = {} MATCH (t) WHERE id(t) = {} CALL algo.SPpaths({}) {}",
+            self.source,
+            target,
+            self.config_map(true)?,
+            PATH_YIELD
+        ))
+    }
+
+    /// The query calling algo.SSpaths, a target is ignored. Fails if the
+    /// max cost is NaN or infinite.
+    pub fn ss_paths_query(&self) -> RedisResult<String> {
+        Ok(format!(
+            "MATCH (s) WHERE id(s) = {} CALL algo.SSpaths({}) {}",
+            self.source,
+            self.config_map(false)?,
+            PATH_YIELD
+        ))
+    }
+
+    fn config_map(&self, with_target: bool) -> RedisResult<String> {
+        let mut entries = vec![("sourceNode", "s".to_string())];
+        if with_target {
+            entries.push(("targetNode", "t".to_string()));
+        }
+        if !self.rel_types.is_empty() {
+            entries.push(("relTypes", string_list(&self.rel_types)));
+        }
+        let direction = match self.direction {
+            Direction::Outgoing => "outgoing",
+            Direction::Incoming => "incoming",
+            Direction::Both => "both",
+        };
+        entries.push(("relDirection", string_literal(direction)));
+        push_param(&mut entries, "maxLen", self.max_len);
+        if let Some(weight_prop) = &self.weight_prop {
+            entries.push(("weightProp", string_literal(weight_prop)));
+        }
+        if let Some(cost_prop) = &self.cost_prop {
+            entries.push(("costProp", string_literal(cost_prop)));
+        }
+        if let Some(max_cost) = self.max_cost {
+            entries.push(("maxCost", ParamValue::Float(max_cost).to_cypher()?));
+        }
+        push_param(&mut entries, "pathCount", self.path_count);
+        Ok(cypher_map(&entries))
+    }
+}
+
+const PATH_YIELD: &str = "YIELD path, pathWeight, pathCost RETURN path, pathWeight, pathCost";
+
+/// A path returned by algo.SPpaths or algo.SSpaths with its summed weight and
+/// cost.
+#[derive(Default, Clone, Debug)]
+pub struct AlgoPath {
+    pub path: PathValue,
+    pub weight: f64,
+    pub cost: f64,
+}
+
+/// Configuration of the algo.pageRank procedure.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PageRankConfig {
+    label: Option<String>,
+    relationship_type: Option<String>,
+}
+
+impl PageRankConfig {
+    /// Ranks all nodes over all relations.
+    pub fn new() -> Self {
+        PageRankConfig::default()
+    }
+
+    /// Only ranks nodes with the given label.
+    pub fn label<T: Into<String>>(mut self, label: T) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Only follows relations of the given type.
+    pub fn relationship_type<T: Into<String>>(mut self, rel_type: T) -> Self {
+        self.relationship_type = Some(rel_type.into());
+        self
+    }
+
+    /// The query calling the procedure.
+    pub fn to_query(&self) -> String {
+        format!(
+            "CALL algo.pageRank({}, {}) YIELD node, score RETURN node, score",
+            optional_string_literal(self.label.as_deref()),
+            optional_string_literal(self.relationship_type.as_deref())
+        )
+    }
+}
+
+/// Configuration of the algo.WCC procedure (weakly connected components).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WccConfig {
+    node_labels: Vec<String>,
+    relationship_types: Vec<String>,
+}
+
+impl WccConfig {
+    /// Computes the components of all nodes over all relations.
+    pub fn new() -> Self {
+        WccConfig::default()
+    }
+
+    /// Only considers nodes with one of the given labels.
+    pub fn node_labels<T: Into<String>>(mut self, labels: Vec<T>) -> Self {
+        self.node_labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only follows relations of the given types.
+    pub fn relationship_types<T: Into<String>>(mut self, rel_types: Vec<T>) -> Self {
+        self.relationship_types = rel_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The query calling the procedure.
+    pub fn to_query(&self) -> String {
+        let entries = filter_entries(&self.node_labels, &self.relationship_types);
+        format!(
+            "CALL algo.WCC({}) YIELD node, componentId RETURN node, componentId",
+            cypher_map(&entries)
+        )
+    }
+}
+
+/// Configuration of the algo.betweenness procedure (betweenness centrality).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BetweennessConfig {
+    node_labels: Vec<String>,
+    relationship_types: Vec<String>,
+    sampling_size: Option<i64>,
+    sampling_seed: Option<i64>,
+}
+
+impl BetweennessConfig {
+    /// Computes the centrality of all nodes over all relations.
+    pub fn new() -> Self {
+        BetweennessConfig::default()
+    }
+
+    /// Only considers nodes with one of the given labels.
+    pub fn node_labels<T: Into<String>>(mut self, labels: Vec<T>) -> Self {
+        self.node_labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only follows relations of the given types.
+    pub fn relationship_types<T: Into<String>>(mut self, rel_types: Vec<T>) -> Self {
+        self.relationship_types = rel_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Approximates the centrality from the given number of source nodes.
+    pub fn sampling_size(mut self, sampling_size: i64) -> Self {
+        self.sampling_size = Some(sampling_size);
+        self
+    }
+
+    /// The random seed for choosing sample nodes.
+    pub fn sampling_seed(mut self, sampling_seed: i64) -> Self {
+        self.sampling_seed = Some(sampling_seed);
+        self
+    }
+
+    /// The query calling the procedure.
+    pub fn to_query(&self) -> String {
+        let mut entries = filter_entries(&self.node_labels, &self.relationship_types);
+        push_param(&mut entries, "samplingSize", self.sampling_size);
+        push_param(&mut entries, "samplingSeed", self.sampling_seed);
+        format!(
+            "CALL algo.betweenness({}) YIELD node, score RETURN node, score",
+            cypher_map(&entries)
+        )
+    }
+}
+
+/// Configuration of the algo.labelPropagation procedure (community
+/// detection).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LabelPropagationConfig {
+    node_labels: Vec<String>,
+    relationship_types: Vec<String>,
+    max_iterations: Option<i64>,
+}
+
+impl LabelPropagationConfig {
+    /// Detects communities of all nodes over all relations.
+    pub fn new() -> Self {
+        LabelPropagationConfig::default()
+    }
+
+    /// Only considers nodes with one of the given labels.
+    pub fn node_labels<T: Into<String>>(mut self, labels: Vec<T>) -> Self {
+        self.node_labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only follows relations of the given types.
+    pub fn relationship_types<T: Into<String>>(mut self, rel_types: Vec<T>) -> Self {
+        self.relationship_types = rel_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The maximum number of propagation rounds.
+    pub fn max_iterations(mut self, max_iterations: i64) -> Self {
+        self.max_iterations = Some(max_iterations);
+        self
+    }
+
+    /// The query calling the procedure.
+    pub fn to_query(&self) -> String {
+        let mut entries = filter_entries(&self.node_labels, &self.relationship_types);
+        push_param(&mut entries, "maxIterations", self.max_iterations);
+        format!(
+            "CALL algo.labelPropagation({}) YIELD node, communityId RETURN node, communityId",
+            cypher_map(&entries)
+        )
+    }
+}
+
+/// A row returned by graph_call with the values keyed by yield name.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct ProcedureRow {
+    pub values: HashMap<String, Value>,
+}
+
+impl ProcedureRow {
+    /// Extracts the value yielded as name into an Option of the desired type.
+    /// Will return None in case the procedure did not yield name or yielded
+    /// null. Will return an error in case the value failed to be parsed into T.
+    pub fn get_value<T: FromRedisValue>(&self, name: &str) -> RedisResult<Option<T>> {
+        match self.values.get(name) {
+            Some(value) => from_redis_value(value),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Whether a procedure only reads or also modifies the graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcedureMode {
+    Read,
+    Write,
+}
+
+/// A procedure as listed by dbms.procedures.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcedureInfo {
+    pub name: String,
+    pub mode: ProcedureMode,
+    /// The arguments and yields as `name(args) :: (yields)`. Taken from the
+    /// signature output of dbms.procedures if the server reports it, else
+    /// only known for built-in procedures.
+    pub signature: Option<String>,
+}
+
+impl ProcedureInfo {
+    /// Returns true if the procedure can be called with GRAPH.RO_QUERY.
+    pub fn is_read_only(&self) -> bool {
+        self.mode == ProcedureMode::Read
+    }
+}
+
+// Modes and signatures of the procedures shipped with Redis graph. The modes
+// route graph_call without a lookup, the signatures are the fallback for
+// servers whose dbms.procedures does not report them.
+const BUILTIN_PROCEDURES: &[(&str, ProcedureMode, &str)] = &[
+    ("db.labels", ProcedureMode::Read, "db.labels() :: (label)"),
+    (
+        "db.relationshipTypes",
+        ProcedureMode::Read,
+        "db.relationshipTypes() :: (relationshipType)",
+    ),
+    (
+        "db.propertyKeys",
+        ProcedureMode::Read,
+        "db.propertyKeys() :: (propertyKey)",
+    ),
+    (
+        "db.indexes",
+        ProcedureMode::Read,
+        "db.indexes() :: (type, label, properties, language, stopwords, entitytype)",
+    ),
+    (
+        "db.idx.fulltext.createNodeIndex",
+        ProcedureMode::Write,
+        "db.idx.fulltext.createNodeIndex(label, property...) :: ()",
+    ),
+    (
+        "db.idx.fulltext.drop",
+        ProcedureMode::Write,
+        "db.idx.fulltext.drop(label) :: ()",
+    ),
+    (
+        "db.idx.fulltext.queryNodes",
+        ProcedureMode::Read,
+        "db.idx.fulltext.queryNodes(label, query) :: (node, score)",
+    ),
+    (
+        "dbms.procedures",
+        ProcedureMode::Read,
+        "dbms.procedures() :: (name, mode)",
+    ),
+    (
+        "algo.BFS",
+        ProcedureMode::Read,
+        "algo.BFS(sourceNode, maxLevel, relationshipType) :: (nodes, edges)",
+    ),
+    (
+        "algo.pageRank",
+        ProcedureMode::Read,
+        "algo.pageRank(label, relationshipType) :: (node, score)",
+    ),
+    (
+        "algo.SPpaths",
+        ProcedureMode::Read,
+        "algo.SPpaths(config) :: (path, pathWeight, pathCost)",
+    ),
+    (
+        "algo.SSpaths",
+        ProcedureMode::Read,
+        "algo.SSpaths(config) :: (path, pathWeight, pathCost)",
+    ),
+    (
+        "algo.WCC",
+        ProcedureMode::Read,
+        "algo.WCC(config) :: (node, componentId)",
+    ),
+    (
+        "algo.betweenness",
+        ProcedureMode::Read,
+        "algo.betweenness(config) :: (node, score)",
+    ),
+    (
+        "algo.labelPropagation",
+        ProcedureMode::Read,
+        "algo.labelPropagation(config) :: (node, communityId)",
+    ),
+];
+
+// Builds `CALL procedure($arg0, ...) YIELD a, b RETURN a, b` with the
+// arguments passed as query parameters
+pub(crate) fn call_query(
+    procedure: &str,
+    args: &[ParamValue],
+    yields: &[&str],
+) -> RedisResult<String> {
+    let valid = procedure.split('.').all(|part| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    });
+    if !valid {
+        return Err(create_error(&format!(
+            "Invalid procedure name {}",
+            procedure
+        )));
+    }
+    let params: Vec<(String, ParamValue)> = args
+        .iter()
+        .enumerate()
+        .map(|(idx, arg)| (format!("arg{}", idx), arg.clone()))
+        .collect();
+    let placeholders: Vec<String> = params
+        .iter()
+        .map(|(name, _)| format!("${}", name))
+        .collect();
+    let mut query = format!("CALL {}({})", procedure, placeholders.join(", "));
+    if !yields.is_empty() {
+        let yields: Vec<String> = yields.iter().map(|y| escape_identifier(y)).collect();
+        let yields = yields.join(", ");
+        query.push_str(&format!(" YIELD {} RETURN {}", yields, yields));
+    }
+    query_with_params(&query, &params)
+}
+
+pub(crate) fn parse_procedure_rows(v: &Value) -> RedisResult<Vec<ProcedureRow>> {
+    let header: Vec<String> = match v {
+        Value::Bulk(values) if values.len() > 1 => from_redis_value(&values[0])?,
+        _ => Vec::new(),
+    };
+    result_rows(v)?
+        .into_iter()
+        .map(|row| {
+            if row.len() != header.len() {
+                return Err(create_error("Could not parse procedure result"));
+            }
+            Ok(ProcedureRow {
+                values: header.iter().cloned().zip(row).collect(),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn parse_procedures(v: &Value) -> RedisResult<Vec<ProcedureInfo>> {
+    parse_procedure_rows(v)?
+        .iter()
+        .map(|row| {
+            let name: String = row
+                .get_value("name")?
+                .ok_or_else(|| create_error("Procedure without name"))?;
+            let mode: Option<String> = row.get_value("mode")?;
+            let mode = match mode.as_deref().map(str::to_ascii_uppercase).as_deref() {
+                Some("READ") => ProcedureMode::Read,
+                _ => ProcedureMode::Write,
+            };
+            let signature = match row.get_value::<String>("signature")? {
+                Some(signature) => Some(signature),
+                None => BUILTIN_PROCEDURES
+                    .iter()
+                    .find(|(builtin, _, _)| *builtin == name)
+                    .map(|(_, _, signature)| signature.to_string()),
+            };
+            Ok(ProcedureInfo {
+                name,
+                mode,
+                signature,
+            })
+        })
+        .collect()
+}
+
+// The mode of a built-in procedure, None for other procedures
+pub(crate) fn builtin_mode(procedure: &str) -> Option<ProcedureMode> {
+    BUILTIN_PROCEDURES
+        .iter()
+        .find(|(builtin, _, _)| *builtin == procedure)
+        .map(|(_, mode, _)| *mode)
+}
+
+// The mode of a listed procedure, unlisted procedures are taken as writing
+pub(crate) fn listed_mode(procedures: &[ProcedureInfo], procedure: &str) -> ProcedureMode {
+    procedures
+        .iter()
+        .find(|info| info.name == procedure)
+        .map_or(ProcedureMode::Write, |info| info.mode)
+}
+
+// Read-only procedures are called with GRAPH.RO_QUERY
+pub(crate) fn call_command(mode: ProcedureMode) -> &'static str {
+    match mode {
+        ProcedureMode::Read => "GRAPH.RO_QUERY",
+        ProcedureMode::Write => "GRAPH.QUERY",
+    }
+}
+
+pub(crate) fn parse_bfs(v: &Value) -> RedisResult<BfsResult> {
+    match result_rows(v)?.first() {
+        Some(row) if row.len() == 2 => Ok(BfsResult {
+            nodes: from_redis_value(&row[0])?,
+            relations: from_redis_value(&row[1])?,
+        }),
+        Some(_) => Err(create_error("Could not parse algo.BFS result")),
+        None => Ok(BfsResult::default()),
+    }
+}
+
+pub(crate) fn parse_paths(v: &Value) -> RedisResult<Vec<AlgoPath>> {
+    result_rows(v)?
+        .iter()
+        .map(|row| match row.as_slice() {
+            [path, weight, cost] => Ok(AlgoPath {
+                path: from_redis_value(path)?,
+                weight: from_redis_value(weight)?,
+                cost: from_redis_value(cost)?,
+            }),
+            _ => Err(create_error("Could not parse path result")),
+        })
+        .collect()
+}
+
+pub(crate) fn parse_node_scores(v: &Value) -> RedisResult<Vec<(NodeValue, f64)>> {
+    result_rows(v)?
+        .iter()
+        .map(|row| match row.as_slice() {
+            [node, score] => Ok((from_redis_value(node)?, from_redis_value(score)?)),
+            _ => Err(create_error("Could not parse node score result")),
+        })
+        .collect()
+}
+
+pub(crate) fn parse_node_groups(v: &Value) -> RedisResult<Vec<(NodeValue, u64)>> {
+    result_rows(v)?
+        .iter()
+        .map(|row| match row.as_slice() {
+            [node, group] => Ok((from_redis_value(node)?, from_redis_value(group)?)),
+            _ => Err(create_error("Could not parse node group result")),
+        })
+        .collect()
+}
+
+// The raw rows of a graph query response
+fn result_rows(v: &Value) -> RedisResult<Vec<Vec<Value>>> {
+    match v {
+        Value::Bulk(values) if values.len() > 1 => from_redis_value(&values[1]),
+        Value::Bulk(_) => Ok(Vec::new()),
+        _ => Err(create_error("Could not parse graph result")),
+    }
+}
+
+fn filter_entries(
+    node_labels: &[String],
+    relationship_types: &[String],
+) -> Vec<(&'static str, String)> {
+    let mut entries = Vec::new();
+    if !node_labels.is_empty() {
+        entries.push(("nodeLabels", string_list(node_labels)));
+    }
+    if !relationship_types.is_empty() {
+        entries.push(("relationshipTypes", string_list(relationship_types)));
+    }
+    entries
+}
+
+fn push_param(entries: &mut Vec<(&'static str, String)>, key: &'static str, value: Option<i64>) {
+    if let Some(value) = value {
+        entries.push((key, value.to_string()));
+    }
+}
+
+fn string_list(values: &[String]) -> String {
+    let values: Vec<String> = values.iter().map(|v| string_literal(v)).collect();
+    format!("[{}]", values.join(", "))
+}
+
+fn cypher_map(entries: &[(&str, String)]) -> String {
+    let entries: Vec<String> = entries
+        .iter()
+        .map(|(key, value)| format!("{}: {}", key, value))
+        .collect();
+    format!("{{{}}}", entries.join(", "))
+}
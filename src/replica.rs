@@ -0,0 +1,321 @@
+use redis::{cmd, Cmd, ConnectionLike, RedisResult, Value};
+use std::time::{Duration, Instant};
+
+/// Strategy used by the ReplicaRouter to pick a replica for read-only
+/// commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplicaStrategy {
+    /// Cycles through all replicas in order.
+    RoundRobin,
+    /// Picks the replica with the lowest (moving average) response time of
+    /// successful reads. Replicas without a measurement are tried first.
+    /// Every PROBE_INTERVAL-th read is sent to the next replica in order
+    /// instead, so the averages of the other replicas are refreshed and a
+    /// replica that was slow once can be picked again.
+    LeastLoaded,
+}
+
+/// Number of reads after which a LeastLoaded router probes the next replica
+/// in order instead of the fastest one.
+pub const PROBE_INTERVAL: usize = 8;
+
+// Response time state of a single replica
+#[derive(Clone, Copy, Debug)]
+enum Latency {
+    Unmeasured,
+    Average(Duration),
+    Failed,
+}
+
+/// Routes graph commands between a primary and a set of replica connections.
+/// Read-only commands (GRAPH.RO_QUERY, GRAPH.EXPLAIN and GRAPH.SLOWLOG) are
+/// sent to the replicas while all other commands go to the primary. As the
+/// router itself is a ConnectionLike, the GraphCommands (or AsyncGraphCommands
+/// for async connections) are available on it.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let primary = redis::Client::open("redis://127.0.0.1:6379/")?.get_connection()?;
+/// let replica = redis::Client::open("redis://127.0.0.1:6380/")?.get_connection()?;
+///
+/// let mut router = ReplicaRouter::new(primary, vec![replica])
+///     .with_strategy(ReplicaStrategy::LeastLoaded)
+///     .with_read_your_writes(1, 100);
+///
+/// /// Sent to the primary
+/// let _:GraphResultSet = router.graph_query("my_graph", "CREATE (:Rider {name:'Pam'})")?;
+///
+/// /// Issues WAIT 1 100 on the primary and then reads from the replica
+/// let _:GraphResultSet = router.graph_ro_query("my_graph", "MATCH (r:Rider) RETURN r")?;
+/// # Ok(()) }
+/// ```
+pub struct ReplicaRouter<C> {
+    primary: C,
+    replicas: Vec<C>,
+    strategy: ReplicaStrategy,
+    next: usize,
+    reads: usize,
+    latencies: Vec<Latency>,
+    read_your_writes: Option<(usize, usize)>,
+    pending_writes: bool,
+}
+
+impl<C> ReplicaRouter<C> {
+    /// Creates a round-robin router for the given primary and replica connections.
+    pub fn new(primary: C, replicas: Vec<C>) -> Self {
+        let latencies = vec![Latency::Unmeasured; replicas.len()];
+        ReplicaRouter {
+            primary,
+            replicas,
+            strategy: ReplicaStrategy::RoundRobin,
+            next: 0,
+            reads: 0,
+            latencies,
+            read_your_writes: None,
+            pending_writes: false,
+        }
+    }
+
+    /// Sets the strategy for picking a replica.
+    pub fn with_strategy(mut self, strategy: ReplicaStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Enables read-your-writes. After a write command (a graph command that
+    /// can change the graph like GRAPH.QUERY or GRAPH.DELETE, or a command
+    /// replacing keys like DEL or RENAME) was sent to the primary, the next
+    /// read issues a WAIT for num_replicas to acknowledge the write (or the
+    /// timeout in milliseconds to pass) before the read is sent to a replica.
+    /// Other commands like GRAPH.LIST or GRAPH.CONFIG do not cause a WAIT.
+    pub fn with_read_your_writes(mut self, num_replicas: usize, timeout: usize) -> Self {
+        self.read_your_writes = Some((num_replicas, timeout));
+        self
+    }
+
+    /// The primary connection.
+    pub fn primary(&mut self) -> &mut C {
+        &mut self.primary
+    }
+
+    /// The replica connections.
+    pub fn replicas(&mut self) -> &mut [C] {
+        &mut self.replicas
+    }
+
+    // Returns the replica index for read-only commands and None for commands
+    // that need to go to the primary.
+    fn route(&mut self, command: Option<&[u8]>) -> Option<usize> {
+        if self.replicas.is_empty() || !command.is_some_and(is_replica_command) {
+            return None;
+        }
+        self.reads += 1;
+        let probe = self.reads == PROBE_INTERVAL;
+        if probe {
+            self.reads = 0;
+        }
+        let fastest = match self.strategy {
+            ReplicaStrategy::RoundRobin => None,
+            ReplicaStrategy::LeastLoaded if probe => None,
+            ReplicaStrategy::LeastLoaded => self
+                .latencies
+                .iter()
+                .position(|latency| matches!(latency, Latency::Unmeasured))
+                .or_else(|| {
+                    self.latencies
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(idx, latency)| match latency {
+                            Latency::Average(average) => Some((idx, *average)),
+                            _ => None,
+                        })
+                        .min_by_key(|(_, average)| *average)
+                        .map(|(idx, _)| idx)
+                }),
+        };
+        fastest.or_else(|| {
+            let idx = self.next % self.replicas.len();
+            self.next = idx + 1;
+            Some(idx)
+        })
+    }
+
+    fn wait_command(&self) -> Option<Cmd> {
+        match self.read_your_writes {
+            Some((num_replicas, timeout)) if self.pending_writes => {
+                Some(cmd("WAIT").arg(num_replicas).arg(timeout).to_owned())
+            }
+            _ => None,
+        }
+    }
+
+    // Keeps an exponential moving average of replica response times. A failed
+    // read discards the average, so a replica that fails fast is only used
+    // for probing until it answers successfully again.
+    fn record_latency<T>(&mut self, idx: usize, elapsed: Duration, res: &RedisResult<T>) {
+        self.latencies[idx] = match (res, self.latencies[idx]) {
+            (Err(_), _) => Latency::Failed,
+            (Ok(_), Latency::Average(average)) => Latency::Average((average * 4 + elapsed) / 5),
+            (Ok(_), _) => Latency::Average(elapsed),
+        };
+    }
+}
+
+/// Returns true if the command is a read-only graph command that can be
+/// served by a replica.
+pub fn is_replica_command(command: &[u8]) -> bool {
+    matches!(
+        command.to_ascii_uppercase().as_slice(),
+        b"GRAPH.RO_QUERY" | b"GRAPH.EXPLAIN" | b"GRAPH.SLOWLOG"
+    )
+}
+
+// Returns true if the command can change graph data, so that the next read
+// from a replica has to wait for the replicas with read-your-writes.
+fn is_write_command(command: &[u8]) -> bool {
+    matches!(
+        command.to_ascii_uppercase().as_slice(),
+        b"GRAPH.QUERY"
+            | b"GRAPH.PROFILE"
+            | b"GRAPH.DELETE"
+            | b"GRAPH.BULK"
+            | b"GRAPH.COPY"
+            | b"GRAPH.CONSTRAINT"
+            | b"DEL"
+            | b"UNLINK"
+            | b"RENAME"
+            | b"RENAMENX"
+            | b"RESTORE"
+            | b"COPY"
+            | b"FLUSHDB"
+            | b"FLUSHALL"
+    )
+}
+
+// Returns true if any of the commands is a write. Commands that cannot be
+// parsed count as writes.
+fn has_writes(names: Option<Vec<&[u8]>>) -> bool {
+    names.is_none_or(|names| names.into_iter().any(is_write_command))
+}
+
+impl<C: ConnectionLike> ConnectionLike for ReplicaRouter<C> {
+    fn req_packed_command(&mut self, packed: &[u8]) -> RedisResult<Value> {
+        let names = packed_command_names(packed);
+        match self.route(names.as_ref().and_then(|names| names.first().copied())) {
+            Some(idx) => {
+                if let Some(wait) = self.wait_command() {
+                    let _: i64 = wait.query(&mut self.primary)?;
+                    self.pending_writes = false;
+                }
+                let start = Instant::now();
+                let res = self.replicas[idx].req_packed_command(packed);
+                self.record_latency(idx, start.elapsed(), &res);
+                res
+            }
+            None => {
+                self.pending_writes |= has_writes(names);
+                self.primary.req_packed_command(packed)
+            }
+        }
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        packed: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.pending_writes |= has_writes(packed_command_names(packed));
+        self.primary.req_packed_commands(packed, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.primary.get_db()
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.primary.check_connection() && self.replicas.iter_mut().all(|r| r.check_connection())
+    }
+
+    fn is_open(&self) -> bool {
+        self.primary.is_open() && self.replicas.iter().all(|r| r.is_open())
+    }
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+impl<C: redis::aio::ConnectionLike + Send> redis::aio::ConnectionLike for ReplicaRouter<C> {
+    fn req_packed_command<'a>(&'a mut self, command: &'a Cmd) -> redis::RedisFuture<'a, Value> {
+        Box::pin(async move {
+            let name = command_name(command);
+            match self.route(name) {
+                Some(idx) => {
+                    if let Some(wait) = self.wait_command() {
+                        let _: i64 = wait.query_async(&mut self.primary).await?;
+                        self.pending_writes = false;
+                    }
+                    let start = Instant::now();
+                    let res = self.replicas[idx].req_packed_command(command).await;
+                    self.record_latency(idx, start.elapsed(), &res);
+                    res
+                }
+                None => {
+                    self.pending_writes |= name.is_none_or(is_write_command);
+                    self.primary.req_packed_command(command).await
+                }
+            }
+        })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        pipeline: &'a redis::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisFuture<'a, Vec<Value>> {
+        self.pending_writes |= pipeline
+            .cmd_iter()
+            .any(|command| command_name(command).is_none_or(is_write_command));
+        self.primary.req_packed_commands(pipeline, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.primary.get_db()
+    }
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+fn command_name(command: &Cmd) -> Option<&[u8]> {
+    match command.args_iter().next() {
+        Some(redis::Arg::Simple(name)) => Some(name),
+        _ => None,
+    }
+}
+
+// Extracts the command names from packed (RESP encoded) commands, None if
+// the commands cannot be parsed
+fn packed_command_names(mut packed: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut names = vec![];
+    while !packed.is_empty() {
+        let (count, rest) = packed_length(packed, b'*')?;
+        packed = rest;
+        for idx in 0..count {
+            let (len, rest) = packed_length(packed, b'$')?;
+            if idx == 0 {
+                names.push(rest.get(..len)?);
+            }
+            packed = rest.get(len + 2..)?;
+        }
+    }
+    Some(names)
+}
+
+// Reads the length of a `*count` or `$len` line and returns it with the rest
+// of the packed commands
+fn packed_length(packed: &[u8], prefix: u8) -> Option<(usize, &[u8])> {
+    let line = packed.strip_prefix(&[prefix])?;
+    let end = line.iter().position(|b| *b == b'\r')?;
+    let len = std::str::from_utf8(&line[..end]).ok()?.parse().ok()?;
+    Some((len, line.get(end + 2..)?))
+}
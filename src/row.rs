@@ -0,0 +1,130 @@
+use crate::types::*;
+use redis::{from_redis_value, RedisResult, Value};
+
+/// Conversion of a single GraphValue (one column of a result row) into a rust
+/// type. Scalars convert into the basic rust types, Nodes and Relations into
+/// NodeValue and RelationValue. A Nil scalar converts into None for Option.
+pub trait FromGraphValue: Sized {
+    fn from_graph_value(v: &GraphValue) -> RedisResult<Self>;
+}
+
+/// Conversion of a complete result row into a rust type. Implemented for
+/// tuples of up to 12 elements, `Vec<GraphValue>` and any single
+/// FromGraphValue. Elements are matched to the columns of the row by position
+/// and the number of elements must match the number of columns.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let riders: Vec<(NodeValue, String, i64)> = con.graph_query_as(
+///     "my_graph",
+///     "MATCH (r:Rider) RETURN r, r.name, r.born",
+/// )?;
+/// # Ok(()) }
+/// ```
+pub trait FromGraphRow: Sized {
+    fn from_graph_row(values: &[GraphValue]) -> RedisResult<Self>;
+}
+
+impl FromGraphValue for GraphValue {
+    fn from_graph_value(v: &GraphValue) -> RedisResult<Self> {
+        Ok(v.clone())
+    }
+}
+
+impl FromGraphValue for NodeValue {
+    fn from_graph_value(v: &GraphValue) -> RedisResult<Self> {
+        match v {
+            GraphValue::Node(node) => Ok(node.clone()),
+            _ => Err(create_error("Graph value is not a node")),
+        }
+    }
+}
+
+impl FromGraphValue for RelationValue {
+    fn from_graph_value(v: &GraphValue) -> RedisResult<Self> {
+        match v {
+            GraphValue::Relation(relation) => Ok(relation.clone()),
+            _ => Err(create_error("Graph value is not a relation")),
+        }
+    }
+}
+
+impl<T: FromGraphValue> FromGraphValue for Option<T> {
+    fn from_graph_value(v: &GraphValue) -> RedisResult<Self> {
+        match v {
+            GraphValue::Scalar(Value::Nil) => Ok(None),
+            v => T::from_graph_value(v).map(Some),
+        }
+    }
+}
+
+macro_rules! scalar_from_graph_value {
+    ($($t:ty),*) => {
+        $(impl FromGraphValue for $t {
+            fn from_graph_value(v: &GraphValue) -> RedisResult<Self> {
+                match v {
+                    GraphValue::Scalar(value) => from_redis_value(value),
+                    _ => Err(create_error("Graph value is not a scalar")),
+                }
+            }
+        })*
+    };
+}
+
+scalar_from_graph_value!(
+    Value, String, bool, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64
+);
+
+impl<T: FromGraphValue> FromGraphRow for T {
+    fn from_graph_row(values: &[GraphValue]) -> RedisResult<Self> {
+        match values {
+            [value] => T::from_graph_value(value),
+            _ => Err(row_length_error(values.len(), 1)),
+        }
+    }
+}
+
+impl FromGraphRow for Vec<GraphValue> {
+    fn from_graph_row(values: &[GraphValue]) -> RedisResult<Self> {
+        Ok(values.to_vec())
+    }
+}
+
+macro_rules! tuple_from_graph_row {
+    ($len:expr; $($name:ident),+) => {
+        impl<$($name: FromGraphValue),+> FromGraphRow for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn from_graph_row(values: &[GraphValue]) -> RedisResult<Self> {
+                match values {
+                    [$($name),+] => Ok(($($name::from_graph_value($name)?,)+)),
+                    _ => Err(row_length_error(values.len(), $len)),
+                }
+            }
+        }
+    };
+}
+
+tuple_from_graph_row!(1; A);
+tuple_from_graph_row!(2; A, B);
+tuple_from_graph_row!(3; A, B, C);
+tuple_from_graph_row!(4; A, B, C, D);
+tuple_from_graph_row!(5; A, B, C, D, E);
+tuple_from_graph_row!(6; A, B, C, D, E, F);
+tuple_from_graph_row!(7; A, B, C, D, E, F, G);
+tuple_from_graph_row!(8; A, B, C, D, E, F, G, H);
+tuple_from_graph_row!(9; A, B, C, D, E, F, G, H, I);
+tuple_from_graph_row!(10; A, B, C, D, E, F, G, H, I, J);
+tuple_from_graph_row!(11; A, B, C, D, E, F, G, H, I, J, K);
+tuple_from_graph_row!(12; A, B, C, D, E, F, G, H, I, J, K, L);
+
+fn row_length_error(actual: usize, expected: usize) -> redis::RedisError {
+    create_error(&format!(
+        "Row has {} columns, expected {}",
+        actual, expected
+    ))
+}
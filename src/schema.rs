@@ -0,0 +1,670 @@
+use crate::params::escape_identifier;
+use crate::procedures::{parse_procedure_rows, ProcedureRow};
+use crate::types::*;
+use redis::{from_redis_value, RedisResult, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+/// The structure of a graph: labels, relationship types and property keys,
+/// node and relation counts, sampled property types and the indexes and
+/// constraints.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let schema = con.graph_schema("my_graph", 100)?;
+/// println!("{}", schema);
+/// if let Some(riders) = schema.label("Rider") {
+///     assert!(riders.count > 0);
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Default, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GraphSchema {
+    /// The node labels ordered by name.
+    pub labels: Vec<EntitySchema>,
+    /// The relationship types ordered by name.
+    pub relationship_types: Vec<EntitySchema>,
+    /// All property keys ordered by name.
+    pub property_keys: Vec<String>,
+    pub indexes: Vec<IndexInfo>,
+    pub constraints: Vec<ConstraintInfo>,
+}
+
+/// A node label or relationship type with the number of entities and the
+/// property types found in a sample of them.
+#[derive(Default, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct EntitySchema {
+    pub name: String,
+    pub count: u64,
+    /// The sampled types of every property, more than one type if the
+    /// property values are of mixed type.
+    pub properties: BTreeMap<String, Vec<PropertyType>>,
+}
+
+/// The type of a property value as reported by the typeOf function.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", from = "String")
+)]
+pub enum PropertyType {
+    Boolean,
+    Integer,
+    Float,
+    String,
+    List,
+    Map,
+    Point,
+    Other(String),
+}
+
+/// Whether an index or constraint applies to nodes or relations.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EntityType {
+    #[default]
+    Node,
+    Relationship,
+}
+
+/// An index as listed by db.indexes.
+#[derive(Default, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct IndexInfo {
+    /// The node label or relationship type.
+    pub label: String,
+    pub properties: Vec<String>,
+    /// The kind of index, e.g. exact-match, full-text or vector.
+    pub index_type: String,
+    pub entity_type: EntityType,
+}
+
+/// A constraint as listed by db.constraints.
+#[derive(Default, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct ConstraintInfo {
+    /// The node label or relationship type.
+    pub label: String,
+    pub properties: Vec<String>,
+    /// UNIQUE or MANDATORY.
+    pub constraint_type: String,
+    pub entity_type: EntityType,
+    /// The constraint status, e.g. OPERATIONAL or UNDER CONSTRUCTION.
+    pub status: String,
+}
+
+impl GraphSchema {
+    /// Creates an empty schema, e.g. to declare the expected schema of a
+    /// graph with the with_ methods.
+    ///
+    /// ```rust
+    /// use redis_graph::*;
+    ///
+    /// let expected = GraphSchema::new()
+    ///     .with_label("Rider", &[("name", PropertyType::String)])
+    ///     .with_relationship_type("rides", &[])
+    ///     .with_index(IndexInfo::node("Rider", "range", &["name"]))
+    ///     .with_constraint(ConstraintInfo::node("Rider", "UNIQUE", &["name"]));
+    /// assert_eq!(expected.labels.len(), 1);
+    /// ```
+    pub fn new() -> Self {
+        GraphSchema::default()
+    }
+
+    /// Adds a node label with the given property types.
+    pub fn with_label(mut self, name: &str, properties: &[(&str, PropertyType)]) -> Self {
+        self.labels.push(declared_entity(name, properties));
+        self.labels.sort_by(|a, b| a.name.cmp(&b.name));
+        self
+    }
+
+    /// Adds a relationship type with the given property types.
+    pub fn with_relationship_type(
+        mut self,
+        name: &str,
+        properties: &[(&str, PropertyType)],
+    ) -> Self {
+        self.relationship_types
+            .push(declared_entity(name, properties));
+        self.relationship_types.sort_by(|a, b| a.name.cmp(&b.name));
+        self
+    }
+
+    /// Adds an index.
+    pub fn with_index(mut self, index: IndexInfo) -> Self {
+        self.indexes.push(index);
+        self.indexes.sort();
+        self
+    }
+
+    /// Adds a constraint.
+    pub fn with_constraint(mut self, constraint: ConstraintInfo) -> Self {
+        self.constraints.push(constraint);
+        self.constraints.sort();
+        self
+    }
+
+    /// Returns the schema of a node label.
+    pub fn label(&self, name: &str) -> Option<&EntitySchema> {
+        self.labels.iter().find(|label| label.name == name)
+    }
+
+    /// Returns the schema of a relationship type.
+    pub fn relationship_type(&self, name: &str) -> Option<&EntitySchema> {
+        self.relationship_types.iter().find(|rel| rel.name == name)
+    }
+
+    /// Encodes the schema as pretty printed JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> RedisResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| create_error(&format!("Could not encode schema: {}", e)))
+    }
+
+    /// Decodes a schema from JSON as written by to_json. All fields are
+    /// optional, which allows declaring only the parts of a schema to check.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> RedisResult<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| create_error(&format!("Could not decode schema: {}", e)))
+    }
+
+    /// Encodes the schema as TOML.
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> RedisResult<String> {
+        toml::to_string(self).map_err(|e| create_error(&format!("Could not encode schema: {}", e)))
+    }
+
+    /// Decodes a schema from TOML, all fields are optional.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "toml")]
+    /// # fn run() -> redis::RedisResult<()> {
+    /// use redis_graph::*;
+    ///
+    /// let expected = GraphSchema::from_toml(r#"
+    ///     [[labels]]
+    ///     name = "Rider"
+    ///     properties = { name = ["String"] }
+    ///
+    ///     [[indexes]]
+    ///     label = "Rider"
+    ///     properties = ["name"]
+    ///     index_type = "range"
+    /// "#)?;
+    /// assert_eq!(expected.indexes[0].entity_type, EntityType::Node);
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> RedisResult<Self> {
+        toml::from_str(toml).map_err(|e| create_error(&format!("Could not decode schema: {}", e)))
+    }
+
+    /// Reads a schema from a .json file, or with the `toml` feature from a
+    /// .toml file.
+    #[cfg(feature = "serde")]
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> RedisResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => GraphSchema::from_json(&content),
+            #[cfg(feature = "toml")]
+            Some("toml") => GraphSchema::from_toml(&content),
+            _ => Err(create_error(&format!(
+                "Unsupported schema file {}",
+                path.display()
+            ))),
+        }
+    }
+}
+
+impl IndexInfo {
+    /// An index on node properties, index_type is e.g. range or fulltext.
+    pub fn node(label: &str, index_type: &str, properties: &[&str]) -> Self {
+        IndexInfo {
+            label: label.to_string(),
+            properties: properties.iter().map(|p| p.to_string()).collect(),
+            index_type: index_type.to_string(),
+            entity_type: EntityType::Node,
+        }
+    }
+
+    /// An index on relation properties.
+    pub fn relationship(rel_type: &str, index_type: &str, properties: &[&str]) -> Self {
+        IndexInfo {
+            entity_type: EntityType::Relationship,
+            ..IndexInfo::node(rel_type, index_type, properties)
+        }
+    }
+}
+
+impl ConstraintInfo {
+    /// A constraint on node properties, constraint_type is UNIQUE or
+    /// MANDATORY.
+    pub fn node(label: &str, constraint_type: &str, properties: &[&str]) -> Self {
+        ConstraintInfo {
+            label: label.to_string(),
+            properties: properties.iter().map(|p| p.to_string()).collect(),
+            constraint_type: constraint_type.to_string(),
+            entity_type: EntityType::Node,
+            status: String::new(),
+        }
+    }
+
+    /// A constraint on relation properties.
+    pub fn relationship(rel_type: &str, constraint_type: &str, properties: &[&str]) -> Self {
+        ConstraintInfo {
+            entity_type: EntityType::Relationship,
+            ..ConstraintInfo::node(rel_type, constraint_type, properties)
+        }
+    }
+}
+
+fn declared_entity(name: &str, properties: &[(&str, PropertyType)]) -> EntitySchema {
+    EntitySchema {
+        name: name.to_string(),
+        count: 0,
+        properties: properties
+            .iter()
+            .map(|(key, property_type)| (key.to_string(), vec![property_type.clone()]))
+            .collect(),
+    }
+}
+
+impl fmt::Display for GraphSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Labels:")?;
+        for label in self.labels.iter() {
+            write_entity(f, label, "nodes")?;
+        }
+        writeln!(f, "Relationship types:")?;
+        for rel in self.relationship_types.iter() {
+            write_entity(f, rel, "relations")?;
+        }
+        writeln!(f, "Property keys:")?;
+        if !self.property_keys.is_empty() {
+            writeln!(f, "  {}", self.property_keys.join(", "))?;
+        }
+        writeln!(f, "Indexes:")?;
+        for index in self.indexes.iter() {
+            writeln!(
+                f,
+                "  {} {}",
+                index.index_type,
+                pattern(index.entity_type, &index.label, &index.properties)
+            )?;
+        }
+        writeln!(f, "Constraints:")?;
+        for constraint in self.constraints.iter() {
+            writeln!(
+                f,
+                "  {} {} {}",
+                constraint.constraint_type,
+                pattern(
+                    constraint.entity_type,
+                    &constraint.label,
+                    &constraint.properties
+                ),
+                constraint.status
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn write_entity(f: &mut fmt::Formatter<'_>, entity: &EntitySchema, unit: &str) -> fmt::Result {
+    writeln!(f, "  :{} ({} {})", entity.name, entity.count, unit)?;
+    for (key, types) in entity.properties.iter() {
+        let types: Vec<String> = types.iter().map(ToString::to_string).collect();
+        writeln!(f, "    {}: {}", key, types.join(" | "))?;
+    }
+    Ok(())
+}
+
+// (:Label {a, b}) for nodes and [:TYPE {a, b}] for relations
+pub(crate) fn pattern(entity_type: EntityType, label: &str, properties: &[String]) -> String {
+    let inner = format!(":{} {{{}}}", label, properties.join(", "));
+    match entity_type {
+        EntityType::Node => format!("({})", inner),
+        EntityType::Relationship => format!("[{}]", inner),
+    }
+}
+
+impl fmt::Display for PropertyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyType::Boolean => f.write_str("Boolean"),
+            PropertyType::Integer => f.write_str("Integer"),
+            PropertyType::Float => f.write_str("Float"),
+            PropertyType::String => f.write_str("String"),
+            PropertyType::List => f.write_str("List"),
+            PropertyType::Map => f.write_str("Map"),
+            PropertyType::Point => f.write_str("Point"),
+            PropertyType::Other(name) => f.write_str(name),
+        }
+    }
+}
+
+impl From<&str> for PropertyType {
+    fn from(name: &str) -> Self {
+        match name {
+            "Boolean" => PropertyType::Boolean,
+            "Integer" => PropertyType::Integer,
+            "Float" => PropertyType::Float,
+            "String" => PropertyType::String,
+            "List" | "Array" => PropertyType::List,
+            "Map" => PropertyType::Map,
+            "Point" => PropertyType::Point,
+            other => PropertyType::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for PropertyType {
+    fn from(name: String) -> Self {
+        PropertyType::from(name.as_str())
+    }
+}
+
+impl From<PropertyType> for String {
+    fn from(property_type: PropertyType) -> Self {
+        property_type.to_string()
+    }
+}
+
+// The queries that make up a schema fetch and how their results are applied
+#[derive(Clone, Copy, Debug)]
+enum SchemaStep {
+    Procedures,
+    Labels,
+    RelationshipTypes,
+    PropertyKeys,
+    Indexes,
+    Constraints,
+    LabelCount(usize),
+    LabelProperties(usize),
+    RelationCount(usize),
+    RelationProperties(usize),
+}
+
+// Fetches a schema with a sequence of read-only queries. The labels and
+// relationship types are listed first, then counted and sampled one by one.
+pub(crate) struct SchemaFetch {
+    schema: GraphSchema,
+    sample_size: usize,
+    pending: VecDeque<SchemaStep>,
+}
+
+impl SchemaFetch {
+    pub(crate) fn new(sample_size: usize) -> Self {
+        SchemaFetch {
+            schema: GraphSchema::default(),
+            sample_size: sample_size.max(1),
+            pending: vec![
+                SchemaStep::Procedures,
+                SchemaStep::Labels,
+                SchemaStep::RelationshipTypes,
+                SchemaStep::PropertyKeys,
+                SchemaStep::Indexes,
+            ]
+            .into(),
+        }
+    }
+
+    // Fetches only the indexes and constraints
+    pub(crate) fn indexes_and_constraints() -> Self {
+        SchemaFetch {
+            schema: GraphSchema::default(),
+            sample_size: 1,
+            pending: vec![SchemaStep::Procedures, SchemaStep::Indexes].into(),
+        }
+    }
+
+    // Fetches only the indexes
+    #[cfg(feature = "csv")]
+    pub(crate) fn indexes() -> Self {
+        SchemaFetch {
+            schema: GraphSchema::default(),
+            sample_size: 1,
+            pending: vec![SchemaStep::Indexes].into(),
+        }
+    }
+
+    // The next query to run, None once the schema is complete
+    pub(crate) fn next_query(&self) -> Option<String> {
+        let step = self.pending.front()?;
+        Some(match *step {
+            SchemaStep::Procedures => "CALL dbms.procedures() YIELD name RETURN name".to_string(),
+            SchemaStep::Labels => "CALL db.labels() YIELD label RETURN label".to_string(),
+            SchemaStep::RelationshipTypes => {
+                "CALL db.relationshipTypes() YIELD relationshipType RETURN relationshipType"
+                    .to_string()
+            }
+            SchemaStep::PropertyKeys => {
+                "CALL db.propertyKeys() YIELD propertyKey RETURN propertyKey".to_string()
+            }
+            SchemaStep::Indexes => "CALL db.indexes()".to_string(),
+            SchemaStep::Constraints => "CALL db.constraints()".to_string(),
+            SchemaStep::LabelCount(idx) => format!(
+                "MATCH (n:{}) RETURN count(n) AS count",
+                escape_identifier(&self.schema.labels[idx].name)
+            ),
+            SchemaStep::LabelProperties(idx) => format!(
+                "MATCH (n:{}) WITH n LIMIT {} UNWIND keys(n) AS key \
+                 RETURN key, collect(DISTINCT typeOf(n[key])) AS types",
+                escape_identifier(&self.schema.labels[idx].name),
+                self.sample_size
+            ),
+            SchemaStep::RelationCount(idx) => format!(
+                "MATCH ()-[r:{}]->() RETURN count(r) AS count",
+                escape_identifier(&self.schema.relationship_types[idx].name)
+            ),
+            SchemaStep::RelationProperties(idx) => format!(
+                "MATCH ()-[r:{}]->() WITH r LIMIT {} UNWIND keys(r) AS key \
+                 RETURN key, collect(DISTINCT typeOf(r[key])) AS types",
+                escape_identifier(&self.schema.relationship_types[idx].name),
+                self.sample_size
+            ),
+        })
+    }
+
+    // Applies the response to the query returned by next_query
+    pub(crate) fn add_response(&mut self, v: &Value) -> RedisResult<()> {
+        let step = match self.pending.pop_front() {
+            Some(step) => step,
+            None => return Err(create_error("Schema fetch is already complete")),
+        };
+        let rows = parse_procedure_rows(v)?;
+        match step {
+            SchemaStep::Procedures => {
+                if string_column(&rows, "name")?
+                    .iter()
+                    .any(|name| name == "db.constraints")
+                {
+                    self.pending.push_back(SchemaStep::Constraints);
+                }
+            }
+            SchemaStep::Labels => {
+                self.schema.labels = entities(string_column(&rows, "label")?);
+                for idx in 0..self.schema.labels.len() {
+                    self.pending.push_back(SchemaStep::LabelCount(idx));
+                    self.pending.push_back(SchemaStep::LabelProperties(idx));
+                }
+            }
+            SchemaStep::RelationshipTypes => {
+                self.schema.relationship_types =
+                    entities(string_column(&rows, "relationshipType")?);
+                for idx in 0..self.schema.relationship_types.len() {
+                    self.pending.push_back(SchemaStep::RelationCount(idx));
+                    self.pending.push_back(SchemaStep::RelationProperties(idx));
+                }
+            }
+            SchemaStep::PropertyKeys => {
+                let mut keys = string_column(&rows, "propertyKey")?;
+                keys.sort();
+                self.schema.property_keys = keys;
+            }
+            SchemaStep::Indexes => {
+                self.schema.indexes = parse_indexes(&rows)?;
+            }
+            SchemaStep::Constraints => {
+                self.schema.constraints = parse_constraints(&rows)?;
+            }
+            SchemaStep::LabelCount(idx) => {
+                self.schema.labels[idx].count = count(&rows)?;
+            }
+            SchemaStep::LabelProperties(idx) => {
+                self.schema.labels[idx].properties = property_types(&rows)?;
+            }
+            SchemaStep::RelationCount(idx) => {
+                self.schema.relationship_types[idx].count = count(&rows)?;
+            }
+            SchemaStep::RelationProperties(idx) => {
+                self.schema.relationship_types[idx].properties = property_types(&rows)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> GraphSchema {
+        self.schema
+    }
+}
+
+fn string_column(rows: &[ProcedureRow], name: &str) -> RedisResult<Vec<String>> {
+    rows.iter()
+        .filter_map(|row| row.get_value::<String>(name).transpose())
+        .collect()
+}
+
+fn entities(mut names: Vec<String>) -> Vec<EntitySchema> {
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| EntitySchema {
+            name,
+            ..EntitySchema::default()
+        })
+        .collect()
+}
+
+fn count(rows: &[ProcedureRow]) -> RedisResult<u64> {
+    match rows.first() {
+        Some(row) => Ok(row.get_value("count")?.unwrap_or(0)),
+        None => Ok(0),
+    }
+}
+
+fn property_types(rows: &[ProcedureRow]) -> RedisResult<BTreeMap<String, Vec<PropertyType>>> {
+    let mut properties = BTreeMap::new();
+    for row in rows {
+        let key: String = match row.get_value("key")? {
+            Some(key) => key,
+            None => continue,
+        };
+        let names: Vec<String> = row.get_value("types")?.unwrap_or_default();
+        let mut types: Vec<PropertyType> = names.into_iter().map(PropertyType::from).collect();
+        types.sort();
+        types.dedup();
+        properties.insert(key, types);
+    }
+    Ok(properties)
+}
+
+fn entity_type(row: &ProcedureRow) -> RedisResult<EntityType> {
+    let name: Option<String> = row.get_value("entitytype")?;
+    match name.map(|n| n.to_ascii_uppercase()).as_deref() {
+        Some("RELATIONSHIP") => Ok(EntityType::Relationship),
+        _ => Ok(EntityType::Node),
+    }
+}
+
+// Older servers report one index per row with its type, newer ones a map of
+// property to index types. Both are normalized to one index per type.
+fn parse_indexes(rows: &[ProcedureRow]) -> RedisResult<Vec<IndexInfo>> {
+    let mut indexes = Vec::new();
+    for row in rows {
+        let label: String = row.get_value("label")?.unwrap_or_default();
+        let entity_type = entity_type(row)?;
+        if let Some(index_type) = row.get_value::<String>("type")? {
+            indexes.push(IndexInfo {
+                label,
+                properties: row.get_value("properties")?.unwrap_or_default(),
+                index_type,
+                entity_type,
+            });
+            continue;
+        }
+        let mut by_type: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        if let Some(types) = row.values.get("types") {
+            // Maps are returned as a flat list of keys and values
+            let entries: Vec<Value> = from_redis_value(types)?;
+            for entry in entries.chunks(2) {
+                let (property, property_types) = match entry {
+                    [property, property_types] => (property, property_types),
+                    _ => return Err(create_error("Could not parse index types")),
+                };
+                let property: String = from_redis_value(property)?;
+                let property_types: Vec<String> = from_redis_value(property_types)?;
+                for index_type in property_types {
+                    by_type
+                        .entry(index_type.to_ascii_lowercase())
+                        .or_default()
+                        .push(property.clone());
+                }
+            }
+        }
+        for (index_type, mut properties) in by_type {
+            properties.sort();
+            indexes.push(IndexInfo {
+                label: label.clone(),
+                properties,
+                index_type,
+                entity_type,
+            });
+        }
+    }
+    indexes.sort();
+    Ok(indexes)
+}
+
+fn parse_constraints(rows: &[ProcedureRow]) -> RedisResult<Vec<ConstraintInfo>> {
+    let mut constraints = rows
+        .iter()
+        .map(|row| {
+            Ok(ConstraintInfo {
+                label: row.get_value("label")?.unwrap_or_default(),
+                properties: row.get_value("properties")?.unwrap_or_default(),
+                constraint_type: row.get_value("type")?.unwrap_or_default(),
+                entity_type: entity_type(row)?,
+                status: row.get_value("status")?.unwrap_or_default(),
+            })
+        })
+        .collect::<RedisResult<Vec<ConstraintInfo>>>()?;
+    constraints.sort();
+    Ok(constraints)
+}
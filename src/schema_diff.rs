@@ -0,0 +1,562 @@
+use crate::params::{escape_identifier, string_literal};
+use crate::schema::*;
+use crate::types::create_error;
+use redis::{cmd, Cmd, RedisResult, ToRedisArgs};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// The differences between an expected and an actual graph schema, e.g. a
+/// staging and a production graph, or a declared schema and a live graph.
+/// Missing entries are part of the expected schema only, extra entries of the
+/// actual schema only. Node and relation counts are not compared.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let mut staging = redis::Client::open("redis://staging/")?.get_connection()?;
+/// let mut production = redis::Client::open("redis://production/")?.get_connection()?;
+///
+/// let expected = staging.graph_schema("my_graph", 100)?;
+/// let diff = production.graph_schema_diff("my_graph", &expected, 100)?;
+/// if !diff.is_empty() {
+///     println!("{}", diff);
+///     for statement in diff.statements(false) {
+///         let _: redis::Value = statement.to_cmd("my_graph").query(&mut production)?;
+///     }
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Default, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SchemaDiff {
+    pub missing_labels: Vec<String>,
+    pub extra_labels: Vec<String>,
+    pub missing_relationship_types: Vec<String>,
+    pub extra_relationship_types: Vec<String>,
+    pub property_differences: Vec<PropertyDifference>,
+    /// Missing indexes with one entry per indexed property.
+    pub missing_indexes: Vec<IndexInfo>,
+    /// Extra indexes with one entry per indexed property.
+    pub extra_indexes: Vec<IndexInfo>,
+    pub missing_constraints: Vec<ConstraintInfo>,
+    pub extra_constraints: Vec<ConstraintInfo>,
+    // The expected full-text properties per label, needed to recreate an
+    // index after dropping it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    expected_fulltext: BTreeMap<String, Vec<String>>,
+}
+
+/// A property of a label or relationship type that is missing, extra or has
+/// different types. Properties are only compared if both schemas list
+/// properties for the label or relationship type.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PropertyDifference {
+    pub entity_type: EntityType,
+    /// The node label or relationship type.
+    pub label: String,
+    pub property: String,
+    /// The expected types, None if the property is not expected. An empty
+    /// list only requires the property to exist.
+    pub expected: Option<Vec<PropertyType>>,
+    /// The actual types, None if the property does not exist.
+    pub actual: Option<Vec<PropertyType>>,
+}
+
+/// A statement converging a graph to the expected schema. Indexes are changed
+/// with Cypher queries, constraints with the GRAPH.CONSTRAINT command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaStatement {
+    Query(String),
+    CreateConstraint(ConstraintInfo),
+    DropConstraint(ConstraintInfo),
+}
+
+impl SchemaDiff {
+    /// Compares the actual schema against the expected one.
+    pub fn compare(expected: &GraphSchema, actual: &GraphSchema) -> Self {
+        let (missing_labels, extra_labels) = compare_names(&expected.labels, &actual.labels);
+        let (missing_relationship_types, extra_relationship_types) =
+            compare_names(&expected.relationship_types, &actual.relationship_types);
+        let mut property_differences =
+            compare_properties(EntityType::Node, &expected.labels, &actual.labels);
+        property_differences.extend(compare_properties(
+            EntityType::Relationship,
+            &expected.relationship_types,
+            &actual.relationship_types,
+        ));
+        let expected_indexes = index_entries(&expected.indexes);
+        let (missing_indexes, extra_indexes) =
+            difference(&expected_indexes, &index_entries(&actual.indexes));
+        let expected_constraints = constraint_entries(&expected.constraints);
+        let actual_constraints = constraint_entries(&actual.constraints);
+        let (missing_constraints, extra_constraints) = difference(
+            &expected_constraints.keys().cloned().collect(),
+            &actual_constraints.keys().cloned().collect(),
+        );
+        SchemaDiff {
+            missing_labels,
+            extra_labels,
+            missing_relationship_types,
+            extra_relationship_types,
+            property_differences,
+            missing_indexes: missing_indexes.into_iter().map(index_info).collect(),
+            extra_indexes: extra_indexes.into_iter().map(index_info).collect(),
+            missing_constraints: missing_constraints
+                .iter()
+                .map(|key| expected_constraints[key].clone())
+                .collect(),
+            extra_constraints: extra_constraints
+                .iter()
+                .map(|key| actual_constraints[key].clone())
+                .collect(),
+            expected_fulltext: fulltext_properties(expected_indexes.into_iter().map(index_info)),
+        }
+    }
+
+    /// Returns true if the schemas match.
+    pub fn is_empty(&self) -> bool {
+        self.missing_labels.is_empty()
+            && self.extra_labels.is_empty()
+            && self.missing_relationship_types.is_empty()
+            && self.extra_relationship_types.is_empty()
+            && self.property_differences.is_empty()
+            && self.missing_indexes.is_empty()
+            && self.extra_indexes.is_empty()
+            && self.missing_constraints.is_empty()
+            && self.extra_constraints.is_empty()
+    }
+
+    /// The statements that create the missing indexes and constraints, and
+    /// with drop_extra also drop the extra ones. Labels, relationship types
+    /// and properties only exist through data and are not converged. Indexes
+    /// other than range and fulltext indexes on nodes are skipped since they
+    /// need options not part of the schema.
+    pub fn statements(&self, drop_extra: bool) -> Vec<SchemaStatement> {
+        let mut statements = Vec::new();
+        if drop_extra {
+            statements.extend(
+                self.extra_constraints
+                    .iter()
+                    .cloned()
+                    .map(SchemaStatement::DropConstraint),
+            );
+            statements.extend(
+                self.extra_indexes
+                    .iter()
+                    .filter(|index| index.index_type == "range")
+                    .map(|index| SchemaStatement::Query(drop_range_index(index))),
+            );
+        }
+        statements.extend(
+            self.missing_indexes
+                .iter()
+                .filter(|index| index.index_type == "range")
+                .map(|index| SchemaStatement::Query(create_range_index(index))),
+        );
+        statements.extend(self.fulltext_statements(drop_extra));
+        statements.extend(
+            self.missing_constraints
+                .iter()
+                .cloned()
+                .map(SchemaStatement::CreateConstraint),
+        );
+        statements
+    }
+
+    // A full-text index can only be dropped as a whole, so a label with extra
+    // properties is dropped and recreated with the remaining properties.
+    fn fulltext_statements(&self, drop_extra: bool) -> Vec<SchemaStatement> {
+        let missing = fulltext_properties(self.missing_indexes.iter().cloned());
+        let extra = if drop_extra {
+            fulltext_properties(self.extra_indexes.iter().cloned())
+        } else {
+            BTreeMap::new()
+        };
+        let labels: BTreeSet<&String> = missing.keys().chain(extra.keys()).collect();
+        let mut statements = Vec::new();
+        for label in labels {
+            let properties = if extra.contains_key(label) {
+                statements.push(SchemaStatement::Query(format!(
+                    "CALL db.idx.fulltext.drop({})",
+                    string_literal(label)
+                )));
+                self.expected_fulltext
+                    .get(label)
+                    .cloned()
+                    .unwrap_or_default()
+            } else {
+                missing.get(label).cloned().unwrap_or_default()
+            };
+            if properties.is_empty() {
+                continue;
+            }
+            let args: Vec<String> = properties.iter().map(|p| string_literal(p)).collect();
+            statements.push(SchemaStatement::Query(format!(
+                "CALL db.idx.fulltext.createNodeIndex({}, {})",
+                string_literal(label),
+                args.join(", ")
+            )));
+        }
+        statements
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "Schemas are equal");
+        }
+        for label in self.missing_labels.iter() {
+            writeln!(f, "missing label :{}", label)?;
+        }
+        for label in self.extra_labels.iter() {
+            writeln!(f, "extra label :{}", label)?;
+        }
+        for rel_type in self.missing_relationship_types.iter() {
+            writeln!(f, "missing relationship type :{}", rel_type)?;
+        }
+        for rel_type in self.extra_relationship_types.iter() {
+            writeln!(f, "extra relationship type :{}", rel_type)?;
+        }
+        for property in self.property_differences.iter() {
+            writeln!(f, "{}", property)?;
+        }
+        for index in self.missing_indexes.iter() {
+            writeln!(f, "missing index {}", index_pattern(index))?;
+        }
+        for index in self.extra_indexes.iter() {
+            writeln!(f, "extra index {}", index_pattern(index))?;
+        }
+        for constraint in self.missing_constraints.iter() {
+            writeln!(f, "missing constraint {}", constraint_pattern(constraint))?;
+        }
+        for constraint in self.extra_constraints.iter() {
+            writeln!(f, "extra constraint {}", constraint_pattern(constraint))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PropertyDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "property :{}.{} ", self.label, self.property)?;
+        match (&self.expected, &self.actual) {
+            (Some(_), None) => f.write_str("missing"),
+            (None, Some(_)) => f.write_str("extra"),
+            (expected, actual) => write!(
+                f,
+                "expected {}, found {}",
+                type_names(expected),
+                type_names(actual)
+            ),
+        }
+    }
+}
+
+impl SchemaStatement {
+    /// Parses a statement as displayed, a GRAPH.CONSTRAINT command without
+    /// the graph key or else a Cypher query.
+    pub fn parse(statement: &str) -> RedisResult<Self> {
+        let statement = statement.trim();
+        let args: Vec<&str> = statement.split_whitespace().collect();
+        if !args
+            .first()
+            .is_some_and(|c| c.eq_ignore_ascii_case("GRAPH.CONSTRAINT"))
+        {
+            return Ok(SchemaStatement::Query(statement.to_string()));
+        }
+        let invalid = || create_error(&format!("Invalid constraint statement {}", statement));
+        let (action, constraint_type, entity, label, count) = match args.as_slice() {
+            [_, action, constraint_type, entity, label, properties, count, ..]
+                if properties.eq_ignore_ascii_case("PROPERTIES") =>
+            {
+                (action, constraint_type, entity, label, count)
+            }
+            _ => return Err(invalid()),
+        };
+        let properties = &args[7..];
+        if count.parse::<usize>().ok() != Some(properties.len()) {
+            return Err(invalid());
+        }
+        let entity_type = match entity.to_ascii_uppercase().as_str() {
+            "NODE" => EntityType::Node,
+            "RELATIONSHIP" => EntityType::Relationship,
+            _ => return Err(invalid()),
+        };
+        let constraint = ConstraintInfo {
+            label: label.to_string(),
+            properties: properties.iter().map(|p| p.to_string()).collect(),
+            constraint_type: constraint_type.to_ascii_uppercase(),
+            entity_type,
+            status: String::new(),
+        };
+        match action.to_ascii_uppercase().as_str() {
+            "CREATE" => Ok(SchemaStatement::CreateConstraint(constraint)),
+            "DROP" => Ok(SchemaStatement::DropConstraint(constraint)),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// The command executing the statement on the graph at key.
+    pub fn to_cmd<K: ToRedisArgs>(&self, key: K) -> Cmd {
+        match self {
+            SchemaStatement::Query(query) => cmd("GRAPH.QUERY").arg(key).arg(query).clone(),
+            SchemaStatement::CreateConstraint(constraint) => {
+                constraint_cmd("CREATE", key, constraint)
+            }
+            SchemaStatement::DropConstraint(constraint) => constraint_cmd("DROP", key, constraint),
+        }
+    }
+}
+
+/// Queries are displayed as is, constraints as GRAPH.CONSTRAINT command
+/// without the graph key.
+impl fmt::Display for SchemaStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaStatement::Query(query) => f.write_str(query),
+            SchemaStatement::CreateConstraint(constraint) => {
+                write!(
+                    f,
+                    "GRAPH.CONSTRAINT CREATE {}",
+                    constraint_args(constraint).join(" ")
+                )
+            }
+            SchemaStatement::DropConstraint(constraint) => {
+                write!(
+                    f,
+                    "GRAPH.CONSTRAINT DROP {}",
+                    constraint_args(constraint).join(" ")
+                )
+            }
+        }
+    }
+}
+
+fn compare_names(expected: &[EntitySchema], actual: &[EntitySchema]) -> (Vec<String>, Vec<String>) {
+    let names = |entities: &[EntitySchema]| -> BTreeSet<String> {
+        entities.iter().map(|e| e.name.clone()).collect()
+    };
+    difference(&names(expected), &names(actual))
+}
+
+fn compare_properties(
+    entity_type: EntityType,
+    expected: &[EntitySchema],
+    actual: &[EntitySchema],
+) -> Vec<PropertyDifference> {
+    let mut differences = Vec::new();
+    for expected in expected.iter() {
+        let actual = match actual.iter().find(|a| a.name == expected.name) {
+            Some(actual) => actual,
+            None => continue,
+        };
+        if expected.properties.is_empty() || actual.properties.is_empty() {
+            continue;
+        }
+        let keys: BTreeSet<&String> = expected
+            .properties
+            .keys()
+            .chain(actual.properties.keys())
+            .collect();
+        for key in keys {
+            let expected_types = expected.properties.get(key).map(|t| sorted(t));
+            let actual_types = actual.properties.get(key).map(|t| sorted(t));
+            let equal = match (&expected_types, &actual_types) {
+                (Some(e), Some(_)) if e.is_empty() => true,
+                (e, a) => e == a,
+            };
+            if !equal {
+                differences.push(PropertyDifference {
+                    entity_type,
+                    label: expected.name.clone(),
+                    property: key.clone(),
+                    expected: expected_types,
+                    actual: actual_types,
+                });
+            }
+        }
+    }
+    differences
+}
+
+// (entity type, label, normalized index type, property)
+type IndexEntry = (EntityType, String, String, String);
+
+fn index_entries(indexes: &[IndexInfo]) -> BTreeSet<IndexEntry> {
+    let mut entries = BTreeSet::new();
+    for index in indexes.iter() {
+        let index_type = normalize_index_type(&index.index_type);
+        for property in index.properties.iter() {
+            entries.insert((
+                index.entity_type,
+                index.label.clone(),
+                index_type.clone(),
+                property.clone(),
+            ));
+        }
+    }
+    entries
+}
+
+fn index_info((entity_type, label, index_type, property): IndexEntry) -> IndexInfo {
+    IndexInfo {
+        label,
+        properties: vec![property],
+        index_type,
+        entity_type,
+    }
+}
+
+// Older servers report exact-match and full-text indexes
+fn normalize_index_type(index_type: &str) -> String {
+    match index_type.to_ascii_lowercase().as_str() {
+        "exact-match" => "range".to_string(),
+        "full-text" => "fulltext".to_string(),
+        other => other.to_string(),
+    }
+}
+
+// Constraints are compared without their status
+fn constraint_entries(
+    constraints: &[ConstraintInfo],
+) -> BTreeMap<(EntityType, String, String, Vec<String>), ConstraintInfo> {
+    constraints
+        .iter()
+        .map(|constraint| {
+            let mut properties = constraint.properties.clone();
+            properties.sort();
+            let key = (
+                constraint.entity_type,
+                constraint.label.clone(),
+                constraint.constraint_type.to_ascii_uppercase(),
+                properties,
+            );
+            (key, constraint.clone())
+        })
+        .collect()
+}
+
+// The entries only in expected and the entries only in actual
+fn difference<T: Ord + Clone>(expected: &BTreeSet<T>, actual: &BTreeSet<T>) -> (Vec<T>, Vec<T>) {
+    (
+        expected.difference(actual).cloned().collect(),
+        actual.difference(expected).cloned().collect(),
+    )
+}
+
+// The sorted properties of node full-text indexes per label
+fn fulltext_properties<I: Iterator<Item = IndexInfo>>(indexes: I) -> BTreeMap<String, Vec<String>> {
+    let mut by_label: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for index in indexes
+        .filter(|index| index.index_type == "fulltext" && index.entity_type == EntityType::Node)
+    {
+        by_label
+            .entry(index.label)
+            .or_default()
+            .extend(index.properties);
+    }
+    for properties in by_label.values_mut() {
+        properties.sort();
+        properties.dedup();
+    }
+    by_label
+}
+
+fn sorted(types: &[PropertyType]) -> Vec<PropertyType> {
+    let mut types = types.to_vec();
+    types.sort();
+    types.dedup();
+    types
+}
+
+fn type_names(types: &Option<Vec<PropertyType>>) -> String {
+    match types {
+        Some(types) if !types.is_empty() => {
+            let names: Vec<String> = types.iter().map(ToString::to_string).collect();
+            names.join(" | ")
+        }
+        Some(_) => "any".to_string(),
+        None => "none".to_string(),
+    }
+}
+
+fn index_pattern(index: &IndexInfo) -> String {
+    format!(
+        "{} {}",
+        index.index_type,
+        pattern(index.entity_type, &index.label, &index.properties)
+    )
+}
+
+fn constraint_pattern(constraint: &ConstraintInfo) -> String {
+    format!(
+        "{} {}",
+        constraint.constraint_type,
+        pattern(
+            constraint.entity_type,
+            &constraint.label,
+            &constraint.properties
+        )
+    )
+}
+
+fn create_range_index(index: &IndexInfo) -> String {
+    let property = escape_identifier(&index.properties[0]);
+    match index.entity_type {
+        EntityType::Node => format!(
+            "CREATE INDEX FOR (n:{}) ON (n.{})",
+            escape_identifier(&index.label),
+            property
+        ),
+        EntityType::Relationship => format!(
+            "CREATE INDEX FOR ()-[r:{}]-() ON (r.{})",
+            escape_identifier(&index.label),
+            property
+        ),
+    }
+}
+
+fn drop_range_index(index: &IndexInfo) -> String {
+    let property = escape_identifier(&index.properties[0]);
+    match index.entity_type {
+        EntityType::Node => format!(
+            "DROP INDEX ON :{}({})",
+            escape_identifier(&index.label),
+            property
+        ),
+        EntityType::Relationship => format!(
+            "DROP INDEX FOR ()-[r:{}]-() ON (r.{})",
+            escape_identifier(&index.label),
+            property
+        ),
+    }
+}
+
+// UNIQUE NODE Rider PROPERTIES 1 name
+fn constraint_args(constraint: &ConstraintInfo) -> Vec<String> {
+    let entity = match constraint.entity_type {
+        EntityType::Node => "NODE",
+        EntityType::Relationship => "RELATIONSHIP",
+    };
+    let mut args = vec![
+        constraint.constraint_type.to_ascii_uppercase(),
+        entity.to_string(),
+        constraint.label.clone(),
+        "PROPERTIES".to_string(),
+        constraint.properties.len().to_string(),
+    ];
+    args.extend(constraint.properties.iter().cloned());
+    args
+}
+
+fn constraint_cmd<K: ToRedisArgs>(action: &str, key: K, constraint: &ConstraintInfo) -> Cmd {
+    let mut c = cmd("GRAPH.CONSTRAINT");
+    c.arg(action).arg(key);
+    for arg in constraint_args(constraint) {
+        c.arg(arg);
+    }
+    c
+}
@@ -0,0 +1,232 @@
+use crate::types::*;
+use redis::{from_redis_value, FromRedisValue, RedisResult, Value};
+use std::sync::Arc;
+
+/// A lazily decoded graph query result. Other than a GraphResultSet, which
+/// decodes all rows upfront, the stream keeps the raw rows and only decodes a
+/// row when it is requested from the iterator. All rows share a single header
+/// allocation and index their values by position.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let riders = con.graph_ro_query_stream("my_graph", "MATCH (r:Rider) RETURN r, r.name")?;
+/// for row in riders {
+///     let row = row?;
+///     let rider: Option<&NodeValue> = row.get_node("r");
+///     let name: Option<String> = row.get_scalar_at(1);
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct GraphResultStream {
+    header: Arc<[String]>,
+    rows: std::vec::IntoIter<Value>,
+    metadata: Vec<String>,
+    strict: bool,
+}
+
+/// A single decoded row of a GraphResultStream. Values can be accessed by
+/// their RETURN key or by column position. Like for a GraphResult, the last
+/// column is returned for duplicate keys.
+#[derive(Clone, Debug)]
+pub struct GraphRow {
+    header: Arc<[String]>,
+    values: Vec<GraphValue>,
+}
+
+impl GraphResultStream {
+    /// Creates a stream from an owned graph query response without copying the
+    /// contained rows. The stream is only created from owned responses, as
+    /// decoding it from a borrowed value would copy the whole response.
+    pub fn from_value(v: Value) -> RedisResult<Self> {
+        match v {
+            Value::Bulk(values) if values.is_empty() => Ok(GraphResultStream::default()),
+            Value::Bulk(values) if values.len() == 1 => Ok(GraphResultStream {
+                metadata: from_redis_value(&values[0])?,
+                ..GraphResultStream::default()
+            }),
+            Value::Bulk(values) => {
+                let mut values = values.into_iter();
+                let header: Vec<String> = match values.next() {
+                    Some(v) => from_redis_value(&v)?,
+                    _ => Vec::default(),
+                };
+                let rows: Vec<Value> = match values.next() {
+                    Some(Value::Bulk(rows)) => rows,
+                    _ => Vec::default(),
+                };
+                let metadata: Vec<String> = match values.next() {
+                    Some(v) => from_redis_value(&v)?,
+                    _ => Vec::default(),
+                };
+                Ok(GraphResultStream {
+                    header: header.into(),
+                    rows: rows.into_iter(),
+                    metadata,
+                    strict: false,
+                })
+            }
+            _ => Err(create_error("Could not parse graph result")),
+        }
+    }
+
+    /// Enables strict mode in which decoding a row fails if its length differs
+    /// from the header.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// The keys occuring in the RETURN statement of the query.
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    /// Metadata returned with the query (eg. execution time).
+    pub fn metadata(&self) -> &[String] {
+        &self.metadata
+    }
+
+    // Decodes the remaining rows without consuming them
+    #[cfg(feature = "arrow")]
+    pub(crate) fn peek_rows(&self) -> impl Iterator<Item = RedisResult<Vec<GraphValue>>> + '_ {
+        self.rows.as_slice().iter().map(from_redis_value)
+    }
+}
+
+impl Default for GraphResultStream {
+    fn default() -> Self {
+        GraphResultStream {
+            header: Arc::from(Vec::default()),
+            rows: Vec::default().into_iter(),
+            metadata: Vec::default(),
+            strict: false,
+        }
+    }
+}
+
+impl Iterator for GraphResultStream {
+    type Item = RedisResult<GraphRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.rows.next()?;
+        let values: Vec<GraphValue> = match from_redis_value(&row) {
+            Ok(values) => values,
+            Err(e) => return Some(Err(e)),
+        };
+        if self.strict && values.len() != self.header.len() {
+            return Some(Err(create_error(&format!(
+                "Row has {} values for {} header columns",
+                values.len(),
+                self.header.len()
+            ))));
+        }
+        Some(Ok(GraphRow {
+            header: self.header.clone(),
+            values,
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl ExactSizeIterator for GraphResultStream {}
+
+impl GraphRow {
+    /// The keys occuring in the RETURN statement of the query.
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    /// All values of the row in column order.
+    pub fn values(&self) -> &[GraphValue] {
+        &self.values
+    }
+
+    /// Converts the row into a GraphResult.
+    pub fn into_result(self) -> GraphResult {
+        GraphResult::from_values(self.header, self.values)
+    }
+
+    /// Consumes the row returning its values in column order.
+    pub fn into_values(self) -> Vec<GraphValue> {
+        self.values
+    }
+
+    /// Returns the column position of a RETURN key, the last one for
+    /// duplicate keys.
+    pub fn position(&self, key: &str) -> Option<usize> {
+        self.header[..self.header.len().min(self.values.len())]
+            .iter()
+            .rposition(|name| name == key)
+    }
+
+    /// Returns all values for a RETURN key, which can be multiple in case the
+    /// query returns duplicate column names.
+    pub fn get_all(&self, key: &str) -> Vec<&GraphValue> {
+        self.header
+            .iter()
+            .zip(self.values.iter())
+            .filter(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Returns a single GraphValue by it's key.
+    pub fn get_value(&self, key: &str) -> Option<&GraphValue> {
+        self.get_at(self.position(key)?)
+    }
+
+    /// Returns a single GraphValue by it's column position.
+    pub fn get_at(&self, idx: usize) -> Option<&GraphValue> {
+        self.values.get(idx)
+    }
+
+    /// Tries to extract a graph Scalar value at key into target type T. Will
+    /// return None in case the key does not exist, the target value is not a
+    /// Scalar or the value could not be parsed into T.
+    pub fn get_scalar<T: FromRedisValue>(&self, key: &str) -> Option<T> {
+        self.get_scalar_at(self.position(key)?)
+    }
+
+    /// Tries to extract a graph Scalar value at column position into target type T.
+    pub fn get_scalar_at<T: FromRedisValue>(&self, idx: usize) -> Option<T> {
+        match self.get_at(idx) {
+            Some(GraphValue::Scalar(value)) => from_redis_value(value).unwrap_or(None),
+            _ => None,
+        }
+    }
+
+    /// Tries to extract a graph Node value at key.
+    pub fn get_node(&self, key: &str) -> Option<&NodeValue> {
+        self.get_node_at(self.position(key)?)
+    }
+
+    /// Tries to extract a graph Node value at column position.
+    pub fn get_node_at(&self, idx: usize) -> Option<&NodeValue> {
+        match self.get_at(idx) {
+            Some(GraphValue::Node(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Tries to extract a graph Relation value at key.
+    pub fn get_relation(&self, key: &str) -> Option<&RelationValue> {
+        self.get_relation_at(self.position(key)?)
+    }
+
+    /// Tries to extract a graph Relation value at column position.
+    pub fn get_relation_at(&self, idx: usize) -> Option<&RelationValue> {
+        match self.get_at(idx) {
+            Some(GraphValue::Relation(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use crate::params::query_with_id;
+use crate::types::*;
+use redis::{FromRedisValue, RedisResult, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// An in-memory graph built from the nodes and relations contained in query
+/// results. Nodes and relations are deduplicated by their id and relations
+/// are indexed by their source and destination nodes, which allows to walk
+/// the graph without issuing further queries.
+///
+/// Relations can point to nodes that were not returned by the query. Those
+/// nodes are part of the adjacency (and the neighbor ids) but can not be
+/// looked up with `node`.
+///
+/// ```rust,no_run
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_graph::*;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/")?;
+/// let mut con = client.get_connection()?;
+///
+/// let res = con.graph_ro_query("my_graph", "MATCH (a)-[r]->(b) RETURN a, r, b")?;
+/// let graph = res.to_subgraph();
+/// for rider in graph.nodes_with_label("Rider") {
+///     let teams = graph.successors(rider.id);
+///     let out_degree = graph.out_degree(rider.id);
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Default, Clone, Debug)]
+pub struct Subgraph {
+    nodes: BTreeMap<u64, NodeValue>,
+    relations: BTreeMap<u64, RelationValue>,
+    outgoing: HashMap<u64, Vec<u64>>,
+    incoming: HashMap<u64, Vec<u64>>,
+}
+
+impl Subgraph {
+    /// Creates an empty subgraph.
+    pub fn new() -> Self {
+        Subgraph::default()
+    }
+
+    /// Builds a subgraph from all Node and Relation values of a result set.
+    pub fn from_result_set(res: &GraphResultSet) -> Self {
+        let mut graph = Subgraph::new();
+        for row in res.data.iter() {
+            graph.add_result(row);
+        }
+        graph
+    }
+
+    /// Adds all Node and Relation values of a single result row.
+    pub fn add_result(&mut self, row: &GraphResult) {
+        for value in row.values().iter() {
+            match value {
+                GraphValue::Node(node) => {
+                    self.add_node(node.clone());
+                }
+                GraphValue::Relation(relation) => {
+                    self.add_relation(relation.clone());
+                }
+                GraphValue::Scalar(_) => {}
+            }
+        }
+    }
+
+    /// Adds a node. Returns false if a node with the same id already exists,
+    /// in which case the subgraph is unchanged.
+    pub fn add_node(&mut self, node: NodeValue) -> bool {
+        if self.nodes.contains_key(&node.id) {
+            return false;
+        }
+        self.nodes.insert(node.id, node);
+        true
+    }
+
+    /// Adds a relation and indexes it by its source and destination node.
+    /// Returns false if a relation with the same id already exists, in which
+    /// case the subgraph is unchanged.
+    pub fn add_relation(&mut self, relation: RelationValue) -> bool {
+        if self.relations.contains_key(&relation.id) {
+            return false;
+        }
+        self.outgoing
+            .entry(relation.src_node)
+            .or_default()
+            .push(relation.id);
+        self.incoming
+            .entry(relation.dest_node)
+            .or_default()
+            .push(relation.id);
+        self.relations.insert(relation.id, relation);
+        true
+    }
+
+    /// The number of distinct nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The number of distinct relations.
+    pub fn relation_count(&self) -> usize {
+        self.relations.len()
+    }
+
+    /// Returns a node by its id.
+    pub fn node(&self, id: u64) -> Option<&NodeValue> {
+        self.nodes.get(&id)
+    }
+
+    /// Returns a relation by its id.
+    pub fn relation(&self, id: u64) -> Option<&RelationValue> {
+        self.relations.get(&id)
+    }
+
+    /// All nodes ordered by id.
+    pub fn nodes(&self) -> impl Iterator<Item = &NodeValue> {
+        self.nodes.values()
+    }
+
+    /// All relations ordered by id.
+    pub fn relations(&self) -> impl Iterator<Item = &RelationValue> {
+        self.relations.values()
+    }
+
+    /// All nodes carrying label.
+    pub fn nodes_with_label(&self, label: &str) -> Vec<&NodeValue> {
+        self.nodes()
+            .filter(|node| node.labels.iter().any(|l| l == label))
+            .collect()
+    }
+
+    /// All relations of type rel_type.
+    pub fn relations_of_type(&self, rel_type: &str) -> Vec<&RelationValue> {
+        self.relations()
+            .filter(|relation| relation.rel_type == rel_type)
+            .collect()
+    }
+
+    /// Relations starting at node id.
+    pub fn outgoing(&self, id: u64) -> Vec<&RelationValue> {
+        self.adjacent(&self.outgoing, id)
+    }
+
+    /// Relations ending at node id.
+    pub fn incoming(&self, id: u64) -> Vec<&RelationValue> {
+        self.adjacent(&self.incoming, id)
+    }
+
+    /// Ids of the nodes reachable by an outgoing relation of node id.
+    pub fn successors(&self, id: u64) -> Vec<u64> {
+        dedup(self.outgoing(id).iter().map(|r| r.dest_node))
+    }
+
+    /// Ids of the nodes with a relation ending at node id.
+    pub fn predecessors(&self, id: u64) -> Vec<u64> {
+        dedup(self.incoming(id).iter().map(|r| r.src_node))
+    }
+
+    /// Ids of the nodes connected to node id in any direction.
+    pub fn neighbors(&self, id: u64) -> Vec<u64> {
+        let successors = self.successors(id);
+        let predecessors = self.predecessors(id);
+        dedup(successors.into_iter().chain(predecessors))
+    }
+
+    /// The number of relations starting at node id.
+    pub fn out_degree(&self, id: u64) -> usize {
+        self.outgoing.get(&id).map_or(0, Vec::len)
+    }
+
+    /// The number of relations ending at node id.
+    pub fn in_degree(&self, id: u64) -> usize {
+        self.incoming.get(&id).map_or(0, Vec::len)
+    }
+
+    /// The number of relations starting or ending at node id. A self loop is
+    /// counted twice.
+    pub fn degree(&self, id: u64) -> usize {
+        self.out_degree(id) + self.in_degree(id)
+    }
+
+    /// Ids of all nodes including nodes that are only referenced by relations.
+    pub fn node_ids(&self) -> BTreeSet<u64> {
+        self.nodes
+            .keys()
+            .chain(self.outgoing.keys())
+            .chain(self.incoming.keys())
+            .copied()
+            .collect()
+    }
+
+    fn adjacent(&self, index: &HashMap<u64, Vec<u64>>, id: u64) -> Vec<&RelationValue> {
+        index
+            .get(&id)
+            .map(|ids| ids.iter().filter_map(|id| self.relations.get(id)).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum FetchStep {
+    Nodes(u64),
+    // The index of the first source node of the window in node_ids and the
+    // smallest relation id of the next page
+    Relations(usize, u64),
+    Done,
+}
+
+// Fetches all nodes and then all relations of a graph page by page ordered
+// by id. Pages may be truncated by the RESULTSET_SIZE of the server, so only
+// an empty page ends the nodes or the relations of a window.
+//
+// Relations are read per window of page_size source nodes, so a page only
+// scans and sorts the outgoing relations of the window instead of every
+// relation of the graph. Each window costs one additional empty page.
+pub(crate) struct SubgraphFetch {
+    page_size: usize,
+    step: FetchStep,
+    node_ids: Vec<u64>,
+    graph: Subgraph,
+}
+
+impl SubgraphFetch {
+    pub(crate) fn new(page_size: usize) -> Self {
+        SubgraphFetch {
+            page_size: page_size.max(1),
+            step: FetchStep::Nodes(0),
+            node_ids: vec![],
+            graph: Subgraph::new(),
+        }
+    }
+
+    // The next query to run, None once the graph is fetched
+    pub(crate) fn next_query(&self) -> Option<String> {
+        match self.step {
+            FetchStep::Nodes(from) => Some(query_with_id(
+                &format!(
+                    "MATCH (n) WHERE id(n) >= $from RETURN n ORDER BY id(n) LIMIT {}",
+                    self.page_size
+                ),
+                "from",
+                from,
+            )),
+            FetchStep::Relations(window, from) => {
+                let end = (window + self.page_size).min(self.node_ids.len());
+                Some(format!(
+                    "CYPHER first={} last={} from={} MATCH (a)-[n]->() \
+                     WHERE id(a) >= $first AND id(a) <= $last AND id(n) >= $from \
+                     RETURN n ORDER BY id(n) LIMIT {}",
+                    self.node_ids[window],
+                    self.node_ids[end - 1],
+                    from,
+                    self.page_size
+                ))
+            }
+            FetchStep::Done => None,
+        }
+    }
+
+    // Applies the response to the query returned by next_query
+    pub(crate) fn add_response(&mut self, v: &Value) -> RedisResult<()> {
+        let res = GraphResultSet::from_redis_value(v)?;
+        let mut last = None;
+        for value in res.data.iter().flat_map(|row| row.values()) {
+            match value {
+                GraphValue::Node(node) => {
+                    last = Some(node.id);
+                    self.graph.add_node(node.clone());
+                }
+                GraphValue::Relation(relation) => {
+                    last = Some(relation.id);
+                    self.graph.add_relation(relation.clone());
+                }
+                GraphValue::Scalar(_) => {}
+            }
+        }
+        self.step = match (self.step, last) {
+            (FetchStep::Nodes(_), Some(id)) => FetchStep::Nodes(id + 1),
+            (FetchStep::Nodes(_), None) => {
+                self.node_ids = self.graph.nodes.keys().copied().collect();
+                self.window_step(0)
+            }
+            (FetchStep::Relations(window, _), Some(id)) => FetchStep::Relations(window, id + 1),
+            (FetchStep::Relations(window, _), None) => self.window_step(window + self.page_size),
+            (FetchStep::Done, _) => return Err(create_error("Subgraph is already fetched")),
+        };
+        Ok(())
+    }
+
+    // Starts the relations of the window beginning at the given node index
+    fn window_step(&self, window: usize) -> FetchStep {
+        if window < self.node_ids.len() {
+            FetchStep::Relations(window, 0)
+        } else {
+            FetchStep::Done
+        }
+    }
+
+    pub(crate) fn finish(self) -> Subgraph {
+        self.graph
+    }
+}
+
+impl From<&GraphResultSet> for Subgraph {
+    fn from(res: &GraphResultSet) -> Self {
+        Subgraph::from_result_set(res)
+    }
+}
+
+// Removes duplicate ids keeping the first occurrence
+fn dedup<I: IntoIterator<Item = u64>>(ids: I) -> Vec<u64> {
+    let mut seen = HashSet::new();
+    ids.into_iter().filter(|id| seen.insert(*id)).collect()
+}
@@ -1,5 +1,9 @@
 use redis::{from_redis_value, FromRedisValue, RedisError, RedisResult, Value};
 use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::row::FromGraphRow;
+use crate::subgraph::Subgraph;
 
 /// Contains the result of a Redis graph operation. All types of graph
 /// operations will return a result in this format. Some (for example
@@ -16,10 +20,81 @@ pub struct GraphResultSet {
     pub metadata: Vec<String>,
 }
 
+/// Statistics of a write query as reported in the result set metadata.
+/// Statistics of multiple queries can be summed up with add.
+#[derive(Default, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct QueryStatistics {
+    pub labels_added: u64,
+    pub labels_removed: u64,
+    pub nodes_created: u64,
+    pub nodes_deleted: u64,
+    pub relationships_created: u64,
+    pub relationships_deleted: u64,
+    pub properties_set: u64,
+    pub properties_removed: u64,
+    pub indices_created: u64,
+    pub indices_deleted: u64,
+    /// Query internal execution time in milliseconds.
+    pub execution_time: f64,
+}
+
+impl QueryStatistics {
+    /// Parses metadata lines like `Nodes created: 2`. Unknown lines are
+    /// ignored.
+    pub fn from_metadata<S: AsRef<str>>(metadata: &[S]) -> Self {
+        let mut stats = QueryStatistics::default();
+        for line in metadata {
+            let (name, value) = match line.as_ref().split_once(':') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => continue,
+            };
+            let count = value.parse().unwrap_or(0);
+            match name {
+                "Labels added" => stats.labels_added = count,
+                "Labels removed" => stats.labels_removed = count,
+                "Nodes created" => stats.nodes_created = count,
+                "Nodes deleted" => stats.nodes_deleted = count,
+                "Relationships created" => stats.relationships_created = count,
+                "Relationships deleted" => stats.relationships_deleted = count,
+                "Properties set" => stats.properties_set = count,
+                "Properties removed" => stats.properties_removed = count,
+                "Indices created" => stats.indices_created = count,
+                "Indices deleted" => stats.indices_deleted = count,
+                "Query internal execution time" => {
+                    stats.execution_time = value
+                        .trim_end_matches("milliseconds")
+                        .trim()
+                        .parse()
+                        .unwrap_or(0.0)
+                }
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    /// Adds the statistics of another query.
+    pub fn add(&mut self, other: &QueryStatistics) {
+        self.labels_added += other.labels_added;
+        self.labels_removed += other.labels_removed;
+        self.nodes_created += other.nodes_created;
+        self.nodes_deleted += other.nodes_deleted;
+        self.relationships_created += other.relationships_created;
+        self.relationships_deleted += other.relationships_deleted;
+        self.properties_set += other.properties_set;
+        self.properties_removed += other.properties_removed;
+        self.indices_created += other.indices_created;
+        self.indices_deleted += other.indices_deleted;
+        self.execution_time += other.execution_time;
+    }
+}
+
 /// A graph query can return one or multiple values for every matching entry.
-/// A GraphResult contains a map of values for a single match. The map keys
-/// are the query RETURN statements, values can be of any GraphValue type in
-/// any order.
+/// A GraphResult contains the values for a single match in the column order
+/// of the header, which is shared by all rows of a result set. Values can be
+/// of any GraphValue type and are accessed by their RETURN key or by column
+/// position, which allows access to columns with duplicate names.
 /// The impl also contains some helper methods for easier extraction of graph
 /// values.
 ///
@@ -30,13 +105,14 @@ pub struct GraphResultSet {
 ///let name:Option<String> = res.get_scalar("person2.name");
 ///let person = res.get_node("person");
 ///let friend_rel = res.get_relation("friend");
+///let first = res.get_at(0);
 ///
 /// ```
 ///
-#[derive(Default, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct GraphResult {
-    /// A map of raw return keys to GraphValues.
-    pub data: HashMap<String, GraphValue>,
+    header: Arc<[String]>,
+    values: Vec<GraphValue>,
 }
 
 /// Redis graph values can be one of 3 different types. Scalars are single
@@ -70,6 +146,15 @@ pub struct RelationValue {
     pub properties: HashMap<String, Value>,
 }
 
+/// Represents a graph path as returned by path functions and procedures. A
+/// path alternates between nodes and relations, so it contains one node more
+/// than relations.
+#[derive(Default, Clone, Debug)]
+pub struct PathValue {
+    pub nodes: Vec<NodeValue>,
+    pub relations: Vec<RelationValue>,
+}
+
 /// Represents an entry returned from the GRAPH.SLOWLOG command.
 #[derive(Default, Clone, Debug)]
 pub struct SlowLogEntry {
@@ -110,14 +195,151 @@ impl GraphResultSet {
             metadata,
         }
     }
+
+    /// Validates that every row contains exactly one value per header column.
+    /// Returns the result set unchanged or an error for the first row whose
+    /// length differs from the header.
+    pub fn strict(self) -> RedisResult<Self> {
+        for (idx, row) in self.data.iter().enumerate() {
+            if row.len() != self.header.len() {
+                return Err(create_error(&format!(
+                    "Row {} has {} values for {} header columns",
+                    idx,
+                    row.len(),
+                    self.header.len()
+                )));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Builds an in-memory Subgraph of all distinct nodes and relations
+    /// contained in the result set.
+    pub fn to_subgraph(&self) -> Subgraph {
+        Subgraph::from_result_set(self)
+    }
+
+    /// Parses the query statistics (created nodes, set properties, execution
+    /// time, ...) from the metadata.
+    pub fn statistics(&self) -> QueryStatistics {
+        QueryStatistics::from_metadata(&self.metadata)
+    }
+
+    /// Converts all rows into T by column position, eg. into a tuple
+    /// `(NodeValue, String, i64)`. Fails if a row can not be converted.
+    pub fn rows_as<T: FromGraphRow>(&self) -> RedisResult<Vec<T>> {
+        self.data
+            .iter()
+            .map(|row| T::from_graph_row(row.values()))
+            .collect()
+    }
+}
+
+impl Default for GraphResult {
+    fn default() -> Self {
+        GraphResult {
+            header: Arc::from(Vec::default()),
+            values: Vec::default(),
+        }
+    }
 }
 
 /// Represents a group of returned graph values for a single matched result in
 /// the query. Contains some helper methods for easier extraction of graph values.
 impl GraphResult {
-    /// Returns a single GraphValue by it's key.
+    /// Creates a result from a shared header and the row values in column order.
+    pub fn from_values(header: Arc<[String]>, values: Vec<GraphValue>) -> Self {
+        GraphResult { header, values }
+    }
+
+    /// Builds a map of RETURN keys to values. For duplicate keys only the last
+    /// column is contained.
+    #[deprecated(note = "the map is built on every call, use get_value, get_at or iter")]
+    pub fn data(&self) -> HashMap<String, GraphValue> {
+        self.iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    /// The RETURN keys of the query shared by all rows of a result set.
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    /// All values of the row in column order.
+    pub fn values(&self) -> &[GraphValue] {
+        &self.values
+    }
+
+    /// Consumes the row returning its values in column order.
+    pub fn into_values(self) -> Vec<GraphValue> {
+        self.values
+    }
+
+    /// Returns the number of values in the row.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if the row does not contain any values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Iterates over all RETURN keys and values in column order. Values without
+    /// a header column are not contained.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &GraphValue)> {
+        self.header
+            .iter()
+            .map(|name| name.as_str())
+            .zip(self.values.iter())
+    }
+
+    /// Returns all values for a RETURN key, which can be multiple in case the
+    /// query returns duplicate column names.
+    pub fn get_all(&self, key: &str) -> Vec<&GraphValue> {
+        self.iter()
+            .filter(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Returns a single GraphValue by it's column position.
+    pub fn get_at(&self, idx: usize) -> Option<&GraphValue> {
+        self.values.get(idx)
+    }
+
+    /// Tries to extract a graph Scalar value at column position into target type T.
+    pub fn get_scalar_at<T: FromRedisValue>(&self, idx: usize) -> Option<T> {
+        match self.get_at(idx) {
+            Some(GraphValue::Scalar(value)) => from_redis_value(value).unwrap_or(None),
+            _ => None,
+        }
+    }
+
+    /// Tries to extract a graph Node value at column position.
+    pub fn get_node_at(&self, idx: usize) -> Option<&NodeValue> {
+        match self.get_at(idx) {
+            Some(GraphValue::Node(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Tries to extract a graph Relation value at column position.
+    pub fn get_relation_at(&self, idx: usize) -> Option<&RelationValue> {
+        match self.get_at(idx) {
+            Some(GraphValue::Relation(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns a single GraphValue by it's key. For duplicate keys the value
+    /// of the last column is returned.
     pub fn get_value(&self, key: &str) -> Option<&GraphValue> {
-        self.data.get(key)
+        self.iter()
+            .filter(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+            .last()
     }
 
     /// Tries to extract a graph Scalar value into target type T. Will return
@@ -207,18 +429,13 @@ impl FromRedisValue for GraphResultSet {
                     _ => Vec::default(),
                 };
 
+                let shared: Arc<[String]> = header.clone().into();
                 let data: Vec<GraphResult> = match values.get(1) {
                     Some(Value::Bulk(v)) => v
                         .iter()
                         .map(|bulk| {
                             let items: Vec<GraphValue> = from_redis_value(bulk)?;
-                            let mut data: HashMap<String, GraphValue> = HashMap::new();
-                            for (idx, name) in header.iter().enumerate() {
-                                if let Some(value) = items.get(idx) {
-                                    data.insert(name.to_string(), value.to_owned());
-                                }
-                            }
-                            Ok(GraphResult { data })
+                            Ok(GraphResult::from_values(shared.clone(), items))
                         })
                         .collect::<RedisResult<Vec<GraphResult>>>()?,
                     _ => Vec::default(),
@@ -317,6 +534,36 @@ impl FromRedisValue for RelationValue {
     }
 }
 
+impl PathValue {
+    /// The number of relations in the path.
+    pub fn len(&self) -> usize {
+        self.relations.len()
+    }
+
+    /// Returns true if the path does not contain any relation.
+    pub fn is_empty(&self) -> bool {
+        self.relations.is_empty()
+    }
+}
+
+impl FromRedisValue for PathValue {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let items: Vec<Value> = from_redis_value(v)?;
+        let mut path = PathValue::default();
+        for item in items.iter() {
+            let values = to_property_map(item)?;
+            if values.contains_key("labels") {
+                path.nodes.push(from_redis_value(item)?);
+            } else if values.contains_key("type") {
+                path.relations.push(from_redis_value(item)?);
+            } else {
+                return Err(create_error("Could not parse graph path"));
+            }
+        }
+        Ok(path)
+    }
+}
+
 impl FromRedisValue for SlowLogEntry {
     fn from_redis_value(v: &Value) -> RedisResult<Self> {
         match v {
@@ -0,0 +1,72 @@
+// Shared helpers of the tests answering commands with mock connections.
+// Included by the test files with `#[macro_use] mod common;`.
+
+/// Splits a packed command into its binary arguments. Bulk strings are read
+/// by their `$len` length, so empty arguments and arguments containing CRLF
+/// are kept as they are.
+#[allow(dead_code)]
+pub fn unpack(cmd: &[u8]) -> Vec<Vec<u8>> {
+    let line = |pos: usize, prefix: u8| {
+        let end = pos + cmd[pos..].windows(2).position(|w| w == b"\r\n").unwrap();
+        assert_eq!(cmd[pos], prefix, "malformed packed command");
+        (
+            String::from_utf8_lossy(&cmd[pos + 1..end])
+                .parse::<usize>()
+                .unwrap(),
+            end + 2,
+        )
+    };
+    let (count, mut pos) = line(0, b'*');
+    let mut args = vec![];
+    for _ in 0..count {
+        let (len, start) = line(pos, b'$');
+        assert_eq!(&cmd[start + len..start + len + 2], b"\r\n");
+        args.push(cmd[start..start + len].to_vec());
+        pos = start + len + 2;
+    }
+    args
+}
+
+/// Splits a packed command into its arguments as strings.
+#[allow(dead_code)]
+pub fn arguments(cmd: &[u8]) -> Vec<String> {
+    unpack(cmd)
+        .iter()
+        .map(|arg| String::from_utf8_lossy(arg).to_string())
+        .collect()
+}
+
+/// Implements `redis::ConnectionLike` for a mock connection answering every
+/// command with its inherent method
+/// `fn reply(&mut self, args: Vec<String>) -> RedisResult<Value>`.
+#[allow(unused_macros)]
+macro_rules! mock_connection {
+    ($name:ty) => {
+        impl redis::ConnectionLike for $name {
+            fn req_packed_command(&mut self, cmd: &[u8]) -> redis::RedisResult<redis::Value> {
+                self.reply(common::arguments(cmd))
+            }
+
+            fn req_packed_commands(
+                &mut self,
+                _: &[u8],
+                _: usize,
+                _: usize,
+            ) -> redis::RedisResult<Vec<redis::Value>> {
+                Ok(vec![])
+            }
+
+            fn get_db(&self) -> i64 {
+                0
+            }
+
+            fn check_connection(&mut self) -> bool {
+                true
+            }
+
+            fn is_open(&self) -> bool {
+                true
+            }
+        }
+    };
+}
@@ -90,6 +90,151 @@ pub async fn issue_query_all_nodes(name: &str) -> GraphResultSet {
         .unwrap()
 }
 
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_query_stream(name: &str) -> GraphResultStream {
+    ensure_test_data(name).await;
+    get_con()
+        .await
+        .graph_ro_query_stream(name, "MATCH (r:Rider) RETURN r")
+        .await
+        .unwrap()
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_query_as(name: &str) -> Vec<(NodeValue, String, Option<i64>)> {
+    ensure_test_data(name).await;
+    get_con()
+        .await
+        .graph_ro_query_as(name, "MATCH (r:Rider) RETURN r, r.name, r.born")
+        .await
+        .unwrap()
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_fulltext_query(name: &str) -> Vec<(NodeValue, f64)> {
+    ensure_test_data(name).await;
+    let mut con = get_con().await;
+    let _: GraphResultSet = con
+        .graph_query(
+            name,
+            "CALL db.idx.fulltext.createNodeIndex('Rider', 'name')",
+        )
+        .await
+        .unwrap();
+    con.graph_fulltext_query(name, "Rider", FulltextQuery::new().prefix("ros"))
+        .await
+        .unwrap()
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_call_labels(name: &str) -> Vec<ProcedureRow> {
+    ensure_test_data(name).await;
+    let mut con = get_con().await;
+    con.graph_call(name, "db.labels", &[], &["label"])
+        .await
+        .unwrap()
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_subgraph(name: &str) -> Subgraph {
+    ensure_test_data(name).await;
+    let mut con = get_con().await;
+    con.graph_subgraph(name).await.unwrap()
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_dump_restore(name: &str) -> (DumpResult, RestoreProgress, Subgraph) {
+    ensure_test_data(name).await;
+    let mut con = get_con().await;
+    let copy = format!("{}_copy", name);
+    let _: () = con.del(&copy).await.unwrap();
+    let mut script = Vec::new();
+    let dump = con
+        .graph_dump(name, &GraphDump::new(DumpFormat::Cypher), &mut script)
+        .await
+        .unwrap();
+    let restore = GraphRestore::new(DumpFormat::Cypher);
+    let progress = con
+        .graph_restore(&copy, &restore, script.as_slice(), |_| {})
+        .await
+        .unwrap();
+    (dump, progress, con.graph_subgraph(&copy).await.unwrap())
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_graph_copy(name: &str) -> (CopyResult, Subgraph) {
+    ensure_test_data(name).await;
+    let mut source = get_con().await;
+    let mut target = get_con().await;
+    let copy = format!("{}_copy", name);
+    let _: () = target.del(&copy).await.unwrap();
+    let result = GraphCopy::new()
+        .copy_async(&mut source, name, &mut target, &copy, |_| {})
+        .await
+        .unwrap();
+    (result, target.graph_subgraph(&copy).await.unwrap())
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_schema(name: &str) -> GraphSchema {
+    ensure_test_data(name).await;
+    let mut con = get_con().await;
+    con.graph_schema(name, 100).await.unwrap()
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_schema_diff(name: &str, expected: &GraphSchema) -> SchemaDiff {
+    ensure_test_data(name).await;
+    let mut con = get_con().await;
+    con.graph_schema_diff(name, expected, 100).await.unwrap()
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_migrate(
+    name: &str,
+    migrator: &Migrator,
+) -> (Vec<AppliedMigration>, Vec<AppliedMigration>) {
+    ensure_test_data(name).await;
+    let mut con = get_con().await;
+    con.graph_migrate(name, migrator, false).await.unwrap();
+    let migrated = con.graph_migrations_applied(name, migrator).await.unwrap();
+    con.graph_rollback(name, migrator, 0, false).await.unwrap();
+    let rolled_back = con.graph_migrations_applied(name, migrator).await.unwrap();
+    (migrated, rolled_back)
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_bulk_write(name: &str, rows: Vec<ParamValue>) -> BulkWriteResult {
+    ensure_test_data(name).await;
+    let bulk = BulkWrite::create_nodes("Circuit").with_batch_rows(2);
+    get_con()
+        .await
+        .graph_bulk_write(name, &bulk, rows)
+        .await
+        .unwrap()
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_bulk_load(
+    name: &str,
+    nodes: Vec<BulkNode>,
+    relations: Vec<BulkRelation>,
+) -> BulkLoadResult {
+    let mut con = get_con().await;
+    let _: () = con.del(name).await.unwrap();
+    con.graph_bulk_load(name, &BulkLoader::new(), nodes, relations)
+        .await
+        .unwrap()
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_paginate(name: &str) -> Vec<RedisResult<GraphResult>> {
+    use futures::StreamExt;
+    ensure_test_data(name).await;
+    let pagination = Pagination::new(name, "MATCH (r:Rider) RETURN r ORDER BY r.name", 2);
+    get_con().await.graph_paginate(pagination).collect().await
+}
+
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
 pub async fn issue_query_option(name: &str) -> GraphResultSet {
     ensure_test_data(name).await;
@@ -164,6 +309,12 @@ pub async fn issue_graph_explain(name: &str) -> RedisResult<Vec<String>> {
         .await
 }
 
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+pub async fn issue_graph_list(name: &str) -> RedisResult<Vec<String>> {
+    ensure_test_data(name).await;
+    get_con().await.graph_list().await
+}
+
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
 fn get_redis_url() -> String {
     let redis_host_key = "REDIS_HOST";
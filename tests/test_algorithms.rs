@@ -0,0 +1,145 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::Value;
+use redis_graph::*;
+use std::collections::HashMap;
+
+fn road(id: u64, src_node: u64, dest_node: u64, distance: Value) -> RelationValue {
+    let mut properties = HashMap::new();
+    properties.insert("distance".to_string(), distance);
+    RelationValue {
+        id,
+        rel_type: "road".to_string(),
+        src_node,
+        dest_node,
+        properties,
+    }
+}
+
+fn float(v: &str) -> Value {
+    Value::Data(v.as_bytes().to_vec())
+}
+
+// 1 -> 2 -> 4, 1 -> 3 -> 4 with the route over 3 being shorter, 5 -> 6
+// being disconnected and 7 a node without relations
+fn roads() -> Subgraph {
+    let mut graph = Subgraph::new();
+    for id in 1..=7 {
+        graph.add_node(NodeValue {
+            id,
+            ..NodeValue::default()
+        });
+    }
+    graph.add_relation(road(10, 1, 2, Value::Int(4)));
+    graph.add_relation(road(11, 2, 4, Value::Int(4)));
+    graph.add_relation(road(12, 1, 3, float("1.5")));
+    graph.add_relation(road(13, 3, 4, float("2.5")));
+    graph.add_relation(road(14, 5, 6, Value::Int(1)));
+    graph
+}
+
+#[test]
+fn test_traversal() {
+    let graph = roads();
+    assert_eq!(bfs(&graph, 1, Direction::Outgoing), [1, 2, 3, 4]);
+    assert_eq!(dfs(&graph, 1, Direction::Outgoing), [1, 2, 4, 3]);
+    assert_eq!(bfs(&graph, 4, Direction::Incoming), [4, 2, 3, 1]);
+    assert_eq!(bfs(&graph, 4, Direction::Outgoing), [4]);
+    assert_eq!(dfs(&graph, 6, Direction::Both), [6, 5]);
+}
+
+#[test]
+fn test_shortest_path() {
+    let graph = roads();
+    let path = dijkstra(&graph, 1, 4, "distance", Direction::Outgoing)
+        .unwrap()
+        .unwrap();
+    assert_eq!(path.nodes, [1, 3, 4]);
+    assert_eq!(path.relations, [12, 13]);
+    assert_eq!(path.cost, 4.0);
+
+    let back = dijkstra(&graph, 4, 1, "distance", Direction::Both)
+        .unwrap()
+        .unwrap();
+    assert_eq!(back.nodes, [4, 3, 1]);
+    assert!(dijkstra(&graph, 4, 1, "distance", Direction::Outgoing)
+        .unwrap()
+        .is_none());
+    assert!(dijkstra(&graph, 1, 4, "time", Direction::Outgoing).is_err());
+
+    let path = astar(&graph, 1, 4, "distance", Direction::Outgoing, |id| {
+        if id == 4 {
+            0.0
+        } else {
+            1.0
+        }
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(path.cost, 4.0);
+}
+
+#[test]
+fn test_invalid_weight() {
+    let mut graph = roads();
+    graph.add_relation(road(15, 6, 7, float("far")));
+    graph.add_relation(road(16, 7, 5, Value::Int(-1)));
+    assert!(dijkstra(&graph, 6, 7, "distance", Direction::Outgoing).is_err());
+    assert!(dijkstra(&graph, 7, 5, "distance", Direction::Outgoing)
+        .unwrap_err()
+        .to_string()
+        .contains("negative weight"));
+    graph.add_relation(road(17, 5, 8, float("NaN")));
+    assert!(dijkstra(&graph, 5, 8, "distance", Direction::Outgoing)
+        .unwrap_err()
+        .to_string()
+        .contains("has weight distance NaN"));
+}
+
+#[test]
+fn test_astar_inconsistent_heuristic() {
+    // 1 -> 2 -> 4 -> 5 is the cheapest path, but the heuristic overrates 2
+    // so 4 is first reached over 3
+    let mut graph = Subgraph::new();
+    graph.add_relation(road(1, 1, 2, Value::Int(1)));
+    graph.add_relation(road(2, 2, 4, Value::Int(1)));
+    graph.add_relation(road(3, 1, 3, Value::Int(1)));
+    graph.add_relation(road(4, 3, 4, Value::Int(3)));
+    graph.add_relation(road(5, 4, 5, Value::Int(3)));
+    let path = astar(&graph, 1, 5, "distance", Direction::Outgoing, |id| {
+        if id == 2 {
+            4.0
+        } else {
+            0.0
+        }
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(path.nodes, [1, 2, 4, 5]);
+    assert_eq!(path.cost, 5.0);
+}
+
+#[test]
+fn test_connected_components() {
+    let mut graph = roads();
+    graph.add_relation(road(15, 8, 6, Value::Int(1)));
+    assert_eq!(
+        connected_components(&graph),
+        vec![vec![1, 2, 3, 4], vec![5, 6, 8], vec![7]]
+    );
+}
+
+#[test]
+fn test_cycles() {
+    let mut graph = roads();
+    assert!(!is_cyclic(&graph));
+    graph.add_relation(road(15, 4, 2, Value::Int(1)));
+    assert_eq!(find_cycle(&graph), Some(vec![2, 4]));
+    graph.add_relation(road(16, 7, 7, Value::Int(1)));
+    assert!(is_cyclic(&graph));
+
+    let mut self_loop = Subgraph::new();
+    self_loop.add_relation(road(1, 9, 9, Value::Int(1)));
+    assert_eq!(find_cycle(&self_loop), Some(vec![9]));
+}
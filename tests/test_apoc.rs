@@ -0,0 +1,202 @@
+#![cfg(feature = "serde")]
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{RedisResult, Value};
+use redis_graph::*;
+
+#[macro_use]
+mod common;
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn strings(values: &[&str]) -> Value {
+    Value::Bulk(values.iter().map(|v| data(v)).collect())
+}
+
+// Answers the batch queries, created nodes get the Neo4j id plus 100.
+// Optionally the id of the last node of a batch is missing.
+fn answer(query: &str, missing_id: bool) -> Value {
+    if query.contains("CREATE (n") {
+        let mut rows: Vec<Value> = query
+            .split("{id: '")
+            .skip(1)
+            .map(|row| {
+                let id: i64 = row.split('\'').next().unwrap().parse().unwrap();
+                Value::Bulk(vec![data(&id.to_string()), Value::Int(id + 100)])
+            })
+            .collect();
+        if missing_id {
+            rows.pop();
+        }
+        return Value::Bulk(vec![
+            strings(&["row.id", "id(n)"]),
+            Value::Bulk(rows),
+            strings(&["Nodes created: 1"]),
+        ]);
+    }
+    Value::Bulk(vec![strings(&["Relationships created: 1"])])
+}
+
+struct ApocConnection {
+    queries: Vec<String>,
+    missing_id: bool,
+}
+
+impl ApocConnection {
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        assert_eq!(args[..2], ["GRAPH.QUERY", "my_graph"]);
+        let value = answer(&args[2], self.missing_id);
+        self.queries.push(args[2].clone());
+        Ok(value)
+    }
+}
+
+mock_connection!(ApocConnection);
+
+fn import(import: &ApocImport, input: &str) -> (RedisResult<ApocReport>, Vec<String>) {
+    let mut con = ApocConnection {
+        queries: vec![],
+        missing_id: false,
+    };
+    let report = con.graph_import_apoc("my_graph", import, input.as_bytes());
+    (report, con.queries)
+}
+
+const EXPORT: &str = r#"{"type":"node","id":"0","labels":["User"],"properties":{"born":"2015-07-04T19:32:24","name":"Adam","place":{"crs":"wgs-84","latitude":13.1,"longitude":33.46789,"height":null},"age":42,"male":true,"kids":["Sam","Anna","Grace"]}}
+{"type":"node","id":"1","labels":["User"],"properties":{"name":"Jim","age":42,"home":{"crs":"cartesian","x":1.0,"y":2.0,"z":null},"tags":{"a":1}}}
+
+{"type":"node","id":2,"labels":["User","Admin"],"properties":{"name":"Eve","born":"1999-12-31"}}
+{"id":"0","type":"relationship","label":"KNOWS","properties":{"since":1993,"bffSince":"P5M1DT12H"},"start":{"id":"0","labels":["User"]},"end":{"id":"1","labels":["User"]}}
+{"id":"1","type":"relationship","label":"KNOWS","start":{"id":"1","labels":["User"]},"end":{"id":"7","labels":["User"]}}
+{"id":"2","type":"relationship","label":"MANAGES","properties":{"scores":[1,null]},"start":{"id":"2","labels":["User","Admin"]},"end":{"id":"0","labels":["User"]}}
+"#;
+
+#[test]
+fn test_import_apoc() {
+    let apoc = ApocImport::new()
+        .with_batch_rows(2)
+        .with_timestamp_properties(&["born"]);
+    let (report, queries) = import(&apoc, EXPORT);
+    let report = report.unwrap();
+    assert_eq!(
+        queries,
+        vec![
+            "UNWIND [{id: '0', properties: {age: 42, born: 1436038344000, kids: ['Sam', 'Anna', 'Grace'], \
+             male: true, name: 'Adam', place: point({latitude: 13.1, longitude: 33.46789})}}, \
+             {id: '1', properties: {age: 42, name: 'Jim'}}] AS row \
+             CREATE (n:User) SET n = row.properties RETURN row.id, id(n)",
+            "UNWIND [{id: '2', properties: {born: 946598400000, name: 'Eve'}}] AS row \
+             CREATE (n:User:Admin) SET n = row.properties RETURN row.id, id(n)",
+            "UNWIND [{src: 100, dest: 101, properties: {bffSince: 'P5M1DT12H', since: 1993}}] AS row \
+             MATCH (a) WHERE id(a) = row.src MATCH (b) WHERE id(b) = row.dest \
+             CREATE (a)-[r:KNOWS]->(b) SET r = row.properties",
+            "UNWIND [{src: 102, dest: 100, properties: {}}] AS row \
+             MATCH (a) WHERE id(a) = row.src MATCH (b) WHERE id(b) = row.dest \
+             CREATE (a)-[r:MANAGES]->(b) SET r = row.properties",
+        ]
+    );
+    assert_eq!(report.nodes, 3);
+    assert_eq!(report.relations, 2);
+    assert_eq!(report.statistics.nodes_created, 2);
+    assert_eq!(report.statistics.relationships_created, 2);
+    assert_eq!(report.node_ids.len(), 3);
+    assert_eq!(report.node_ids["2"], 102);
+
+    let skipped: Vec<(usize, &str, Option<&str>, &str)> = report
+        .skipped
+        .iter()
+        .map(|s| {
+            (
+                s.line,
+                s.id.as_str(),
+                s.property.as_deref(),
+                s.reason.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        skipped,
+        vec![
+            (2, "1", Some("home"), "cartesian points are not supported"),
+            (2, "1", Some("tags"), "Maps are not supported"),
+            (6, "1", None, "The end node 7 was not imported"),
+            (
+                7,
+                "2",
+                Some("scores"),
+                "Lists with nulls, maps or points are not supported"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_import_apoc_timestamps() {
+    let input = [
+        "2015-07-04T19:32:24.5+01:00[Europe/London]",
+        "1969-12-31T23:00-05:30",
+        "1600-02-29",
+        "2015-07-04T19:32:24Z",
+        "P5M1DT12H",
+    ]
+    .iter()
+    .enumerate()
+    .map(|(id, t)| {
+        format!(
+            r#"{{"type":"node","id":"{}","labels":["Event"],"properties":{{"at":"{}"}}}}"#,
+            id, t
+        )
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+    let apoc = ApocImport::new().with_timestamp_properties(&["at"]);
+    let (report, queries) = import(&apoc, &input);
+    assert_eq!(
+        queries,
+        vec![
+            "UNWIND [{id: '0', properties: {at: 1436034744500}}, {id: '1', properties: {at: 16200000}}, \
+             {id: '2', properties: {at: -11670998400000}}, {id: '3', properties: {at: 1436038344000}}, \
+             {id: '4', properties: {}}] AS row \
+             CREATE (n:Event) SET n = row.properties RETURN row.id, id(n)"
+        ]
+    );
+    let report = report.unwrap();
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(
+        report.skipped[0].reason,
+        "'P5M1DT12H' is no ISO 8601 date or datetime"
+    );
+
+    // Without timestamp properties temporal values stay strings
+    let (_, queries) = import(&ApocImport::new(), &input);
+    assert!(queries[0].contains("{at: '1600-02-29'}"));
+}
+
+#[test]
+fn test_import_apoc_invalid() {
+    let (report, queries) = import(
+        &ApocImport::new(),
+        "{\"type\":\"node\",\"id\":\"0\"}\n{\"type\":\"path\",\"id\":\"1\"}\n",
+    );
+    let err = report.unwrap_err();
+    assert!(err.to_string().contains("Line 2: unknown variant `path`"));
+    assert!(queries.is_empty());
+}
+
+#[test]
+fn test_import_apoc_missing_node_ids() {
+    let mut con = ApocConnection {
+        queries: vec![],
+        missing_id: true,
+    };
+    let err = con
+        .graph_import_apoc("my_graph", &ApocImport::new(), EXPORT.as_bytes())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Expected 2 node ids of an import batch, got 1"));
+    assert_eq!(con.queries.len(), 1);
+}
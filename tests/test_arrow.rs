@@ -0,0 +1,345 @@
+#![cfg(feature = "arrow")]
+extern crate redis;
+extern crate redis_graph;
+
+use arrow::array::{
+    Array, BinaryArray, BooleanArray, Float64Array, Int64Array, ListArray, MapArray, StringArray,
+    StructArray, UInt64Array,
+};
+use arrow::datatypes::DataType;
+use redis::{from_redis_value, Value};
+use redis_graph::*;
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn pair(key: &str, value: Value) -> Value {
+    Value::Bulk(vec![data(key), value])
+}
+
+fn node(id: i64, name: &str) -> Value {
+    Value::Bulk(vec![
+        pair("id", Value::Int(id)),
+        pair("labels", Value::Bulk(vec![data("Rider")])),
+        pair(
+            "properties",
+            Value::Bulk(vec![
+                pair("name", data(name)),
+                pair("born", Value::Int(1978 + id)),
+            ]),
+        ),
+    ])
+}
+
+fn relation(id: i64) -> Value {
+    Value::Bulk(vec![
+        pair("id", Value::Int(id)),
+        pair("type", data("rides")),
+        pair("src_node", Value::Int(id)),
+        pair("dest_node", Value::Int(10)),
+        pair("properties", Value::Bulk(vec![])),
+    ])
+}
+
+// A response with columns of every inferred type over two rows
+fn response() -> Value {
+    let header = [
+        "int", "float", "bool", "string", "binary", "list", "mixed", "number", "null", "r", "rel",
+    ];
+    Value::Bulk(vec![
+        Value::Bulk(header.iter().map(|h| data(h)).collect()),
+        Value::Bulk(vec![
+            Value::Bulk(vec![
+                Value::Int(1),
+                data("1.5"),
+                data("true"),
+                data("Yamaha"),
+                Value::Data(vec![0, 159]),
+                Value::Nil,
+                Value::Int(1),
+                Value::Int(2),
+                Value::Nil,
+                node(1, "Valentino Rossi"),
+                relation(1),
+            ]),
+            Value::Bulk(vec![
+                Value::Nil,
+                data("2"),
+                data("false"),
+                Value::Nil,
+                Value::Nil,
+                Value::Nil,
+                node(2, "Dani Pedrosa"),
+                data("2.5"),
+                Value::Nil,
+                Value::Nil,
+                relation(2),
+            ]),
+        ]),
+        Value::Bulk(vec![]),
+    ])
+}
+
+// Arrays in result rows are parsed as relations, so the list cells are set
+// on the parsed result set
+fn result_set() -> GraphResultSet {
+    let mut res: GraphResultSet = from_redis_value(&response()).unwrap();
+    let lists = vec![vec![Value::Int(1), Value::Int(2)], vec![data("a")]];
+    for (row, list) in res.data.iter_mut().zip(lists) {
+        let mut values = row.values().to_vec();
+        values[5] = GraphValue::Scalar(Value::Bulk(list));
+        *row = GraphResult::from_values(res.header.clone().into(), values);
+    }
+    res
+}
+
+#[test]
+fn test_record_batch_types() {
+    let res = result_set();
+    let batches = ArrowExport::new()
+        .with_column_type("float", DataType::Float64)
+        .with_column_type("bool", DataType::Boolean)
+        .to_record_batches(&res)
+        .unwrap();
+    assert_eq!(batches.len(), 1);
+    let batch = &batches[0];
+    assert_eq!(batch.num_rows(), 2);
+    let column = |name: &str| batch.column_by_name(name).unwrap();
+
+    let int = column("int").as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(int.value(0), 1);
+    assert!(int.is_null(1));
+    let float = column("float")
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(float.values().to_vec(), vec![1.5, 2.0]);
+    let boolean = column("bool")
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .unwrap();
+    assert!(boolean.value(0) && !boolean.value(1));
+    let string = column("string")
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(string.value(0), "Yamaha");
+    let binary = column("binary")
+        .as_any()
+        .downcast_ref::<BinaryArray>()
+        .unwrap();
+    assert_eq!(binary.value(0), &[0, 159]);
+
+    // Lists with different element types widen to a list of strings
+    let list = column("list").as_any().downcast_ref::<ListArray>().unwrap();
+    assert_eq!(list.value_type(), DataType::Utf8);
+    let items = list.value(0);
+    let items = items.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(items.value(1), "2");
+
+    // Mixed columns widen to strings in Cypher notation
+    let mixed = column("mixed")
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(mixed.value(0), "1");
+    assert_eq!(
+        mixed.value(1),
+        "(2:Rider {born: 1980, name: 'Dani Pedrosa'})"
+    );
+    let number = column("number")
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(number.value(0), "2");
+    assert_eq!(number.value(1), "2.5");
+    assert_eq!(column("null").data_type(), &DataType::Utf8);
+    assert_eq!(column("null").null_count(), 2);
+}
+
+#[test]
+fn test_record_batch_strings() {
+    // Strings looking like numbers or booleans stay strings unless the
+    // column type is set
+    let res = result_set();
+    let batch = &ArrowExport::new().to_record_batches(&res).unwrap()[0];
+    let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+    let float = column("float");
+    let float = float.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(float.value(0), "1.5");
+    assert_eq!(float.value(1), "2");
+    let boolean = column("bool");
+    let boolean = boolean.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(boolean.value(0), "true");
+
+    let header = vec!["code".to_string()];
+    let res = GraphResultSet {
+        header: header.clone(),
+        data: vec![GraphResult::from_values(
+            header.into(),
+            vec![GraphValue::Scalar(data("007"))],
+        )],
+        metadata: vec![],
+    };
+    let batch = &ArrowExport::new().to_record_batches(&res).unwrap()[0];
+    let code = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(code.value(0), "007");
+}
+
+#[test]
+fn test_record_batch_entities() {
+    let res = result_set();
+    let batch = &ArrowExport::new().to_record_batches(&res).unwrap()[0];
+
+    let riders = batch.column_by_name("r").unwrap();
+    let riders = riders.as_any().downcast_ref::<StructArray>().unwrap();
+    assert!(riders.is_null(1));
+    let ids = riders.column_by_name("id").unwrap();
+    assert_eq!(
+        ids.as_any().downcast_ref::<UInt64Array>().unwrap().value(0),
+        1
+    );
+    let labels = riders.column_by_name("labels").unwrap();
+    let labels = labels
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap()
+        .value(0);
+    assert_eq!(
+        labels
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .value(0),
+        "Rider"
+    );
+    let properties = riders.column_by_name("properties").unwrap();
+    let properties = properties.as_any().downcast_ref::<MapArray>().unwrap();
+    let entries = properties.value(0);
+    let keys = entries
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    let values = entries
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(keys.value(0), "born");
+    assert_eq!(values.value(0), "1979");
+    assert_eq!(values.value(1), "Valentino Rossi");
+
+    let rides = batch.column_by_name("rel").unwrap();
+    let rides = rides.as_any().downcast_ref::<StructArray>().unwrap();
+    let field = |name: &str| rides.column_by_name(name).unwrap().clone();
+    assert_eq!(
+        field("type")
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .value(1),
+        "rides"
+    );
+    let src = field("src");
+    assert_eq!(
+        src.as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap()
+            .values()
+            .to_vec(),
+        vec![1, 2]
+    );
+}
+
+#[test]
+fn test_record_batch_column_types() {
+    let res = result_set();
+    let export = ArrowExport::new()
+        .with_column_type("int", DataType::Float64)
+        .with_column_type("number", DataType::Float64)
+        .with_batch_rows(1);
+    let batches = export.to_record_batches(&res).unwrap();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[1].schema(), batches[0].schema());
+    let number = batches[1].column_by_name("number").unwrap();
+    let number = number.as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(number.value(0), 2.5);
+
+    let err = ArrowExport::new()
+        .with_column_type("string", DataType::Int64)
+        .to_record_batches(&res)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Column string: Yamaha does not match Int64"));
+}
+
+#[test]
+fn test_stream_batches() {
+    let stream = GraphResultStream::from_value(response()).unwrap();
+    let batches: Vec<_> = ArrowExport::new()
+        .stream_batches(stream)
+        .collect::<redis::RedisResult<_>>()
+        .unwrap();
+    assert_eq!(batches.len(), 1);
+
+    // The number column has an integer in the first and a string in the
+    // second batch, the schema of both batches is inferred from all rows
+    let stream = GraphResultStream::from_value(response()).unwrap();
+    let batches = ArrowExport::new().with_batch_rows(1).stream_batches(stream);
+    let schema = batches.schema();
+    assert_eq!(
+        schema.field_with_name("number").unwrap().data_type(),
+        &DataType::Utf8
+    );
+    let batches: Vec<_> = batches.collect::<redis::RedisResult<_>>().unwrap();
+    assert_eq!(batches.len(), 2);
+    for batch in &batches {
+        assert_eq!(batch.schema(), schema);
+    }
+    let number = batches[1].column_by_name("number").unwrap();
+    let number = number.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(number.value(0), "2.5");
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_write_parquet() {
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let res = result_set();
+    let path = std::env::temp_dir().join("redis_graph_test_write_parquet.parquet");
+    ArrowExport::new()
+        .write_parquet(&res, std::fs::File::create(&path).unwrap())
+        .unwrap();
+    let batches: Vec<_> =
+        ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+    let expected = &ArrowExport::new().to_record_batches(&res).unwrap()[0];
+    assert_eq!(batches[0].num_rows(), 2);
+    assert_eq!(batches[0].column_by_name("r"), expected.column_by_name("r"));
+    assert_eq!(
+        batches[0].column_by_name("float"),
+        expected.column_by_name("float")
+    );
+
+    let stream = GraphResultStream::from_value(response()).unwrap();
+    let rows = ArrowExport::new()
+        .with_batch_rows(1)
+        .with_column_type("mixed", DataType::Utf8)
+        .with_column_type("number", DataType::Float64)
+        .write_parquet_stream(stream, std::fs::File::create(&path).unwrap())
+        .unwrap();
+    assert_eq!(rows, 2);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -54,6 +54,94 @@ fn test_query_all_nodes() {
     check_query_all_nodes(res);
 }
 
+#[test]
+fn test_query_stream() {
+    let res = create_runtime().block_on(issue_query_stream("test_query_stream_tokio"));
+    check_query_stream(res);
+}
+
+#[test]
+fn test_query_as() {
+    let res = create_runtime().block_on(issue_query_as("test_query_as_tokio"));
+    check_query_as(res);
+}
+
+#[test]
+fn test_fulltext_query() {
+    let res = create_runtime().block_on(issue_fulltext_query("test_fulltext_query_tokio"));
+    check_fulltext_query(res);
+}
+
+#[test]
+fn test_call() {
+    let res = create_runtime().block_on(issue_call_labels("test_call_tokio"));
+    check_call_labels(res);
+}
+
+#[test]
+fn test_subgraph() {
+    let res = create_runtime().block_on(issue_subgraph("test_subgraph_tokio"));
+    check_subgraph(res);
+}
+
+#[test]
+fn test_dump_restore() {
+    let res = create_runtime().block_on(issue_dump_restore("test_dump_restore_tokio"));
+    check_dump_restore(res);
+}
+
+#[test]
+fn test_graph_copy() {
+    let res = create_runtime().block_on(issue_graph_copy("test_graph_copy_tokio"));
+    check_graph_copy(res);
+}
+
+#[test]
+fn test_schema() {
+    let res = create_runtime().block_on(issue_schema("test_schema_tokio"));
+    check_schema(res);
+}
+
+#[test]
+fn test_schema_diff() {
+    let res = create_runtime().block_on(issue_schema_diff(
+        "test_schema_diff_tokio",
+        &test_data_schema(),
+    ));
+    check_schema_diff(res);
+}
+
+#[test]
+fn test_migrate() {
+    let res = create_runtime().block_on(issue_migrate("test_migrate_tokio", &test_migrator()));
+    check_migrate(res);
+}
+
+#[test]
+fn test_bulk_write() {
+    let res = create_runtime().block_on(issue_bulk_write(
+        "test_bulk_write_tokio",
+        test_bulk_circuits(),
+    ));
+    check_bulk_write(res);
+}
+
+#[test]
+fn test_bulk_load() {
+    let res = create_runtime().block_on(issue_bulk_load(
+        "test_bulk_load_tokio",
+        test_bulk_nodes(),
+        test_bulk_relations(),
+    ));
+    check_bulk_load(res);
+}
+
+#[test]
+fn test_paginate() {
+    let res = create_runtime().block_on(issue_paginate("test_paginate_tokio"));
+    check_paginate(res);
+}
+
 #[test]
 fn test_unserialize_option() {
     let res = create_runtime().block_on(issue_query_option("test_unserialize_option_tokio"));
@@ -107,3 +195,9 @@ fn test_graph_explain() {
     let res = create_runtime().block_on(issue_graph_explain("test_graph_explain_tokio"));
     check_graph_explain_result(res);
 }
+
+#[test]
+fn test_graph_list() {
+    let res = create_runtime().block_on(issue_graph_list("test_graph_list_tokio"));
+    check_graph_list_result(res, "test_graph_list_tokio");
+}
@@ -0,0 +1,181 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{ErrorKind, RedisError, RedisResult, Value};
+use redis_graph::*;
+use std::collections::HashMap;
+
+#[macro_use]
+mod common;
+
+// Records all queries and answers them with the statistics of a CREATE.
+// Queries with a row named 'fail' fail.
+struct BulkConnection {
+    queries: Vec<String>,
+}
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+impl BulkConnection {
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        let query = args[2].clone();
+        let rows = query.matches("name: '").count();
+        if query.contains("name: 'fail'") {
+            return Err(RedisError::from((ErrorKind::ResponseError, "Write failed")));
+        }
+        self.queries.push(query);
+        Ok(Value::Bulk(vec![Value::Bulk(vec![
+            data("Labels added: 1"),
+            data(&format!("Nodes created: {}", rows)),
+            data(&format!("Properties set: {}", rows * 2)),
+            data("Cached execution: 0"),
+            data("Query internal execution time: 0.250000 milliseconds"),
+        ])]))
+    }
+}
+
+mock_connection!(BulkConnection);
+
+fn riders(count: usize) -> Vec<HashMap<String, ParamValue>> {
+    (0..count)
+        .map(|idx| {
+            let mut rider = HashMap::new();
+            rider.insert("name".to_string(), ParamValue::from(format!("r{}", idx)));
+            rider.insert("born".to_string(), ParamValue::from(1970 + idx as i64));
+            rider
+        })
+        .collect()
+}
+
+#[test]
+fn test_bulk_write_batch_rows() {
+    let mut con = BulkConnection { queries: vec![] };
+    let bulk = BulkWrite::create_nodes("Rider").with_batch_rows(2);
+    let res = con.graph_bulk_write("my_graph", &bulk, riders(5)).unwrap();
+    assert_eq!(res.batches, 3);
+    assert_eq!(res.rows, 5);
+    assert_eq!(res.statistics.nodes_created, 5);
+    assert_eq!(res.statistics.properties_set, 10);
+    assert_eq!(res.statistics.labels_added, 3);
+    assert_eq!(res.statistics.execution_time, 0.75);
+    assert_eq!(
+        con.queries[2],
+        "CYPHER rows=[{born: 1974, name: 'r4'}] UNWIND $rows AS row CREATE (n:Rider) SET n = row"
+    );
+}
+
+#[test]
+fn test_bulk_write_resume() {
+    let mut con = BulkConnection { queries: vec![] };
+    let bulk = BulkWrite::create_nodes("Rider").with_batch_rows(2);
+    let mut rows = riders(5);
+    rows[3].insert("name".to_string(), ParamValue::from("fail"));
+    let mut written = BulkWriteResult::default();
+    let err = con
+        .graph_bulk_write_progress("my_graph", &bulk, rows.clone(), |res| written = res.clone())
+        .unwrap_err();
+    assert!(err.to_string().contains("Write failed"));
+    assert_eq!(written.batches, 1);
+    assert_eq!(written.rows, 2);
+
+    // Resuming skips the written rows
+    rows[3].insert("name".to_string(), ParamValue::from("r3"));
+    let res = con
+        .graph_bulk_write("my_graph", &bulk, rows.into_iter().skip(written.rows))
+        .unwrap();
+    assert_eq!(res.rows, 3);
+    assert_eq!(con.queries.len(), 3);
+    assert!(con.queries[1]
+        .starts_with("CYPHER rows=[{born: 1972, name: 'r2'}, {born: 1973, name: 'r3'}]"));
+}
+
+#[test]
+fn test_bulk_write_non_finite_float() {
+    let mut con = BulkConnection { queries: vec![] };
+    let bulk = BulkWrite::create_nodes("Rider").with_batch_rows(2);
+    let mut rows = riders(3);
+    rows[2].insert("height".to_string(), ParamValue::from(f64::NAN));
+    let err = con.graph_bulk_write("my_graph", &bulk, rows).unwrap_err();
+    assert!(err.to_string().contains("no Cypher literal"));
+    assert_eq!(con.queries.len(), 1);
+}
+
+#[test]
+fn test_bulk_write_batch_bytes() {
+    let mut con = BulkConnection { queries: vec![] };
+    // Every encoded row has 24 bytes, so two rows with separator fit into 50
+    let bulk = BulkWrite::merge_nodes("Rider", &["name"]).with_batch_bytes(50);
+    let res = con.graph_bulk_write("my_graph", &bulk, riders(5)).unwrap();
+    assert_eq!(res.batches, 3);
+    assert_eq!(res.rows, 5);
+    assert_eq!(
+        con.queries[0],
+        "CYPHER rows=[{born: 1970, name: 'r0'}, {born: 1971, name: 'r1'}] \
+         UNWIND $rows AS row MERGE (n:Rider {name: row.name}) SET n += row"
+    );
+
+    // Rows larger than the limit are sent alone
+    let mut con = BulkConnection { queries: vec![] };
+    let bulk = BulkWrite::create_nodes("Rider").with_batch_bytes(1);
+    let res = con.graph_bulk_write("my_graph", &bulk, riders(2)).unwrap();
+    assert_eq!(res.batches, 2);
+
+    let res = con
+        .graph_bulk_write("my_graph", &bulk, Vec::<ParamValue>::new())
+        .unwrap();
+    assert_eq!(res, BulkWriteResult::default());
+}
+
+#[test]
+fn test_query_statistics() {
+    let stats = QueryStatistics::from_metadata(&[
+        "Nodes deleted: 2",
+        "Relationships deleted: 1",
+        "Indices created: 1",
+        "Query internal execution time: 1.5 milliseconds",
+        "unknown",
+    ]);
+    assert_eq!(stats.nodes_deleted, 2);
+    assert_eq!(stats.relationships_deleted, 1);
+    assert_eq!(stats.indices_created, 1);
+    assert_eq!(stats.execution_time, 1.5);
+    let mut sum = stats.clone();
+    sum.add(&stats);
+    assert_eq!(sum.nodes_deleted, 4);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_bulk_write_serialize() {
+    #[derive(serde::Serialize)]
+    struct Rider {
+        name: String,
+        born: Option<u16>,
+        teams: Vec<&'static str>,
+    }
+
+    let riders = vec![
+        Rider {
+            name: "Valentino Rossi".to_string(),
+            born: Some(1979),
+            teams: vec!["Yamaha", "Honda"],
+        },
+        Rider {
+            name: "Andrea Dovizioso".to_string(),
+            born: None,
+            teams: vec![],
+        },
+    ];
+    let mut con = BulkConnection { queries: vec![] };
+    let bulk = BulkWrite::create_nodes("Rider");
+    let res = con.graph_bulk_write("my_graph", &bulk, &riders).unwrap();
+    assert_eq!(res.rows, 2);
+    assert_eq!(
+        con.queries[0],
+        "CYPHER rows=[{born: 1979, name: 'Valentino Rossi', teams: ['Yamaha', 'Honda']}, \
+         {born: null, name: 'Andrea Dovizioso', teams: []}] \
+         UNWIND $rows AS row CREATE (n:Rider) SET n = row"
+    );
+}
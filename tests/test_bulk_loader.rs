@@ -0,0 +1,285 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{ConnectionLike, RedisResult, Value};
+use redis_graph::*;
+use std::convert::TryInto;
+
+mod common;
+
+// Records the arguments of all GRAPH.BULK commands and answers with the
+// number of nodes and relations of the command.
+struct BulkLoaderConnection {
+    commands: Vec<Vec<Vec<u8>>>,
+}
+
+impl ConnectionLike for BulkLoaderConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let args = common::unpack(cmd);
+        let offset = if args[2] == b"BEGIN" { 3 } else { 2 };
+        let count = |idx: usize| String::from_utf8_lossy(&args[offset + idx]).to_string();
+        let reply = format!("{} nodes created, {} edges created", count(0), count(1));
+        self.commands.push(args);
+        Ok(Value::Data(reply.into_bytes()))
+    }
+
+    fn req_packed_commands(&mut self, _: &[u8], _: usize, _: usize) -> RedisResult<Vec<Value>> {
+        Ok(vec![])
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
+
+fn nodes() -> Vec<BulkNode> {
+    vec![
+        BulkNode::new("r1", "Rider")
+            .with_property("name", "Valentino Rossi")
+            .with_property("born", 1979),
+        BulkNode::new("t1", "Team").with_property("name", "Yamaha"),
+        BulkNode::new("r2", "Rider").with_property("name", "Andrea Dovizioso"),
+    ]
+}
+
+fn relations() -> Vec<BulkRelation> {
+    vec![
+        BulkRelation::new("r1", "rides", "t1").with_property("since", 2004),
+        BulkRelation::new("r2", "rides", "t1"),
+    ]
+}
+
+#[test]
+fn test_bulk_load_encoding() {
+    let mut con = BulkLoaderConnection { commands: vec![] };
+    let res = con
+        .graph_bulk_load("my_graph", &BulkLoader::new(), nodes(), relations())
+        .unwrap();
+    assert_eq!(
+        res,
+        BulkLoadResult {
+            commands: 1,
+            nodes_created: 3,
+            relations_created: 2,
+        }
+    );
+
+    let args = &con.commands[0];
+    assert_eq!(args.len(), 10);
+    let strings: Vec<String> = args[..7]
+        .iter()
+        .map(|a| String::from_utf8_lossy(a).to_string())
+        .collect();
+    assert_eq!(
+        strings,
+        vec!["GRAPH.BULK", "my_graph", "BEGIN", "3", "2", "2", "1"]
+    );
+
+    let mut riders = b"Rider\0\x02\0\0\0name\0born\0".to_vec();
+    riders.extend_from_slice(b"\x03Valentino Rossi\0\x04");
+    riders.extend_from_slice(&1979i64.to_le_bytes());
+    riders.extend_from_slice(b"\x03Andrea Dovizioso\0\x00");
+    assert_eq!(args[7], riders);
+    assert_eq!(args[8], b"Team\0\x01\0\0\0name\0\x03Yamaha\0".to_vec());
+
+    // The server creates the nodes token by token: r1 = 0, r2 = 1, t1 = 2
+    let mut rides = b"rides\0\x01\0\0\0since\0".to_vec();
+    rides.extend_from_slice(&0u64.to_le_bytes());
+    rides.extend_from_slice(&2u64.to_le_bytes());
+    rides.push(4);
+    rides.extend_from_slice(&2004i64.to_le_bytes());
+    rides.extend_from_slice(&1u64.to_le_bytes());
+    rides.extend_from_slice(&2u64.to_le_bytes());
+    rides.push(0);
+    assert_eq!(args[9], rides);
+}
+
+// The source and destination ids of the relations in a rides token
+fn relation_ids(token: &[u8]) -> Vec<(u64, u64)> {
+    let header = b"rides\0\0\0\0\0".len();
+    token[header..]
+        .chunks(16)
+        .map(|ids| {
+            let id = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap());
+            (id(&ids[..8]), id(&ids[8..]))
+        })
+        .collect()
+}
+
+#[test]
+fn test_bulk_load_interleaved_labels() {
+    // Teams and riders alternate, the riders are split into two tokens
+    let nodes = vec![
+        BulkNode::new("t1", "Team"),
+        BulkNode::new("r1", "Rider"),
+        BulkNode::new("t2", "Team"),
+        BulkNode::new("r2", "Rider"),
+        BulkNode::new("r3", "Rider").with_property("born", 1985),
+        BulkNode::new("t3", "Team"),
+    ];
+    let relations = vec![
+        BulkRelation::new("r1", "rides", "t1"),
+        BulkRelation::new("r2", "rides", "t2"),
+        BulkRelation::new("r3", "rides", "t3"),
+    ];
+    let mut con = BulkLoaderConnection { commands: vec![] };
+    con.graph_bulk_load("my_graph", &BulkLoader::new(), nodes, relations)
+        .unwrap();
+    // The first Rider token is finished by r3: r1 = 0, r2 = 1, then the open
+    // tokens are finished by the first relation: t1 = 2, t2 = 3, t3 = 4, r3 = 5
+    let args = &con.commands[0];
+    assert_eq!(args.len(), 11);
+    assert!(args[7].starts_with(b"Rider\0\0"));
+    assert!(args[8].starts_with(b"Team\0"));
+    assert!(args[9].starts_with(b"Rider\0\x01"));
+    assert_eq!(relation_ids(&args[10]), vec![(0, 2), (1, 3), (5, 4)]);
+}
+
+#[test]
+fn test_bulk_load_values() {
+    let node = BulkNode::new("n", "Value")
+        .with_label("Other")
+        .with_property("flag", true)
+        .with_property("score", 1.5)
+        .with_property("tags", vec![ParamValue::from("a"), ParamValue::from(1)]);
+    let mut con = BulkLoaderConnection { commands: vec![] };
+    con.graph_bulk_load(
+        "my_graph",
+        &BulkLoader::new(),
+        vec![node],
+        Vec::<BulkRelation>::new(),
+    )
+    .unwrap();
+
+    let mut expected = b"Value:Other\0\x03\0\0\0flag\0score\0tags\0\x01\x01\x02".to_vec();
+    expected.extend_from_slice(&1.5f64.to_le_bytes());
+    expected.push(5);
+    expected.extend_from_slice(&2i64.to_le_bytes());
+    expected.extend_from_slice(b"\x03a\0\x04");
+    expected.extend_from_slice(&1i64.to_le_bytes());
+    assert_eq!(con.commands[0][7], expected);
+}
+
+#[test]
+fn test_bulk_load_limits() {
+    // One token per command
+    let mut con = BulkLoaderConnection { commands: vec![] };
+    let loader = BulkLoader::new().with_max_token_count(1);
+    let res = con
+        .graph_bulk_load("my_graph", &loader, nodes(), relations())
+        .unwrap();
+    assert_eq!(res.commands, 4);
+    assert_eq!(res.nodes_created, 3);
+    assert_eq!(res.relations_created, 2);
+    assert_eq!(con.commands[0][2], b"BEGIN".to_vec());
+    assert_eq!(con.commands[1][2], b"1".to_vec());
+    assert_eq!(con.commands[3].len(), 7);
+
+    // One entity per token and command
+    let mut con = BulkLoaderConnection { commands: vec![] };
+    let loader = BulkLoader::new().with_max_buffer_size(1);
+    let res = con
+        .graph_bulk_load("my_graph", &loader, nodes(), relations())
+        .unwrap();
+    assert_eq!(res.commands, 5);
+
+    // Full tokens are split, which gives two Rider and two rides tokens
+    let mut con = BulkLoaderConnection { commands: vec![] };
+    let loader = BulkLoader::new().with_max_token_size(1);
+    let res = con
+        .graph_bulk_load("my_graph", &loader, nodes(), relations())
+        .unwrap();
+    assert_eq!(res.commands, 1);
+    assert_eq!(con.commands[0].len(), 12);
+}
+
+#[test]
+fn test_bulk_load_errors() {
+    let mut con = BulkLoaderConnection { commands: vec![] };
+    let loader = BulkLoader::new();
+    let mut duplicate = nodes();
+    duplicate.push(BulkNode::new("r1", "Rider"));
+    assert!(con
+        .graph_bulk_load("my_graph", &loader, duplicate, Vec::<BulkRelation>::new())
+        .is_err());
+    let unknown = vec![BulkRelation::new("r1", "rides", "t2")];
+    assert!(con
+        .graph_bulk_load("my_graph", &loader, nodes(), unknown)
+        .is_err());
+    let map = vec![BulkNode::new("n", "Value").with_property(
+        "map",
+        ParamValue::Map(vec![("a".to_string(), ParamValue::Null)]),
+    )];
+    assert!(con
+        .graph_bulk_load("my_graph", &loader, map, Vec::<BulkRelation>::new())
+        .is_err());
+    assert!(con.commands.is_empty());
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_bulk_load_csv() {
+    let dir = std::env::temp_dir().join("redis_graph_test_bulk_loader");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(
+        dir.join("Rider.csv"),
+        "_id,name,born:INT,teams,note:IGNORE\n\
+         1,Valentino Rossi,1979,\"['Yamaha', 'Honda']\",x\n\
+         2,\"Rossi, Jr\",,[],y\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("Team.csv"), "id,active\nYamaha,true\n").unwrap();
+    std::fs::write(dir.join("RIDES.csv"), "src,dest,since\n1,Yamaha,2004.5\n").unwrap();
+
+    let riders: Vec<BulkNode> = CsvNodes::open(dir.join("Rider.csv"))
+        .unwrap()
+        .collect::<RedisResult<_>>()
+        .unwrap();
+    assert_eq!(
+        riders[0],
+        BulkNode::new("1", "Rider")
+            .with_property("name", "Valentino Rossi")
+            .with_property("born", 1979)
+            .with_property(
+                "teams",
+                vec![ParamValue::from("Yamaha"), ParamValue::from("Honda")]
+            )
+    );
+    assert_eq!(riders[1].properties[0].1, ParamValue::from("Rossi, Jr"));
+    assert_eq!(riders[1].properties[1].1, ParamValue::Null);
+    assert_eq!(riders[1].properties[2].1, ParamValue::List(vec![]));
+
+    let nodes = CsvNodes::open(dir.join("Rider.csv")).unwrap().chain(
+        CsvNodes::open(dir.join("Team.csv"))
+            .unwrap()
+            .with_label("Team:Brand"),
+    );
+    let relations = CsvRelations::open(dir.join("RIDES.csv"))
+        .unwrap()
+        .with_type("rides");
+    let mut con = BulkLoaderConnection { commands: vec![] };
+    let res = con
+        .graph_bulk_load("my_graph", &BulkLoader::new(), nodes, relations)
+        .unwrap();
+    assert_eq!(res.nodes_created, 3);
+    assert_eq!(res.relations_created, 1);
+    assert!(con.commands[0][8].starts_with(b"Team:Brand\0\x02\0\0\0id\0active\0"));
+    assert!(con.commands[0][9].starts_with(b"rides\0\x01\0\0\0since\0"));
+
+    std::fs::write(dir.join("Bad.csv"), "id,born:INT\n1,abc\n").unwrap();
+    assert!(CsvNodes::open(dir.join("Bad.csv"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,68 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::cluster_routing::{
+    get_slot, MultipleNodeRoutingInfo, Route, RoutingInfo, SingleNodeRoutingInfo, SlotAddr,
+};
+use redis::{cmd, ToRedisArgs};
+use redis_graph::*;
+
+#[test]
+fn test_graph_key_format() {
+    assert_eq!(GraphKey::new("social").to_string(), "social");
+    let key = GraphKey::tagged("tenant42", "social").unwrap();
+    assert_eq!(key.to_string(), "{tenant42}:social");
+    assert_eq!(key.tag(), Some("tenant42"));
+    assert_eq!(key.name(), "social");
+    assert_eq!(key.to_redis_args(), vec![b"{tenant42}:social".to_vec()]);
+}
+
+#[test]
+fn test_graph_key_invalid_tag() {
+    assert!(GraphKey::tagged("", "social").is_err());
+    assert!(GraphKey::tagged("ten{ant", "social").is_err());
+    assert!(GraphKey::tagged("ten}ant", "social").is_err());
+}
+
+#[test]
+fn test_graph_key_siblings_share_slot() {
+    let key = GraphKey::tagged("tenant42", "social").unwrap();
+    let sibling = key.sibling("billing");
+    assert_eq!(sibling.tag(), Some("tenant42"));
+    assert_eq!(key.slot(), sibling.slot());
+    assert_eq!(key.slot(), get_slot(b"tenant42"));
+}
+
+#[test]
+fn test_graph_routing_by_key() {
+    let key = GraphKey::tagged("tenant42", "social").unwrap();
+    assert_eq!(
+        graph_routing(cmd("GRAPH.QUERY").arg(&key).arg("MATCH (n) RETURN n")),
+        Some(RoutingInfo::SingleNode(
+            SingleNodeRoutingInfo::SpecificNode(Route::new(key.slot(), SlotAddr::Master))
+        ))
+    );
+    assert_eq!(
+        graph_routing(cmd("GRAPH.RO_QUERY").arg(&key).arg("MATCH (n) RETURN n")),
+        Some(RoutingInfo::SingleNode(
+            SingleNodeRoutingInfo::SpecificNode(Route::new(key.slot(), SlotAddr::Replica))
+        ))
+    );
+    assert_eq!(
+        RoutingInfo::for_routable(cmd("GRAPH.QUERY").arg(&key).arg("MATCH (n) RETURN n")),
+        graph_routing(cmd("GRAPH.QUERY").arg(&key).arg("MATCH (n) RETURN n"))
+    );
+}
+
+#[test]
+fn test_graph_routing_keyless() {
+    match graph_routing(&cmd("GRAPH.LIST")) {
+        Some(RoutingInfo::MultiNode((MultipleNodeRoutingInfo::AllMasters, _))) => {}
+        other => panic!("unexpected routing {:?}", other),
+    }
+    match graph_routing(cmd("GRAPH.CONFIG").arg("SET").arg("TIMEOUT").arg(10)) {
+        Some(RoutingInfo::MultiNode((MultipleNodeRoutingInfo::AllNodes, _))) => {}
+        other => panic!("unexpected routing {:?}", other),
+    }
+    assert_eq!(graph_routing(cmd("GET").arg("foo")), None);
+}
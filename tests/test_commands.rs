@@ -75,6 +75,20 @@ fn test_match_ro_query_result() {
     check_match_query_result(r);
 }
 
+#[test]
+fn test_match_auto_query_result() {
+    ensure_simple_data("test_match_auto_query_result");
+
+    let r = get_con()
+        .graph_auto_query(
+            "test_match_auto_query_result",
+            "MATCH (n1)-[r]->(n2) RETURN n1, r, n2.name",
+        )
+        .unwrap();
+
+    check_match_query_result(r);
+}
+
 #[test]
 fn test_match_scalar_result() {
     ensure_test_data("test_match_scalar_result");
@@ -96,6 +110,407 @@ fn test_query_all_nodes() {
     check_query_all_nodes(res);
 }
 
+#[test]
+fn test_query_stream() {
+    ensure_test_data("test_query_stream");
+    let res = get_con()
+        .graph_ro_query_stream("test_query_stream", "MATCH (r:Rider) RETURN r")
+        .unwrap();
+    check_query_stream(res);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_query_json() {
+    ensure_test_data("test_query_json");
+    let mut con = get_con();
+    let res = con
+        .graph_ro_query(
+            "test_query_json",
+            "MATCH (r:Rider {name:'Valentino Rossi'}) RETURN r, r.born",
+        )
+        .unwrap();
+    assert!(res
+        .to_json()
+        .unwrap()
+        .contains(r#""properties":{"born":1979,"name":"Valentino Rossi"}},"r.born":1979}"#));
+
+    let stream = con
+        .graph_ro_query_stream("test_query_json", "MATCH (r:Rider) RETURN r.name")
+        .unwrap();
+    let mut writer = NdjsonWriter::new(Vec::new());
+    assert_eq!(writer.write_stream(stream).unwrap(), 3);
+    assert_eq!(writer.into_inner().split(|b| *b == b'\n').count(), 4);
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_query_arrow() {
+    ensure_test_data("test_query_arrow");
+    let res = get_con()
+        .graph_ro_query("test_query_arrow", "MATCH (r:Rider) RETURN r, r.born")
+        .unwrap();
+    let batches = ArrowExport::new().to_record_batches(&res).unwrap();
+    assert_eq!(batches[0].num_rows(), 3);
+    assert_eq!(
+        batches[0].schema().field(1).data_type(),
+        &arrow::datatypes::DataType::Int64
+    );
+}
+
+#[cfg(feature = "petgraph")]
+#[test]
+fn test_petgraph_round_trip() {
+    let mut con = get_con();
+    let _: () = con.del("test_petgraph_round_trip").unwrap();
+    let _: () = con.del("test_petgraph_round_trip_copy").unwrap();
+    con.graph_query(
+        "test_petgraph_round_trip",
+        "CREATE (:Rider {name:'1.5', score:1.5, active:true})-[:rides {since:2004}]->(:Team)",
+    )
+    .unwrap();
+    let indexed = con
+        .graph_read_petgraph("test_petgraph_round_trip", 1)
+        .unwrap();
+    assert_eq!(indexed.graph.node_count(), 2);
+    assert_eq!(indexed.graph.edge_count(), 1);
+    con.graph_write_petgraph("test_petgraph_round_trip_copy", &indexed.graph, 10)
+        .unwrap();
+    let types: (String, String, String) = con
+        .graph_ro_query_as(
+            "test_petgraph_round_trip_copy",
+            "MATCH (r:Rider) RETURN typeOf(r.name), typeOf(r.score), typeOf(r.active)",
+        )
+        .unwrap()
+        .remove(0);
+    assert_eq!(
+        types,
+        (
+            "String".to_string(),
+            "Float".to_string(),
+            "Boolean".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_query_as() {
+    ensure_test_data("test_query_as");
+    let res = get_con()
+        .graph_ro_query_as("test_query_as", "MATCH (r:Rider) RETURN r, r.name, r.born")
+        .unwrap();
+    check_query_as(res);
+}
+
+#[test]
+fn test_fulltext_query() {
+    ensure_test_data("test_fulltext_query");
+    let mut con = get_con();
+    let _: GraphResultSet = con
+        .graph_query(
+            "test_fulltext_query",
+            "CALL db.idx.fulltext.createNodeIndex('Rider', 'name')",
+        )
+        .unwrap();
+    let query = FulltextQuery::new().prefix("ros");
+    check_fulltext_query(
+        con.graph_fulltext_query("test_fulltext_query", "Rider", &query)
+            .unwrap(),
+    );
+    let page = con
+        .graph_fulltext_query_page("test_fulltext_query", "Rider", "valentino|dani", 1, 5)
+        .unwrap();
+    assert_eq!(page.len(), 1);
+}
+
+#[test]
+fn test_call() {
+    ensure_test_data("test_call");
+    let mut con = get_con();
+    check_call_labels(
+        con.graph_call("test_call", "db.labels", &[], &["label"])
+            .unwrap(),
+    );
+    let procedures = con.graph_procedures("test_call").unwrap();
+    assert!(procedures
+        .iter()
+        .any(|p| p.name == "db.labels" && p.is_read_only()));
+}
+
+fn check_algo_bfs(res: BfsResult, team: u64) {
+    assert_eq!(res.nodes.len(), 1);
+    assert_eq!(res.nodes[0].id, team);
+    assert_eq!(res.nodes[0].labels, ["Team"]);
+    assert_eq!(res.relations.len(), 1);
+    assert_eq!(res.relations[0].rel_type, "rides");
+}
+
+fn check_algo_paths(res: Vec<AlgoPath>, rider: u64, team: u64) {
+    assert_eq!(res.len(), 1);
+    let path = &res[0].path;
+    let ids: Vec<u64> = path.nodes.iter().map(|node| node.id).collect();
+    assert_eq!(ids, [rider, team]);
+    assert_eq!(path.relations.len(), 1);
+    assert_eq!(path.relations[0].src_node, rider);
+    assert_eq!(res[0].weight, 1.0);
+}
+
+#[test]
+fn test_algo_procedures() {
+    ensure_test_data("test_algo_procedures");
+    let mut con = get_con();
+    let ids: Vec<(u64, u64)> = con
+        .graph_ro_query_as(
+            "test_algo_procedures",
+            "MATCH (r:Rider {name:'Valentino Rossi'})-[:rides]->(t:Team) RETURN id(r), id(t)",
+        )
+        .unwrap();
+    let (rider, team) = ids[0];
+    check_algo_bfs(
+        con.graph_algo_bfs("test_algo_procedures", &BfsConfig::new(rider))
+            .unwrap(),
+        team,
+    );
+    check_algo_paths(
+        con.graph_algo_sp_paths("test_algo_procedures", &PathConfig::new(rider).target(team))
+            .unwrap(),
+        rider,
+        team,
+    );
+    check_algo_paths(
+        con.graph_algo_ss_paths("test_algo_procedures", &PathConfig::new(rider))
+            .unwrap(),
+        rider,
+        team,
+    );
+}
+
+#[test]
+fn test_subgraph() {
+    ensure_test_data("test_subgraph");
+    check_subgraph(get_con().graph_subgraph("test_subgraph").unwrap());
+}
+
+#[test]
+fn test_dump_restore() {
+    ensure_test_data("test_dump_restore");
+    let mut con = get_con();
+    let _: () = con.del("test_dump_restore_copy").unwrap();
+    let mut script = Vec::new();
+    let dump = con
+        .graph_dump(
+            "test_dump_restore",
+            &GraphDump::new(DumpFormat::Cypher).with_page_size(2),
+            &mut script,
+        )
+        .unwrap();
+    let progress = con
+        .graph_restore(
+            "test_dump_restore_copy",
+            &GraphRestore::new(DumpFormat::Cypher),
+            script.as_slice(),
+            |_| {},
+        )
+        .unwrap();
+    let copy = con.graph_subgraph("test_dump_restore_copy").unwrap();
+    check_dump_restore((dump, progress, copy));
+}
+
+#[test]
+fn test_graph_copy() {
+    ensure_test_data("test_graph_copy");
+    let mut source = get_con();
+    let mut target = get_con();
+    let _: () = target.del("test_graph_copy_copy").unwrap();
+    let result = GraphCopy::new()
+        .with_page_size(2)
+        .copy(
+            &mut source,
+            "test_graph_copy",
+            &mut target,
+            "test_graph_copy_copy",
+            |_| {},
+        )
+        .unwrap();
+    let copy = target.graph_subgraph("test_graph_copy_copy").unwrap();
+    check_graph_copy((result, copy));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_import_apoc() {
+    let mut con = get_con();
+    let _: () = con.del("test_import_apoc").unwrap();
+    let export = r#"{"type":"node","id":"10","labels":["Rider"],"properties":{"name":"Valentino Rossi","born":"1979-02-16","home":{"crs":"wgs-84","latitude":43.9,"longitude":12.9,"height":null}}}
+{"type":"node","id":"11","labels":["Team"],"properties":{"name":"Yamaha","address":{"city":"Iwata"}}}
+{"id":"20","type":"relationship","label":"rides","properties":{},"start":{"id":"10","labels":["Rider"]},"end":{"id":"11","labels":["Team"]}}
+"#;
+    let import = ApocImport::new().with_timestamp_properties(&["born"]);
+    let report = con
+        .graph_import_apoc("test_import_apoc", &import, export.as_bytes())
+        .unwrap();
+    assert_eq!(report.nodes, 2);
+    assert_eq!(report.relations, 1);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].property.as_deref(), Some("address"));
+
+    let res = con
+        .graph_ro_query(
+            "test_import_apoc",
+            "MATCH (r:Rider)-[:rides]->(t:Team) RETURN r.born, t.name",
+        )
+        .unwrap();
+    assert_eq!(res.data.len(), 1);
+    assert_eq!(
+        res.data[0].get_scalar::<i64>("r.born"),
+        Some(287_971_200_000)
+    );
+    assert_eq!(
+        res.data[0].get_scalar::<String>("t.name").unwrap(),
+        "Yamaha"
+    );
+}
+
+#[test]
+fn test_schema() {
+    ensure_test_data("test_schema");
+    let mut con = get_con();
+    check_schema(con.graph_schema("test_schema", 100).unwrap());
+}
+
+#[test]
+fn test_schema_diff() {
+    ensure_test_data("test_schema_diff");
+    let mut con = get_con();
+    check_schema_diff(
+        con.graph_schema_diff("test_schema_diff", &test_data_schema(), 100)
+            .unwrap(),
+    );
+}
+
+#[test]
+fn test_migrate() {
+    ensure_test_data("test_migrate");
+    let mut con = get_con();
+    let migrator = test_migrator();
+    con.graph_migrate("test_migrate", &migrator, false).unwrap();
+    let migrated = con
+        .graph_migrations_applied("test_migrate", &migrator)
+        .unwrap();
+    con.graph_rollback("test_migrate", &migrator, 0, false)
+        .unwrap();
+    let rolled_back = con
+        .graph_migrations_applied("test_migrate", &migrator)
+        .unwrap();
+    check_migrate((migrated, rolled_back));
+}
+
+#[test]
+fn test_bulk_write() {
+    ensure_test_data("test_bulk_write");
+    let bulk = BulkWrite::create_nodes("Circuit").with_batch_rows(2);
+    check_bulk_write(
+        get_con()
+            .graph_bulk_write("test_bulk_write", &bulk, test_bulk_circuits())
+            .unwrap(),
+    );
+}
+
+#[test]
+fn test_bulk_load() {
+    let mut con = get_con();
+    let _: () = con.del("test_bulk_load").unwrap();
+    check_bulk_load(
+        con.graph_bulk_load(
+            "test_bulk_load",
+            &BulkLoader::new(),
+            test_bulk_nodes(),
+            test_bulk_relations(),
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_bulk_load_interleaved_labels() {
+    let mut con = get_con();
+    let _: () = con.del("test_bulk_load_interleaved").unwrap();
+    let nodes = vec![
+        BulkNode::new("t1", "Team").with_property("name", "Yamaha"),
+        BulkNode::new("r1", "Rider").with_property("name", "Valentino Rossi"),
+        BulkNode::new("t2", "Team").with_property("name", "Honda"),
+        BulkNode::new("r2", "Rider")
+            .with_property("name", "Dani Pedrosa")
+            .with_property("born", 1985),
+    ];
+    let relations = vec![
+        BulkRelation::new("r1", "rides", "t1"),
+        BulkRelation::new("r2", "rides", "t2"),
+    ];
+    let loader = BulkLoader::new().with_max_token_count(2);
+    con.graph_bulk_load("test_bulk_load_interleaved", &loader, nodes, relations)
+        .unwrap();
+    let rides: Vec<(String, String)> = con
+        .graph_ro_query_as(
+            "test_bulk_load_interleaved",
+            "MATCH (r:Rider)-[:rides]->(t:Team) RETURN r.name, t.name ORDER BY r.name",
+        )
+        .unwrap();
+    assert_eq!(
+        rides,
+        vec![
+            ("Dani Pedrosa".to_string(), "Honda".to_string()),
+            ("Valentino Rossi".to_string(), "Yamaha".to_string()),
+        ]
+    );
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_export_import_csv() {
+    let mut con = get_con();
+    let _: () = con.del("test_export_csv").unwrap();
+    let _: () = con.del("test_import_csv").unwrap();
+    con.graph_bulk_load(
+        "test_export_csv",
+        &BulkLoader::new(),
+        test_bulk_nodes(),
+        test_bulk_relations(),
+    )
+    .unwrap();
+    let dir = env::temp_dir().join("redis_graph_test_export_import_csv");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    con.graph_export_csv("test_export_csv", &dir).unwrap();
+    con.graph_query("test_import_csv", "CREATE (:Rider {_id: 'keep'})")
+        .unwrap();
+    let import = CsvImport::from_dir(&dir).unwrap();
+    let res = con.graph_import_csv("test_import_csv", &import).unwrap();
+    assert_eq!(res.statistics.nodes_created, 3);
+    assert_eq!(res.statistics.relationships_created, 2);
+    let ids: Vec<(String,)> = con
+        .graph_ro_query_as(
+            "test_import_csv",
+            "MATCH (n) WHERE n._id IS NOT NULL RETURN n._id",
+        )
+        .unwrap();
+    assert_eq!(ids, vec![("keep".to_string(),)]);
+    let schema = con.graph_schema("test_import_csv", 1).unwrap();
+    assert_eq!(schema.indexes, vec![]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_paginate() {
+    ensure_test_data("test_paginate");
+    let pagination = Pagination::new(
+        "test_paginate",
+        "MATCH (r:Rider) RETURN r ORDER BY r.name",
+        2,
+    );
+    check_paginate(get_con().graph_paginate(pagination).collect());
+}
+
 #[test]
 fn test_unserialize_option() {
     ensure_test_data("test_unserialize_option");
@@ -155,6 +570,12 @@ fn test_graph_explain() {
     );
 }
 
+#[test]
+fn test_graph_list() {
+    ensure_test_data("test_graph_list");
+    check_graph_list_result(get_con().graph_list(), "test_graph_list");
+}
+
 fn get_redis_url() -> String {
     let redis_host_key = "REDIS_HOST";
     let redis_host_port = "REDIS_PORT";
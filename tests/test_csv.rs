@@ -0,0 +1,499 @@
+#![cfg(feature = "csv")]
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{RedisResult, Value};
+use redis_graph::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[macro_use]
+mod common;
+
+// Records all queries and answers the export queries with a graph of three
+// riders, a team and an unlabeled circuit with an index on the rider names.
+struct CsvConnection {
+    queries: Vec<String>,
+    nodes: Vec<Value>,
+    relations: Vec<Value>,
+}
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn pair(key: &str, value: Value) -> Value {
+    Value::Bulk(vec![data(key), value])
+}
+
+fn node(id: i64, labels: &[&str], properties: Vec<Value>) -> Value {
+    Value::Bulk(vec![
+        pair("id", Value::Int(id)),
+        pair(
+            "labels",
+            Value::Bulk(labels.iter().map(|l| data(l)).collect()),
+        ),
+        pair("properties", Value::Bulk(properties)),
+    ])
+}
+
+fn relation(id: i64, rel_type: &str, src: i64, dest: i64, properties: Vec<Value>) -> Value {
+    Value::Bulk(vec![
+        pair("id", Value::Int(id)),
+        pair("type", data(rel_type)),
+        pair("src_node", Value::Int(src)),
+        pair("dest_node", Value::Int(dest)),
+        pair("properties", Value::Bulk(properties)),
+    ])
+}
+
+impl Default for CsvConnection {
+    fn default() -> Self {
+        let rides = |id: i64, src: i64, since: i64| {
+            relation(id, "rides", src, 1, vec![pair("since", Value::Int(since))])
+        };
+        CsvConnection {
+            queries: vec![],
+            nodes: vec![
+                node(
+                    0,
+                    &["Rider"],
+                    vec![
+                        pair("name", data("Valentino Rossi")),
+                        pair("born", Value::Int(1979)),
+                    ],
+                ),
+                node(1, &["Team"], vec![pair("name", data("Honda"))]),
+                node(2, &["Rider"], vec![pair("name", data("Dani Pedrosa, Jr"))]),
+                node(
+                    3,
+                    &["Rider", "Champion"],
+                    vec![pair("name", data("Marc Marquez"))],
+                ),
+                node(4, &[], vec![pair("name", data("Mugello"))]),
+            ],
+            relations: vec![
+                rides(0, 0, 2000),
+                rides(1, 2, 2006),
+                relation(2, "raced.at", 3, 4, vec![]),
+            ],
+        }
+    }
+}
+
+fn response(header: &[&str], rows: Vec<Vec<Value>>) -> Value {
+    Value::Bulk(vec![
+        Value::Bulk(header.iter().map(|h| data(h)).collect()),
+        Value::Bulk(rows.into_iter().map(Value::Bulk).collect()),
+        Value::Bulk(vec![]),
+    ])
+}
+
+fn entity_id(entity: &Value) -> i64 {
+    match entity {
+        Value::Bulk(fields) => match &fields[0] {
+            Value::Bulk(id) => redis::from_redis_value(&id[1]).unwrap(),
+            _ => panic!("Entity without id"),
+        },
+        _ => panic!("Entity without id"),
+    }
+}
+
+// The entities of a page query with the id in the `CYPHER from=..` prefix
+fn page(query: &str, entities: &[Value]) -> Value {
+    let from: i64 = query
+        .trim_start_matches("CYPHER from=")
+        .split(' ')
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let limit: usize = query.rsplit(' ').next().unwrap().parse().unwrap();
+    let rows = entities
+        .iter()
+        .filter(|entity| entity_id(entity) >= from)
+        .take(limit)
+        .map(|entity| vec![entity.clone()])
+        .collect();
+    response(&["n"], rows)
+}
+
+impl CsvConnection {
+    fn answer(&self, query: &str) -> Value {
+        if query.contains("CALL db.indexes") {
+            return response(
+                &["label", "properties", "type", "entitytype"],
+                vec![vec![
+                    data("Rider"),
+                    Value::Bulk(vec![data("name")]),
+                    data("exact-match"),
+                    data("NODE"),
+                ]],
+            );
+        }
+        if query.contains("MATCH (n) WHERE id(n) >= $from") {
+            return page(query, &self.nodes);
+        }
+        if query.contains("MATCH ()-[r]->() WHERE id(r) >= $from") {
+            return page(query, &self.relations);
+        }
+        Value::Bulk(vec![Value::Bulk(vec![data("Nodes created: 1")])])
+    }
+
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        let query = args[2].clone();
+        let value = self.answer(&query);
+        self.queries.push(query);
+        Ok(value)
+    }
+}
+
+mock_connection!(CsvConnection);
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    dir
+}
+
+fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_import_csv() {
+    let dir = test_dir("redis_graph_test_import_csv");
+    write(
+        &dir,
+        "Rider.csv",
+        "name:ID,born:INT,teams:ARRAY\n\
+         Valentino Rossi,1979,\"['Yamaha', 'Honda']\"\n",
+    );
+    write(&dir, "Team.csv", "_key:ID,name\nt1,Honda\n");
+    write(
+        &dir,
+        "rides.csv",
+        ":START_ID(Rider),:END_ID(Team),since:INT\nValentino Rossi,t1,2000\n",
+    );
+
+    let import = CsvImport::new()
+        .with_nodes(dir.join("Rider.csv"))
+        .with_nodes_as(dir.join("Team.csv"), "Team")
+        .with_relations(dir.join("rides.csv"));
+    let mut con = CsvConnection::default();
+    let res = con.graph_import_csv("my_graph", &import).unwrap();
+    assert_eq!(res.rows, 3);
+    assert_eq!(res.batches, 3);
+    assert_eq!(
+        con.queries,
+        vec![
+            "CYPHER rows=[{name: 'Valentino Rossi', born: 1979, teams: ['Yamaha', 'Honda']}] \
+             UNWIND $rows AS row CREATE (n:Rider) SET n = row",
+            "CYPHER rows=[{name: 'Honda', _key: 't1'}] \
+             UNWIND $rows AS row CREATE (n:Team) SET n = row, n.__csv_import = true",
+            "CALL db.indexes()",
+            "CREATE INDEX FOR (n:Team) ON (n._key)",
+            "CYPHER rows=[{src: 'Valentino Rossi', dest: 't1', properties: {since: 2000}}] \
+             UNWIND $rows AS row MATCH (a:Rider {name: row.src}), (b:Team {_key: row.dest}) \
+             CREATE (a)-[r:rides]->(b) SET r = row.properties",
+            "DROP INDEX ON :Team(_key)",
+            "MATCH (n:Team) WHERE n.__csv_import = true SET n._key = NULL, n.__csv_import = NULL",
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_import_csv_errors() {
+    let dir = test_dir("redis_graph_test_import_csv_errors");
+    let rider = write(&dir, "Rider.csv", "name:ID,born:INT\na,1\nb,x\n");
+    let team = write(&dir, "Team.csv", "name:ID\nt1\n");
+    let rides = write(&dir, "rides.csv", "src,dest\na,t1\n");
+    let unknown = write(&dir, "drives.csv", ":START_ID(Car),:END_ID(Team)\na,t1\n");
+    let header = write(&dir, "Circuit.csv", "name:TEXT\nMugello\n");
+    let car = write(&dir, "Car.csv", "number:INT\n46\n");
+
+    let mut con = CsvConnection::default();
+    let err = con
+        .graph_import_csv("my_graph", &CsvImport::new().with_nodes(&rider))
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Rider.csv line 3: born: Invalid value x"),
+        "{}",
+        err
+    );
+    let err = con
+        .graph_import_csv(
+            "my_graph",
+            &CsvImport::new().with_nodes(&team).with_relations(&rides),
+        )
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("START_ID needs a label"),
+        "{}",
+        err
+    );
+    let err = con
+        .graph_import_csv(
+            "my_graph",
+            &CsvImport::new().with_nodes(&team).with_relations(&unknown),
+        )
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("No node file with label Car"),
+        "{}",
+        err
+    );
+    let err = con
+        .graph_import_csv(
+            "my_graph",
+            &CsvImport::new()
+                .with_nodes(&car)
+                .with_nodes(&team)
+                .with_relations(&unknown),
+        )
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("drives.csv line 2: START_ID: Invalid value a"),
+        "{}",
+        err
+    );
+    let err = con
+        .graph_import_csv("my_graph", &CsvImport::new().with_nodes(&header))
+        .unwrap_err();
+    assert!(err.to_string().contains("Circuit.csv: line 1"), "{}", err);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_export_csv() {
+    let mut properties = HashMap::new();
+    properties.insert("since".to_string(), Value::Int(2000));
+    let rides = RelationValue {
+        id: 3,
+        rel_type: "rides".to_string(),
+        src_node: 0,
+        dest_node: 1,
+        properties,
+    };
+    let header: Arc<[String]> = vec!["r".to_string(), "teams".to_string()].into();
+    let res = GraphResultSet {
+        header: header.to_vec(),
+        data: vec![
+            GraphResult::from_values(
+                header.clone(),
+                vec![
+                    GraphValue::Relation(rides),
+                    GraphValue::Scalar(Value::Bulk(vec![data("Yamaha"), Value::Int(1)])),
+                ],
+            ),
+            GraphResult::from_values(
+                header,
+                vec![
+                    GraphValue::Scalar(Value::Nil),
+                    GraphValue::Scalar(Value::Nil),
+                ],
+            ),
+        ],
+        metadata: vec![],
+    };
+
+    assert_eq!(
+        CsvExport::new().to_string(&res).unwrap(),
+        "r._id,r._type,r._src,r._dest,r.since,teams\n\
+         3,rides,0,1,2000,\"[\"\"Yamaha\"\",1]\"\n\
+         ,,,,,\n"
+    );
+    assert_eq!(
+        CsvExport::new()
+            .with_entities(EntityColumns::Json)
+            .to_string(&res)
+            .unwrap(),
+        "r,teams\n\
+         \"{\"\"id\"\":3,\"\"type\"\":\"\"rides\"\",\"\"src\"\":0,\"\"dest\"\":1,\"\"properties\"\":{\"\"since\"\":2000}}\",\
+         \"[\"\"Yamaha\"\",1]\"\n\
+         ,\n"
+    );
+}
+
+#[test]
+fn test_export_import_graph() {
+    let dir = test_dir("redis_graph_test_export_graph");
+    let mut con = CsvConnection::default();
+    let files = con.graph_export_csv("my_graph", &dir).unwrap();
+    assert_eq!(
+        files,
+        vec![
+            dir.join("nodes.csv"),
+            dir.join("nodes.Rider.csv"),
+            dir.join("nodes.Rider.Champion.csv"),
+            dir.join("nodes.Team.csv"),
+            dir.join("relations.raced_at.csv"),
+            dir.join("relations.rides.csv"),
+        ]
+    );
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(read("nodes.csv"), "_id:ID,:LABEL,name:STRING\n4,,Mugello\n");
+    assert_eq!(
+        read("nodes.Rider.csv"),
+        "_id:ID,:LABEL,born:INT,name:STRING\n\
+         0,Rider,1979,Valentino Rossi\n\
+         2,Rider,,\"Dani Pedrosa, Jr\"\n"
+    );
+    assert_eq!(
+        read("nodes.Rider.Champion.csv"),
+        "_id:ID,:LABEL,name:STRING\n3,Rider:Champion,Marc Marquez\n"
+    );
+    assert_eq!(
+        read("nodes.Team.csv"),
+        "_id:ID,:LABEL,name:STRING\n1,Team,Honda\n"
+    );
+    assert_eq!(
+        read("relations.raced_at.csv"),
+        ":START_ID(Rider),:END_ID(),:TYPE\n3,4,raced.at\n"
+    );
+    assert_eq!(
+        read("relations.rides.csv"),
+        ":START_ID(Rider),:END_ID(Team),:TYPE,since:INT\n0,1,rides,2000\n2,1,rides,2006\n"
+    );
+
+    let mut con = CsvConnection::default();
+    let import = CsvImport::from_dir(&dir).unwrap();
+    let res = con.graph_import_csv("copy", &import).unwrap();
+    assert_eq!(res.rows, 8);
+    assert_eq!(
+        con.queries[..4],
+        [
+            "CYPHER rows=[{name: 'Marc Marquez', _id: '3'}] \
+             UNWIND $rows AS row CREATE (n:Rider:Champion) SET n = row, n.__csv_import = true",
+            "CYPHER rows=[{born: 1979, name: 'Valentino Rossi', _id: '0'}, \
+             {born: null, name: 'Dani Pedrosa, Jr', _id: '2'}] \
+             UNWIND $rows AS row CREATE (n:Rider) SET n = row, n.__csv_import = true",
+            "CYPHER rows=[{name: 'Honda', _id: '1'}] \
+             UNWIND $rows AS row CREATE (n:Team) SET n = row, n.__csv_import = true",
+            "CYPHER rows=[{name: 'Mugello', _id: '4'}] \
+             UNWIND $rows AS row CREATE (n) SET n = row, n.__csv_import = true",
+        ]
+    );
+    assert_eq!(
+        con.queries[5..9],
+        [
+            "CREATE INDEX FOR (n:Rider) ON (n._id)",
+            "CYPHER rows=[{src: '3', dest: '4', properties: {}}] \
+             UNWIND $rows AS row MATCH (a:Rider {_id: row.src}), (b {_id: row.dest}) \
+             CREATE (a)-[r:`raced.at`]->(b) SET r = row.properties",
+            "CREATE INDEX FOR (n:Team) ON (n._id)",
+            "CYPHER rows=[{src: '0', dest: '1', properties: {since: 2000}}, \
+             {src: '2', dest: '1', properties: {since: 2006}}] \
+             UNWIND $rows AS row MATCH (a:Rider {_id: row.src}), (b:Team {_id: row.dest}) \
+             CREATE (a)-[r:rides]->(b) SET r = row.properties",
+        ]
+    );
+    assert_eq!(
+        con.queries[14],
+        "MATCH (n) WHERE n.__csv_import = true SET n._id = NULL, n.__csv_import = NULL"
+    );
+    assert_eq!(con.queries.len(), 15);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_import_csv_labels_and_types() {
+    let dir = test_dir("redis_graph_test_import_csv_labels");
+    let riders = write(
+        &dir,
+        "riders.csv",
+        "name:ID,:LABEL\nValentino Rossi,Rider:Champion\nMarc Marquez,Rider:Champion\nDani Pedrosa,Rider\n",
+    );
+    let relations = write(
+        &dir,
+        "relations.csv",
+        ":START_ID(Rider),:END_ID(Rider),:TYPE\nValentino Rossi,Marc Marquez,beat\nMarc Marquez,Dani Pedrosa,\n",
+    );
+    let import = CsvImport::new()
+        .with_nodes(&riders)
+        .with_relations_as(&relations, "rival");
+    let mut con = CsvConnection::default();
+    let res = con.graph_import_csv("my_graph", &import).unwrap();
+    assert_eq!(res.rows, 5);
+    assert_eq!(
+        con.queries,
+        vec![
+            "CYPHER rows=[{name: 'Valentino Rossi'}, {name: 'Marc Marquez'}] \
+             UNWIND $rows AS row CREATE (n:Rider:Champion) SET n = row",
+            "CYPHER rows=[{name: 'Dani Pedrosa'}] UNWIND $rows AS row CREATE (n:Rider) SET n = row",
+            "CALL db.indexes()",
+            "CYPHER rows=[{src: 'Valentino Rossi', dest: 'Marc Marquez', properties: {}}] \
+             UNWIND $rows AS row MATCH (a:Rider {name: row.src}), (b:Rider {name: row.dest}) \
+             CREATE (a)-[r:beat]->(b) SET r = row.properties",
+            "CYPHER rows=[{src: 'Marc Marquez', dest: 'Dani Pedrosa', properties: {}}] \
+             UNWIND $rows AS row MATCH (a:Rider {name: row.src}), (b:Rider {name: row.dest}) \
+             CREATE (a)-[r:rival]->(b) SET r = row.properties",
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_export_csv_pages() {
+    let dir = test_dir("redis_graph_test_export_csv_pages");
+    let mut con = CsvConnection::default();
+    con.nodes
+        .extend((5..2500).map(|id| node(id, &["Team"], vec![pair("name", data("Ducati"))])));
+    con.graph_export_csv("my_graph", &dir).unwrap();
+    let teams = std::fs::read_to_string(dir.join("nodes.Team.csv")).unwrap();
+    assert_eq!(teams.lines().count(), 2497);
+    assert!(teams.ends_with("2499,Team,Ducati\n"));
+    let pages: Vec<&String> = con
+        .queries
+        .iter()
+        .filter(|q| q.contains("MATCH (n)"))
+        .collect();
+    assert_eq!(
+        pages,
+        [
+            "CYPHER from=0 MATCH (n) WHERE id(n) >= $from RETURN n ORDER BY id(n) LIMIT 1000",
+            "CYPHER from=1000 MATCH (n) WHERE id(n) >= $from RETURN n ORDER BY id(n) LIMIT 1000",
+            "CYPHER from=2000 MATCH (n) WHERE id(n) >= $from RETURN n ORDER BY id(n) LIMIT 1000",
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_export_csv_file_names() {
+    let dir = test_dir("redis_graph_test_export_csv_names");
+    let mut con = CsvConnection {
+        nodes: vec![node(0, &["../up"], vec![])],
+        relations: vec![],
+        ..Default::default()
+    };
+    let files = con.graph_export_csv("my_graph", &dir).unwrap();
+    assert_eq!(files, vec![dir.join("nodes.___up.csv")]);
+
+    con.nodes = vec![node(0, &["A B"], vec![]), node(1, &["A_B"], vec![])];
+    let err = con.graph_export_csv("my_graph", &dir).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("nodes.A_B.csv would be written for more than one label"),
+        "{}",
+        err
+    );
+
+    con.nodes = vec![node(0, &["Rider"], vec![])];
+    con.relations = vec![relation(0, "rides", 0, 7, vec![])];
+    let err = con.graph_export_csv("my_graph", &dir).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Relation 0 connects node 7, which was not exported"),
+        "{}",
+        err
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
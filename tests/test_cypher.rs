@@ -0,0 +1,51 @@
+extern crate redis_graph;
+
+use redis_graph::*;
+
+#[test]
+fn test_classify_read_queries() {
+    for query in [
+        "MATCH (n) RETURN n",
+        "MATCH (r:Rider)-[:rides]->(t:Team) WHERE t.name = 'Yamaha' RETURN r.name",
+        "MATCH (n:Set)-[:CREATED]->(m:Merge) RETURN n.delete, m.remove",
+        "MATCH (n {set: 1}) RETURN n",
+        "MATCH (n) WHERE n.name = 'CREATE (:A)' RETURN n",
+        "MATCH (n) WHERE n.name = \"it\\\"s SET\" RETURN n",
+        "MATCH (`create`) RETURN `create`",
+        "MATCH (n) // SET n.x = 1\nRETURN n",
+        "MATCH (n) /* DELETE n */ RETURN n",
+        "MATCH (n) WHERE n.x = $set RETURN n",
+        "CALL db.labels()",
+        "CALL db.idx.fulltext.queryNodes('Rider', 'Valentino') YIELD node RETURN node",
+    ] {
+        assert_eq!(classify_query(query), QueryKind::ReadOnly, "{}", query);
+    }
+}
+
+#[test]
+fn test_classify_write_queries() {
+    for query in [
+        "CREATE (:Rider {name:'Valentino Rossi'})",
+        "MERGE (n:Rider {name:'Pam'})",
+        "MATCH (n) SET n.name = 'x'",
+        "match (n) detach delete n",
+        "MATCH (n) REMOVE n.name",
+        "MATCH (n) WHERE n.name = 'x' /* comment */ DELETE n",
+        "CREATE INDEX FOR (r:Rider) ON (r.name)",
+        "DROP INDEX ON :Rider(name)",
+        "CALL db.idx.fulltext.createNodeIndex('Rider', 'name')",
+        "CALL db.idx.fulltext.drop('Rider')",
+    ] {
+        assert_eq!(classify_query(query), QueryKind::Write, "{}", query);
+    }
+}
+
+#[test]
+fn test_unterminated_literals() {
+    assert!(!is_read_only_query("MATCH (n) WHERE n.name = 'CREATE"));
+    assert!(!is_read_only_query("MATCH (n) WHERE n.name = 'x"));
+    assert!(!is_read_only_query("MATCH (n) /* DELETE n"));
+    assert!(!is_read_only_query("MATCH (`n) RETURN 1"));
+    assert!(!is_read_only_query("MATCH (n) WHERE n.name = 'x\\'"));
+    assert!(is_read_only_query("MATCH (n) WHERE n.name = 'x' // CREATE"));
+}
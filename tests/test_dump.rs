@@ -0,0 +1,442 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{RedisResult, Value};
+use redis_graph::*;
+
+#[macro_use]
+mod common;
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn strings(values: &[&str]) -> Value {
+    Value::Bulk(values.iter().map(|v| data(v)).collect())
+}
+
+fn pair(key: &str, value: Value) -> Value {
+    Value::Bulk(vec![data(key), value])
+}
+
+fn response(header: &[&str], rows: Vec<Vec<Value>>) -> Value {
+    Value::Bulk(vec![
+        strings(header),
+        Value::Bulk(rows.into_iter().map(Value::Bulk).collect()),
+        Value::Bulk(vec![]),
+    ])
+}
+
+// A property with its value, type and list element types
+fn property(key: &str, value: Value, type_name: &str, elements: &[&str]) -> (Value, Value) {
+    (
+        pair(key, value),
+        Value::Bulk(vec![data(key), data(type_name), strings(elements)]),
+    )
+}
+
+fn node(id: i64, labels: &[&str], properties: Vec<(Value, Value)>) -> Vec<Value> {
+    let (values, types): (Vec<Value>, Vec<Value>) = properties.into_iter().unzip();
+    vec![
+        Value::Bulk(vec![
+            pair("id", Value::Int(id)),
+            pair("labels", strings(labels)),
+            pair("properties", Value::Bulk(values)),
+        ]),
+        Value::Bulk(types),
+    ]
+}
+
+fn relation(
+    id: i64,
+    rel_type: &str,
+    src: i64,
+    dest: i64,
+    properties: Vec<(Value, Value)>,
+) -> Vec<Value> {
+    let (values, types): (Vec<Value>, Vec<Value>) = properties.into_iter().unzip();
+    vec![
+        Value::Bulk(vec![
+            pair("id", Value::Int(id)),
+            pair("type", data(rel_type)),
+            pair("src_node", Value::Int(src)),
+            pair("dest_node", Value::Int(dest)),
+            pair("properties", Value::Bulk(values)),
+        ]),
+        Value::Bulk(types),
+    ]
+}
+
+// Answers the dump queries with a rider, a team with a point and an
+// unlabeled node in pages of at most resultset_size rows, and the restore
+// commands with statistics. With missing_node the unlabeled node is deleted before the
+// nodes are read.
+fn answer(args: &[String], resultset_size: usize, missing_node: bool) -> Value {
+    let query = args.get(2).map(|q| q.as_str()).unwrap_or_default();
+    match args[0].as_str() {
+        "EXISTS" => return Value::Int((args[1] == "existing") as i64),
+        "GRAPH.CONSTRAINT" => return Value::Okay,
+        "GRAPH.QUERY" if query.contains("CREATE (n") => {
+            return Value::Bulk(vec![strings(&["Nodes created: 1"])])
+        }
+        "GRAPH.QUERY" if query.contains("CREATE (a)") => {
+            return Value::Bulk(vec![strings(&["Relationships created: 1"])])
+        }
+        "GRAPH.QUERY" => return Value::Bulk(vec![strings(&[])]),
+        _ => {}
+    }
+    if query.starts_with("CALL dbms.procedures") {
+        return response(&["name"], vec![vec![data("db.constraints")]]);
+    }
+    if query.starts_with("CALL db.indexes") {
+        return response(
+            &["label", "properties", "types", "entitytype"],
+            vec![vec![
+                data("Rider"),
+                strings(&["name"]),
+                Value::Bulk(vec![data("name"), strings(&["RANGE", "FULLTEXT"])]),
+                data("NODE"),
+            ]],
+        );
+    }
+    if query.starts_with("CALL db.constraints") {
+        return response(
+            &["type", "label", "properties", "entitytype", "status"],
+            vec![vec![
+                data("UNIQUE"),
+                data("Rider"),
+                strings(&["name"]),
+                data("NODE"),
+                data("OPERATIONAL"),
+            ]],
+        );
+    }
+    let from: i64 = query["CYPHER from=".len()..]
+        .split(' ')
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let limit: usize = query.rsplit("LIMIT ").next().unwrap().parse().unwrap();
+    let rows = if query.contains("(n)") {
+        let mut nodes = vec![
+            node(
+                0,
+                &["Rider"],
+                vec![
+                    property("name", data("Valentino 'The Doctor' Rossi"), "String", &[]),
+                    property("born", Value::Int(1979), "Integer", &[]),
+                    property("height", data("1.82"), "Float", &[]),
+                    property("active", data("false"), "Boolean", &[]),
+                    property(
+                        "wins",
+                        Value::Bulk(vec![Value::Int(89), data("2.5")]),
+                        "List",
+                        &["Integer", "Float"],
+                    ),
+                ],
+            ),
+            node(
+                1,
+                &["Team"],
+                vec![
+                    property("name", data("1"), "String", &[]),
+                    property(
+                        "hq",
+                        data("point({latitude:44.8, longitude:10.3})"),
+                        "Point",
+                        &[],
+                    ),
+                ],
+            ),
+            node(2, &[], vec![]),
+        ];
+        if missing_node {
+            nodes.pop();
+        }
+        nodes
+    } else {
+        vec![
+            relation(
+                0,
+                "rides",
+                0,
+                1,
+                vec![property("since", Value::Int(2004), "Integer", &[])],
+            ),
+            relation(
+                1,
+                "sponsors",
+                2,
+                1,
+                vec![property(
+                    "terms",
+                    Value::Bulk(vec![Value::Bulk(vec![data("years"), Value::Int(2)])]),
+                    "List",
+                    &["Map"],
+                )],
+            ),
+        ]
+    };
+    let rows = rows
+        .into_iter()
+        .enumerate()
+        .filter(|(id, _)| *id as i64 >= from)
+        .map(|(_, row)| row)
+        .take(limit.min(resultset_size))
+        .collect();
+    response(&["x", "types"], rows)
+}
+
+struct DumpConnection {
+    commands: Vec<Vec<String>>,
+    resultset_size: usize,
+    missing_node: bool,
+}
+
+impl DumpConnection {
+    fn new() -> Self {
+        DumpConnection {
+            commands: vec![],
+            resultset_size: 10000,
+            missing_node: false,
+        }
+    }
+
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        let value = answer(&args, self.resultset_size, self.missing_node);
+        self.commands.push(args);
+        Ok(value)
+    }
+}
+
+mock_connection!(DumpConnection);
+
+// Answers async commands like DumpConnection
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+struct AsyncDumpConnection(DumpConnection);
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+impl redis::aio::ConnectionLike for AsyncDumpConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a redis::Cmd) -> redis::RedisFuture<'a, Value> {
+        let value =
+            redis::ConnectionLike::req_packed_command(&mut self.0, &cmd.get_packed_command());
+        Box::pin(async move { value })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        _: &'a redis::Pipeline,
+        _: usize,
+        _: usize,
+    ) -> redis::RedisFuture<'a, Vec<Value>> {
+        Box::pin(async move { Ok(vec![]) })
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
+
+fn dump(format: DumpFormat) -> String {
+    let mut con = DumpConnection::new();
+    let mut out = Vec::new();
+    let result = con
+        .graph_dump(
+            "my_graph",
+            &GraphDump::new(format).with_page_size(2),
+            &mut out,
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        DumpResult {
+            nodes: 3,
+            relations: 2,
+            indexes: 2,
+            constraints: 1,
+            skipped: vec![
+                DumpSkipped {
+                    entity_type: EntityType::Node,
+                    id: 1,
+                    property: "hq".to_string(),
+                    reason: "unsupported type Point".to_string(),
+                },
+                DumpSkipped {
+                    entity_type: EntityType::Relationship,
+                    id: 1,
+                    property: "terms".to_string(),
+                    reason: "unsupported type Map".to_string(),
+                },
+            ],
+        }
+    );
+    // Three schema queries, node pages from 0, 2 and 3 and relation pages
+    // from 0 and 2
+    assert_eq!(con.commands.len(), 8);
+    assert!(con.commands.iter().all(|c| c[0] == "GRAPH.RO_QUERY"));
+    String::from_utf8(out).unwrap()
+}
+
+// The restore commands without the graph key
+fn restore(restore: &GraphRestore, input: &str) -> (Vec<String>, Vec<RestoreProgress>) {
+    let mut con = DumpConnection::new();
+    let mut progress = vec![];
+    let result = con
+        .graph_restore("my_copy", restore, input.as_bytes(), |p| {
+            progress.push(p.clone())
+        })
+        .unwrap();
+    assert_eq!(progress.last(), Some(&result));
+    assert_eq!(con.commands[0], vec!["EXISTS", "my_copy"]);
+    let commands = con.commands[1..]
+        .iter()
+        .map(|c| {
+            let mut args = c.clone();
+            args.retain(|arg| arg != "my_copy");
+            assert_eq!(args.len(), c.len() - 1);
+            args.join(" ")
+        })
+        .collect();
+    (commands, progress)
+}
+
+const SCRIPT: &str = "// Logical dump of a Redis graph, one statement per line
+CREATE INDEX FOR (n:Rider) ON (n.__dump_id)
+CREATE INDEX FOR (n:Team) ON (n.__dump_id)
+UNWIND [{__dump_id: 0, active: false, born: 1979, height: 1.82, name: 'Valentino \\'The Doctor\\' Rossi', wins: [89, 2.5]}] AS row CREATE (n:Rider) SET n = row
+UNWIND [{__dump_id: 1, name: '1'}] AS row CREATE (n:Team) SET n = row
+UNWIND [{__dump_id: 2}] AS row CREATE (n) SET n = row
+UNWIND [{src: 0, dest: 1, properties: {since: 2004}}] AS row MATCH (a:Rider {__dump_id: row.src}), (b:Team {__dump_id: row.dest}) CREATE (a)-[r:rides]->(b) SET r = row.properties
+UNWIND [{src: 2, dest: 1, properties: {}}] AS row MATCH (a {__dump_id: row.src}), (b:Team {__dump_id: row.dest}) CREATE (a)-[r:sponsors]->(b) SET r = row.properties
+MATCH (n:Rider) WHERE n.__dump_id IS NOT NULL SET n.__dump_id = NULL
+DROP INDEX ON :Rider(__dump_id)
+MATCH (n:Team) WHERE n.__dump_id IS NOT NULL SET n.__dump_id = NULL
+DROP INDEX ON :Team(__dump_id)
+MATCH (n) WHERE n.__dump_id IS NOT NULL SET n.__dump_id = NULL
+CREATE INDEX FOR (n:Rider) ON (n.name)
+CALL db.idx.fulltext.createNodeIndex('Rider', 'name')
+GRAPH.CONSTRAINT CREATE UNIQUE NODE Rider PROPERTIES 1 name
+";
+
+#[test]
+fn test_dump_cypher() {
+    assert_eq!(dump(DumpFormat::Cypher), SCRIPT);
+}
+
+#[test]
+fn test_restore_cypher() {
+    let (commands, progress) = restore(&GraphRestore::new(DumpFormat::Cypher), SCRIPT);
+    let statements: Vec<String> = SCRIPT
+        .lines()
+        .skip(1)
+        .map(|line| match line.strip_prefix("GRAPH.CONSTRAINT ") {
+            Some(args) => format!("GRAPH.CONSTRAINT {}", args),
+            None => format!("GRAPH.QUERY {}", line),
+        })
+        .collect();
+    assert_eq!(commands, statements);
+    assert_eq!(progress.len(), 15);
+    let last = &progress[14];
+    assert_eq!(last.statements, 15);
+    assert_eq!(last.statistics.nodes_created, 3);
+    assert_eq!(last.statistics.relationships_created, 2);
+
+    let mut con = DumpConnection::new();
+    let err = con
+        .graph_restore(
+            "existing",
+            &GraphRestore::new(DumpFormat::Cypher),
+            SCRIPT.as_bytes(),
+            |_| {},
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("needs a fresh key"));
+    assert_eq!(con.commands.len(), 1);
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+#[test]
+fn test_restore_cypher_async() {
+    let mut con = AsyncDumpConnection(DumpConnection::new());
+    let restore = GraphRestore::new(DumpFormat::Cypher);
+    let result = futures::executor::block_on(con.graph_restore(
+        "my_copy",
+        &restore,
+        SCRIPT.as_bytes(),
+        |_| {},
+    ))
+    .unwrap();
+    assert_eq!(result.statements, 15);
+    assert_eq!(con.0.commands.len(), 16);
+    assert_eq!(
+        con.0.commands[15].join(" "),
+        "GRAPH.CONSTRAINT CREATE my_copy UNIQUE NODE Rider PROPERTIES 1 name"
+    );
+}
+
+#[test]
+fn test_dump_truncated_pages() {
+    // Pages of three truncated to two rows by RESULTSET_SIZE still dump
+    // every node and relation
+    let mut con = DumpConnection {
+        resultset_size: 2,
+        ..DumpConnection::new()
+    };
+    let mut out = Vec::new();
+    let result = con
+        .graph_dump(
+            "my_graph",
+            &GraphDump::new(DumpFormat::Cypher).with_page_size(3),
+            &mut out,
+        )
+        .unwrap();
+    assert_eq!(result.nodes, 3);
+    assert_eq!(result.relations, 2);
+    assert_eq!(String::from_utf8(out).unwrap(), SCRIPT);
+}
+
+#[test]
+fn test_dump_missing_node() {
+    // Node 2 is deleted before the nodes are read
+    let mut con = DumpConnection {
+        missing_node: true,
+        ..DumpConnection::new()
+    };
+    let err = con
+        .graph_dump(
+            "my_graph",
+            &GraphDump::new(DumpFormat::Cypher).with_page_size(3),
+            Vec::new(),
+        )
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Relation 1 references node 2 missing from the dump"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_dump_restore_json() {
+    let archive = dump(DumpFormat::Json);
+    assert!(archive.starts_with(
+        "{\"version\":1,\"indexes\":[{\"label\":\"Rider\",\"properties\":[\"name\"],\"index_type\":\"fulltext\""
+    ));
+    assert!(archive.contains(
+        "\"nodes\":[\n{\"id\":0,\"labels\":[\"Rider\"],\"properties\":{\"active\":false,\"born\":1979,\
+         \"height\":1.82,\"name\":\"Valentino 'The Doctor' Rossi\",\"wins\":[89,2.5]}},\n\
+         {\"id\":1,\"labels\":[\"Team\"],\"properties\":{\"name\":\"1\"}},\n"
+    ));
+    assert!(archive.ends_with(
+        "\"relations\":[\n{\"id\":0,\"type\":\"rides\",\"src\":0,\"dest\":1,\"properties\":{\"since\":2004}},\n\
+         {\"id\":1,\"type\":\"sponsors\",\"src\":2,\"dest\":1,\"properties\":{}}\n]}\n"
+    ));
+
+    // Restoring the archive in batches of the dump page size replays the
+    // same statements as the script
+    let restore_json = GraphRestore::new(DumpFormat::Json).with_batch_rows(2);
+    let (from_json, progress) = restore(&restore_json, &archive);
+    let (from_script, _) = restore(&GraphRestore::new(DumpFormat::Cypher), SCRIPT);
+    assert_eq!(from_json, from_script);
+    assert_eq!(progress.last().unwrap().statistics.nodes_created, 3);
+}
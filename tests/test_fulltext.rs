@@ -0,0 +1,64 @@
+extern crate redis_graph;
+
+use redis_graph::*;
+
+#[test]
+fn test_escape_fulltext() {
+    assert_eq!(escape_fulltext("rossi"), "rossi");
+    assert_eq!(escape_fulltext("o'neil-smith"), "o\\'neil\\-smith");
+    assert_eq!(escape_fulltext("a b@c.d"), "a\\ b\\@c\\.d");
+    assert_eq!(escape_fulltext("50%*"), "50\\%\\*");
+    assert_eq!(escape_fulltext("back\\slash"), "back\\\\slash");
+}
+
+#[test]
+fn test_fulltext_query_builder() {
+    assert!(FulltextQuery::new().is_empty());
+    assert_eq!(FulltextQuery::new().term("rossi").to_string(), "rossi");
+    assert_eq!(FulltextQuery::new().prefix("ros*").to_string(), "ros\\**");
+    assert_eq!(
+        FulltextQuery::new()
+            .fuzzy("rosi", 2)
+            .fuzzy("x", 9)
+            .to_string(),
+        "%%rosi%% %%%x%%%"
+    );
+    assert_eq!(
+        FulltextQuery::new().phrase("say \"hi\"").to_string(),
+        "\"say \\\"hi\\\"\""
+    );
+    assert_eq!(
+        FulltextQuery::new()
+            .field("name", FulltextQuery::new().term("a").prefix("b"))
+            .to_string(),
+        "@name:(a b*)"
+    );
+    assert_eq!(
+        FulltextQuery::new()
+            .any(vec![
+                FulltextQuery::new().term("honda"),
+                FulltextQuery::new().term("red").term("bull"),
+            ])
+            .not(FulltextQuery::new().term("ducati"))
+            .raw("@born:[1970 1980]")
+            .to_string(),
+        "(honda|(red bull)) -ducati @born:[1970 1980]"
+    );
+}
+
+#[test]
+fn test_fulltext_query_empty_clauses() {
+    let empty = FulltextQuery::new;
+    assert!(empty().any(vec![]).is_empty());
+    assert!(empty().any(vec![empty(), empty()]).is_empty());
+    assert!(empty().field("name", empty()).is_empty());
+    assert!(empty().not(empty()).term("").is_empty());
+    assert_eq!(
+        empty()
+            .term("rossi")
+            .any(vec![empty(), empty().term("honda")])
+            .field("name", empty())
+            .to_string(),
+        "rossi honda"
+    );
+}
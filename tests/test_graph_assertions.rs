@@ -10,7 +10,7 @@ pub fn check_match_query_result(r: GraphResultSet) {
     assert_eq!(r.header, ["n1", "r", "n2.name"]);
     assert_eq!(r.data.len(), 1);
     let data = r.data.get(0).unwrap();
-    assert_eq!(data.data.len(), 3);
+    assert_eq!(data.len(), 3);
 
     match data.get_value("n1") {
         Some(GraphValue::Node(node)) => {
@@ -54,7 +54,7 @@ pub fn check_match_query_result(r: GraphResultSet) {
 
 pub fn check_match_scalar_result(res: GraphResultSet) {
     assert_eq!(res.data.len(), 1);
-    assert_eq!(res.data.get(0).unwrap().data.len(), 2);
+    assert_eq!(res.data.get(0).unwrap().len(), 2);
     let driver: String = res.data.get(0).unwrap().get_scalar("r.name").unwrap();
     let team: String = res.data.get(0).unwrap().get_scalar("t.name").unwrap();
     assert_eq!(driver, "Valentino Rossi");
@@ -68,6 +68,153 @@ pub fn check_query_all_nodes(res: GraphResultSet) {
     }
 }
 
+pub fn check_query_stream(res: GraphResultStream) {
+    assert_eq!(res.header(), ["r"]);
+    assert_eq!(res.len(), 3);
+    for row in res {
+        let node = row.unwrap().get_node("r").unwrap().clone();
+        assert!(node.labels.contains(&"Rider".to_string()));
+    }
+}
+
+pub fn check_query_as(res: Vec<(NodeValue, String, Option<i64>)>) {
+    assert_eq!(res.len(), 3);
+    for (node, name, _) in res.iter() {
+        assert!(node.labels.contains(&"Rider".to_string()));
+        assert_eq!(node.get_property_option::<String>("name").unwrap(), *name);
+    }
+    assert_eq!(res.iter().filter(|(_, _, born)| born.is_none()).count(), 1);
+}
+
+pub fn check_fulltext_query(res: Vec<(NodeValue, f64)>) {
+    assert_eq!(res.len(), 1);
+    let name: String = res[0].0.get_property_option("name").unwrap();
+    assert_eq!(name, "Valentino Rossi");
+    assert!(res[0].1 > 0.0);
+}
+
+pub fn check_call_labels(rows: Vec<ProcedureRow>) {
+    let mut labels: Vec<String> = rows
+        .iter()
+        .map(|row| row.get_value("label").unwrap().unwrap())
+        .collect();
+    labels.sort();
+    assert_eq!(labels, vec!["Rider", "Team"]);
+}
+
+pub fn check_subgraph(graph: Subgraph) {
+    assert_eq!(graph.node_count(), 6);
+    assert_eq!(graph.relation_count(), 3);
+    let dot = GraphExport::new(GraphFormat::Dot)
+        .with_caption("name")
+        .to_string(&graph);
+    assert!(dot.contains("[label=\"Valentino Rossi\""));
+    assert_eq!(dot.matches("[label=\"rides\"").count(), 3);
+}
+
+pub fn check_dump_restore((dump, progress, copy): (DumpResult, RestoreProgress, Subgraph)) {
+    assert_eq!(dump.nodes, 6);
+    assert_eq!(dump.relations, 3);
+    assert_eq!(progress.statistics.nodes_created, 6);
+    assert_eq!(progress.statistics.relationships_created, 3);
+    check_subgraph(copy);
+}
+
+pub fn check_graph_copy((result, copy): (CopyResult, Subgraph)) {
+    assert_eq!(result.nodes, 6);
+    assert_eq!(result.relations, 3);
+    assert_eq!(result.statistics.nodes_created, 6);
+    check_subgraph(copy);
+}
+
+pub fn check_schema(schema: GraphSchema) {
+    let riders = schema.label("Rider").unwrap();
+    assert_eq!(riders.count, 3);
+    assert_eq!(riders.properties["name"], vec![PropertyType::String]);
+    assert_eq!(riders.properties["born"], vec![PropertyType::Integer]);
+    assert_eq!(schema.label("Team").unwrap().count, 3);
+    assert_eq!(schema.relationship_type("rides").unwrap().count, 3);
+    assert!(schema.property_keys.contains(&"name".to_string()));
+}
+
+pub fn test_data_schema() -> GraphSchema {
+    GraphSchema::new()
+        .with_label(
+            "Rider",
+            &[
+                ("name", PropertyType::String),
+                ("born", PropertyType::Integer),
+            ],
+        )
+        .with_label("Team", &[("name", PropertyType::String)])
+        .with_relationship_type("rides", &[])
+}
+
+pub fn check_schema_diff(diff: SchemaDiff) {
+    assert!(diff.is_empty(), "{}", diff);
+    assert!(diff.statements(true).is_empty());
+}
+
+pub fn test_migrator() -> Migrator {
+    Migrator::new(vec![Migration::cypher(
+        1,
+        "circuit",
+        "CREATE (:Circuit {name: 'Mugello'})",
+    )
+    .with_down("MATCH (c:Circuit) DELETE c")])
+    .unwrap()
+}
+
+pub fn check_migrate(res: (Vec<AppliedMigration>, Vec<AppliedMigration>)) {
+    let (migrated, rolled_back) = res;
+    assert_eq!(migrated.len(), 1);
+    assert_eq!(migrated[0].name, "circuit");
+    assert!(rolled_back.is_empty());
+}
+
+pub fn test_bulk_circuits() -> Vec<ParamValue> {
+    ["Mugello", "Assen", "Jerez"]
+        .iter()
+        .map(|name| ParamValue::Map(vec![("name".to_string(), ParamValue::from(*name))]))
+        .collect()
+}
+
+pub fn check_bulk_write(res: BulkWriteResult) {
+    assert_eq!(res.batches, 2);
+    assert_eq!(res.rows, 3);
+    assert_eq!(res.statistics.nodes_created, 3);
+    assert_eq!(res.statistics.properties_set, 3);
+}
+
+pub fn test_bulk_nodes() -> Vec<BulkNode> {
+    vec![
+        BulkNode::new("r1", "Rider")
+            .with_property("name", "Valentino Rossi")
+            .with_property("born", 1979),
+        BulkNode::new("r2", "Rider").with_property("name", "Andrea Dovizioso"),
+        BulkNode::new("t1", "Team").with_property("name", "Yamaha"),
+    ]
+}
+
+pub fn test_bulk_relations() -> Vec<BulkRelation> {
+    vec![
+        BulkRelation::new("r1", "rides", "t1"),
+        BulkRelation::new("r2", "rides", "t1"),
+    ]
+}
+
+pub fn check_bulk_load(res: BulkLoadResult) {
+    assert_eq!(res.nodes_created, 3);
+    assert_eq!(res.relations_created, 2);
+}
+
+pub fn check_paginate(res: Vec<RedisResult<GraphResult>>) {
+    assert_eq!(res.len(), 3);
+    for row in res {
+        assert!(row.unwrap().get_node("r").is_some());
+    }
+}
+
 pub fn check_unserialize_option(res: GraphResultSet) {
     let born: Vec<Option<usize>> = res.data.iter().map(|v| v.get_scalar("r.born")).collect();
     assert_eq!(born.iter().filter(|v| v.is_none()).count(), 1);
@@ -109,3 +256,7 @@ pub fn check_graph_delete_success(r: RedisResult<String>) {
 pub fn check_graph_explain_result(r: RedisResult<Vec<String>>) {
     assert!(r.unwrap().len() > 0);
 }
+
+pub fn check_graph_list_result(r: RedisResult<Vec<String>>, name: &str) {
+    assert!(r.unwrap().contains(&name.to_string()));
+}
@@ -0,0 +1,418 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{ErrorKind, RedisError, RedisResult, Value};
+use redis_graph::*;
+
+#[macro_use]
+mod common;
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn strings(values: &[&str]) -> Value {
+    Value::Bulk(values.iter().map(|v| data(v)).collect())
+}
+
+fn pair(key: &str, value: Value) -> Value {
+    Value::Bulk(vec![data(key), value])
+}
+
+fn response(header: &[&str], rows: Vec<Vec<Value>>) -> Value {
+    Value::Bulk(vec![
+        strings(header),
+        Value::Bulk(rows.into_iter().map(Value::Bulk).collect()),
+        Value::Bulk(vec![]),
+    ])
+}
+
+// A node with a name and optionally a birth year, with their types
+fn node(id: i64, label: &str, name: &str, born: Option<i64>) -> Vec<Value> {
+    let mut values = vec![pair("name", data(name))];
+    let mut types = vec![strings(&["name", "String"])];
+    if let Some(born) = born {
+        values.push(pair("born", Value::Int(born)));
+        types.push(strings(&["born", "Integer"]));
+    }
+    let types = types
+        .into_iter()
+        .map(|t| match t {
+            Value::Bulk(mut t) => {
+                t.push(strings(&[]));
+                Value::Bulk(t)
+            }
+            t => t,
+        })
+        .collect();
+    vec![
+        Value::Bulk(vec![
+            pair("id", Value::Int(id)),
+            pair("labels", strings(&[label])),
+            pair("properties", Value::Bulk(values)),
+        ]),
+        Value::Bulk(types),
+    ]
+}
+
+fn rides(id: i64, src: i64, dest: i64, since: i64) -> Vec<Value> {
+    vec![
+        Value::Bulk(vec![
+            pair("id", Value::Int(id)),
+            pair("type", data("rides")),
+            pair("src_node", Value::Int(src)),
+            pair("dest_node", Value::Int(dest)),
+            pair(
+                "properties",
+                Value::Bulk(vec![pair("since", Value::Int(since))]),
+            ),
+        ]),
+        Value::Bulk(vec![Value::Bulk(vec![
+            data("since"),
+            data("Integer"),
+            strings(&[]),
+        ])]),
+    ]
+}
+
+// Answers the source queries with two riders and two teams in pages of two
+fn answer_source(query: &str) -> Value {
+    if query.starts_with("CALL dbms.procedures") {
+        return response(&["name"], vec![vec![data("db.constraints")]]);
+    }
+    if query.starts_with("CALL db.indexes") {
+        let index = |label| {
+            vec![
+                data(label),
+                strings(&["name"]),
+                Value::Bulk(vec![data("name"), strings(&["RANGE"])]),
+                data("NODE"),
+            ]
+        };
+        return response(
+            &["label", "properties", "types", "entitytype"],
+            vec![index("Rider"), index("Team")],
+        );
+    }
+    if query.starts_with("CALL db.constraints") {
+        return response(
+            &["type", "label", "properties", "entitytype", "status"],
+            vec![vec![
+                data("UNIQUE"),
+                data("Team"),
+                strings(&["name"]),
+                data("NODE"),
+                data("OPERATIONAL"),
+            ]],
+        );
+    }
+    let page = |rows| response(&["x", "types"], rows);
+    match query.split(" MATCH ").next().unwrap_or_default() {
+        "CYPHER from=0" if query.contains("(n)") => page(vec![
+            node(0, "Rider", "Valentino Rossi", Some(1979)),
+            node(1, "Team", "Yamaha", None),
+        ]),
+        "CYPHER from=2" if query.contains("(n)") => page(vec![
+            node(2, "Rider", "Dani Pedrosa", Some(1985)),
+            node(3, "Team", "Ducati", None),
+        ]),
+        "CYPHER from=1" if query.contains("(n)") => page(vec![
+            node(1, "Team", "Yamaha", None),
+            node(2, "Rider", "Dani Pedrosa", Some(1985)),
+        ]),
+        "CYPHER from=3" if query.contains("(n)") => page(vec![node(3, "Team", "Ducati", None)]),
+        "CYPHER from=4" if query.contains("(n)") => page(vec![]),
+        "CYPHER from=0" => page(vec![rides(0, 0, 1, 2004), rides(1, 2, 3, 2006)]),
+        "CYPHER from=2" => page(vec![]),
+        _ => panic!("unexpected query {}", query),
+    }
+}
+
+// Answers the target writes, created nodes get the source id plus 100 unless
+// their ids are dropped. The node id reads find the first two nodes.
+fn answer_target(args: &[String], drop_ids: bool) -> Value {
+    let query = args.get(2).map(|q| q.as_str()).unwrap_or_default();
+    if args[0] == "GRAPH.CONSTRAINT" {
+        return Value::Okay;
+    }
+    if args[0] == "GRAPH.RO_QUERY" {
+        let rows = match query.split(" MATCH ").next().unwrap_or_default() {
+            "CYPHER from=0" => vec![
+                vec![Value::Int(0), Value::Int(100)],
+                vec![Value::Int(1), Value::Int(101)],
+            ],
+            _ => vec![],
+        };
+        return response(&["n.__copy_id", "id(n)"], rows);
+    }
+    if query.contains("CREATE (n") {
+        let rows = query
+            .split("{id: ")
+            .skip(1)
+            .filter(|_| !drop_ids)
+            .map(|row| {
+                let id: i64 = row.split(',').next().unwrap().parse().unwrap();
+                vec![Value::Int(id), Value::Int(id + 100)]
+            })
+            .collect();
+        let mut value = response(&["row.id", "id(n)"], rows);
+        if let Value::Bulk(values) = &mut value {
+            values[2] = strings(&["Nodes created: 1"]);
+        }
+        return value;
+    }
+    if query.contains("CREATE (a)") {
+        return Value::Bulk(vec![strings(&["Relationships created: 1"])]);
+    }
+    Value::Bulk(vec![strings(&["Indices created: 1"])])
+}
+
+struct CopyConnection {
+    source: bool,
+    // The number of commands answered before failing
+    fail_after: Option<usize>,
+    // Whether the failing command is applied before its reply is lost
+    lose_reply: bool,
+    drop_ids: bool,
+    commands: Vec<Vec<String>>,
+}
+
+impl CopyConnection {
+    fn new(source: bool) -> Self {
+        CopyConnection {
+            source,
+            fail_after: None,
+            lose_reply: false,
+            drop_ids: false,
+            commands: vec![],
+        }
+    }
+
+    // The commands without the graph key
+    fn statements(&self) -> Vec<String> {
+        self.commands
+            .iter()
+            .map(|c| {
+                let mut args = c.clone();
+                args.retain(|arg| arg != "my_copy");
+                args.join(" ")
+            })
+            .collect()
+    }
+
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        if self.fail_after == Some(self.commands.len()) {
+            if self.lose_reply {
+                self.commands.push(args);
+            }
+            return Err(RedisError::from((ErrorKind::IoError, "Connection lost")));
+        }
+        let value = if self.source {
+            assert_eq!(args[..2], ["GRAPH.RO_QUERY", "my_graph"]);
+            answer_source(&args[2])
+        } else {
+            answer_target(&args, self.drop_ids)
+        };
+        self.commands.push(args);
+        Ok(value)
+    }
+}
+
+mock_connection!(CopyConnection);
+
+// Renames riders to pilots without their birth year, skips Ducati and
+// marks the relations as copied
+fn copier() -> GraphCopy {
+    GraphCopy::new()
+        .with_page_size(2)
+        .with_node_transform(|mut node| {
+            if node.properties.get("name") == Some(&ParamValue::from("Ducati")) {
+                return None;
+            }
+            for label in node.labels.iter_mut().filter(|l| *l == "Rider") {
+                *label = "Pilot".to_string();
+            }
+            node.properties.remove("born");
+            Some(node)
+        })
+        .with_relation_transform(|mut relation| {
+            relation
+                .properties
+                .insert("copied".to_string(), ParamValue::Bool(true));
+            Some(relation)
+        })
+}
+
+const WRITES: [&str; 7] = [
+    "GRAPH.QUERY UNWIND [{id: 0, properties: {name: 'Valentino Rossi'}}] AS row \
+     CREATE (n:Pilot) SET n = row.properties, n.__copy_id = row.id RETURN row.id, id(n)",
+    "GRAPH.QUERY UNWIND [{id: 1, properties: {name: 'Yamaha'}}] AS row \
+     CREATE (n:Team) SET n = row.properties, n.__copy_id = row.id RETURN row.id, id(n)",
+    "GRAPH.QUERY UNWIND [{id: 2, properties: {name: 'Dani Pedrosa'}}] AS row \
+     CREATE (n:Pilot) SET n = row.properties, n.__copy_id = row.id RETURN row.id, id(n)",
+    "GRAPH.QUERY UNWIND [{src: 100, dest: 101, properties: {copied: true, since: 2004}}] AS row \
+     MATCH (a) WHERE id(a) = row.src MATCH (b) WHERE id(b) = row.dest \
+     CREATE (a)-[r:rides]->(b) SET r = row.properties",
+    "GRAPH.QUERY MATCH (n) WHERE n.__copy_id IS NOT NULL SET n.__copy_id = NULL",
+    "GRAPH.QUERY CREATE INDEX FOR (n:Team) ON (n.name)",
+    "GRAPH.CONSTRAINT CREATE UNIQUE NODE Team PROPERTIES 1 name",
+];
+
+#[test]
+fn test_graph_copy() {
+    let mut source = CopyConnection::new(true);
+    let mut target = CopyConnection::new(false);
+    let mut checkpoints = vec![];
+    let mut copy = copier();
+    let result = copy
+        .copy(&mut source, "my_graph", &mut target, "my_copy", |c| {
+            checkpoints.push((c.step, c.next))
+        })
+        .unwrap();
+    assert_eq!(target.statements(), WRITES);
+    assert_eq!(source.commands.len(), 8);
+    assert_eq!(
+        checkpoints,
+        vec![
+            (CopyStep::Nodes, 1),
+            (CopyStep::Nodes, 2),
+            (CopyStep::Nodes, 4),
+            (CopyStep::Relations, 0),
+            (CopyStep::Relations, 2),
+            (CopyStep::Schema, 0),
+            (CopyStep::Schema, 1),
+            (CopyStep::Done, 0),
+        ]
+    );
+    assert_eq!(result.nodes, 3);
+    assert_eq!(result.nodes_skipped, 1);
+    assert_eq!(result.relations, 1);
+    assert_eq!(result.relations_skipped, 1);
+    assert_eq!(result.statements, 2);
+    assert_eq!(result.statistics.nodes_created, 3);
+    assert_eq!(result.statistics.relationships_created, 1);
+
+    let checkpoint = copy.checkpoint();
+    assert_eq!(
+        checkpoint.labels,
+        ["Pilot", "Team"].iter().map(|l| l.to_string()).collect()
+    );
+    assert_eq!(
+        checkpoint.relationship_types,
+        vec!["rides".to_string()].into_iter().collect()
+    );
+}
+
+#[test]
+fn test_graph_copy_truncated_pages() {
+    // Pages of two rows below the page size do not end the copy
+    let mut source = CopyConnection::new(true);
+    let mut target = CopyConnection::new(false);
+    let result = copier()
+        .with_page_size(3)
+        .copy(&mut source, "my_graph", &mut target, "my_copy", |_| {})
+        .unwrap();
+    assert_eq!(target.statements(), WRITES);
+    assert_eq!(source.commands.len(), 8);
+    assert_eq!(result.nodes, 3);
+    assert_eq!(result.relations, 1);
+}
+
+#[test]
+fn test_graph_copy_resume() {
+    let mut source = CopyConnection::new(true);
+    let mut target = CopyConnection::new(false);
+    target.fail_after = Some(2);
+    let mut copy = copier();
+    let err = copy
+        .copy(&mut source, "my_graph", &mut target, "my_copy", |_| {})
+        .unwrap_err();
+    assert!(err.to_string().contains("Connection lost"));
+    let checkpoint = copy.checkpoint().clone();
+    assert_eq!((checkpoint.step, checkpoint.next), (CopyStep::Nodes, 2));
+
+    #[cfg(feature = "serde")]
+    let checkpoint: CopyCheckpoint =
+        serde_json::from_str(&serde_json::to_string(&checkpoint).unwrap()).unwrap();
+
+    // The resumed copy reads the ids of the copied nodes from the target,
+    // then continues with the failed write
+    let mut resumed = CopyConnection::new(false);
+    let result = copier()
+        .with_checkpoint(checkpoint)
+        .copy(&mut source, "my_graph", &mut resumed, "my_copy", |_| {})
+        .unwrap();
+    let mut statements = target.statements();
+    let mut resumed_statements = resumed.statements();
+    let writes = resumed_statements.split_off(2);
+    assert_eq!(
+        resumed_statements,
+        [
+            "GRAPH.RO_QUERY CYPHER from=0 MATCH (n) WHERE id(n) >= $from AND n.__copy_id IS NOT NULL \
+             RETURN n.__copy_id, id(n) ORDER BY id(n) LIMIT 2",
+            "GRAPH.RO_QUERY CYPHER from=102 MATCH (n) WHERE id(n) >= $from AND n.__copy_id IS NOT NULL \
+             RETURN n.__copy_id, id(n) ORDER BY id(n) LIMIT 2",
+        ]
+    );
+    statements.extend(writes);
+    assert_eq!(statements, WRITES);
+    assert_eq!(result.nodes, 1);
+    assert_eq!(result.relations, 1);
+    assert_eq!(result.statements, 2);
+
+    // Resuming a finished copy does nothing
+    let mut done = CopyConnection::new(false);
+    let mut copy = copier().with_checkpoint(CopyCheckpoint {
+        step: CopyStep::Done,
+        ..CopyCheckpoint::default()
+    });
+    let result = copy
+        .copy(&mut source, "my_graph", &mut done, "my_copy", |_| {})
+        .unwrap();
+    assert_eq!(result, CopyResult::default());
+    assert!(done.commands.is_empty());
+}
+
+#[test]
+fn test_graph_copy_lost_reply() {
+    // The write of Yamaha is applied but its reply is lost
+    let mut source = CopyConnection::new(true);
+    let mut target = CopyConnection::new(false);
+    target.fail_after = Some(1);
+    target.lose_reply = true;
+    let mut copy = copier();
+    let err = copy
+        .copy(&mut source, "my_graph", &mut target, "my_copy", |_| {})
+        .unwrap_err();
+    assert!(err.to_string().contains("Connection lost"));
+    let checkpoint = copy.checkpoint().clone();
+    assert_eq!((checkpoint.step, checkpoint.next), (CopyStep::Nodes, 1));
+    assert_eq!(target.statements(), WRITES[..2]);
+
+    // The resumed copy finds Yamaha in the target and does not create it again
+    let mut resumed = CopyConnection::new(false);
+    let result = copier()
+        .with_checkpoint(checkpoint)
+        .copy(&mut source, "my_graph", &mut resumed, "my_copy", |_| {})
+        .unwrap();
+    assert_eq!(resumed.statements()[2..], WRITES[2..]);
+    assert_eq!(result.nodes, 1);
+    assert_eq!(result.nodes_skipped, 1);
+    assert_eq!(result.relations, 1);
+}
+
+#[test]
+fn test_graph_copy_missing_node_ids() {
+    let mut source = CopyConnection::new(true);
+    let mut target = CopyConnection::new(false);
+    target.drop_ids = true;
+    let err = copier()
+        .copy(&mut source, "my_graph", &mut target, "my_copy", |_| {})
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Expected 1 node ids of a copy batch, got 0"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,246 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{from_redis_value, RedisResult, Value};
+use redis_graph::*;
+
+#[macro_use]
+mod common;
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn pair(key: &str, value: Value) -> Value {
+    Value::Bulk(vec![data(key), value])
+}
+
+fn node(id: i64, label: &str, properties: Vec<Value>) -> Value {
+    Value::Bulk(vec![
+        pair("id", Value::Int(id)),
+        pair("labels", Value::Bulk(vec![data(label)])),
+        pair("properties", Value::Bulk(properties)),
+    ])
+}
+
+fn rides(id: i64, src: i64, properties: Vec<Value>) -> Value {
+    Value::Bulk(vec![
+        pair("id", Value::Int(id)),
+        pair("type", data("rides")),
+        pair("src_node", Value::Int(src)),
+        pair("dest_node", Value::Int(2)),
+        pair("properties", Value::Bulk(properties)),
+    ])
+}
+
+// A response for "MATCH (r)-[x]->(t) RETURN r, x, t" in which the team is
+// returned twice
+fn response() -> Value {
+    let team = || node(2, "Team", vec![pair("name", data("Yamaha \"Factory\""))]);
+    Value::Bulk(vec![
+        Value::Bulk(vec![data("r"), data("x"), data("t")]),
+        Value::Bulk(vec![
+            Value::Bulk(vec![
+                node(
+                    1,
+                    "Rider",
+                    vec![
+                        pair("name", data("Valentino Rossi")),
+                        pair("born", Value::Int(1979)),
+                    ],
+                ),
+                rides(5, 1, vec![pair("since", Value::Int(2004))]),
+                team(),
+            ]),
+            Value::Bulk(vec![
+                node(3, "Rider", vec![pair("born", data("unknown"))]),
+                rides(6, 3, vec![]),
+                team(),
+            ]),
+        ]),
+        Value::Bulk(vec![]),
+    ])
+}
+
+fn subgraph() -> Subgraph {
+    let res: GraphResultSet = from_redis_value(&response()).unwrap();
+    res.to_subgraph()
+}
+
+#[test]
+fn test_export_dot() {
+    let export = GraphExport::new(GraphFormat::Dot)
+        .with_caption("name")
+        .with_style(
+            "Team",
+            NodeStyle::new()
+                .with_color("#f4a261")
+                .with_shape(NodeShape::Box),
+        );
+    assert_eq!(
+        export.to_string(&subgraph()),
+        "digraph G {\n\
+         \x20 n1 [label=\"Valentino Rossi\", tooltip=\":Rider\\nborn: 1979\\nname: Valentino Rossi\"];\n\
+         \x20 n2 [label=\"Yamaha \\\"Factory\\\"\", style=filled, fillcolor=\"#f4a261\", shape=box, \
+         tooltip=\":Team\\nname: Yamaha \\\"Factory\\\"\"];\n\
+         \x20 n3 [label=\"Rider 3\", tooltip=\":Rider\\nborn: unknown\"];\n\
+         \x20 n1 -> n2 [label=\"rides\", tooltip=\"since: 2004\"];\n\
+         \x20 n3 -> n2 [label=\"rides\"];\n\
+         }\n"
+    );
+}
+
+#[test]
+fn test_export_mermaid() {
+    let export = GraphExport::new(GraphFormat::Mermaid)
+        .with_style("Rider", NodeStyle::new().with_shape(NodeShape::Round))
+        .with_style("Team", NodeStyle::new().with_color("#f4a261"));
+    assert_eq!(
+        export.to_string(&subgraph()),
+        "flowchart LR\n\
+         \x20   n1(\"Rider 1<br/>born: 1979<br/>name: Valentino Rossi\")\n\
+         \x20   n2[\"Team 2<br/>name: Yamaha #quot;Factory#quot;\"]\n\
+         \x20   n3(\"Rider 3<br/>born: unknown\")\n\
+         \x20   n1 -->|\"rides<br/>since: 2004\"| n2\n\
+         \x20   n3 -->|\"rides\"| n2\n\
+         \x20   classDef Rider\n\
+         \x20   class n1,n3 Rider\n\
+         \x20   classDef Team fill:#f4a261\n\
+         \x20   class n2 Team\n"
+    );
+}
+
+#[test]
+fn test_export_mermaid_escapes() {
+    let mut graph = Subgraph::new();
+    let node = |id: u64, label: &str, name: &str| {
+        let mut properties = std::collections::HashMap::new();
+        properties.insert("name".to_string(), data(name));
+        NodeValue {
+            id,
+            labels: vec![label.to_string()],
+            properties,
+        }
+    };
+    graph.add_node(node(1, "A-B", "Team #1\nFactory"));
+    graph.add_node(node(2, "A_B", "Yamaha"));
+    let export = GraphExport::new(GraphFormat::Mermaid)
+        .with_style("A-B", NodeStyle::new())
+        .with_style("A_B", NodeStyle::new());
+    assert_eq!(
+        export.to_string(&graph),
+        "flowchart LR\n\
+         \x20   n1[\"A-B 1<br/>name: Team #35;1<br/>Factory\"]\n\
+         \x20   n2[\"A_B 2<br/>name: Yamaha\"]\n\
+         \x20   classDef A_2d_B\n\
+         \x20   class n1 A_2d_B\n\
+         \x20   classDef A_5f_B\n\
+         \x20   class n2 A_5f_B\n"
+    );
+}
+
+#[test]
+fn test_export_graphml() {
+    let graphml = GraphExport::new(GraphFormat::GraphMl)
+        .with_caption("name")
+        .to_string(&subgraph());
+    assert!(graphml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<graphml"));
+    // born has an integer and a string value
+    assert!(graphml
+        .contains("  <key id=\"n_born\" for=\"node\" attr.name=\"born\" attr.type=\"string\"/>\n"));
+    assert!(graphml
+        .contains("  <key id=\"e_since\" for=\"edge\" attr.name=\"since\" attr.type=\"long\"/>\n"));
+    assert!(graphml.contains(
+        "    <node id=\"n2\">\n\
+         \x20     <data key=\"labels\">:Team</data>\n\
+         \x20     <data key=\"caption\">Yamaha &quot;Factory&quot;</data>\n\
+         \x20     <data key=\"n_name\">Yamaha &quot;Factory&quot;</data>\n\
+         \x20   </node>\n"
+    ));
+    assert!(graphml.contains(
+        "    <edge id=\"e5\" source=\"n1\" target=\"n2\">\n\
+         \x20     <data key=\"label\">rides</data>\n\
+         \x20     <data key=\"e_since\">2004</data>\n\
+         \x20   </edge>\n"
+    ));
+    assert_eq!(graphml.matches("<node ").count(), 3);
+    assert_eq!(graphml.matches("<edge ").count(), 2);
+    assert!(!graphml.contains("key id=\"color\""));
+    assert!(graphml.ends_with("  </graph>\n</graphml>\n"));
+}
+
+// Answers the node and relation page queries of graph_subgraph with at most
+// two rows like a server with RESULTSET_SIZE 2
+struct SubgraphConnection {
+    queries: Vec<String>,
+}
+
+impl SubgraphConnection {
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        let query = args[2].clone();
+        let param = |name: &str| -> u64 {
+            let prefix = format!("{}=", name);
+            query
+                .split(' ')
+                .find_map(|word| word.strip_prefix(prefix.as_str()))
+                .unwrap()
+                .parse()
+                .unwrap()
+        };
+        let rows: Vec<(u64, Value)> = if query.contains("MATCH (n)") {
+            vec![
+                (1, node(1, "Rider", vec![])),
+                (2, node(2, "Team", vec![])),
+                (4, node(4, "Circuit", vec![])),
+            ]
+        } else {
+            assert_eq!((param("first"), param("last")), (1, 4));
+            vec![(5, rides(5, 1, vec![]))]
+        };
+        let from = param("from");
+        let rows = rows
+            .into_iter()
+            .filter(|(id, _)| *id >= from)
+            .take(2)
+            .map(|(_, row)| Value::Bulk(vec![row]))
+            .collect();
+        self.queries.push(query);
+        Ok(Value::Bulk(vec![
+            Value::Bulk(vec![data("n")]),
+            Value::Bulk(rows),
+            Value::Bulk(vec![]),
+        ]))
+    }
+}
+
+mock_connection!(SubgraphConnection);
+
+#[test]
+fn test_graph_subgraph() {
+    let mut con = SubgraphConnection { queries: vec![] };
+    let graph = con.graph_subgraph("my_graph").unwrap();
+    let nodes = |from: u64| {
+        format!(
+            "CYPHER from={} MATCH (n) WHERE id(n) >= $from RETURN n ORDER BY id(n) LIMIT 1000",
+            from
+        )
+    };
+    let relations = |from: u64| {
+        format!(
+            "CYPHER first=1 last=4 from={} MATCH (a)-[n]->() \
+             WHERE id(a) >= $first AND id(a) <= $last AND id(n) >= $from \
+             RETURN n ORDER BY id(n) LIMIT 1000",
+            from
+        )
+    };
+    assert_eq!(
+        con.queries,
+        vec![nodes(0), nodes(3), nodes(5), relations(0), relations(6)]
+    );
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.relation_count(), 1);
+    assert_eq!(
+        GraphExport::new(GraphFormat::Mermaid).to_string(&graph),
+        "flowchart LR\n    n1[\"Rider 1\"]\n    n2[\"Team 2\"]\n    n4[\"Circuit 4\"]\n    n1 -->|\"rides\"| n2\n"
+    );
+}
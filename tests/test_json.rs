@@ -0,0 +1,158 @@
+#![cfg(feature = "serde")]
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{from_redis_value, Value};
+use redis_graph::*;
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn pair(key: &str, value: Value) -> Value {
+    Value::Bulk(vec![data(key), value])
+}
+
+// A query response with a node, a relation and scalars of all types
+fn response(complete: bool) -> Value {
+    let rider = Value::Bulk(vec![
+        pair("id", Value::Int(1)),
+        pair("labels", Value::Bulk(vec![data("Rider")])),
+        pair(
+            "properties",
+            Value::Bulk(vec![
+                pair("name", data("Valentino Rossi")),
+                pair("born", Value::Int(1979)),
+                pair("teams", Value::Bulk(vec![data("Yamaha"), data("Honda")])),
+            ]),
+        ),
+    ]);
+    let rides = Value::Bulk(vec![
+        pair("id", Value::Int(7)),
+        pair("type", data("rides")),
+        pair("src_node", Value::Int(1)),
+        pair("dest_node", Value::Int(2)),
+        pair(
+            "properties",
+            Value::Bulk(vec![pair("since", Value::Int(2004))]),
+        ),
+    ]);
+    let mut second = vec![Value::Nil, Value::Nil, Value::Data(vec![0, 159, 146, 150])];
+    if !complete {
+        second.pop();
+    }
+    Value::Bulk(vec![
+        Value::Bulk(vec![data("r"), data("rel"), data("score")]),
+        Value::Bulk(vec![
+            Value::Bulk(vec![rider, rides, data("1.5")]),
+            Value::Bulk(second),
+        ]),
+        Value::Bulk(vec![
+            data("Nodes created: 2"),
+            data("Query internal execution time: 0.5 milliseconds"),
+        ]),
+    ])
+}
+
+const ROW_1: &str = r#"{"r":{"id":1,"labels":["Rider"],"properties":{"born":1979,"name":"Valentino Rossi","teams":["Yamaha","Honda"]}},"rel":{"id":7,"type":"rides","src":1,"dest":2,"properties":{"since":2004}},"score":"1.5"}"#;
+const ROW_2: &str = r#"{"r":null,"rel":null,"score":"AJ+Slg=="}"#;
+
+#[test]
+fn test_result_set_to_json() {
+    let res: GraphResultSet = from_redis_value(&response(true)).unwrap();
+    let json = res.to_json().unwrap();
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"header":["r","rel","score"],"rows":[{},{}],"statistics":{{"labels_added":0,"labels_removed":0,"nodes_created":2,"nodes_deleted":0,"relationships_created":0,"relationships_deleted":0,"properties_set":0,"properties_removed":0,"indices_created":0,"indices_deleted":0,"execution_time":0.5}}}}"#,
+            ROW_1, ROW_2
+        )
+    );
+
+    let empty = GraphResultSet::default().to_json().unwrap();
+    assert!(empty.starts_with(r#"{"header":[],"rows":[],"statistics":{"#));
+}
+
+#[test]
+fn test_ndjson_writer() {
+    let res: GraphResultSet = from_redis_value(&response(true)).unwrap();
+    let mut writer = NdjsonWriter::new(Vec::new());
+    assert_eq!(writer.write_result_set(&res).unwrap(), 2);
+    let stream = GraphResultStream::from_value(response(true)).unwrap();
+    assert_eq!(writer.write_stream(stream).unwrap(), 2);
+    assert_eq!(writer.rows(), 4);
+    assert_eq!(
+        String::from_utf8(writer.into_inner()).unwrap(),
+        format!("{}\n{}\n{}\n{}\n", ROW_1, ROW_2, ROW_1, ROW_2)
+    );
+
+    // Rows written before a decoding error stay written
+    let stream = GraphResultStream::from_value(response(false))
+        .unwrap()
+        .strict();
+    let mut writer = NdjsonWriter::new(Vec::new());
+    assert!(writer.write_stream(stream).is_err());
+    assert_eq!(writer.rows(), 1);
+}
+
+#[test]
+fn test_result_set_to_json_with_types() {
+    let header = vec![
+        "name".to_string(),
+        "score".to_string(),
+        "active".to_string(),
+        "laps".to_string(),
+    ];
+    let row = |values: Vec<Value>| {
+        GraphResult::from_values(
+            header.clone().into(),
+            values.into_iter().map(GraphValue::Scalar).collect(),
+        )
+    };
+    let res = GraphResultSet {
+        header: header.clone(),
+        data: vec![
+            row(vec![
+                data("007"),
+                data("1.5"),
+                data("true"),
+                Value::Bulk(vec![data("1"), Value::Int(2)]),
+            ]),
+            row(vec![Value::Nil, Value::Int(2), data("false"), Value::Nil]),
+        ],
+        metadata: vec![],
+    };
+    let types = [
+        ("score", PropertyType::Float),
+        ("active", PropertyType::Boolean),
+        ("laps", PropertyType::Integer),
+    ];
+    let json = res.to_json_with_types(&types).unwrap();
+    assert!(
+        json.contains(
+            r#""rows":[{"name":"007","score":1.5,"active":true,"laps":[1,2]},{"name":null,"score":2.0,"active":false,"laps":null}]"#
+        ),
+        "{}",
+        json
+    );
+    assert!(res
+        .to_json()
+        .unwrap()
+        .contains(r#""score":"1.5","active":"true""#));
+    let err = res
+        .to_json_with_types(&[("active", PropertyType::Float)])
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("Could not encode result"),
+        "{}",
+        err
+    );
+
+    let mut writer = NdjsonWriter::new(Vec::new()).with_column_type("score", PropertyType::Float);
+    writer.write_result_set(&res).unwrap();
+    assert_eq!(
+        String::from_utf8(writer.into_inner()).unwrap(),
+        "{\"name\":\"007\",\"score\":1.5,\"active\":\"true\",\"laps\":[\"1\",2]}\n\
+         {\"name\":null,\"score\":2.0,\"active\":\"false\",\"laps\":null}\n"
+    );
+}
@@ -0,0 +1,365 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{RedisResult, Value};
+use redis_graph::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[macro_use]
+mod common;
+
+// Keeps migration records in a hash or in _Migration nodes and records all
+// executed graph statements and the keys of the hash commands.
+#[derive(Default)]
+struct MigrationConnection {
+    hash: BTreeMap<String, String>,
+    hash_keys: BTreeSet<String>,
+    nodes: BTreeMap<i64, (String, String)>,
+    statements: Vec<String>,
+    graph_exists: bool,
+}
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+impl MigrationConnection {
+    fn node_rows(&self) -> Value {
+        let rows = self
+            .nodes
+            .iter()
+            .map(|(version, (name, checksum))| {
+                Value::Bulk(vec![Value::Int(*version), data(name), data(checksum)])
+            })
+            .collect();
+        Value::Bulk(vec![
+            Value::Bulk(vec![data("version"), data("name"), data("checksum")]),
+            Value::Bulk(rows),
+            Value::Bulk(vec![]),
+        ])
+    }
+
+    // Parses the properties of CREATE (:_Migration {version: 1, name: 'a', checksum: 'b'})
+    fn create_node(&mut self, query: &str) {
+        let props = &query[query.find('{').unwrap() + 1..query.rfind('}').unwrap()];
+        let values: Vec<&str> = props
+            .split(", ")
+            .map(|p| p.split(": ").nth(1).unwrap().trim_matches('\''))
+            .collect();
+        self.nodes.insert(
+            values[0].parse().unwrap(),
+            (values[1].to_string(), values[2].to_string()),
+        );
+    }
+
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        // Only writes create the graph, reading a missing graph fails
+        if args[0].starts_with("GRAPH.") && args[0] != "GRAPH.RO_QUERY" {
+            self.graph_exists = true;
+        }
+        if args[0].starts_with('H') {
+            self.hash_keys.insert(args[1].to_string());
+        }
+        match args[0] {
+            "EXISTS" => Ok(Value::Int(self.graph_exists as i64)),
+            "GRAPH.RO_QUERY" if !self.graph_exists => {
+                Err((redis::ErrorKind::ResponseError, "Invalid graph operation").into())
+            }
+            "HGETALL" => Ok(Value::Bulk(
+                self.hash
+                    .iter()
+                    .flat_map(|(k, v)| vec![data(k), data(v)])
+                    .collect(),
+            )),
+            "HSET" => {
+                self.hash.insert(args[2].to_string(), args[3].to_string());
+                Ok(Value::Int(1))
+            }
+            "HDEL" => {
+                self.hash.remove(args[2]);
+                Ok(Value::Int(1))
+            }
+            "GRAPH.RO_QUERY" if args[2].starts_with("MATCH (m:_Migration)") => Ok(self.node_rows()),
+            "GRAPH.QUERY" if args[2].starts_with("CREATE (:_Migration") => {
+                self.create_node(args[2]);
+                Ok(Value::Bulk(vec![Value::Bulk(vec![])]))
+            }
+            "GRAPH.QUERY" if args[2].starts_with("MATCH (m:_Migration)") => {
+                let version = args[2].split("m.version = ").nth(1).unwrap();
+                self.nodes
+                    .remove(&version.split(' ').next().unwrap().parse().unwrap());
+                Ok(Value::Bulk(vec![Value::Bulk(vec![])]))
+            }
+            _ => {
+                let mut statement = args[0].to_string();
+                for arg in args[1..].iter().filter(|a| **a != "my_graph") {
+                    statement.push(' ');
+                    statement.push_str(arg);
+                }
+                self.statements.push(statement);
+                Ok(Value::Bulk(vec![Value::Bulk(vec![])]))
+            }
+        }
+    }
+}
+
+mock_connection!(MigrationConnection);
+
+fn migrations() -> Vec<Migration> {
+    vec![
+        Migration::cypher(
+            1,
+            "rider_index",
+            "// Riders are looked up by name
+            CREATE INDEX FOR (r:Rider) ON (r.name);
+            GRAPH.CONSTRAINT CREATE UNIQUE NODE Rider PROPERTIES 1 name;",
+        )
+        .with_down(
+            "GRAPH.CONSTRAINT DROP UNIQUE NODE Rider PROPERTIES 1 name;
+            DROP INDEX ON :Rider(name)",
+        ),
+        Migration::rust(2, "teams", |ctx| {
+            for team in ["Yamaha", "Honda"].iter() {
+                ctx.query_with_params(
+                    "CREATE (:Team {name: $name})",
+                    &[("name", ParamValue::from(*team))],
+                )?;
+            }
+            Ok(())
+        })
+        .with_down_rust(|ctx| {
+            ctx.query("MATCH (t:Team) DELETE t");
+            Ok(())
+        }),
+    ]
+}
+
+#[test]
+fn test_parse_script() {
+    let statements = parse_script(
+        "// comment
+        CREATE (:Rider {name: 'a;b'});
+        MATCH (r:`x;y`) RETURN r;
+
+        GRAPH.CONSTRAINT CREATE MANDATORY RELATIONSHIP rides PROPERTIES 2 since until",
+    )
+    .unwrap();
+    assert_eq!(statements.len(), 3);
+    assert_eq!(
+        statements[0],
+        SchemaStatement::Query("CREATE (:Rider {name: 'a;b'})".to_string())
+    );
+    assert_eq!(
+        statements[1],
+        SchemaStatement::Query("MATCH (r:`x;y`) RETURN r".to_string())
+    );
+    assert_eq!(
+        statements[2],
+        SchemaStatement::CreateConstraint(ConstraintInfo::relationship(
+            "rides",
+            "MANDATORY",
+            &["since", "until"]
+        ))
+    );
+    assert!(parse_script("CREATE (:Rider {name: 'a})").is_err());
+
+    // Escaped backslashes do not escape the closing quote
+    let statements =
+        parse_script(r#"CREATE (:Rider {name: 'a\\', nick: "b\";c"}); MATCH (r:`x\`) RETURN r"#)
+            .unwrap();
+    assert_eq!(
+        statements,
+        vec![
+            SchemaStatement::Query(r#"CREATE (:Rider {name: 'a\\', nick: "b\";c"})"#.to_string()),
+            SchemaStatement::Query(r#"MATCH (r:`x\`) RETURN r"#.to_string()),
+        ]
+    );
+    assert!(parse_script(r"CREATE (:Rider {name: 'a\'})").is_err());
+
+    // Semicolons in comments do not split statements
+    let statements = parse_script(
+        "CREATE (:Rider) // riders; teams\n\
+        /* MATCH (n); DELETE n; */CREATE (:Team {name: '/* a; */'});\n\
+        RETURN '//;' /* ; */",
+    )
+    .unwrap();
+    assert_eq!(
+        statements,
+        vec![
+            SchemaStatement::Query(
+                "CREATE (:Rider) \n CREATE (:Team {name: '/* a; */'})".to_string()
+            ),
+            SchemaStatement::Query("RETURN '//;'".to_string()),
+        ]
+    );
+    assert!(parse_script("CREATE (:Rider) /* ; */ /* ;").is_err());
+    assert!(parse_script("GRAPH.CONSTRAINT CREATE UNIQUE NODE Rider PROPERTIES 2 name").is_err());
+}
+
+#[test]
+fn test_migrator() {
+    let migrator = Migrator::new(migrations()).unwrap();
+    assert!(migrator.migrations()[0].is_reversible());
+    assert_eq!(migrator.migrations()[0].checksum().unwrap().len(), 16);
+    assert_ne!(
+        migrator.migrations()[0].checksum().unwrap(),
+        migrator.migrations()[1].checksum().unwrap()
+    );
+    assert!(Migrator::new(vec![
+        Migration::cypher(1, "a", "RETURN 1"),
+        Migration::cypher(1, "b", "RETURN 2"),
+    ])
+    .is_err());
+
+    let plan = migrator.plan_migrate(&[]).unwrap();
+    let expected = "\
+up 1 rider_index
+  CREATE INDEX FOR (r:Rider) ON (r.name)
+  GRAPH.CONSTRAINT CREATE UNIQUE NODE Rider PROPERTIES 1 name
+up 2 teams
+  CYPHER name='Yamaha' CREATE (:Team {name: $name})
+  CYPHER name='Honda' CREATE (:Team {name: $name})
+";
+    assert_eq!(plan.to_string(), expected);
+
+    let applied = vec![AppliedMigration {
+        version: 2,
+        name: "teams".to_string(),
+        checksum: migrator.migrations()[1].checksum().unwrap(),
+    }];
+    assert!(migrator.plan_migrate(&applied).is_err());
+    assert_eq!(
+        migrator.plan_rollback(&applied, 0).unwrap().to_string(),
+        "down 2 teams\n  MATCH (t:Team) DELETE t\n"
+    );
+
+    let changed = vec![AppliedMigration {
+        checksum: "0000000000000000".to_string(),
+        ..applied[0].clone()
+    }];
+    assert!(migrator.plan_rollback(&changed, 0).is_err());
+
+    let irreversible = Migrator::new(vec![Migration::cypher(1, "a", "RETURN 1")]).unwrap();
+    let applied = vec![AppliedMigration {
+        version: 1,
+        name: "a".to_string(),
+        checksum: irreversible.migrations()[0].checksum().unwrap(),
+    }];
+    assert!(irreversible.plan_rollback(&applied, 0).is_err());
+    assert!(irreversible.plan_migrate(&applied).unwrap().is_empty());
+}
+
+#[test]
+fn test_migrate_and_rollback() {
+    for store in [
+        MigrationStore::default(),
+        MigrationStore::Key("migrations".to_string()),
+        MigrationStore::Node("_Migration".to_string()),
+    ] {
+        let migrator = Migrator::new(migrations()).unwrap().with_store(store);
+        let mut con = MigrationConnection::default();
+
+        let plan = con.graph_migrate("my_graph", &migrator, true).unwrap();
+        assert_eq!(plan.steps.len(), 2);
+        assert!(con.statements.is_empty());
+
+        con.graph_migrate("my_graph", &migrator, false).unwrap();
+        assert_eq!(
+            con.statements,
+            vec![
+                "GRAPH.QUERY CREATE INDEX FOR (r:Rider) ON (r.name)",
+                "GRAPH.CONSTRAINT CREATE UNIQUE NODE Rider PROPERTIES 1 name",
+                "GRAPH.QUERY CYPHER name='Yamaha' CREATE (:Team {name: $name})",
+                "GRAPH.QUERY CYPHER name='Honda' CREATE (:Team {name: $name})",
+            ]
+        );
+        let applied = con.graph_migrations_applied("my_graph", &migrator).unwrap();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[1].name, "teams");
+        assert!(con
+            .graph_migrate("my_graph", &migrator, false)
+            .unwrap()
+            .is_empty());
+
+        con.statements.clear();
+        let plan = con.graph_rollback("my_graph", &migrator, 0, false).unwrap();
+        assert_eq!(plan.steps[0].version, 2);
+        assert_eq!(plan.steps[1].direction, MigrationDirection::Down);
+        assert_eq!(
+            con.statements,
+            vec![
+                "GRAPH.QUERY MATCH (t:Team) DELETE t",
+                "GRAPH.CONSTRAINT DROP UNIQUE NODE Rider PROPERTIES 1 name",
+                "GRAPH.QUERY DROP INDEX ON :Rider(name)",
+            ]
+        );
+        assert!(con
+            .graph_migrations_applied("my_graph", &migrator)
+            .unwrap()
+            .is_empty());
+    }
+}
+
+#[test]
+fn test_default_store_is_companion_hash() {
+    let migrator = Migrator::new(migrations()).unwrap();
+    assert_eq!(
+        migrator.store(),
+        &MigrationStore::Companion(":migrations".to_string())
+    );
+    let mut con = MigrationConnection::default();
+    con.graph_migrate("my_graph", &migrator, false).unwrap();
+    assert!(con.nodes.is_empty());
+    assert_eq!(con.hash.len(), 2);
+    assert_eq!(
+        con.hash_keys.into_iter().collect::<Vec<_>>(),
+        vec!["my_graph:migrations"]
+    );
+}
+
+#[test]
+fn test_migration_comments() {
+    let plain = Migration::cypher(
+        1,
+        "riders",
+        "CREATE (:Rider {name: 'a'});MATCH (r) RETURN r",
+    );
+    let commented = Migration::cypher(
+        1,
+        "riders",
+        "CREATE (:Rider {name: 'a'}); // the first rider
+        /* all riders */ MATCH (r) RETURN r // done",
+    );
+    assert_eq!(
+        commented.up_statements().unwrap(),
+        vec![
+            SchemaStatement::Query("CREATE (:Rider {name: 'a'})".to_string()),
+            SchemaStatement::Query("MATCH (r) RETURN r".to_string()),
+        ]
+    );
+    assert_eq!(commented.checksum().unwrap(), plain.checksum().unwrap());
+}
+
+#[test]
+fn test_migrator_from_dir() {
+    let dir = std::env::temp_dir().join("redis_graph_test_migrations");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(dir.join("2_teams.cypher"), "CREATE (:Team)").unwrap();
+    std::fs::write(dir.join("1_riders.up.cypher"), "CREATE (:Rider)").unwrap();
+    std::fs::write(dir.join("1_riders.down.cypher"), "MATCH (r:Rider) DELETE r").unwrap();
+    std::fs::write(dir.join("README.md"), "ignored").unwrap();
+
+    let migrator = Migrator::from_dir(&dir).unwrap();
+    let migrations = migrator.migrations();
+    assert_eq!(migrations.len(), 2);
+    assert_eq!(migrations[0].name(), "riders");
+    assert!(migrations[0].is_reversible());
+    assert_eq!(migrations[1].name(), "teams");
+    assert!(!migrations[1].is_reversible());
+
+    std::fs::write(dir.join("3_circuits.down.cypher"), "RETURN 1").unwrap();
+    assert!(Migrator::from_dir(&dir).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
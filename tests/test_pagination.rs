@@ -0,0 +1,281 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{ErrorKind, RedisError, RedisResult, Value};
+use redis_graph::*;
+
+#[macro_use]
+mod common;
+
+// Serves the rows 0..rows of "RETURN n" honouring SKIP/LIMIT, a key-set
+// cursor and a server side result set cap. With a float cursor the rows are
+// n + 0.5 returned as strings like Redis graph does for doubles.
+struct PagingConnection {
+    rows: i64,
+    cap: Option<i64>,
+    queries: Vec<String>,
+}
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn number_after(query: &str, word: &str) -> Option<f64> {
+    let mut parts = query.split(|c: char| c.is_whitespace() || c == '=');
+    parts.position(|p| p == word)?;
+    parts.next()?.parse().ok()
+}
+
+impl PagingConnection {
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        if args[0] == "GRAPH.CONFIG" {
+            let cap = self.cap.ok_or_else(|| {
+                RedisError::from((ErrorKind::ResponseError, "unknown command 'GRAPH.CONFIG'"))
+            })?;
+            return Ok(Value::Bulk(vec![data("RESULTSET_SIZE"), Value::Int(cap)]));
+        }
+        let query = args[2].clone();
+        self.queries.push(query.clone());
+        let float = query.starts_with("CYPHER cursor=") && query.contains('.');
+        let skip = number_after(&query, "SKIP").unwrap_or(0.0) as i64;
+        let start = number_after(&query, "cursor").map_or(skip, |c| c.floor() as i64 + 1);
+        let mut limit = number_after(&query, "LIMIT").unwrap() as i64;
+        if let Some(cap) = self.cap.filter(|cap| *cap >= 0) {
+            limit = limit.min(cap);
+        }
+        let rows = (start..self.rows.min(start + limit))
+            .map(|n| match float {
+                true => Value::Bulk(vec![data(&format!("{}.5", n))]),
+                false => Value::Bulk(vec![Value::Int(n)]),
+            })
+            .collect();
+        Ok(Value::Bulk(vec![
+            Value::Bulk(vec![data("n")]),
+            Value::Bulk(rows),
+            Value::Bulk(vec![]),
+        ]))
+    }
+}
+
+mock_connection!(PagingConnection);
+
+fn create_con(rows: i64, cap: i64) -> PagingConnection {
+    PagingConnection {
+        rows,
+        cap: Some(cap),
+        queries: vec![],
+    }
+}
+
+fn collect(pages: GraphPages<PagingConnection>) -> Vec<i64> {
+    pages
+        .map(|row| row.unwrap().get_scalar("n").unwrap())
+        .collect()
+}
+
+#[test]
+fn test_skip_limit_pagination() {
+    let mut con = create_con(5, -1);
+    let pagination = Pagination::new("g", "MATCH (n) RETURN n ORDER BY n", 2);
+    assert_eq!(collect(con.graph_paginate(pagination)), vec![0, 1, 2, 3, 4]);
+    assert_eq!(
+        con.queries,
+        vec![
+            "MATCH (n) RETURN n ORDER BY n SKIP 0 LIMIT 2",
+            "MATCH (n) RETURN n ORDER BY n SKIP 2 LIMIT 2",
+            "MATCH (n) RETURN n ORDER BY n SKIP 4 LIMIT 2",
+        ]
+    );
+}
+
+#[test]
+fn test_pagination_exact_page_end() {
+    let mut con = create_con(4, -1);
+    let pagination = Pagination::new("g", "MATCH (n) RETURN n ORDER BY n", 2);
+    assert_eq!(collect(con.graph_paginate(pagination)), vec![0, 1, 2, 3]);
+    assert_eq!(con.queries.len(), 3);
+}
+
+#[test]
+fn test_pagination_respects_result_set_cap() {
+    let mut con = create_con(5, 2);
+    let pagination = Pagination::new("g", "MATCH (n) RETURN n ORDER BY n", 10);
+    let mut pages = con.graph_paginate(pagination);
+    assert!(!pages.truncated());
+    let rows: Vec<i64> = pages
+        .by_ref()
+        .map(|row| row.unwrap().get_scalar("n").unwrap())
+        .collect();
+    assert_eq!(rows, vec![0, 1, 2, 3, 4]);
+    assert!(pages.truncated());
+    assert_eq!(pages.page_size(), 2);
+    assert_eq!(
+        con.queries,
+        vec![
+            "MATCH (n) RETURN n ORDER BY n SKIP 0 LIMIT 10",
+            "MATCH (n) RETURN n ORDER BY n SKIP 2 LIMIT 2",
+            "MATCH (n) RETURN n ORDER BY n SKIP 4 LIMIT 2",
+        ]
+    );
+
+    // Full pages of a page size equal to the cap are not truncated
+    let mut con = create_con(5, 2);
+    let pagination = Pagination::new("g", "MATCH (n) RETURN n ORDER BY n", 2);
+    let mut pages = con.graph_paginate(pagination);
+    assert_eq!(pages.by_ref().count(), 5);
+    assert!(!pages.truncated());
+
+    // A last page of exactly the cap is followed by an empty page
+    let mut con = create_con(4, 2);
+    let pagination = Pagination::new("g", "MATCH (n) RETURN n ORDER BY n", 10);
+    assert_eq!(collect(con.graph_paginate(pagination)), vec![0, 1, 2, 3]);
+    assert_eq!(con.queries.len(), 3);
+}
+
+#[test]
+fn test_pagination_config_error() {
+    let mut con = create_con(5, -1);
+    con.cap = None;
+    let pagination = Pagination::new("g", "MATCH (n) RETURN n ORDER BY n", 2);
+    let rows: Vec<RedisResult<GraphResult>> = con.graph_paginate(pagination).collect();
+    assert_eq!(rows.len(), 1);
+    assert!(rows[0]
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("GRAPH.CONFIG"));
+    assert!(con.queries.is_empty());
+}
+
+#[test]
+fn test_pagination_given_result_set_cap() {
+    let mut con = create_con(5, 3);
+    let pagination =
+        Pagination::new("g", "MATCH (n) RETURN n ORDER BY n", 10).with_result_set_cap(Some(3));
+    let mut pages = con.graph_paginate(pagination);
+    assert_eq!(
+        pages.next().unwrap().unwrap().get_scalar::<i64>("n"),
+        Some(0)
+    );
+    assert_eq!(pages.page_size(), 3);
+    assert_eq!(pages.result_set_cap(), Some(3));
+}
+
+#[test]
+fn test_key_set_pagination() {
+    let mut con = create_con(5, -1);
+    let pagination = Pagination::new("g", "MATCH (n) WHERE n > $cursor RETURN n ORDER BY n", 2)
+        .with_key_set("n", "cursor", -1);
+    assert_eq!(collect(con.graph_paginate(pagination)), vec![0, 1, 2, 3, 4]);
+    assert_eq!(
+        con.queries,
+        vec![
+            "CYPHER cursor=-1 MATCH (n) WHERE n > $cursor RETURN n ORDER BY n LIMIT 2",
+            "CYPHER cursor=1 MATCH (n) WHERE n > $cursor RETURN n ORDER BY n LIMIT 2",
+            "CYPHER cursor=3 MATCH (n) WHERE n > $cursor RETURN n ORDER BY n LIMIT 2",
+        ]
+    );
+}
+
+#[test]
+fn test_key_set_float_cursor() {
+    let mut con = create_con(5, -1);
+    let pagination = Pagination::new("g", "MATCH (n) WHERE n > $cursor RETURN n ORDER BY n", 2)
+        .with_key_set("n", "cursor", -0.5);
+    let rows: Vec<String> = con
+        .graph_paginate(pagination)
+        .map(|row| row.unwrap().get_scalar("n").unwrap())
+        .collect();
+    assert_eq!(rows, vec!["0.5", "1.5", "2.5", "3.5", "4.5"]);
+    assert_eq!(
+        con.queries,
+        vec![
+            "CYPHER cursor=-0.5 MATCH (n) WHERE n > $cursor RETURN n ORDER BY n LIMIT 2",
+            "CYPHER cursor=1.5 MATCH (n) WHERE n > $cursor RETURN n ORDER BY n LIMIT 2",
+            "CYPHER cursor=3.5 MATCH (n) WHERE n > $cursor RETURN n ORDER BY n LIMIT 2",
+        ]
+    );
+}
+
+#[test]
+fn test_key_set_missing_column() {
+    let mut con = create_con(5, -1);
+    let pagination = Pagination::new("g", "MATCH (n) RETURN n ORDER BY n", 2)
+        .with_key_set("missing", "cursor", -1);
+    let rows: Vec<RedisResult<GraphResult>> = con.graph_paginate(pagination).collect();
+    assert!(rows.last().unwrap().is_err());
+}
+
+#[test]
+fn test_query_params() {
+    assert_eq!(
+        query_with_params(
+            "RETURN $a, $b, $c",
+            &[
+                ("a", ParamValue::from(vec![1, 2])),
+                ("b", ParamValue::from(Some(1.5))),
+                ("c", ParamValue::from(None::<bool>)),
+            ]
+        )
+        .unwrap(),
+        "CYPHER a=[1, 2] b=1.5 c=null RETURN $a, $b, $c"
+    );
+    assert_eq!(
+        ParamValue::from("a\nb'\\").to_cypher().unwrap(),
+        "'a\\nb\\'\\\\'"
+    );
+    assert!(ParamValue::from(vec![1.0, f64::NAN]).to_cypher().is_err());
+    assert!(query_with_params("RETURN $a", &[("a", ParamValue::from(f64::INFINITY))]).is_err());
+    assert_eq!(escape_identifier("my key"), "`my key`");
+    assert_eq!(escape_identifier("name"), "name");
+}
+
+#[test]
+fn test_pagination_query_end() {
+    let mut con = create_con(3, -1);
+    let pagination = Pagination::new("g", "MATCH (n) RETURN n ORDER BY n; // all nodes\n", 2);
+    assert_eq!(collect(con.graph_paginate(pagination)), vec![0, 1, 2]);
+    assert_eq!(
+        con.queries[0],
+        "MATCH (n) RETURN n ORDER BY n SKIP 0 LIMIT 2"
+    );
+
+    // LIMIT in an earlier clause and as a map key is kept
+    let mut con = create_con(3, -1);
+    let query = "MATCH (n) WITH n LIMIT 5 RETURN n, {limit: 1} ORDER BY n /* x */";
+    assert_eq!(
+        collect(con.graph_paginate(Pagination::new("g", query, 2))),
+        vec![0, 1, 2]
+    );
+    assert_eq!(
+        con.queries[0],
+        "MATCH (n) WITH n LIMIT 5 RETURN n, {limit: 1} ORDER BY n SKIP 0 LIMIT 2"
+    );
+
+    for (query, error) in [
+        (
+            "MATCH (n) RETURN n ORDER BY n LIMIT 10",
+            "already has a LIMIT clause",
+        ),
+        (
+            "MATCH (n) RETURN n ORDER BY n skip 1",
+            "already has a SKIP clause",
+        ),
+        (
+            "MATCH (n) RETURN n; MATCH (m) RETURN m",
+            "multiple statements",
+        ),
+        ("MATCH (n) RETURN n // 'x\n ORDER BY 'n", "Unterminated"),
+    ]
+    .iter()
+    {
+        let mut con = create_con(3, -1);
+        let rows: Vec<RedisResult<GraphResult>> = con
+            .graph_paginate(Pagination::new("g", *query, 2))
+            .collect();
+        assert_eq!(rows.len(), 1);
+        let err = rows[0].as_ref().unwrap_err().to_string();
+        assert!(err.contains(error), "{}", err);
+        assert!(con.queries.is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+extern crate petgraph;
+extern crate redis;
+extern crate redis_graph;
+
+use petgraph::algo::{is_cyclic_directed, toposort};
+use petgraph::graph::Graph;
+use petgraph::stable_graph::StableGraph;
+use redis::{RedisResult, Value};
+use redis_graph::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[macro_use]
+mod common;
+
+// Answers CREATE queries with increasing node ids for every returned column
+// and records all issued queries.
+struct CreateConnection {
+    next_id: i64,
+    queries: Vec<String>,
+}
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+impl CreateConnection {
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        let query = args[2].clone();
+        self.queries.push(query.clone());
+        let columns = query.matches("id(").count();
+        if !query.starts_with("CREATE") {
+            return Ok(Value::Bulk(vec![Value::Bulk(vec![])]));
+        }
+        let header = (0..columns).map(|idx| data(&format!("id(n{})", idx)));
+        let first = self.next_id;
+        let ids = (0..columns).map(|idx| Value::Int(first + idx as i64));
+        self.next_id += columns as i64;
+        Ok(Value::Bulk(vec![
+            Value::Bulk(header.collect()),
+            Value::Bulk(vec![Value::Bulk(ids.collect())]),
+            Value::Bulk(vec![]),
+        ]))
+    }
+}
+
+mock_connection!(CreateConnection);
+
+fn node(id: u64, label: &str) -> NodeValue {
+    NodeValue {
+        id,
+        labels: vec![label.to_string()],
+        properties: HashMap::new(),
+    }
+}
+
+fn relation(id: u64, src_node: u64, dest_node: u64) -> RelationValue {
+    RelationValue {
+        id,
+        rel_type: "depends".to_string(),
+        src_node,
+        dest_node,
+        properties: HashMap::new(),
+    }
+}
+
+// Result of "MATCH (a)-[r]->(b) RETURN a, r, b" where node 4 is not returned
+fn result_set() -> GraphResultSet {
+    let header: Arc<[String]> = vec!["a".to_string(), "r".to_string(), "b".to_string()].into();
+    let rows = vec![
+        (node(1, "Task"), relation(10, 1, 2), node(2, "Task")),
+        (node(2, "Task"), relation(11, 2, 3), node(3, "Task")),
+        (node(1, "Task"), relation(12, 1, 3), node(3, "Task")),
+        (node(3, "Task"), relation(13, 3, 4), node(3, "Task")),
+    ];
+    GraphResultSet {
+        header: header.to_vec(),
+        data: rows
+            .into_iter()
+            .map(|(a, r, b)| {
+                let values = vec![
+                    GraphValue::Node(a),
+                    GraphValue::Relation(r),
+                    GraphValue::Node(b),
+                ];
+                GraphResult::from_values(header.clone(), values)
+            })
+            .collect(),
+        metadata: Vec::new(),
+    }
+}
+
+#[test]
+fn test_to_petgraph() {
+    let indexed = result_set().to_petgraph();
+    assert_eq!(indexed.graph.node_count(), 4);
+    assert_eq!(indexed.graph.edge_count(), 4);
+    let first = indexed.node_index(1).unwrap();
+    assert_eq!(indexed.graph[first].labels, ["Task"]);
+    let placeholder = indexed.node_index(4).unwrap();
+    assert!(indexed.graph[placeholder].labels.is_empty());
+    let edge = indexed.edge_index(12).unwrap();
+    assert_eq!(
+        indexed.graph.edge_endpoints(edge),
+        Some((first, indexed.node_index(3).unwrap()))
+    );
+
+    let order: Vec<u64> = toposort(&indexed.graph, None)
+        .unwrap()
+        .into_iter()
+        .map(|idx| indexed.graph[idx].id)
+        .collect();
+    assert_eq!(order, [1, 2, 3, 4]);
+}
+
+#[test]
+fn test_to_stable_graph() {
+    let mut indexed = result_set().to_stable_graph();
+    let removed = indexed.node_index(2).unwrap();
+    indexed.graph.remove_node(removed);
+    let last = indexed.node_index(4).unwrap();
+    assert_eq!(indexed.graph[last].id, 4);
+    assert_eq!(indexed.graph.edge_count(), 2);
+    assert!(!is_cyclic_directed(&indexed.graph));
+}
+
+#[test]
+fn test_write_petgraph() {
+    let mut graph = Graph::new();
+    let mut rider = node(0, "Rider");
+    rider
+        .properties
+        .insert("name".to_string(), data("Valentino 'The Doctor' Rossi"));
+    let a = graph.add_node(rider);
+    let b = graph.add_node(node(0, "Team"));
+    let c = graph.add_node(node(0, "Team"));
+    let mut rides = relation(0, 0, 0);
+    rides.rel_type = "rides".to_string();
+    rides
+        .properties
+        .insert("since".to_string(), Value::Int(2004));
+    graph.add_edge(a, b, rides);
+    graph.add_edge(a, c, relation(0, 0, 0));
+
+    let mut con = CreateConnection {
+        next_id: 100,
+        queries: vec![],
+    };
+    let res = con.graph_write_petgraph("my_graph", &graph, 2).unwrap();
+    assert_eq!(res.nodes_created, 3);
+    assert_eq!(res.relations_created, 2);
+    assert_eq!(res.node_ids[&a], 100);
+    assert_eq!(res.node_ids[&c], 102);
+    assert_eq!(
+        con.queries,
+        [
+            "CREATE (n0:Rider {name: 'Valentino \\'The Doctor\\' Rossi'}), (n1:Team) RETURN id(n0), id(n1)",
+            "CREATE (n0:Team) RETURN id(n0)",
+            "MATCH (n0), (n1), (n2) WHERE id(n0) = 100 AND id(n1) = 101 AND id(n2) = 102 \
+             CREATE (n0)-[:rides {since: 2004}]->(n1), (n0)-[:depends]->(n2)",
+        ]
+    );
+}
+
+#[test]
+fn test_write_stable_graph() {
+    let mut graph = StableGraph::new();
+    let a = graph.add_node(node(0, "Task"));
+    let b = graph.add_node(node(0, "Task"));
+    let removed = graph.add_node(node(0, "Task"));
+    graph.add_edge(b, a, relation(0, 0, 0));
+    graph.remove_node(removed);
+
+    let mut con = CreateConnection {
+        next_id: 0,
+        queries: vec![],
+    };
+    let res = con.graph_write_petgraph("my_graph", &graph, 10).unwrap();
+    assert_eq!(res.nodes_created, 2);
+    assert_eq!(con.queries.len(), 2);
+    assert!(con.queries[1].ends_with("CREATE (n0)-[:depends]->(n1)"));
+}
+
+fn strings(values: &[&str]) -> Value {
+    Value::Bulk(values.iter().map(|v| data(v)).collect())
+}
+
+fn pair(key: &str, value: Value) -> Value {
+    Value::Bulk(vec![data(key), value])
+}
+
+// An entity page of the typed read with a single row
+fn page(entity: Vec<Value>, types: &[(&str, &str)]) -> Value {
+    let types = types
+        .iter()
+        .map(|(key, t)| Value::Bulk(vec![data(key), data(t), strings(&[])]))
+        .collect();
+    Value::Bulk(vec![
+        strings(&["x", "types"]),
+        Value::Bulk(vec![Value::Bulk(vec![
+            Value::Bulk(entity),
+            Value::Bulk(types),
+        ])]),
+        Value::Bulk(vec![]),
+    ])
+}
+
+// Answers the typed read of a rider with a double and a boolean property
+// riding for a team that is missing from the node pages
+struct ReadConnection {
+    queries: Vec<String>,
+}
+
+impl ReadConnection {
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        let query = args[2].clone();
+        assert_eq!(args[0], "GRAPH.RO_QUERY");
+        self.queries.push(query.clone());
+        let empty = Value::Bulk(vec![
+            strings(&["x", "types"]),
+            Value::Bulk(vec![]),
+            Value::Bulk(vec![]),
+        ]);
+        if !query.starts_with("CYPHER from=0 ") {
+            return Ok(empty);
+        }
+        Ok(if query.contains("MATCH (n)") {
+            page(
+                vec![
+                    pair("id", Value::Int(0)),
+                    pair("labels", strings(&["Rider"])),
+                    pair(
+                        "properties",
+                        Value::Bulk(vec![
+                            pair("score", data("1.5")),
+                            pair("active", data("true")),
+                            pair("name", data("2.5")),
+                        ]),
+                    ),
+                ],
+                &[
+                    ("score", "Float"),
+                    ("active", "Boolean"),
+                    ("name", "String"),
+                ],
+            )
+        } else {
+            page(
+                vec![
+                    pair("id", Value::Int(7)),
+                    pair("type", data("rides")),
+                    pair("src_node", Value::Int(0)),
+                    pair("dest_node", Value::Int(1)),
+                    pair(
+                        "properties",
+                        Value::Bulk(vec![pair("since", data("2004.5"))]),
+                    ),
+                ],
+                &[("since", "Float")],
+            )
+        })
+    }
+}
+
+mock_connection!(ReadConnection);
+
+#[test]
+fn test_read_petgraph_round_trip() {
+    let mut con = ReadConnection { queries: vec![] };
+    let indexed = con.graph_read_petgraph("my_graph", 10).unwrap();
+    assert_eq!(con.queries.len(), 4);
+    assert_eq!(indexed.graph.node_count(), 2);
+    let rider = &indexed.graph[indexed.node_index(0).unwrap()];
+    assert_eq!(rider.properties["score"], ParamValue::Float(1.5));
+    assert_eq!(rider.properties["active"], ParamValue::Bool(true));
+    assert!(indexed.graph[indexed.node_index(1).unwrap()]
+        .labels
+        .is_empty());
+    assert_eq!(indexed.edge_index(7).map(|e| e.index()), Some(0));
+
+    let mut con = CreateConnection {
+        next_id: 100,
+        queries: vec![],
+    };
+    con.graph_write_petgraph("my_copy", &indexed.graph, 10)
+        .unwrap();
+    assert_eq!(
+        con.queries,
+        [
+            "CREATE (n0:Rider {active: true, name: '2.5', score: 1.5}), (n1) RETURN id(n0), id(n1)",
+            "MATCH (n0), (n1) WHERE id(n0) = 100 AND id(n1) = 101 \
+             CREATE (n0)-[:rides {since: 2004.5}]->(n1)",
+        ]
+    );
+}
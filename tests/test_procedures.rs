@@ -0,0 +1,301 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{RedisResult, Value};
+use redis_graph::*;
+
+#[macro_use]
+mod common;
+
+// Records the issued commands and queries and answers dbms.procedures with
+// the prepared procedures and all other queries with the prepared response
+struct ProcedureConnection {
+    response: Value,
+    procedures: Value,
+    commands: Vec<String>,
+    queries: Vec<String>,
+}
+
+impl ProcedureConnection {
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        self.commands.push(args[0].clone());
+        self.queries.push(args[2].clone());
+        if args[2].starts_with("CALL dbms.procedures") {
+            return Ok(self.procedures.clone());
+        }
+        Ok(self.response.clone())
+    }
+}
+
+mock_connection!(ProcedureConnection);
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn pair(key: &str, value: Value) -> Value {
+    Value::Bulk(vec![data(key), value])
+}
+
+fn node(id: i64) -> Value {
+    Value::Bulk(vec![
+        pair("id", Value::Int(id)),
+        pair("labels", Value::Bulk(vec![data("City")])),
+        pair("properties", Value::Bulk(vec![])),
+    ])
+}
+
+fn edge(id: i64, src: i64, dest: i64) -> Value {
+    Value::Bulk(vec![
+        pair("id", Value::Int(id)),
+        pair("type", data("road")),
+        pair("src_node", Value::Int(src)),
+        pair("dest_node", Value::Int(dest)),
+        pair("properties", Value::Bulk(vec![])),
+    ])
+}
+
+fn response(header: &[&str], rows: Vec<Vec<Value>>) -> Value {
+    Value::Bulk(vec![
+        Value::Bulk(header.iter().map(|h| data(h)).collect()),
+        Value::Bulk(rows.into_iter().map(Value::Bulk).collect()),
+        Value::Bulk(vec![]),
+    ])
+}
+
+fn response_of_procedures(procedures: &[(&str, &str)]) -> Value {
+    response(
+        &["name", "mode"],
+        procedures
+            .iter()
+            .map(|(name, mode)| vec![data(name), data(mode)])
+            .collect(),
+    )
+}
+
+fn create_con(response: Value) -> ProcedureConnection {
+    ProcedureConnection {
+        response,
+        procedures: response_of_procedures(&[("my.proc", "READ"), ("my.write", "WRITE")]),
+        commands: vec![],
+        queries: vec![],
+    }
+}
+
+#[test]
+fn test_algo_bfs() {
+    let mut con = create_con(response(
+        &["nodes", "edges"],
+        vec![vec![
+            Value::Bulk(vec![node(1), node(2)]),
+            Value::Bulk(vec![edge(5, 0, 1), edge(6, 1, 2)]),
+        ]],
+    ));
+    let res = con
+        .graph_algo_bfs(
+            "g",
+            &BfsConfig::new(0).max_level(2).relationship_type("road"),
+        )
+        .unwrap();
+    assert_eq!(res.nodes.len(), 2);
+    assert_eq!(res.relations[1].dest_node, 2);
+    assert_eq!(
+        con.queries[0],
+        "MATCH (s) WHERE id(s) = 0 CALL algo.BFS(s, 2, 'road') YIELD nodes, edges RETURN nodes, edges"
+    );
+}
+
+#[test]
+fn test_algo_paths() {
+    let path = Value::Bulk(vec![
+        node(0),
+        edge(5, 0, 1),
+        node(1),
+        edge(6, 1, 2),
+        node(2),
+    ]);
+    let mut con = create_con(response(
+        &["path", "pathWeight", "pathCost"],
+        vec![vec![path, data("3.5"), Value::Int(2)]],
+    ));
+    let config = PathConfig::new(0)
+        .target(2)
+        .rel_types(vec!["road"])
+        .direction(Direction::Both)
+        .max_len(4)
+        .weight_prop("distance")
+        .path_count(1);
+    let res = con.graph_algo_sp_paths("g", &config).unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].path.nodes.len(), 3);
+    assert_eq!(res[0].path.len(), 2);
+    assert_eq!(res[0].weight, 3.5);
+    assert_eq!(res[0].cost, 2.0);
+    assert_eq!(
+        con.queries[0],
+        "MATCH (s) WHERE id(s) = 0 MATCH (t) WHERE id(t) = 2 CALL algo.SPpaths({sourceNode: s, \
+         targetNode: t, relTypes: ['road'], relDirection: 'both', maxLen: 4, weightProp: 'distance', \
+         pathCount: 1}) YIELD path, pathWeight, pathCost RETURN path, pathWeight, pathCost"
+    );
+
+    con.graph_algo_ss_paths("g", &PathConfig::new(0).max_cost(10.0))
+        .unwrap();
+    assert!(con.queries[1]
+        .contains("CALL algo.SSpaths({sourceNode: s, relDirection: 'outgoing', maxCost: 10.0})"));
+    assert!(con.graph_algo_sp_paths("g", &PathConfig::new(0)).is_err());
+}
+
+#[test]
+fn test_algo_rankings() {
+    let mut con = create_con(response(
+        &["node", "score"],
+        vec![vec![node(1), data("0.25")], vec![node(2), data("0.75")]],
+    ));
+    let ranks = con
+        .graph_algo_page_rank("g", &PageRankConfig::new().label("City"))
+        .unwrap();
+    assert_eq!(ranks[1].0.id, 2);
+    assert_eq!(ranks[1].1, 0.75);
+    assert_eq!(
+        con.queries[0],
+        "CALL algo.pageRank('City', null) YIELD node, score RETURN node, score"
+    );
+
+    let config = BetweennessConfig::new()
+        .node_labels(vec!["City"])
+        .sampling_size(10);
+    assert_eq!(con.graph_algo_betweenness("g", &config).unwrap().len(), 2);
+    assert_eq!(
+        con.queries[1],
+        "CALL algo.betweenness({nodeLabels: ['City'], samplingSize: 10}) YIELD node, score RETURN node, score"
+    );
+}
+
+#[test]
+fn test_algo_groups() {
+    let mut con = create_con(response(
+        &["node", "componentId"],
+        vec![vec![node(1), Value::Int(0)], vec![node(2), Value::Int(1)]],
+    ));
+    let components = con.graph_algo_wcc("g", &WccConfig::new()).unwrap();
+    assert_eq!(components[1].1, 1);
+    assert_eq!(
+        con.queries[0],
+        "CALL algo.WCC({}) YIELD node, componentId RETURN node, componentId"
+    );
+
+    let config = LabelPropagationConfig::new()
+        .relationship_types(vec!["road"])
+        .max_iterations(5);
+    assert_eq!(
+        con.graph_algo_label_propagation("g", &config).unwrap()[0].1,
+        0
+    );
+    assert_eq!(
+        con.queries[1],
+        "CALL algo.labelPropagation({relationshipTypes: ['road'], maxIterations: 5}) \
+         YIELD node, communityId RETURN node, communityId"
+    );
+}
+
+#[test]
+fn test_call() {
+    let mut con = create_con(response(
+        &["label", "count"],
+        vec![
+            vec![data("Rider"), Value::Int(3)],
+            vec![data("Team"), Value::Nil],
+        ],
+    ));
+    let rows = con
+        .graph_call(
+            "my_graph",
+            "my.proc",
+            &[ParamValue::from("it's"), ParamValue::from(vec![1i64, 2])],
+            &["label", "count"],
+        )
+        .unwrap();
+    assert_eq!(
+        con.queries,
+        [
+            "CALL dbms.procedures()",
+            "CYPHER arg0='it\\'s' arg1=[1, 2] CALL my.proc($arg0, $arg1) YIELD label, count RETURN label, count"
+        ]
+    );
+    assert_eq!(con.commands, ["GRAPH.RO_QUERY", "GRAPH.RO_QUERY"]);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(
+        rows[0].get_value::<String>("label").unwrap(),
+        Some("Rider".to_string())
+    );
+    assert_eq!(rows[0].get_value::<i64>("count").unwrap(), Some(3));
+    assert_eq!(rows[1].get_value::<i64>("count").unwrap(), None);
+    assert_eq!(rows[1].get_value::<i64>("missing").unwrap(), None);
+
+    let mut con = create_con(response(&["label"], vec![]));
+    con.graph_call("my_graph", "db.labels", &[], &[]).unwrap();
+    assert_eq!(con.queries[0], "CALL db.labels()");
+    assert_eq!(con.commands, ["GRAPH.RO_QUERY"]);
+    assert!(con
+        .graph_call("my_graph", "db.labels() MATCH (n)", &[], &[])
+        .is_err());
+}
+
+#[test]
+fn test_call_write_procedures() {
+    let mut con = create_con(response(&[], vec![]));
+    let arg = [ParamValue::from("Rider")];
+    for procedure in ["db.idx.fulltext.drop", "my.write", "unknown.proc"].iter() {
+        con.graph_call("my_graph", procedure, &arg, &[]).unwrap();
+    }
+    assert_eq!(
+        con.commands,
+        [
+            "GRAPH.QUERY",
+            "GRAPH.RO_QUERY",
+            "GRAPH.QUERY",
+            "GRAPH.RO_QUERY",
+            "GRAPH.QUERY"
+        ]
+    );
+}
+
+#[test]
+fn test_procedures() {
+    let mut con = create_con(Value::Nil);
+    con.procedures = response_of_procedures(&[
+        ("db.labels", "READ"),
+        ("db.idx.fulltext.drop", "WRITE"),
+        ("my.proc", "READ"),
+    ]);
+    let procedures = con.graph_procedures("my_graph").unwrap();
+    assert_eq!(con.queries[0], "CALL dbms.procedures()");
+    assert_eq!(procedures.len(), 3);
+    assert!(procedures[0].is_read_only());
+    assert_eq!(
+        procedures[0].signature.as_deref(),
+        Some("db.labels() :: (label)")
+    );
+    assert_eq!(procedures[1].mode, ProcedureMode::Write);
+    assert_eq!(procedures[2].name, "my.proc");
+    assert_eq!(procedures[2].signature, None);
+
+    // Signatures reported by the server take precedence
+    con.procedures = response(
+        &["name", "mode", "signature"],
+        vec![
+            vec![
+                data("db.labels"),
+                data("READ"),
+                data("db.labels() :: (label :: STRING)"),
+            ],
+            vec![data("my.proc"), data("READ"), Value::Nil],
+        ],
+    );
+    let procedures = con.graph_procedures("my_graph").unwrap();
+    assert_eq!(
+        procedures[0].signature.as_deref(),
+        Some("db.labels() :: (label :: STRING)")
+    );
+    assert_eq!(procedures[1].signature, None);
+}
@@ -0,0 +1,244 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{RedisResult, Value};
+use redis_graph::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+type Log = Rc<RefCell<Vec<(String, String)>>>;
+
+// Records every command with the name of the connection it was sent to
+struct MockConnection {
+    name: String,
+    log: Log,
+    failing: bool,
+    delay: Duration,
+}
+
+impl MockConnection {
+    fn new(name: &str, log: &Log) -> Self {
+        MockConnection {
+            name: name.to_string(),
+            log: log.clone(),
+            failing: false,
+            delay: Duration::default(),
+        }
+    }
+
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        let command = args[0].clone();
+        self.log
+            .borrow_mut()
+            .push((self.name.clone(), command.clone()));
+        std::thread::sleep(self.delay);
+        if self.failing {
+            return Err((redis::ErrorKind::IoError, "Connection refused").into());
+        }
+        match command.as_str() {
+            "WAIT" => Ok(Value::Int(1)),
+            "GRAPH.SLOWLOG" => Ok(Value::Bulk(vec![])),
+            _ => Ok(Value::Bulk(vec![Value::Bulk(vec![])])),
+        }
+    }
+}
+
+mock_connection!(MockConnection);
+
+fn create_router(log: &Log, replicas: usize) -> ReplicaRouter<MockConnection> {
+    ReplicaRouter::new(
+        MockConnection::new("primary", log),
+        (0..replicas)
+            .map(|i| MockConnection::new(&format!("replica{}", i), log))
+            .collect(),
+    )
+}
+
+fn sent(log: &Log) -> Vec<(String, String)> {
+    log.borrow_mut().drain(..).collect()
+}
+
+fn entry(con: &str, command: &str) -> (String, String) {
+    (con.to_string(), command.to_string())
+}
+
+#[test]
+fn test_writes_go_to_primary() {
+    let log = Log::default();
+    let mut router = create_router(&log, 2);
+    router.graph_query("g", "CREATE (:A)").unwrap();
+    router.graph_delete("g").unwrap_or_default();
+    assert_eq!(
+        sent(&log),
+        vec![
+            entry("primary", "GRAPH.QUERY"),
+            entry("primary", "GRAPH.DELETE")
+        ]
+    );
+}
+
+#[test]
+fn test_reads_round_robin() {
+    let log = Log::default();
+    let mut router = create_router(&log, 2);
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    router.graph_slowlog("g").unwrap();
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    assert_eq!(
+        sent(&log),
+        vec![
+            entry("replica0", "GRAPH.RO_QUERY"),
+            entry("replica1", "GRAPH.SLOWLOG"),
+            entry("replica0", "GRAPH.RO_QUERY")
+        ]
+    );
+}
+
+#[test]
+fn test_reads_without_replicas() {
+    let log = Log::default();
+    let mut router = create_router(&log, 0).with_read_your_writes(1, 10);
+    router.graph_query("g", "CREATE (:A)").unwrap();
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    assert_eq!(
+        sent(&log),
+        vec![
+            entry("primary", "GRAPH.QUERY"),
+            entry("primary", "GRAPH.RO_QUERY")
+        ]
+    );
+}
+
+#[test]
+fn test_read_your_writes() {
+    let log = Log::default();
+    let mut router = create_router(&log, 1).with_read_your_writes(1, 10);
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    router.graph_query("g", "CREATE (:A)").unwrap();
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    assert_eq!(
+        sent(&log),
+        vec![
+            entry("replica0", "GRAPH.RO_QUERY"),
+            entry("primary", "GRAPH.QUERY"),
+            entry("primary", "WAIT"),
+            entry("replica0", "GRAPH.RO_QUERY"),
+            entry("replica0", "GRAPH.RO_QUERY")
+        ]
+    );
+}
+
+#[test]
+fn test_read_your_writes_only_waits_after_writes() {
+    let log = Log::default();
+    let mut router = create_router(&log, 1).with_read_your_writes(1, 10);
+    for command in ["GRAPH.LIST", "PING"].iter() {
+        let _: Value = redis::cmd(command).query(&mut router).unwrap();
+    }
+    let _: Value = redis::cmd("GRAPH.CONFIG")
+        .arg("GET")
+        .arg("TIMEOUT")
+        .query(&mut router)
+        .unwrap();
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    assert_eq!(
+        sent(&log),
+        vec![
+            entry("primary", "GRAPH.LIST"),
+            entry("primary", "PING"),
+            entry("primary", "GRAPH.CONFIG"),
+            entry("replica0", "GRAPH.RO_QUERY")
+        ]
+    );
+
+    // Pipelines with a write wait before the next read
+    let _: () = redis::pipe()
+        .cmd("PING")
+        .cmd("GRAPH.QUERY")
+        .arg("g")
+        .arg("CREATE (:A)")
+        .query(&mut router)
+        .unwrap();
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    assert_eq!(
+        sent(&log),
+        vec![
+            entry("primary", "WAIT"),
+            entry("replica0", "GRAPH.RO_QUERY")
+        ]
+    );
+}
+
+#[test]
+fn test_least_loaded_uses_unmeasured_replicas_first() {
+    let log = Log::default();
+    let mut router = create_router(&log, 2).with_strategy(ReplicaStrategy::LeastLoaded);
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    let first = sent(&log);
+    assert_eq!(first.len(), 1);
+    assert!(first[0].0.starts_with("replica"));
+}
+
+fn reads_per_replica(log: &Log) -> [usize; 2] {
+    let mut counts = [0, 0];
+    for (con, _) in sent(log) {
+        match con.as_str() {
+            "replica0" => counts[0] += 1,
+            "replica1" => counts[1] += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+#[test]
+fn test_least_loaded_ignores_failed_reads() {
+    let log = Log::default();
+    let mut router = create_router(&log, 2).with_strategy(ReplicaStrategy::LeastLoaded);
+    router.replicas()[0].failing = true;
+    for _ in 0..2 * PROBE_INTERVAL {
+        router.graph_ro_query("g", "MATCH (n) RETURN n").ok();
+    }
+    // the first read and one probe go to the failing replica
+    assert_eq!(reads_per_replica(&log), [2, 2 * PROBE_INTERVAL - 2]);
+}
+
+#[test]
+fn test_least_loaded_probes_slow_replicas() {
+    let log = Log::default();
+    let mut router = create_router(&log, 2).with_strategy(ReplicaStrategy::LeastLoaded);
+    router.replicas()[0].delay = Duration::from_millis(20);
+    router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    router.replicas()[0].delay = Duration::default();
+    for _ in 1..PROBE_INTERVAL {
+        router.graph_ro_query("g", "MATCH (n) RETURN n").unwrap();
+    }
+    assert_eq!(reads_per_replica(&log), [2, PROBE_INTERVAL - 2]);
+}
+
+#[test]
+fn test_is_replica_command() {
+    assert!(is_replica_command(b"GRAPH.RO_QUERY"));
+    assert!(is_replica_command(b"graph.explain"));
+    assert!(is_replica_command(b"GRAPH.SLOWLOG"));
+    assert!(!is_replica_command(b"GRAPH.QUERY"));
+    assert!(!is_replica_command(b"GRAPH.PROFILE"));
+}
+
+#[test]
+fn test_mock_arguments() {
+    let cmd = redis::cmd("GRAPH.QUERY")
+        .arg("my_graph")
+        .arg("")
+        .arg("RETURN 'a\r\nb'")
+        .get_packed_command();
+    assert_eq!(
+        common::arguments(&cmd),
+        vec!["GRAPH.QUERY", "my_graph", "", "RETURN 'a\r\nb'"]
+    );
+}
@@ -0,0 +1,210 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{RedisResult, Value};
+use redis_graph::*;
+
+#[macro_use]
+mod common;
+
+// Answers the schema queries with a fixed graph of riders and teams and
+// records all issued queries.
+struct SchemaConnection {
+    queries: Vec<String>,
+}
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn strings(values: &[&str]) -> Value {
+    Value::Bulk(values.iter().map(|v| data(v)).collect())
+}
+
+fn response(header: &[&str], rows: Vec<Vec<Value>>) -> Value {
+    Value::Bulk(vec![
+        strings(header),
+        Value::Bulk(rows.into_iter().map(Value::Bulk).collect()),
+        Value::Bulk(vec![]),
+    ])
+}
+
+fn answer(query: &str) -> Value {
+    if query.starts_with("CALL dbms.procedures") {
+        return response(
+            &["name"],
+            vec![vec![data("db.labels")], vec![data("db.constraints")]],
+        );
+    }
+    if query.starts_with("CALL db.labels") {
+        return response(&["label"], vec![vec![data("Team")], vec![data("Rider")]]);
+    }
+    if query.starts_with("CALL db.relationshipTypes") {
+        return response(&["relationshipType"], vec![vec![data("rides")]]);
+    }
+    if query.starts_with("CALL db.propertyKeys") {
+        return response(
+            &["propertyKey"],
+            vec![vec![data("name")], vec![data("born")], vec![data("since")]],
+        );
+    }
+    if query.starts_with("CALL db.indexes") {
+        return response(
+            &["label", "properties", "types", "entitytype"],
+            vec![vec![
+                data("Rider"),
+                strings(&["name", "born"]),
+                Value::Bulk(vec![
+                    data("name"),
+                    strings(&["RANGE", "FULLTEXT"]),
+                    data("born"),
+                    strings(&["RANGE"]),
+                ]),
+                data("NODE"),
+            ]],
+        );
+    }
+    if query.starts_with("CALL db.constraints") {
+        return response(
+            &["type", "label", "properties", "entitytype", "status"],
+            vec![vec![
+                data("UNIQUE"),
+                data("Rider"),
+                strings(&["name"]),
+                data("NODE"),
+                data("OPERATIONAL"),
+            ]],
+        );
+    }
+    let count = |n: i64| response(&["count"], vec![vec![Value::Int(n)]]);
+    let types = |rows: &[(&str, &[&str])]| {
+        response(
+            &["key", "types"],
+            rows.iter()
+                .map(|(key, types)| vec![data(key), strings(types)])
+                .collect(),
+        )
+    };
+    match query {
+        "MATCH (n:Rider) RETURN count(n) AS count" => count(3),
+        "MATCH (n:Team) RETURN count(n) AS count" => count(2),
+        "MATCH ()-[r:rides]->() RETURN count(r) AS count" => count(3),
+        q if q.starts_with("MATCH (n:Rider) WITH n LIMIT 10 ") => {
+            types(&[("name", &["String"]), ("born", &["Integer", "String"])])
+        }
+        q if q.starts_with("MATCH (n:Team) WITH n LIMIT 10 ") => types(&[("name", &["String"])]),
+        q if q.starts_with("MATCH ()-[r:rides]->() WITH r LIMIT 10 ") => {
+            types(&[("since", &["Integer"])])
+        }
+        _ => panic!("unexpected query {}", query),
+    }
+}
+
+impl SchemaConnection {
+    fn reply(&mut self, args: Vec<String>) -> RedisResult<Value> {
+        let query = args[2].clone();
+        let value = answer(&query);
+        self.queries.push(query);
+        Ok(value)
+    }
+}
+
+mock_connection!(SchemaConnection);
+
+fn fetch_schema() -> GraphSchema {
+    let mut con = SchemaConnection { queries: vec![] };
+    let schema = con.graph_schema("my_graph", 10).unwrap();
+    assert_eq!(con.queries.len(), 12);
+    schema
+}
+
+#[test]
+fn test_graph_schema() {
+    let schema = fetch_schema();
+    let names: Vec<&str> = schema.labels.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, vec!["Rider", "Team"]);
+    assert_eq!(schema.property_keys, vec!["born", "name", "since"]);
+
+    let riders = schema.label("Rider").unwrap();
+    assert_eq!(riders.count, 3);
+    assert_eq!(riders.properties["name"], vec![PropertyType::String]);
+    assert_eq!(
+        riders.properties["born"],
+        vec![PropertyType::Integer, PropertyType::String]
+    );
+    assert_eq!(schema.label("Team").unwrap().count, 2);
+    assert!(schema.label("Circuit").is_none());
+
+    let rides = schema.relationship_type("rides").unwrap();
+    assert_eq!(rides.count, 3);
+    assert_eq!(rides.properties["since"], vec![PropertyType::Integer]);
+
+    assert_eq!(
+        schema.indexes,
+        vec![
+            IndexInfo {
+                label: "Rider".to_string(),
+                properties: vec!["born".to_string(), "name".to_string()],
+                index_type: "range".to_string(),
+                entity_type: EntityType::Node,
+            },
+            IndexInfo {
+                label: "Rider".to_string(),
+                properties: vec!["name".to_string()],
+                index_type: "fulltext".to_string(),
+                entity_type: EntityType::Node,
+            },
+        ]
+    );
+    assert_eq!(schema.constraints.len(), 1);
+    assert_eq!(schema.constraints[0].constraint_type, "UNIQUE");
+    assert_eq!(schema.constraints[0].status, "OPERATIONAL");
+}
+
+#[test]
+fn test_graph_schema_display() {
+    let expected = "\
+Labels:
+  :Rider (3 nodes)
+    born: Integer | String
+    name: String
+  :Team (2 nodes)
+    name: String
+Relationship types:
+  :rides (3 relations)
+    since: Integer
+Property keys:
+  born, name, since
+Indexes:
+  range (:Rider {born, name})
+  fulltext (:Rider {name})
+Constraints:
+  UNIQUE (:Rider {name}) OPERATIONAL
+";
+    assert_eq!(fetch_schema().to_string(), expected);
+}
+
+#[test]
+fn test_property_type() {
+    assert_eq!(PropertyType::from("Integer"), PropertyType::Integer);
+    assert_eq!(PropertyType::from("Array"), PropertyType::List);
+    assert_eq!(
+        PropertyType::from("Vectorf32"),
+        PropertyType::Other("Vectorf32".to_string())
+    );
+    assert_eq!(
+        PropertyType::Other("Vectorf32".to_string()).to_string(),
+        "Vectorf32"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_graph_schema_json() {
+    let schema = fetch_schema();
+    let json = schema.to_json().unwrap();
+    assert!(json.contains("\"born\": [\n"));
+    assert!(json.contains("\"Integer\""));
+    assert_eq!(GraphSchema::from_json(&json).unwrap(), schema);
+    assert!(GraphSchema::from_json("{").is_err());
+}
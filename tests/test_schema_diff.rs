@@ -0,0 +1,239 @@
+extern crate redis_graph;
+
+use redis_graph::*;
+
+fn expected() -> GraphSchema {
+    GraphSchema::new()
+        .with_label(
+            "Rider",
+            &[
+                ("name", PropertyType::String),
+                ("born", PropertyType::Integer),
+            ],
+        )
+        .with_label("Circuit", &[])
+        .with_relationship_type("rides", &[])
+        .with_index(IndexInfo::node("Rider", "range", &["name", "born"]))
+        .with_index(IndexInfo::node("Rider", "fulltext", &["name", "nick"]))
+        .with_index(IndexInfo::relationship("rides", "range", &["since"]))
+        .with_constraint(ConstraintInfo::node("Rider", "UNIQUE", &["name"]))
+}
+
+fn actual() -> GraphSchema {
+    let mut schema = GraphSchema::new()
+        .with_label("Rider", &[("name", PropertyType::String)])
+        .with_label("Team", &[])
+        .with_relationship_type("rides", &[])
+        .with_index(IndexInfo::node("Rider", "exact-match", &["name"]))
+        .with_index(IndexInfo::node("Rider", "full-text", &["name", "team"]))
+        .with_constraint(ConstraintInfo::node("Rider", "MANDATORY", &["name"]));
+    schema.labels[0].properties.insert(
+        "born".to_string(),
+        vec![PropertyType::String, PropertyType::Integer],
+    );
+    schema.constraints[0].status = "OPERATIONAL".to_string();
+    schema
+}
+
+#[test]
+fn test_schema_diff() {
+    assert!(SchemaDiff::compare(&expected(), &expected()).is_empty());
+
+    let diff = SchemaDiff::compare(&expected(), &actual());
+    assert!(!diff.is_empty());
+    assert_eq!(diff.missing_labels, vec!["Circuit"]);
+    assert_eq!(diff.extra_labels, vec!["Team"]);
+    assert!(diff.missing_relationship_types.is_empty());
+    assert_eq!(
+        diff.property_differences,
+        vec![PropertyDifference {
+            entity_type: EntityType::Node,
+            label: "Rider".to_string(),
+            property: "born".to_string(),
+            expected: Some(vec![PropertyType::Integer]),
+            actual: Some(vec![PropertyType::Integer, PropertyType::String]),
+        }]
+    );
+    assert_eq!(
+        diff.missing_indexes,
+        vec![
+            IndexInfo::node("Rider", "fulltext", &["nick"]),
+            IndexInfo::node("Rider", "range", &["born"]),
+            IndexInfo::relationship("rides", "range", &["since"]),
+        ]
+    );
+    assert_eq!(
+        diff.extra_indexes,
+        vec![IndexInfo::node("Rider", "fulltext", &["team"])]
+    );
+    assert_eq!(diff.missing_constraints[0].constraint_type, "UNIQUE");
+    assert_eq!(diff.extra_constraints[0].constraint_type, "MANDATORY");
+
+    let expected = "\
+missing label :Circuit
+extra label :Team
+property :Rider.born expected Integer, found Integer | String
+missing index fulltext (:Rider {nick})
+missing index range (:Rider {born})
+missing index range [:rides {since}]
+extra index fulltext (:Rider {team})
+missing constraint UNIQUE (:Rider {name})
+extra constraint MANDATORY (:Rider {name})
+";
+    assert_eq!(diff.to_string(), expected);
+    assert_eq!(
+        SchemaDiff::compare(&actual(), &actual()).to_string(),
+        "Schemas are equal\n"
+    );
+}
+
+#[test]
+fn test_schema_diff_properties() {
+    let expected = GraphSchema::new().with_label(
+        "Rider",
+        &[
+            ("name", PropertyType::String),
+            ("born", PropertyType::Integer),
+        ],
+    );
+    let mut actual = GraphSchema::new().with_label(
+        "Rider",
+        &[
+            ("name", PropertyType::String),
+            ("nick", PropertyType::String),
+        ],
+    );
+    let diff = SchemaDiff::compare(&expected, &actual);
+    let lines: Vec<String> = diff
+        .property_differences
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        lines,
+        vec!["property :Rider.born missing", "property :Rider.nick extra"]
+    );
+
+    // Properties are not compared if one side lists none
+    actual.labels[0].properties.clear();
+    assert!(SchemaDiff::compare(&expected, &actual).is_empty());
+}
+
+#[test]
+fn test_schema_statements() {
+    let diff = SchemaDiff::compare(&expected(), &actual());
+    let statements: Vec<String> = diff
+        .statements(false)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        statements,
+        vec![
+            "CREATE INDEX FOR (n:Rider) ON (n.born)",
+            "CREATE INDEX FOR ()-[r:rides]-() ON (r.since)",
+            "CALL db.idx.fulltext.createNodeIndex('Rider', 'nick')",
+            "GRAPH.CONSTRAINT CREATE UNIQUE NODE Rider PROPERTIES 1 name",
+        ]
+    );
+
+    let statements: Vec<String> = diff
+        .statements(true)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        statements,
+        vec![
+            "GRAPH.CONSTRAINT DROP MANDATORY NODE Rider PROPERTIES 1 name",
+            "CREATE INDEX FOR (n:Rider) ON (n.born)",
+            "CREATE INDEX FOR ()-[r:rides]-() ON (r.since)",
+            "CALL db.idx.fulltext.drop('Rider')",
+            "CALL db.idx.fulltext.createNodeIndex('Rider', 'name', 'nick')",
+            "GRAPH.CONSTRAINT CREATE UNIQUE NODE Rider PROPERTIES 1 name",
+        ]
+    );
+
+    let drop = SchemaDiff::compare(&GraphSchema::new(), &expected()).statements(true);
+    assert_eq!(
+        drop[3].to_string(),
+        "DROP INDEX FOR ()-[r:rides]-() ON (r.since)"
+    );
+    assert_eq!(drop[1].to_string(), "DROP INDEX ON :Rider(born)");
+
+    let cmd = diff.statements(false)[3].to_cmd("my_graph");
+    assert_eq!(
+        cmd.args_iter().count(),
+        9,
+        "GRAPH.CONSTRAINT CREATE my_graph UNIQUE NODE Rider PROPERTIES 1 name"
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_schema_from_toml() {
+    let schema = GraphSchema::from_toml(
+        r#"
+        property_keys = ["born", "name"]
+
+        [[labels]]
+        name = "Rider"
+        properties = { name = ["String"], born = ["Integer"] }
+
+        [[relationship_types]]
+        name = "rides"
+
+        [[indexes]]
+        label = "Rider"
+        properties = ["name"]
+        index_type = "range"
+
+        [[constraints]]
+        label = "rides"
+        properties = ["since"]
+        constraint_type = "MANDATORY"
+        entity_type = "Relationship"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        schema,
+        GraphSchema {
+            property_keys: vec!["born".to_string(), "name".to_string()],
+            ..GraphSchema::new()
+                .with_label(
+                    "Rider",
+                    &[
+                        ("name", PropertyType::String),
+                        ("born", PropertyType::Integer)
+                    ]
+                )
+                .with_relationship_type("rides", &[])
+                .with_index(IndexInfo::node("Rider", "range", &["name"]))
+                .with_constraint(ConstraintInfo::relationship(
+                    "rides",
+                    "MANDATORY",
+                    &["since"]
+                ))
+        }
+    );
+    assert_eq!(
+        GraphSchema::from_toml(&schema.to_toml().unwrap()).unwrap(),
+        schema
+    );
+    assert!(GraphSchema::from_toml("labels = 1").is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_schema_from_file() {
+    let path = std::env::temp_dir().join("redis_graph_test_schema.json");
+    std::fs::write(&path, expected().to_json().unwrap()).unwrap();
+    assert_eq!(GraphSchema::from_file(&path).unwrap(), expected());
+    std::fs::remove_file(&path).unwrap();
+
+    let path = std::env::temp_dir().join("redis_graph_test_schema.yaml");
+    std::fs::write(&path, "labels: []").unwrap();
+    assert!(GraphSchema::from_file(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
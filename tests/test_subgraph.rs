@@ -0,0 +1,126 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis_graph::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn node(id: u64, label: &str) -> GraphValue {
+    GraphValue::Node(NodeValue {
+        id,
+        labels: vec![label.to_string()],
+        properties: HashMap::new(),
+    })
+}
+
+fn relation(id: u64, rel_type: &str, src_node: u64, dest_node: u64) -> GraphValue {
+    GraphValue::Relation(RelationValue {
+        id,
+        rel_type: rel_type.to_string(),
+        src_node,
+        dest_node,
+        properties: HashMap::new(),
+    })
+}
+
+// Result of "MATCH (a)-[r]->(b) RETURN a, r, b" with duplicated nodes
+fn result_set() -> GraphResultSet {
+    let header: Arc<[String]> = vec!["a".to_string(), "r".to_string(), "b".to_string()].into();
+    let rows = vec![
+        vec![
+            node(1, "Rider"),
+            relation(10, "rides", 1, 3),
+            node(3, "Team"),
+        ],
+        vec![
+            node(2, "Rider"),
+            relation(11, "rides", 2, 3),
+            node(3, "Team"),
+        ],
+        vec![
+            node(1, "Rider"),
+            relation(12, "knows", 1, 2),
+            node(2, "Rider"),
+        ],
+        vec![
+            node(1, "Rider"),
+            relation(12, "knows", 1, 2),
+            node(2, "Rider"),
+        ],
+        vec![
+            node(3, "Team"),
+            relation(13, "sponsors", 3, 4),
+            node(3, "Team"),
+        ],
+    ];
+    GraphResultSet {
+        header: header.to_vec(),
+        data: rows
+            .into_iter()
+            .map(|values| GraphResult::from_values(header.clone(), values))
+            .collect(),
+        metadata: Vec::new(),
+    }
+}
+
+#[test]
+fn test_subgraph_dedup() {
+    let graph = result_set().to_subgraph();
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.relation_count(), 4);
+    assert_eq!(graph.node(3).unwrap().labels, ["Team"]);
+    assert!(graph.node(4).is_none());
+    assert_eq!(graph.relation(12).unwrap().rel_type, "knows");
+}
+
+#[test]
+fn test_subgraph_adjacency() {
+    let graph = result_set().to_subgraph();
+    assert_eq!(graph.successors(1), [3, 2]);
+    assert_eq!(graph.predecessors(3), [1, 2]);
+    assert_eq!(graph.neighbors(2), [3, 1]);
+    assert_eq!(graph.neighbors(3), [4, 1, 2]);
+    assert_eq!(graph.out_degree(1), 2);
+    assert_eq!(graph.in_degree(1), 0);
+    assert_eq!(graph.in_degree(3), 2);
+    assert_eq!(graph.degree(3), 3);
+    assert_eq!(graph.in_degree(4), 1);
+    assert!(graph.outgoing(4).is_empty());
+    assert_eq!(graph.incoming(2)[0].id, 12);
+}
+
+#[test]
+fn test_subgraph_lookup() {
+    let graph = result_set().to_subgraph();
+    let riders: Vec<u64> = graph
+        .nodes_with_label("Rider")
+        .iter()
+        .map(|n| n.id)
+        .collect();
+    assert_eq!(riders, [1, 2]);
+    assert_eq!(graph.relations_of_type("rides").len(), 2);
+    assert!(graph.relations_of_type("drives").is_empty());
+}
+
+#[test]
+fn test_subgraph_add() {
+    let mut graph = Subgraph::new();
+    assert!(graph.add_node(NodeValue {
+        id: 1,
+        ..NodeValue::default()
+    }));
+    assert!(!graph.add_node(NodeValue {
+        id: 1,
+        ..NodeValue::default()
+    }));
+    let loop_rel = RelationValue {
+        id: 5,
+        src_node: 1,
+        dest_node: 1,
+        ..RelationValue::default()
+    };
+    assert!(graph.add_relation(loop_rel.clone()));
+    assert!(!graph.add_relation(loop_rel));
+    assert_eq!(graph.degree(1), 2);
+    assert_eq!(graph.neighbors(1), [1]);
+}
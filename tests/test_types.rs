@@ -0,0 +1,209 @@
+extern crate redis;
+extern crate redis_graph;
+
+use redis::{from_redis_value, Value};
+use redis_graph::*;
+
+fn data(s: &str) -> Value {
+    Value::Data(s.as_bytes().to_vec())
+}
+
+fn pair(key: &str, value: Value) -> Value {
+    Value::Bulk(vec![data(key), value])
+}
+
+fn node(id: i64, label: &str, name: &str) -> Value {
+    Value::Bulk(vec![
+        pair("id", Value::Int(id)),
+        pair("labels", Value::Bulk(vec![data(label)])),
+        pair("properties", Value::Bulk(vec![pair("name", data(name))])),
+    ])
+}
+
+// A raw GRAPH.QUERY response for "MATCH (r:Rider) RETURN r, r.name, r.born"
+fn rider_response() -> Value {
+    Value::Bulk(vec![
+        Value::Bulk(vec![data("r"), data("r.name"), data("r.born")]),
+        Value::Bulk(vec![
+            Value::Bulk(vec![
+                node(1, "Rider", "Valentino Rossi"),
+                data("Valentino Rossi"),
+                Value::Int(1979),
+            ]),
+            Value::Bulk(vec![
+                node(2, "Rider", "Dani Pedrosa"),
+                data("Dani Pedrosa"),
+                Value::Int(1985),
+            ]),
+        ]),
+        Value::Bulk(vec![data(
+            "Query internal execution time: 0.1 milliseconds",
+        )]),
+    ])
+}
+
+#[test]
+fn test_result_stream() {
+    let mut stream = GraphResultStream::from_value(rider_response()).unwrap();
+    assert_eq!(stream.header(), ["r", "r.name", "r.born"]);
+    assert_eq!(stream.metadata().len(), 1);
+    assert_eq!(stream.len(), 2);
+
+    let row = stream.next().unwrap().unwrap();
+    assert_eq!(row.get_node("r").unwrap().id, 1);
+    assert_eq!(row.get_node_at(0).unwrap().labels, ["Rider"]);
+    assert_eq!(
+        row.get_scalar::<String>("r.name").unwrap(),
+        "Valentino Rossi"
+    );
+    assert_eq!(row.get_scalar_at::<i64>(2), Some(1979));
+    assert!(row.get_relation("r").is_none());
+    assert!(row.get_value("missing").is_none());
+
+    let row = stream.next().unwrap().unwrap();
+    assert_eq!(row.get_scalar::<String>("r.name").unwrap(), "Dani Pedrosa");
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn test_row_into_result() {
+    let stream = GraphResultStream::from_value(rider_response()).unwrap();
+    let names: Vec<String> = stream
+        .map(|row| row.unwrap().get_scalar("r.name").unwrap())
+        .collect();
+    assert_eq!(names, ["Valentino Rossi", "Dani Pedrosa"]);
+
+    let mut stream = GraphResultStream::from_value(rider_response()).unwrap();
+    let res = stream.next().unwrap().unwrap().into_result();
+    assert_eq!(
+        res.get_scalar::<String>("r.name").unwrap(),
+        "Valentino Rossi"
+    );
+    assert_eq!(res.get_scalar_at::<i64>(2), Some(1979));
+}
+
+#[test]
+fn test_result_stream_metadata_only() {
+    let response = Value::Bulk(vec![Value::Bulk(vec![data("Nodes created: 1")])]);
+    let mut stream = GraphResultStream::from_value(response).unwrap();
+    assert!(stream.header().is_empty());
+    assert_eq!(stream.metadata(), ["Nodes created: 1"]);
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn test_result_set_parse() {
+    let res: GraphResultSet = from_redis_value(&rider_response()).unwrap();
+    assert_eq!(res.data.len(), 2);
+    assert_eq!(
+        res.data[0].get_scalar::<String>("r.name").unwrap(),
+        "Valentino Rossi"
+    );
+}
+
+// A raw response for "RETURN n.name, m.name AS n.name, 1" with a short second row
+fn duplicate_response() -> Value {
+    Value::Bulk(vec![
+        Value::Bulk(vec![data("n.name"), data("n.name"), data("x")]),
+        Value::Bulk(vec![
+            Value::Bulk(vec![data("Pam"), data("Jim"), Value::Int(1)]),
+            Value::Bulk(vec![data("Dwight"), data("Angela")]),
+        ]),
+        Value::Bulk(vec![]),
+    ])
+}
+
+#[test]
+fn test_result_column_order() {
+    let res: GraphResultSet = from_redis_value(&rider_response()).unwrap();
+    let row = &res.data[0];
+    let keys: Vec<&str> = row.iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["r", "r.name", "r.born"]);
+    assert_eq!(row.len(), 3);
+    assert_eq!(row.get_node_at(0).unwrap().id, 1);
+    assert_eq!(row.get_scalar_at::<String>(1).unwrap(), "Valentino Rossi");
+    assert_eq!(row.get_scalar_at::<i64>(2), Some(1979));
+    assert!(row.get_at(3).is_none());
+}
+
+#[test]
+fn test_result_duplicate_columns() {
+    let res: GraphResultSet = from_redis_value(&duplicate_response()).unwrap();
+    let row = &res.data[0];
+    assert_eq!(row.get_scalar_at::<String>(0).unwrap(), "Pam");
+    assert_eq!(row.get_scalar_at::<String>(1).unwrap(), "Jim");
+    assert_eq!(row.get_all("n.name").len(), 2);
+    assert_eq!(row.get_scalar::<String>("n.name").unwrap(), "Jim");
+    #[allow(deprecated)]
+    let map = row.data();
+    assert_eq!(map.len(), 2);
+    assert!(matches!(&map["n.name"], GraphValue::Scalar(v) if *v == data("Jim")));
+    assert_eq!(row.header(), ["n.name", "n.name", "x"]);
+    assert_eq!(res.data[1].len(), 2);
+    assert!(res.data[1].get_value("x").is_none());
+}
+
+#[test]
+fn test_result_strict_mode() {
+    let res: GraphResultSet = from_redis_value(&rider_response()).unwrap();
+    assert!(res.strict().is_ok());
+
+    let res: GraphResultSet = from_redis_value(&duplicate_response()).unwrap();
+    assert!(res.strict().is_err());
+
+    let mut stream = GraphResultStream::from_value(duplicate_response())
+        .unwrap()
+        .strict();
+    assert!(stream.next().unwrap().is_ok());
+    assert!(stream.next().unwrap().is_err());
+}
+
+#[test]
+fn test_stream_duplicate_columns() {
+    let res: GraphResultSet = from_redis_value(&duplicate_response()).unwrap();
+    let mut stream = GraphResultStream::from_value(duplicate_response()).unwrap();
+    let row = stream.next().unwrap().unwrap();
+    assert_eq!(row.get_scalar::<String>("n.name").unwrap(), "Jim");
+    assert_eq!(
+        row.get_scalar::<String>("n.name"),
+        res.data[0].get_scalar::<String>("n.name")
+    );
+    assert_eq!(row.get_all("n.name").len(), 2);
+}
+
+#[test]
+fn test_rows_as_tuple() {
+    let res: GraphResultSet = from_redis_value(&rider_response()).unwrap();
+    let riders: Vec<(NodeValue, String, i64)> = res.rows_as().unwrap();
+    assert_eq!(riders.len(), 2);
+    assert_eq!(riders[0].0.id, 1);
+    assert_eq!(riders[0].1, "Valentino Rossi");
+    assert_eq!(riders[1].2, 1985);
+
+    let values: Vec<Vec<GraphValue>> = res.rows_as().unwrap();
+    assert_eq!(values[0].len(), 3);
+
+    let born: Vec<(GraphValue, Option<String>, Option<u16>)> = res.rows_as().unwrap();
+    assert_eq!(born[0].2, Some(1979));
+}
+
+#[test]
+fn test_rows_as_errors() {
+    let res: GraphResultSet = from_redis_value(&rider_response()).unwrap();
+    assert!(res.rows_as::<(NodeValue, String)>().is_err());
+    assert!(res.rows_as::<(String, String, i64)>().is_err());
+    assert!(res.rows_as::<NodeValue>().is_err());
+
+    let res: GraphResultSet = from_redis_value(&duplicate_response()).unwrap();
+    assert!(res.rows_as::<(String, String, i64)>().is_err());
+}
+
+#[test]
+fn test_from_graph_value() {
+    let nil = GraphValue::Scalar(Value::Nil);
+    assert_eq!(Option::<i64>::from_graph_value(&nil).unwrap(), None);
+    let one = GraphValue::Scalar(Value::Int(1));
+    assert_eq!(i64::from_graph_value(&one).unwrap(), 1);
+    assert!(NodeValue::from_graph_value(&one).is_err());
+    assert!(RelationValue::from_graph_value(&one).is_err());
+}